@@ -0,0 +1,28 @@
+use pyro_core::convert::{FromPyroValue, ToPyroValue};
+use pyro_core::interpreter::{NativeClosure, RuntimeError, Value};
+use pyro_core::plugin::ModuleDescriptor;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn distance(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 4 {
+        return Err(Value::String(Arc::new("Expected 4 arguments".to_string())).into());
+    }
+    let mut coords = [0.0; 4];
+    for (coord, arg) in coords.iter_mut().zip(&args) {
+        *coord = f64::from_value(arg).map_err(|e| Value::String(Arc::new(e)))?;
+    }
+    let [x1, y1, x2, y2] = coords;
+    Ok((x2 - x1).hypot(y2 - y1).to_value())
+}
+
+fn modules() -> Vec<ModuleDescriptor> {
+    let mut geo = HashMap::new();
+    geo.insert(
+        "distance".to_string(),
+        Value::NativeFunction { name: "distance".to_string(), func: NativeClosure(Arc::new(distance)) },
+    );
+    vec![ModuleDescriptor::new("acme.geo", Value::NativeModule(Arc::new(geo)))]
+}
+
+pyro_core::declare_plugin!(modules);
@@ -1,17 +1,22 @@
+use crate::output;
 use crate::util;
 use crate::manifest::Manifest;
 use anyhow::{Context, Result};
+use pyro_core::aliases;
 use pyro_core::ast::Stmt;
+use pyro_core::transpiler::monomorphize::monomorphize;
 use pyro_core::transpiler::Transpiler;
 use std::collections::HashSet;
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::Instant;
 
 use crate::BuildTarget;
 
 pub fn r#impl(file: PathBuf, output: Option<PathBuf>, target: BuildTarget) -> Result<()> {
-    println!("Building {:?}", file);
+    output::status(format!("Building {:?}", file));
 
     // Resolve Manifest
     let parent = file.parent().unwrap_or(Path::new("."));
@@ -27,7 +32,7 @@ pub fn r#impl(file: PathBuf, output: Option<PathBuf>, target: BuildTarget) -> Re
                  // We don't fail build if extern generation fails, just warn or ignore?
                  // Best to try and log error execution but proceed if possible (though likely fail later)
                  if let Err(e) = crate::cmd::externs::generate_externs(&externs_dir) {
-                     eprintln!("Warning: Failed to generate externs: {}", e);
+                     output::warn(format!("Failed to generate externs: {}", e));
                  }
                  break;
              }
@@ -44,17 +49,17 @@ pub fn r#impl(file: PathBuf, output: Option<PathBuf>, target: BuildTarget) -> Re
     let mut defs = Vec::new();
     let mut main_stmts = Vec::new();
 
-    for stmt in statements {
+    for stmt in monomorphize(aliases::resolve(statements)) {
         match stmt {
-            Stmt::FnDecl { .. } => defs.push(stmt),
+            Stmt::FnDecl { .. } | Stmt::RecordDef { .. } => defs.push(stmt),
             _ => main_stmts.push(stmt),
         }
     }
 
     let mut transpiler = Transpiler::new();
     
-    let defs_rs = transpiler.transpile(defs);
-    let main_rs = transpiler.transpile(main_stmts);
+    let defs_rs = transpiler.transpile(defs).map_err(anyhow::Error::msg)?;
+    let main_rs = transpiler.transpile(main_stmts).map_err(anyhow::Error::msg)?;
 
     let full_rs = format!(r#"#![allow(unused_parens)]
 #![allow(unused_variables)]
@@ -88,7 +93,7 @@ fn main() {{
             };
             
             fs::write(&dest, full_rs)?;
-            println!("Transpilation successful! Rust file created: {:?}", dest);
+            output::success(format!("Transpilation successful! Rust file created: {:?}", dest));
         }
         BuildTarget::Binary => {
             // Write Main.rs
@@ -128,19 +133,11 @@ edition = "2021"
 
 
 
-            println!("Compiling to native binary...");
+            output::status("Compiling to native binary...");
             
             let abs_build_dir = build_dir.canonicalize()?;
-            let status = Command::new("cargo")
-                .arg("build")
-                .arg("--release")
-                .current_dir(&abs_build_dir)
-                .status()
-                .context("Failed to run cargo build")?;
-
-            if !status.success() {
-                anyhow::bail!("Compilation failed");
-            }
+            let started = Instant::now();
+            cargo_build(&abs_build_dir)?;
 
             // Copy binary
             let dest = if let Some(out) = output {
@@ -152,9 +149,49 @@ edition = "2021"
             
             fs::copy(build_dir.join("target/release/pyro_program"), &dest)?;
 
-            println!("Build successful! Binary created: {:?}", dest);
+            output::success(format!("Build successful! Binary created: {:?} in {:.1}s", dest, started.elapsed().as_secs_f64()));
+        }
+    }
+
+    Ok(())
+}
+
+// Runs `cargo build --release` in `dir`. Rather than cargo's output, a
+// progress line shows the step it's on; what it printed is shown only if the
+// build fails. --verbose passes cargo's output straight through.
+fn cargo_build(dir: &Path) -> Result<()> {
+    let mut cargo = Command::new("cargo");
+    cargo.arg("build").arg("--release").current_dir(dir);
+    if output::quiet() {
+        cargo.arg("--quiet");
+    }
+    output::command(&cargo);
+    if output::verbose() {
+        let status = cargo.status().context("Failed to run cargo build")?;
+        if !status.success() {
+            anyhow::bail!("Compilation failed");
+        }
+        return Ok(());
+    }
+
+    let mut child = cargo.stderr(Stdio::piped()).spawn().context("Failed to run cargo build")?;
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let mut progress = output::Progress::new(0);
+    let mut log = Vec::new();
+    for line in BufReader::new(stderr).lines() {
+        let line = line?;
+        // Cargo's steps are indented verbs: `   Compiling serde v1.0.0`
+        let step = line.trim_start();
+        if line.starts_with(' ') && step.starts_with(char::is_uppercase) {
+            progress.set_message(step);
         }
+        log.push(line);
     }
+    progress.finish();
 
+    if !child.wait().context("Failed to run cargo build")?.success() {
+        eprintln!("{}", log.join("\n"));
+        anyhow::bail!("Compilation failed");
+    }
     Ok(())
 }
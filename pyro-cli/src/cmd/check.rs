@@ -0,0 +1,47 @@
+use crate::diagnostics::{self, CheckFailure};
+use crate::output;
+use crate::util;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// Parses `file` and everything it imports and runs the checks `pyro run` does
+// before executing, plus the strict ones if asked, reporting every problem.
+pub fn r#impl(file: PathBuf, strict: bool, json_errors: bool) -> Result<()> {
+    let result = check(&file, strict);
+    let Err(error) = result else {
+        output::success(format!("No problems found in {:?}", file));
+        return Ok(());
+    };
+    let found = diagnostics::from_error(&file, &error);
+    if json_errors {
+        diagnostics::emit(&found);
+        std::process::exit(1);
+    }
+    for found in &found {
+        output::diagnostic(&found.file, &found.diagnostic);
+    }
+    anyhow::bail!("{} problem(s) found", found.len())
+}
+
+fn check(file: &Path, strict: bool) -> Result<()> {
+    let mut statements = Vec::new();
+    let mut loaded = HashSet::new();
+    let checked = util::process_file(file.to_path_buf(), &mut loaded, &mut statements);
+
+    let mut diagnostics = match checked {
+        Ok(()) => Vec::new(),
+        Err(error) => match error.downcast::<CheckFailure>() {
+            Ok(failure) => failure.diagnostics,
+            Err(error) => return Err(error),
+        },
+    };
+    if strict {
+        diagnostics.extend(pyro_core::strict::check(&statements).err().unwrap_or_default());
+    }
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(CheckFailure { diagnostics }.into())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::output;
+use crate::util;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+// Parses every .pyro file under `path` so `pyro run` finds them all in the
+// parse cache. Reports every file that fails to parse, not just the first.
+pub fn r#impl(path: PathBuf) -> Result<()> {
+    let mut compiled = 0;
+    let mut failed = 0;
+
+    let entries = WalkDir::new(&path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !e.file_name().to_string_lossy().starts_with('.') || e.depth() == 0);
+    for entry in entries {
+        let entry = entry?;
+        let file = entry.path();
+        if !entry.file_type().is_file() || file.extension().is_none_or(|ext| ext != "pyro") {
+            continue;
+        }
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Could not read file {:?}", file))?;
+        match util::parse_cached(file, &content) {
+            Ok(_) => compiled += 1,
+            Err(e) => {
+                output::error(e);
+                failed += 1;
+            }
+        }
+    }
+
+    output::success(format!("Compiled {} file(s)", compiled));
+    if failed > 0 {
+        anyhow::bail!("{} file(s) failed to parse", failed);
+    }
+    Ok(())
+}
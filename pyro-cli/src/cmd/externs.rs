@@ -1,6 +1,10 @@
 use crate::manifest::Manifest;
+use crate::network;
+use crate::output;
 use anyhow::{Context, Result};
+use pyro_core::ast::NUMERIC_TYPES;
 
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -18,7 +22,63 @@ pub fn generate_externs(output_dir: &Path) -> Result<()> {
     
     let manifest = Manifest::resolve_from(project_root).context("No pyro.mod found. Cannot generate externs.")?;
 
-    
+    let dependencies = match &manifest.rust {
+        Some(rust_config) => &rust_config.dependencies,
+        None => {
+            output::status("No [rust] dependencies found in pyro.mod");
+            return Ok(());
+        }
+    };
+
+    let metadata = fetch_metadata(dependencies)?;
+
+    // Ensure output dir exists
+    if !output_dir.exists() {
+        fs::create_dir_all(output_dir)?;
+    }
+
+    // For each dependency, find the source and parse
+    for dep_name in dependencies.keys() {
+        match module_externs(&metadata, dep_name, &format!("extern_{}_", dep_name))? {
+            Some(externs) => {
+                // Write to file
+                let output_file = output_dir.join(format!("extern.{}.pyro", dep_name));
+                fs::write(&output_file, externs.join("\n"))?;
+                output::status(format!("Created {}", output_file.display()));
+            }
+            None => output::warn(format!("Could not find package {} in metadata", dep_name)),
+        }
+    }
+
+    Ok(())
+}
+
+/// The start of the names the functions of the `index`th of a program's
+/// `import rust` paths are declared with. No two are prefixes of each other,
+/// so every function belongs to exactly one import.
+pub fn rust_import_prefix(index: usize) -> String {
+    format!("rust_import_{}_", index)
+}
+
+/// Extern declarations for modules pulled in with `import rust "<path>"`,
+/// keyed by path, which is a crate or a module in one such as
+/// "serde_json::value". Each import's functions are named with
+/// [`rust_import_prefix`] of its index in `imports`. The crates must be
+/// present in `dependencies`.
+pub fn rust_import_externs(dependencies: &HashMap<String, String>, imports: &[String]) -> Result<HashMap<String, Vec<String>>> {
+    let metadata = fetch_metadata(dependencies)?;
+
+    let mut externs = HashMap::new();
+    for (index, path) in imports.iter().enumerate() {
+        let decls = module_externs(&metadata, path, &rust_import_prefix(index))?
+            .with_context(|| format!("Could not find Rust module '{}'", path))?;
+        externs.insert(path.clone(), decls);
+    }
+    Ok(externs)
+}
+
+// Resolves `dependencies` in a scratch cargo project so their sources can be inspected.
+fn fetch_metadata(dependencies: &HashMap<String, String>) -> Result<cargo_metadata::Metadata> {
     // We need to resolve dependencies to find their source code.
     // We'll create a temporary cargo project similar to how `run` does it.
     
@@ -40,14 +100,9 @@ pub fn generate_externs(output_dir: &Path) -> Result<()> {
     }
 
     // 2. Generate Cargo.toml
-    let mut dependencies = String::new();
-    if let Some(rust_config) = &manifest.rust {
-        for (name, version) in &rust_config.dependencies {
-            dependencies.push_str(&format!("{} = \"{}\"\n", name, version));
-        }
-    } else {
-        println!("No [rust] dependencies found in pyro.mod");
-        return Ok(());
+    let mut deps = String::new();
+    for (name, version) in dependencies {
+        deps.push_str(&format!("{} = \"{}\"\n", name, version));
     }
 
     // We don't strictly need pyro-core for metadata generation usually, but let's include a dummy or real one if needed.
@@ -59,70 +114,103 @@ edition = "2021"
 
 [dependencies]
 {}
-"#, dependencies);
+"#, deps);
 
     fs::write(build_dir.join("Cargo.toml"), cargo_toml)?;
     // Create a dummy main.rs so cargo doesn't complain
     fs::write(build_dir.join("src/main.rs"), "fn main() {}")?;
 
-    println!("Fetching metadata...");
+    output::status("Fetching metadata...");
     
     // 3. Run cargo metadata
-    let output = Command::new("cargo")
-        .arg("metadata")
-        .arg("--format-version")
-        .arg("1")
-        .current_dir(&build_dir)
-        .output()
-        .context("Failed to run cargo metadata")?;
+    let mut cargo = Command::new("cargo");
+    cargo.arg("metadata").arg("--format-version").arg("1").current_dir(&build_dir);
+    network::cargo_args(&mut cargo);
+    output::command(&cargo);
+    let output = cargo.output().context("Failed to run cargo metadata")?;
 
     if !output.status.success() {
         anyhow::bail!("Failed to resolve dependencies: {}", String::from_utf8_lossy(&output.stderr));
     }
 
-    let metadata: cargo_metadata::Metadata = serde_json::from_slice(&output.stdout)?;
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
 
-    // Ensure output dir exists
-    if !output_dir.exists() {
-        fs::create_dir_all(output_dir)?;
-    }
+// Extern declaration lines for the public functions of a module, given by
+// its path from the crate root ("hex", "serde_json::value"), with their
+// names starting with `prefix`. None if the crate isn't part of the resolved
+// metadata or has no such public module.
+fn module_externs(metadata: &cargo_metadata::Metadata, path: &str, prefix: &str) -> Result<Option<Vec<String>>> {
+    let mut segments = path.split("::");
+    let dep_name = segments.next().unwrap_or(path);
+    // Find package in metadata
+    let pkg = match metadata.packages.iter().find(|p| p.name == dep_name) {
+        Some(pkg) => pkg,
+        None => return Ok(None),
+    };
 
-    // 4. For each dependency, find the source and parse
-    if let Some(rust_config) = &manifest.rust {
-        for (dep_name, _) in &rust_config.dependencies {
-            // Find package in metadata
-            if let Some(pkg) = metadata.packages.iter().find(|p| &p.name == dep_name) {
-                // Find lib target
-                if let Some(target) = pkg.targets.iter().find(|t| t.kind.contains(&"lib".to_string())) {
-                    let src_path = &target.src_path;
-                    // println!("Generating externs for {} ({})", dep_name, src_path);
-                    
-                    if let Ok(content) = fs::read_to_string(src_path) {
-                        let ast = syn::parse_file(&content)?;
-                        let mut visitor = FunctionVisitor {
-                            module_path: dep_name.clone(),
-                            externs: Vec::new(),
-                        };
-                        visitor.visit_file(&ast);
-                        
-                        // Write to file
-                        let output_file = output_dir.join(format!("extern.{}.pyro", dep_name));
-                        let output_content = visitor.externs.join("\n");
-                        fs::write(&output_file, output_content)?;
-                        println!("Created {}", output_file.display());
-                    }
+    let mut visitor = FunctionVisitor {
+        module_path: path.to_string(),
+        prefix: prefix.to_string(),
+        externs: Vec::new(),
+    };
+
+    // Find lib target
+    if let Some(target) = pkg.targets.iter().find(|t| t.kind.contains(&"lib".to_string())) {
+        if let Ok(content) = fs::read_to_string(&target.src_path) {
+            let root = syn::parse_file(&content)?;
+            let dir = target.src_path.parent().map(|dir| dir.as_std_path().to_path_buf()).unwrap_or_default();
+            let Some(items) = module_items(root.items, dir, segments)? else {
+                return Ok(None);
+            };
+            // Only the module's own functions; those of its submodules are
+            // imported by their own paths
+            for item in &items {
+                if let syn::Item::Fn(function) = item {
+                    visitor.visit_item_fn(function);
                 }
-            } else {
-                println!("Warning: Could not find package {} in metadata", dep_name);
             }
         }
     }
 
-    Ok(())
+    Ok(Some(visitor.externs))
+}
+
+// The items of the public module at `segments` below a module with `items`
+// whose submodule files are in `dir`. Modules are either inline or in
+// <name>.rs or <name>/mod.rs, and either way their own submodules are in
+// <name>/.
+fn module_items<'a>(mut items: Vec<syn::Item>, mut dir: PathBuf, segments: impl Iterator<Item = &'a str>) -> Result<Option<Vec<syn::Item>>> {
+    for segment in segments {
+        let module = items.into_iter().find_map(|item| match item {
+            syn::Item::Mod(module) if module.ident == segment && matches!(module.vis, syn::Visibility::Public(_)) => Some(module),
+            _ => None,
+        });
+        let Some(module) = module else {
+            return Ok(None);
+        };
+        items = match module.content {
+            Some((_, inline)) => inline,
+            None => {
+                let file = [dir.join(format!("{}.rs", segment)), dir.join(segment).join("mod.rs")]
+                    .into_iter()
+                    .find(|file| file.exists());
+                let Some(file) = file else {
+                    return Ok(None);
+                };
+                let content = fs::read_to_string(&file).with_context(|| format!("Could not read {}", file.display()))?;
+                syn::parse_file(&content)?.items
+            }
+        };
+        dir = dir.join(segment);
+    }
+    Ok(Some(items))
 }
 
 struct FunctionVisitor {
     module_path: String,
+    // What the declared names start with, e.g. "extern_hex_"
+    prefix: String,
     externs: Vec<String>,
 }
 
@@ -157,7 +245,7 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
                                    ];
 
                                    for (pyro_type, rust_type) in variants {
-                                        let variant_name = format!("extern_{}_{}_{}", self.module_path, func_name, pyro_type);
+                                        let variant_name = format!("{}{}_{}", self.prefix, func_name, pyro_type);
                                         // e.g. extern "rand::random::<f64>" def random_float() -> float
                                         let pyro_ret = pyro_type; // same name
                                         self.externs.push(format!(
@@ -239,13 +327,13 @@ impl<'ast> Visit<'ast> for FunctionVisitor {
             };
             
             if valid {
-                let extern_line = format!("extern \"{}::{}\" def extern_{}_{}({}) -> {}", 
-                    self.module_path, func_name, self.module_path, func_name, params.join(", "), return_type);
+                let extern_line = format!("extern \"{}::{}\" def {}{}({}) -> {}", 
+                    self.module_path, func_name, self.prefix, func_name, params.join(", "), return_type);
                 self.externs.push(extern_line);
             } else {
                 // Emit comment
-                let extern_line = format!("// extern \"{}::{}\" def extern_{}_{}({}) -> {} // Generic/Unsupported", 
-                    self.module_path, func_name, self.module_path, func_name, params.join(", "), return_type);
+                let extern_line = format!("// extern \"{}::{}\" def {}{}({}) -> {} // Generic/Unsupported", 
+                    self.module_path, func_name, self.prefix, func_name, params.join(", "), return_type);
                 self.externs.push(extern_line);
             }
         }
@@ -280,8 +368,12 @@ fn map_rust_type(ty: &syn::Type, generics: &std::collections::HashMap<String, St
                     return Some(mapped.clone());
                 }
                 match ident.as_str() {
-                    "i64" | "i32" | "isize" | "u64" | "u32" | "usize" => Some("int".to_string()),
-                    "f64" | "f32" => Some("float".to_string()),
+                    // Sized numbers keep their size so wrappers convert them exactly
+                    "isize" => Some("int".to_string()),
+                    "usize" => Some("uint64".to_string()),
+                    "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "f32" | "f64" => {
+                        NUMERIC_TYPES.iter().find(|(.., rust)| *rust == ident).map(|(name, ..)| name.to_string())
+                    }
                     "bool" => Some("bool".to_string()),
                     "String" | "str" => Some("string".to_string()),
                     "Vec" => {
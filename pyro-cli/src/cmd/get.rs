@@ -1,6 +1,8 @@
+use crate::output;
 use anyhow::{Context, Result};
 use crate::manifest::{Manifest, LockFile};
 use crate::cmd::installer::resolve_package;
+use crate::toolchain;
 
 pub fn r#impl(url: String) -> Result<()> {
     
@@ -11,13 +13,13 @@ pub fn r#impl(url: String) -> Result<()> {
         (url, "HEAD".to_string())
     };
 
-    println!("Getting package: {} version: {}", clean_url, version);
+    output::status(format!("Getting package: {} version: {}", clean_url, version));
 
     // 1. Load Manifest
     let mut manifest = Manifest::load().context("Could not find pyro.mod. Run 'pyro mod init' first.")?;
     
     if manifest.dependencies.contains_key(&clean_url) {
-        println!("Package {} already in pyro.mod", clean_url);
+        output::status(format!("Package {} already in pyro.mod", clean_url));
         // We could update version here if different
     }
 
@@ -36,9 +38,11 @@ pub fn r#impl(url: String) -> Result<()> {
         lockfile.package.remove(pos);
     }
     
+    let commit = lock_pkg.commit.clone().unwrap_or_default();
     lockfile.package.push(lock_pkg);
+    lockfile.environment = Some(toolchain::current(manifest.rust.is_some()));
     lockfile.save()?;
     
-    println!("Package {} added.", clean_url);
+    output::success(format!("Package {} added at commit {}.", clean_url, &commit[..commit.len().min(12)]));
     Ok(())
 }
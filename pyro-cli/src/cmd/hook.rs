@@ -0,0 +1,73 @@
+use crate::manifest::Manifest;
+use crate::network;
+use crate::output;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Runs the project's build.pyro, if pyro.mod has `[build] hook = true`, for
+// code generation and the like before a program in the project runs or
+// builds. `start` is the program or a directory in the project.
+//
+// It runs in its own `pyro run` process from the project root, sandboxed:
+// it may use files under the root, and only the files, hosts and commands
+// `[build]` lists in `allow-fs`, `allow-net` and `allow-run` besides.
+pub fn run(start: &Path) -> Result<()> {
+    let start = std::path::absolute(start)?;
+    let Ok((manifest, root)) = Manifest::locate_from(&start) else {
+        return Ok(());
+    };
+    let Some(build) = manifest.build.filter(|build| build.hook) else {
+        return Ok(());
+    };
+    let script = root.join("build.pyro");
+    if !script.is_file() {
+        anyhow::bail!("pyro.mod turns on the build hook, but {} doesn't exist", script.display());
+    }
+
+    let exe = std::env::current_exe().context("Could not find the pyro executable")?;
+    let mut command = Command::new(exe);
+    command.arg("run").arg("--skip-build").arg("--sandbox");
+    if network::offline() {
+        command.arg("--offline");
+    }
+    let allow_fs = std::iter::once(root.display().to_string()).chain(build.allow_fs).collect::<Vec<_>>();
+    command.arg(format!("--allow-fs={}", allow_fs.join(",")));
+    if !build.allow_net.is_empty() {
+        command.arg(format!("--allow-net={}", build.allow_net.join(",")));
+    }
+    if !build.allow_run.is_empty() {
+        command.arg(format!("--allow-run={}", build.allow_run.join(",")));
+    }
+    command
+        .arg(&script)
+        .current_dir(&root)
+        .env("PYRO_PACKAGE_NAME", &manifest.package.name)
+        .env("PYRO_PACKAGE_VERSION", &manifest.package.version)
+        .env("PYRO_PACKAGE_ROOT", &root);
+
+    output::status("Running build.pyro");
+    output::command(&command);
+    let status = command.status().context("Could not run build.pyro")?;
+    if !status.success() {
+        anyhow::bail!("build.pyro failed");
+    }
+    Ok(())
+}
+
+// Runs the build hooks of the projects `paths` belong to, each project's
+// once, for commands that take files from several places
+pub fn run_all(paths: &[PathBuf]) -> Result<()> {
+    let mut roots = Vec::new();
+    for path in paths {
+        if let Ok((_, root)) = Manifest::locate_from(std::path::absolute(path)?) {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    for root in &roots {
+        run(root)?;
+    }
+    Ok(())
+}
@@ -1,17 +1,18 @@
+use crate::output;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 
 pub fn r#impl(name: String) -> Result<()> {
-    println!("Initializing new Pyro project: {}", name);
+    output::status(format!("Initializing new Pyro project: {}", name));
     
     let path = Path::new("pyro.mod");
     if path.exists() {
-        println!("pyro.mod already exists, skipping creation.");
+        output::status("pyro.mod already exists, skipping creation.");
     } else {
         let manifest = crate::manifest::Manifest::new(name);
         manifest.save().context("Failed to save pyro.mod")?;
-        println!("Created pyro.mod");
+        output::success("Created pyro.mod");
     }
 
     // Create src directory
@@ -25,7 +26,7 @@ pub fn r#impl(name: String) -> Result<()> {
 
 main()
 "#)?;
-        println!("Created src/main.pyro");
+        output::success("Created src/main.pyro");
     }
 
     Ok(())
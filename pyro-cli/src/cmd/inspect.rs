@@ -0,0 +1,104 @@
+// Live inspection of a running program.
+//
+// `pyro run --inspect` listens on a local TCP port. `pyro shell --remote`
+// connects to it and sends complete inputs, which the program evaluates
+// between statements (see runtime::Requests). The protocol is one
+// JSON value per line: the client sends a string of source, the server
+// answers {"ok": bool, "output": str}.
+
+use crate::cmd::shell::is_input_complete;
+use anyhow::{Context, Result};
+use pyro_core::stdlib::runtime::Requests;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+#[derive(Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    output: String,
+}
+
+// Starts accepting inspector connections in the background
+pub fn serve(addr: &str, requests: Requests) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("Could not listen on {}", addr))?;
+    eprintln!("Inspector listening on {}", listener.local_addr()?);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let requests = requests.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = handle(stream, &requests) {
+                    eprintln!("Inspector connection closed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(stream: TcpStream, requests: &Requests) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let source: String = serde_json::from_str(&line?)?;
+        // Blocks until the program reaches its next statement or wait
+        let reply = match requests.eval(&source).recv() {
+            Ok(Ok(output)) => Reply { ok: true, output },
+            Ok(Err(output)) => Reply { ok: false, output },
+            Err(_) => Reply { ok: false, output: "The program is no longer running".to_string() },
+        };
+        writeln!(writer, "{}", serde_json::to_string(&reply)?)?;
+    }
+    Ok(())
+}
+
+// `pyro shell --remote`: a shell whose inputs run in the inspected program
+pub fn connect(addr: &str) -> Result<()> {
+    let stream = TcpStream::connect(addr).with_context(|| format!("Could not connect to {}", addr))?;
+    let mut writer = stream.try_clone()?;
+    let mut replies = BufReader::new(stream).lines();
+
+    let mut rl = DefaultEditor::new()?;
+    println!("Connected to {}", addr);
+    println!("Inputs run between statements of the program. Type 'exit' or Ctrl-D to detach");
+
+    let mut buffer = String::new();
+    loop {
+        let prompt = if buffer.is_empty() { ">> " } else { ".. " };
+        match rl.readline(prompt) {
+            Ok(line) => {
+                if buffer.is_empty() && line.trim() == "exit" {
+                    break;
+                }
+                if buffer.is_empty() && line.trim().is_empty() {
+                    continue;
+                }
+                buffer.push_str(&line);
+                buffer.push('\n');
+                if !is_input_complete(&buffer) {
+                    continue;
+                }
+                let _ = rl.add_history_entry(buffer.trim());
+                writeln!(writer, "{}", serde_json::to_string(buffer.trim())?)?;
+                buffer.clear();
+
+                let reply: Reply = match replies.next() {
+                    Some(line) => serde_json::from_str(&line?)?,
+                    None => anyhow::bail!("The program closed the connection"),
+                };
+                match (reply.ok, reply.output.is_empty()) {
+                    (true, true) => {}
+                    (true, false) => println!("{}", reply.output),
+                    (false, _) => println!("Runtime Error: {}", reply.output),
+                }
+            }
+            Err(ReadlineError::Interrupted) => buffer.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
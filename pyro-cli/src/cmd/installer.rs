@@ -1,13 +1,19 @@
 use anyhow::{Context, Result};
+use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use crate::manifest::{Manifest, LockFile, LockPackage};
-use sha2::{Sha256, Digest};
-use walkdir::WalkDir;
+use crate::network;
+use crate::output::{self, Style};
+use crate::toolchain;
+use pyro_core::stdlib::hash::dir_checksum;
 
-pub fn r#impl() -> Result<()> {
-    println!("Installing dependencies...");
+pub fn r#impl(jobs: usize) -> Result<()> {
+    output::status("Installing dependencies...");
     
     // 1. Load manifest
     let manifest = Manifest::load()?;
@@ -24,100 +30,224 @@ pub fn r#impl() -> Result<()> {
     // (For this iteration, we might just regenerate lock entries based on manifest)
     
     let mut new_lock_packages = Vec::new();
-    
-    for (url, version) in &manifest.dependencies {
-        // Check if already in lockfile
-        let existing = lockfile.package.iter().find(|p| &p.name == url);
-        
-        let lock_pkg = if let Some(pkg) = existing {
-             // If version matches, keep it. If not, we'd need to update.
-             // For simplicity, let's assume if it exists in lock, we trust it, 
-             // unless we are forcing update. 
-             // BUT, user asked for "Maintain a dependencies file and a dependencies lock file to ensure consistency"
-             // So if manifest version differs, we should update.
-             if &pkg.version == version {
-                 pkg.clone()
-             } else {
-                 resolve_package(url, version)?
-             }
-        } else {
-            resolve_package(url, version)?
-        };
-        
-        // Install the package
-        install_package(&lock_pkg)?;
-        new_lock_packages.push(lock_pkg);
+
+    // Packages' own dependencies are installed and locked too. When two
+    // packages ask for different versions of one, the first asked for wins.
+    let mut direct: Vec<_> = manifest.dependencies.iter().map(|(url, version)| (url.clone(), version.clone(), None)).collect();
+    direct.sort();
+    let mut queue = VecDeque::from(direct);
+    let mut seen = HashSet::new();
+
+    // Each package comes with the one whose pyro.mod asked for it, if any,
+    // so errors can say why it was needed. The packages waiting at any time
+    // don't depend on each other, so they're installed together, up to
+    // `jobs` at once, and their results taken in order.
+    let progress = Mutex::new(output::Progress::new(0));
+    let mut changes = Vec::new();
+    let mut failures = Vec::new();
+    while !queue.is_empty() {
+        let batch: Vec<_> = queue.drain(..).filter(|(url, _, _)| seen.insert(url.clone())).collect();
+        progress.lock().unwrap().set_total(seen.len());
+        let results = in_parallel(jobs, &batch, |(url, version, required_by)| {
+            let result = install_dependency(&lockfile, url, version).with_context(|| match required_by {
+                Some(parent) => format!("Could not install {}@{}, required by {}", url, version, parent),
+                None => format!("Could not install {}@{}", url, version),
+            });
+            let mut progress = progress.lock().unwrap();
+            match &result {
+                Ok((_, change)) => progress.status(format!("{} {}", change.label(), url)),
+                Err(_) => progress.status(format!("{} {}", output::styled("failed", Style::Red), url)),
+            }
+            progress.inc();
+            result
+        });
+
+        for ((url, _, _), result) in batch.iter().zip(results) {
+            let (mut lock_pkg, change) = match result {
+                Ok(installed) => installed,
+                Err(e) => {
+                    failures.push(e);
+                    continue;
+                }
+            };
+            let dependencies = package_dependencies(&package_dir(&lock_pkg.name)?)?;
+            lock_pkg.dependencies = if dependencies.is_empty() {
+                None
+            } else {
+                Some(dependencies.iter().map(|(url, _)| url.clone()).collect())
+            };
+            queue.extend(dependencies.into_iter().map(|(dep, dep_version)| (dep, dep_version, Some(url.clone()))));
+            new_lock_packages.push(lock_pkg);
+            changes.push(change);
+        }
+    }
+    progress.lock().unwrap().finish();
+
+    let count = |change: Change| changes.iter().filter(|c| **c == change).count();
+    let summary = format!(
+        "{} installed, {} updated, {} up to date, {} failed",
+        count(Change::Installed),
+        count(Change::Updated),
+        count(Change::Unchanged),
+        failures.len()
+    );
+
+    // Every failure is reported, not just the first, and pyro.lock is left
+    // as it was
+    if !failures.is_empty() {
+        for e in &failures {
+            output::error(format!("{:#}", e));
+        }
+        output::status(&summary);
+        anyhow::bail!("{} package(s) failed to install", failures.len());
     }
     
     lockfile.package = new_lock_packages;
+    lockfile.environment = Some(toolchain::current(manifest.rust.is_some()));
     lockfile.save()?;
     
-    println!("Dependencies installed.");
+    output::success(format!("Dependencies installed: {}", summary));
+    Ok(())
+}
+
+// What installing a package did to its copy in ~/.pyro/pkg
+#[derive(Clone, Copy, PartialEq)]
+enum Change {
+    Installed,
+    Updated,
+    Unchanged,
+}
+
+impl Change {
+    fn label(self) -> String {
+        match self {
+            Change::Installed => output::styled("installed ", Style::Green),
+            Change::Updated => output::styled("updated   ", Style::Yellow),
+            Change::Unchanged => output::styled("up to date", Style::Dim),
+        }
+    }
+}
+
+// Installs one package at `version`, as locked if pyro.lock has it at that
+// version, and returns its lock entry without its dependencies
+fn install_dependency(lockfile: &LockFile, url: &str, version: &str) -> Result<(LockPackage, Change)> {
+    let dir = package_dir(url)?;
+    let before = if dir.exists() { Some(head_commit(&dir)?) } else { None };
+    let existing = lockfile.package.iter().find(|p| p.name == url);
+    
+    let lock_pkg = if let Some(pkg) = existing {
+         // If version matches, keep it. If not, we'd need to update.
+         // For simplicity, let's assume if it exists in lock, we trust it, 
+         // unless we are forcing update. 
+         // BUT, user asked for "Maintain a dependencies file and a dependencies lock file to ensure consistency"
+         // So if manifest version differs, we should update.
+         if pkg.version == version {
+             pkg.clone()
+         } else {
+             resolve_package(url, version)?
+         }
+    } else {
+        resolve_package(url, version)?
+    };
+    
+    // Install the package
+    install_package(&lock_pkg)?;
+    let change = match before {
+        None => Change::Installed,
+        Some(commit) if commit != head_commit(&dir)? => Change::Updated,
+        Some(_) => Change::Unchanged,
+    };
+    Ok((lock_pkg, change))
+}
+
+// Runs `git <subcommand> <args>`, echoing it with --verbose. Git's output is
+// kept rather than shown, as several packages may be fetched at once, and
+// what it printed to stderr becomes the error if it fails.
+fn git(dir: Option<&Path>, subcommand: &str, args: &[&str]) -> Result<()> {
+    let mut git = Command::new("git");
+    if let Some(dir) = dir {
+        git.current_dir(dir);
+    }
+    git.arg(subcommand).args(args);
+    output::command(&git);
+    let result = git.output().with_context(|| format!("Failed to run git {}", subcommand))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        anyhow::bail!("git {} failed: {}", subcommand, stderr.trim());
+    }
     Ok(())
 }
 
+// Calls `f` on each item from up to `jobs` threads, returning the results in
+// the items' order
+pub fn in_parallel<T: Sync, R: Send>(jobs: usize, items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else { break };
+                let result = f(item);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|result| result.expect("every item is processed")).collect()
+}
+
+// Clones a package into `dest`, retrying if the clone fails. A failed clone
+// is removed rather than left to look installed.
+fn clone(name: &str, git_url: &str, dest: &Path) -> Result<()> {
+    if network::offline() {
+        anyhow::bail!("{} isn't in the package cache (~/.pyro/pkg), and --offline doesn't allow downloading it", name);
+    }
+    fs::create_dir_all(dest.parent().unwrap())?;
+    network::retry(&format!("Cloning {}", git_url), || {
+        git(None, "clone", &[git_url, dest.to_str().unwrap()]).inspect_err(|_| {
+            let _ = fs::remove_dir_all(dest);
+        })
+    })
+}
+
+// Checks out `target` (a tag, branch or commit) in a package's clone,
+// fetching first if the clone doesn't have it yet
+fn checkout(name: &str, dest: &Path, target: &str) -> Result<()> {
+    if git(Some(dest), "checkout", &[target]).is_ok() {
+        return Ok(());
+    }
+    if network::offline() {
+        anyhow::bail!("The cached copy of {} doesn't have {}, and --offline doesn't allow fetching it", name, target);
+    }
+    network::retry(&format!("Fetching {}", name), || git(Some(dest), "fetch", &[]))?;
+    git(Some(dest), "checkout", &[target]).with_context(|| format!("{} has no version {}, even after fetching", name, target))
+}
+
 pub fn resolve_package(url: &str, version: &str) -> Result<LockPackage> {
-    println!("Resolving {}@{}", url, version);
+    output::status(format!("Resolving {}@{}", url, version));
     // 1. Clone to temp/cache to get checksum and latest commit for 'version'
     // This is expensive. In Go modules, there's a proxy. Here we might just clone to ~/.pyro/cache first?
     // Let's reuse existing logic: clone to ~/.pyro/pkg directly, checkout version, then checksum.
     
-    let home = std::env::var("HOME").context("Could not find HOME directory")?;
-    let pkg_root = PathBuf::from(home).join(".pyro/pkg");
-    
-    let mut dest = pkg_root.clone();
-    for part in url.split('/') {
-        dest.push(part);
-    }
-    
+    let dest = package_dir(url)?;
+    let git_url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+
     if !dest.exists() {
-         let git_url = if url.contains("://") {
-             url.to_string()
-         } else {
-             format!("https://{}", url)
-         };
-         
-         fs::create_dir_all(dest.parent().unwrap())?;
-         let status = Command::new("git")
-            .arg("clone")
-            .arg(&git_url)
-            .arg(dest.to_str().unwrap())
-            .status()
-            .context("Failed to git clone")?;
-            
-         if !status.success() {
-             anyhow::bail!("Failed to clone {}", url);
-         }
+        clone(url, &git_url, &dest)?;
     }
     
     // Checkout version
     // If version is "latest" or "HEAD", we might pull.
     // Ideally version is a semver tag or commit hash.
     // For now allow simple tags/branches.
-    
-    let status = Command::new("git")
-        .current_dir(&dest)
-        .arg("checkout")
-        .arg(version)
-        .status()?;
-        
-    if !status.success() {
-        // try fetching?
-        Command::new("git").current_dir(&dest).arg("fetch").status()?;
-        let status = Command::new("git").current_dir(&dest).arg("checkout").arg(version).status()?;
-        if !status.success() {
-             anyhow::bail!("Failed to checkout version {} for {}", version, url);
-        }
-    }
+    checkout(url, &dest, version)?;
     
     // Get Commit Hash
-    let output = Command::new("git")
-        .current_dir(&dest)
-        .arg("rev-parse")
-        .arg("HEAD")
-        .output()
-        .context("Failed to get commit hash")?;
-    let commit = String::from_utf8(output.stdout)?.trim().to_string();
+    let commit = head_commit(&dest)?;
 
     // Calculate Checksum
     let checksum = calculate_dir_checksum(&dest)?;
@@ -125,49 +255,25 @@ pub fn resolve_package(url: &str, version: &str) -> Result<LockPackage> {
     Ok(LockPackage {
         name: url.to_string(),
         version: version.to_string(),
-        source: format!("https://{}", url),
+        source: git_url,
         commit: Some(commit),
         checksum,
         dependencies: None, // We are not recursive yet in this step, but we will need to be eventually.
     })
 }
 
-fn install_package(pkg: &LockPackage) -> Result<()> {
-    let home = std::env::var("HOME").context("Could not find HOME directory")?;
-    let mut dest = PathBuf::from(home).join(".pyro/pkg");
-    for part in pkg.name.split('/') {
-        dest.push(part);
-    }
+pub fn install_package(pkg: &LockPackage) -> Result<()> {
+    let dest = package_dir(&pkg.name)?;
     
     if !dest.exists() {
-        // clone logic duplicated, refactor later
-        let git_url = &pkg.source;
-        fs::create_dir_all(dest.parent().unwrap())?;
-         let status = Command::new("git")
-            .arg("clone")
-            .arg(git_url)
-            .arg(dest.to_str().unwrap())
-            .status()?;
-        if !status.success() { anyhow::bail!("Clone failed"); }
+        clone(&pkg.name, &pkg.source, &dest)?;
     }
     
     // Ensure correct version
     // If locked, we want to be sure.
     // We already resolved it above if we called resolve. If we came from lockfile, we might need to checkout.
     let target_ref = pkg.commit.as_ref().unwrap_or(&pkg.version);
-
-     let status = Command::new("git")
-        .current_dir(&dest)
-        .arg("checkout")
-        .arg(target_ref)
-        .status()?;
-            
-    if !status.success() {
-        // Maybe fetch?
-         Command::new("git").current_dir(&dest).arg("fetch").status()?;
-         let status = Command::new("git").current_dir(&dest).arg("checkout").arg(target_ref).status()?;
-         if !status.success() { anyhow::bail!("Checkout failed for locked version {}", target_ref); }
-    }
+    checkout(&pkg.name, &dest, target_ref)?;
     
     // Verify checksum
     let current_checksum = calculate_dir_checksum(&dest)?;
@@ -178,30 +284,54 @@ fn install_package(pkg: &LockPackage) -> Result<()> {
     Ok(())
 }
 
-pub fn calculate_dir_checksum(path: &Path) -> Result<String> {
-    let mut hasher = Sha256::new();
-    
-    for entry in WalkDir::new(path).sort_by_file_name() {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            if path.file_name().unwrap() == ".git" {
-                continue; // Skip .git directory
-            }
-             continue;
-        }
-        
-        // skip .git files if walkdir doesn't skip dir children when skipping dir
-        if path.components().any(|c| c.as_os_str() == ".git") {
-            continue;
-        }
+// Where a package is installed: ~/.pyro/pkg/<name>
+pub fn package_dir(name: &str) -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("Could not find HOME directory")?;
+    let mut dest = PathBuf::from(home).join(".pyro/pkg");
+    for part in name.split('/') {
+        dest.push(part);
+    }
+    Ok(dest)
+}
+
+// The commit checked out in a package's directory
+pub fn head_commit(dir: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .arg("rev-parse")
+        .arg("HEAD")
+        .output()
+        .context("Failed to get commit hash")?;
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
 
-        let content = fs::read(path)?;
-        hasher.update(&content);
-        hasher.update(path.to_string_lossy().as_bytes()); // Include filename in hash
+// The dependencies a package's own pyro.mod lists, if it has one
+fn package_dependencies(dir: &Path) -> Result<Vec<(String, String)>> {
+    let path = dir.join("pyro.mod");
+    if !path.exists() {
+        return Ok(Vec::new());
     }
-    
-    let result = hasher.finalize();
-    Ok(hex::encode(result))
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: Manifest = toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut dependencies: Vec<_> = manifest.dependencies.into_iter().collect();
+    dependencies.sort();
+    Ok(dependencies)
+}
+
+// Puts an installed package back the way pyro.lock has it, discarding local
+// changes and files git doesn't track, or installs it again if it's gone
+pub fn restore_package(pkg: &LockPackage) -> Result<()> {
+    let dest = package_dir(&pkg.name)?;
+    if dest.exists() {
+        let target_ref = pkg.commit.as_ref().unwrap_or(&pkg.version);
+        git(Some(&dest), "reset", &["--hard", target_ref])
+            .with_context(|| format!("Failed to reset {} to {}", pkg.name, target_ref))?;
+        git(Some(&dest), "clean", &["-fdx"]).with_context(|| format!("Failed to remove untracked files from {}", pkg.name))?;
+    }
+    install_package(pkg)
+}
+
+// The checksum recorded in pyro.lock, the same as `hash.dir` gives scripts
+pub fn calculate_dir_checksum(path: &Path) -> Result<String> {
+    dir_checksum(path, &[".git".to_string()]).with_context(|| format!("Failed to checksum {}", path.display()))
 }
@@ -5,3 +5,13 @@ pub mod build;
 pub mod shell;
 pub mod run;
 pub mod externs;
+pub mod parse;
+pub mod compile;
+pub mod inspect;
+pub mod test;
+pub mod check;
+pub mod script;
+pub mod hook;
+pub mod verify;
+pub mod outdated;
+pub mod sync;
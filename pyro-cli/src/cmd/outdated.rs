@@ -0,0 +1,154 @@
+use crate::cmd::installer::{in_parallel, package_dir};
+use crate::manifest::{LockFile, LockPackage};
+use crate::network;
+use crate::output::{self, Style};
+use anyhow::{Context, Result};
+use pyro_core::stdlib::semver::{max_satisfying, parse_requirement, parse_version};
+use std::process::Command;
+
+// How many repositories are asked for their tags at once
+const JOBS: usize = 8;
+
+// A package with a newer release than the one locked
+struct Outdated {
+    name: String,
+    current: String,
+    // The newest release semver says can replace the locked one, if newer
+    compatible: Option<String>,
+    // The newest release of all, if newer
+    latest: Option<String>,
+}
+
+// The tags of a package's repository. Offline, those of its cached clone,
+// which may be behind.
+fn tags(pkg: &LockPackage) -> Result<Vec<String>> {
+    let list = || {
+        let mut git = Command::new("git");
+        if network::offline() {
+            let dir = package_dir(&pkg.name)?;
+            if !dir.exists() {
+                anyhow::bail!("it isn't in the package cache (~/.pyro/pkg), and --offline doesn't allow asking its repository");
+            }
+            git.current_dir(dir).arg("tag");
+        } else {
+            git.args(["ls-remote", "--tags", &pkg.source]);
+        }
+        output::command(&git);
+        let result = git.output().context("Failed to run git")?;
+        if !result.status.success() {
+            anyhow::bail!("git failed: {}", String::from_utf8_lossy(&result.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&result.stdout).into_owned())
+    };
+    let listing = if network::offline() {
+        list()?
+    } else {
+        network::retry(&format!("Listing the tags of {}", pkg.name), list)?
+    };
+
+    // ls-remote prints "<commit>\trefs/tags/<tag>", and annotated tags again
+    // with ^{} after them; git tag prints just the names
+    let mut tags: Vec<String> = listing
+        .lines()
+        .filter_map(|line| line.rsplit('\t').next())
+        .map(|tag| tag.strip_prefix("refs/tags/").unwrap_or(tag).trim_end_matches("^{}").to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+// Compares a locked package against its repository's release tags. Tags that
+// aren't versions, and pre-releases, are ignored.
+fn check(pkg: &LockPackage) -> Result<Option<Outdated>> {
+    let tags = tags(pkg)?;
+    let current = parse_version(&pkg.version).ok();
+    let newer = |tag: Option<&String>| {
+        tag.filter(|tag| match (&current, parse_version(tag)) {
+            (Some(current), Ok(version)) => version > *current,
+            // Locked to a branch or commit, any release is worth a mention
+            _ => true,
+        })
+        .cloned()
+    };
+
+    let latest = newer(max_satisfying(&tags, &parse_requirement("*").map_err(anyhow::Error::msg)?));
+    let compatible = match &current {
+        Some(current) => newer(max_satisfying(&tags, &parse_requirement(&format!("^{}", current)).map_err(anyhow::Error::msg)?)),
+        None => None,
+    };
+    if latest.is_none() && compatible.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Outdated { name: pkg.name.clone(), current: pkg.version.clone(), compatible, latest }))
+}
+
+// What to run to upgrade, preferring a release semver says is compatible
+fn hint(outdated: &Outdated) -> String {
+    match (&outdated.compatible, &outdated.latest) {
+        (Some(compatible), _) => format!("pyro get {}@{}", outdated.name, compatible),
+        (None, Some(latest)) if parse_version(&outdated.current).is_ok() => {
+            format!("breaking: pyro get {}@{}", outdated.name, latest)
+        }
+        (None, Some(latest)) => format!("not a release: pyro get {}@{}", outdated.name, latest),
+        (None, None) => String::new(),
+    }
+}
+
+// Lists packages in pyro.lock with newer release tags in their repositories:
+// the newest compatible with the locked version by semver, the newest of
+// all, and the command to upgrade. Fails if any repository can't be read.
+pub fn r#impl() -> Result<()> {
+    let lockfile = LockFile::load()?;
+    if lockfile.package.is_empty() {
+        output::status("pyro.lock has no packages");
+        return Ok(());
+    }
+
+    output::status(format!("Checking {} package(s) for newer versions", lockfile.package.len()));
+    let results = in_parallel(JOBS, &lockfile.package, |pkg| {
+        check(pkg).with_context(|| format!("Could not check {} for newer versions", pkg.name))
+    });
+    let mut outdated = Vec::new();
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok(Some(pkg)) => outdated.push(pkg),
+            Ok(None) => {}
+            Err(e) => {
+                output::error(format!("{:#}", e));
+                failed += 1;
+            }
+        }
+    }
+
+    if outdated.is_empty() {
+        output::success("All packages are up to date");
+    } else {
+        let header = ["Package", "Current", "Compatible", "Latest", "Upgrade"];
+        let rows: Vec<[String; 5]> = outdated
+            .iter()
+            .map(|pkg| {
+                let or_dash = |tag: &Option<String>| tag.clone().unwrap_or_else(|| "-".to_string());
+                [pkg.name.clone(), pkg.current.clone(), or_dash(&pkg.compatible), or_dash(&pkg.latest), hint(pkg)]
+            })
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| rows.iter().map(|row| row[column].len()).chain([header[column].len()]).max().unwrap_or(0))
+            .collect();
+        let line = |cells: &[String]| {
+            let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        println!("{}", output::styled(line(&header.map(String::from)), Style::Bold));
+        for row in &rows {
+            println!("{}", line(row));
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} package(s) could not be checked", failed);
+    }
+    Ok(())
+}
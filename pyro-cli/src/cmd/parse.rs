@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser as PyroParser;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::ParseFormat;
+
+pub fn r#impl(file: PathBuf, format: ParseFormat) -> Result<()> {
+    let content = fs::read_to_string(&file)
+        .with_context(|| format!("Could not read file {:?}", file))?;
+
+    let tokens = Lexer::new(&content).tokenize();
+    let program = PyroParser::new(&tokens)
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Parse error in {:?}: {}", file, e))?;
+
+    match format {
+        ParseFormat::Json => {
+            let json = serde_json::to_string_pretty(&program).context("Failed to serialize AST")?;
+            println!("{}", json);
+        }
+        ParseFormat::Source => {
+            print!("{}", pyro_core::ast::to_source(&program));
+        }
+        ParseFormat::Debug => {
+            println!("{:#?}", program);
+        }
+    }
+
+    Ok(())
+}
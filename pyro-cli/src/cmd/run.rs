@@ -1,73 +1,307 @@
+use crate::coverage;
+use crate::crash;
+use crate::diagnostics::{self, RuntimeFailure};
 use crate::manifest::Manifest;
+use crate::network;
+use crate::output::{self, Style};
+use crate::toolchain;
 use crate::util;
 use anyhow::{Context, Result};
-use pyro_core::interpreter::Interpreter;
+use pyro_core::ast::Stmt;
+use pyro_core::interpreter::{Flow, Interpreter, RuntimeError, Value, MAIN_MODULE, MODULE_NAME};
+use pyro_core::permissions::Permissions;
+use pyro_core::prelude::Prelude;
+use pyro_core::stdlib::runtime::Requests;
 use sha2::{Sha256, Digest};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn r#impl(file: PathBuf) -> Result<()> {
-    // Check for pyro.mod and Rust dependencies
+// The optional parts of pyro-core, and whether this pyro was built with them
+const CORE_FEATURES: &[(&str, bool)] = &[
+    ("python-interop", cfg!(feature = "python-interop")),
+    ("sftp", cfg!(feature = "sftp")),
+    ("email", cfg!(feature = "email")),
+    ("zstd", cfg!(feature = "zstd")),
+    ("fs-watch", cfg!(feature = "fs-watch")),
+    ("cron", cfg!(feature = "cron")),
+    ("plugins", cfg!(feature = "plugins")),
+];
+
+// Flags of `pyro run` that only apply to interpreted programs
+#[derive(Default)]
+pub struct RunOptions {
+    pub watch: bool,
+    pub inspect: Option<String>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub permissions: Option<Permissions>,
+    pub json_errors: bool,
+    pub post_mortem: bool,
+    pub coverage: Option<PathBuf>,
+}
+
+// Errors the program raised are shown with their traceback, as in the shell,
+// or as JSON with --json-errors
+pub fn r#impl(file: PathBuf, options: RunOptions) -> Result<()> {
+    let json_errors = options.json_errors;
+    let result = run(file.clone(), options);
+    let Err(e) = &result else {
+        return result;
+    };
+    if json_errors {
+        diagnostics::emit(&diagnostics::from_error(&file, e));
+    } else if let Some(failure) = e.downcast_ref::<RuntimeFailure>() {
+        eprintln!("{}", failure);
+    } else {
+        return result;
+    }
+    pyro_core::stdlib::fs::remove_temp_paths();
+    std::process::exit(1);
+}
+
+fn run(file: PathBuf, options: RunOptions) -> Result<()> {
     // Check for pyro.mod and Rust dependencies
-    let manifest = Manifest::resolve_from(file.parent().unwrap_or(Path::new(".")))
-        .or_else(|_| Manifest::load())
+    let located = Manifest::locate_from(file.parent().unwrap_or(Path::new(".")))
+        .or_else(|_| Manifest::locate_from(std::env::current_dir()?))
         .ok();
-    let has_native_deps = manifest.as_ref()
+    let manifest = located.as_ref().map(|(manifest, _)| manifest.clone());
+    // The [rust] dependencies are for the program, not its build.pyro, which
+    // the build hook runs sandboxed and so must be interpreted
+    let is_build_script = located.as_ref().is_some_and(|(_, root)| {
+        std::path::absolute(&file).ok() == std::path::absolute(root.join("build.pyro")).ok()
+    });
+    let has_manifest_deps = !is_build_script && manifest.as_ref()
         .map(|m| m.rust.is_some())
         .unwrap_or(false);
 
-    if has_native_deps {
-        if let Some(m) = manifest {
-            // Generate externs relative to pyro.mod
-            let parent = file.parent().unwrap_or(Path::new("."));
-            let search_path = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
-
-            if let Ok(_manifest_path) = std::fs::canonicalize(search_path) {
-                 // Ideally manifest should tell us its root, but we can infer from where we found it or just use current dir if we loaded it from there.
-                 // Manifest::load uses current dir or recursive parent. Manifest::resolve_from uses file parent. 
-                 // Let's re-resolve correctly to find root.
-                 let mut current = search_path.to_path_buf();
-                 loop {
-                     if current.join("pyro.mod").exists() {
-                         let externs_dir = current.join(".externs");
-                         if let Err(e) = crate::cmd::externs::generate_externs(&externs_dir) {
-                             eprintln!("Warning: Failed to generate externs: {}", e);
-                         }
-                         break;
+    if has_manifest_deps {
+        // Generate externs relative to pyro.mod
+        let parent = file.parent().unwrap_or(Path::new("."));
+        let search_path = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+
+        if let Ok(_manifest_path) = std::fs::canonicalize(search_path) {
+             // Ideally manifest should tell us its root, but we can infer from where we found it or just use current dir if we loaded it from there.
+             // Manifest::load uses current dir or recursive parent. Manifest::resolve_from uses file parent. 
+             // Let's re-resolve correctly to find root.
+             let mut current = search_path.to_path_buf();
+             loop {
+                 if current.join("pyro.mod").exists() {
+                     let externs_dir = current.join(".externs");
+                     if let Err(e) = crate::cmd::externs::generate_externs(&externs_dir) {
+                         output::warn(format!("Failed to generate externs: {}", e));
                      }
-                     if !current.pop() { break; }
+                     break;
                  }
-            }
-            run_with_rust_deps(file, m)
-        } else {
-            // Should be unreachable due to check above, but fallback
-            run_interpreter(file)
+                 if !current.pop() { break; }
+             }
+        }
+    }
+
+    // Parsed once, after the externs above exist, for both ways of running
+    if options.coverage.is_some() {
+        coverage::enable();
+    }
+    let mut statements = Vec::new();
+    let mut loaded = HashSet::new();
+    load(&file, &mut loaded, &mut statements)?;
+
+    // `import rust "crate"` needs a native build even without a [rust] section
+    let rust_imports = find_rust_imports(&statements)?;
+
+    if let Some((_, root)) = &located {
+        toolchain::warn_if_changed(root, has_manifest_deps || !rust_imports.is_empty());
+    }
+
+    if has_manifest_deps || !rust_imports.is_empty() {
+        // Running unrestricted when restrictions were asked for would be unsafe
+        if options.permissions.is_some() {
+            anyhow::bail!("--sandbox and --allow-* are not supported for programs with native dependencies");
+        }
+        let unsupported = [
+            ("--watch", options.watch),
+            ("--inspect", options.inspect.is_some()),
+            ("--record", options.record.is_some()),
+            ("--replay", options.replay.is_some()),
+            ("--coverage-data", options.coverage.is_some()),
+            ("--post-mortem", options.post_mortem),
+        ];
+        for (flag, _) in unsupported.iter().filter(|(_, used)| *used) {
+            output::warn(format!("{} is not supported for programs with native dependencies", flag));
         }
+        run_with_rust_deps(file, manifest, statements, rust_imports)
     } else {
-         // Also check for extern generation even if no rust deps? No, only if pyro.mod exists
-         // But wait, if has_native_deps is false, maybe we still want to generate if rust section exists but is empty? 
-         // manifest.rust.is_some() checks this.
-         run_interpreter(file)
+         run_interpreter(file, statements, loaded, options)
     }
 }
 
-fn run_interpreter(file: PathBuf) -> Result<()> {
+// Inlines the program and its imports and checks it. Panics in the
+// interpreter itself get a crash report instead of just a backtrace.
+fn load(file: &Path, loaded: &mut HashSet<PathBuf>, statements: &mut Vec<Stmt>) -> Result<()> {
+    crash::install_hook();
+    let processed = panic::catch_unwind(AssertUnwindSafe(|| {
+        util::process_file(file.to_path_buf(), loaded, statements)
+    }));
+    match processed {
+        Ok(result) => result,
+        Err(_) => {
+            crash::report(file, None);
+            anyhow::bail!("Interpreter crashed");
+        }
+    }
+}
+
+// Paths imported with `import rust "<path>"` anywhere in the program, each a
+// crate or a module in one, e.g. "serde_json::value"
+fn find_rust_imports(statements: &[Stmt]) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for stmt in statements {
+        if let Stmt::Import(import) = stmt {
+            if let Some(path) = import.strip_prefix("rust.") {
+                if path.split("::").any(|segment| segment.is_empty()) {
+                    anyhow::bail!("import rust \"{}\": expected a crate or a path such as \"crate::module\"", path);
+                }
+                if !paths.iter().any(|p| p == path) {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
+// The crate of an `import rust` path
+fn rust_crate(path: &str) -> &str {
+    path.split("::").next().unwrap_or(path)
+}
+
+// `pyro run --dump-imports`: every file load_file loads for the script,
+// the script itself included, as a sorted JSON list of absolute paths
+pub fn dump_imports(file: PathBuf) -> Result<()> {
     let mut statements = Vec::new();
     let mut loaded = HashSet::new();
-    
-    util::process_file(file.clone(), &mut loaded, &mut statements)?;
+    util::load_file(file, &mut loaded, &mut statements)?;
+
+    let mut files: Vec<String> = loaded.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+    files.sort();
+    println!("{}", serde_json::to_string_pretty(&files)?);
+    Ok(())
+}
+
+fn run_interpreter(file: PathBuf, statements: Vec<Stmt>, loaded: HashSet<PathBuf>, options: RunOptions) -> Result<()> {
+    let mut interpreter = Interpreter::with_prelude(&Prelude::full());
+    if options.watch {
+        watch_files(interpreter.requests(), loaded.into_iter().collect());
+    }
+    if let Some(addr) = &options.inspect {
+        crate::cmd::inspect::serve(addr, interpreter.requests())?;
+    }
+    if let Some(log) = &options.replay {
+        let log = fs::read_to_string(log).with_context(|| format!("Could not read replay log {:?}", log))?;
+        pyro_core::replay::start_replay(&log).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    if options.record.is_some() {
+        pyro_core::replay::start_recording();
+    }
+    interpreter.set_permissions(options.permissions.clone());
+    // std.testing snapshots live next to the script
+    if std::env::var_os("PYRO_SNAPSHOT_DIR").is_none() {
+        let dir = file.parent().unwrap_or(Path::new("."));
+        std::env::set_var("PYRO_SNAPSHOT_DIR", dir.join("__snapshots__"));
+    }
+
+    let result = execute(&file, interpreter, statements, options.post_mortem);
+
+    // Keep the log even when the run failed, as that is usually the run to replay
+    if let Some(log) = &options.record {
+        fs::write(log, pyro_core::replay::finish_recording())
+            .with_context(|| format!("Could not write record log {:?}", log))?;
+    }
+    if let Some(data) = &options.coverage {
+        coverage::save(data)?;
+    }
+    pyro_core::replay::stop();
+    result
+}
 
-    let mut interpreter = Interpreter::new();
-    match interpreter.run(statements) {
-        Ok(_) => Ok(()), 
-        Err(e) => Err(anyhow::anyhow!("Runtime error: {:?}", e)),
+fn execute(file: &Path, mut interpreter: Interpreter, statements: Vec<Stmt>, post_mortem: bool) -> Result<()> {
+    if coverage::enabled() {
+        coverage::install(&mut interpreter);
+    }
+    // One top-level statement at a time, so a crash report can name it
+    for stmt in statements {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| interpreter.run(vec![stmt.clone()])));
+        match result {
+            Ok(Ok(Flow::None)) => {}
+            Ok(Ok(_)) => break,
+            Ok(Err(e)) => {
+                let err = interpreter.native_error(e.clone()).into_value();
+                let report = crate::cmd::shell::error_report(&err, |summary| output::styled_stderr(summary, Style::Red));
+                let mut failure = RuntimeFailure::new(&e, report);
+                if let Some(note) = import_note(file, &interpreter, &e) {
+                    failure = failure.with_note(note);
+                }
+                if post_mortem {
+                    crate::cmd::shell::post_mortem(interpreter, e)?;
+                }
+                return Err(failure.into());
+            }
+            Err(_) => {
+                crash::report(file, Some(&stmt));
+                anyhow::bail!("Interpreter crashed");
+            }
+        }
     }
+    Ok(())
+}
+
+// Where an uncaught error in an imported file came from and the imports that
+// led there. Imports are inlined into one program, so this is the file that
+// defines the innermost function the error unwound through or, for an error
+// in top-level code, the module running at the time.
+fn import_note(file: &Path, interpreter: &Interpreter, error: &RuntimeError) -> Option<String> {
+    let module = interpreter.global(MODULE_NAME);
+    let origin = match error.trace.first() {
+        // Methods are traced by their bare name, which no file defines at the top level
+        Some(frame) if interpreter.global(&frame.function).is_some() => util::Origin::Function(&frame.function),
+        Some(_) => return None,
+        None => match module.as_ref()? {
+            Value::String(name) if name.as_str() != MAIN_MODULE => util::Origin::Module(name.as_str()),
+            _ => return None,
+        },
+    };
+    let (importers, defined_in) = util::find_in_imports(file, &origin)?;
+    let chain = util::import_chain(&importers, &defined_in);
+    Some(match origin {
+        util::Origin::Function(name) => {
+            format!("'{}' is defined in {}, import chain: {}", name, util::display_path(&defined_in), chain)
+        }
+        util::Origin::Module(_) => format!("raised in an imported file, import chain: {}", chain),
+    })
+}
+
+// Polls the given files and queues a reload for each one that changes; the
+// interpreter picks reloads up between statements.
+fn watch_files(requests: Requests, files: Vec<PathBuf>) {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut mtimes: HashMap<PathBuf, _> = files.iter().map(|f| (f.clone(), modified(f))).collect();
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        for (path, last) in mtimes.iter_mut() {
+            let current = modified(path);
+            if current != *last {
+                *last = current;
+                requests.reload(&path.to_string_lossy());
+            }
+        }
+    });
 }
 
-fn run_with_rust_deps(file: PathBuf, manifest: Manifest) -> Result<()> {
-    println!("Found native dependencies. Building custom runner...");
+fn run_with_rust_deps(file: PathBuf, manifest: Option<Manifest>, mut statements: Vec<Stmt>, rust_imports: Vec<String>) -> Result<()> {
+    output::status("Found native dependencies. Building custom runner...");
 
     // 1. Determine Build Directory (~/.pyro/rustpkg/<hash>)
     let home = std::env::var("HOME").context("Could not find HOME directory")?;
@@ -86,12 +320,41 @@ fn run_with_rust_deps(file: PathBuf, manifest: Manifest) -> Result<()> {
     }
 
 
-    // 2. Parse User Code to find Externs (recursively)
-    let mut statements = Vec::new();
-    let mut loaded = std::collections::HashSet::new();
-    
-    // We utilize the updated process_file which handles .externs resolution
-    crate::util::process_file(file.clone(), &mut loaded, &mut statements)?;
+    // 2. The program's statements, loaded with .externs resolved, give the
+    // externs to bind
+
+    // Crates imported with `import rust` are added with any version unless
+    // pyro.mod pins them.
+    let mut rust_deps = manifest.as_ref()
+        .and_then(|m| m.rust.as_ref())
+        .map(|r| r.dependencies.clone())
+        .unwrap_or_default();
+    for path in &rust_imports {
+        rust_deps.entry(rust_crate(path).to_string()).or_insert_with(|| "*".to_string());
+    }
+
+    // Externs for `import rust` paths are generated in memory rather than
+    // through .externs files. Each function is recorded with the import it
+    // belongs to and its name in that module.
+    let mut module_functions: HashMap<String, (String, String)> = HashMap::new();
+    if !rust_imports.is_empty() {
+        let generated = crate::cmd::externs::rust_import_externs(&rust_deps, &rust_imports)?;
+        for (index, path) in rust_imports.iter().enumerate() {
+            let source = generated[path].join("\n");
+            let tokens = pyro_core::lexer::Lexer::new(&source).tokenize();
+            let program = pyro_core::parser::Parser::new(&tokens).parse()
+                .map_err(|e| anyhow::anyhow!("Parse error in generated externs for {}: {}", path, e))?;
+            let prefix = crate::cmd::externs::rust_import_prefix(index);
+            for stmt in &program.statements {
+                if let Stmt::Extern { func_name, .. } = stmt {
+                    if let Some(short) = func_name.strip_prefix(&prefix) {
+                        module_functions.insert(func_name.clone(), (path.clone(), short.to_string()));
+                    }
+                }
+            }
+            statements.extend(program.statements);
+        }
+    }
 
     let mut extern_funcs = Vec::new();
     for stmt in statements {
@@ -109,18 +372,22 @@ fn run_with_rust_deps(file: PathBuf, manifest: Manifest) -> Result<()> {
         cw_dir.join("../../pyro-core"),
     ];
 
-    let mut pyro_core_dep = r#"pyro-core = "0.1.0""#.to_string();
+    // The runner gets the parts of the standard library this pyro has
+    let features: Vec<String> = CORE_FEATURES.iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| format!("{:?}", feature))
+        .collect();
+    let features = features.join(", ");
+    let mut pyro_core_dep = format!(r#"pyro-core = {{ version = "0.1.0", features = [{}] }}"#, features);
     for path in search_paths {
         if path.exists() {
-             pyro_core_dep = format!("pyro-core = {{ path = {:?} }}", path);
+             pyro_core_dep = format!("pyro-core = {{ path = {:?}, features = [{}] }}", path, features);
              break;
         }
     }
 
-    if let Some(rust_config) = &manifest.rust {
-        for (name, version) in &rust_config.dependencies {
-            dependencies.push_str(&format!("{} = \"{}\"\n", name, version));
-        }
+    for (name, version) in &rust_deps {
+        dependencies.push_str(&format!("{} = \"{}\"\n", name, version));
     }
 
     let cargo_toml = format!(r#"[package]
@@ -149,15 +416,16 @@ anyhow = "1.0"
 
     let mut auto_wrappers = String::new();
     let mut auto_registration = String::new();
+    let mut module_entries: HashMap<String, String> = HashMap::new();
 
-    auto_wrappers.push_str("use pyro_core::interpreter::Value;\n");
+    auto_wrappers.push_str("use pyro_core::interpreter::{RuntimeError, Value};\n");
     auto_wrappers.push_str("use anyhow::Result;\n\n");
 
     for (name, params, return_type, rust_path) in extern_funcs {
         if let Some(rust_func_path) = rust_path {
             // Generate auto-wrapper
             let wrapper_name = format!("wrapper_{}", name);
-            auto_wrappers.push_str(&format!("pub fn {}(args: Vec<Value>) -> Result<Value, Value> {{\n", wrapper_name));
+            auto_wrappers.push_str(&format!("pub fn {}(args: Vec<Value>) -> Result<Value, RuntimeError> {{\n", wrapper_name));
             
             // Argument parsing
             if !params.is_empty() {
@@ -185,19 +453,29 @@ anyhow = "1.0"
                  // For now, let's assume direct cast via if check
                  match param_type {
                      pyro_core::ast::Type::Int => {
-                         auto_wrappers.push_str(&format!("    let {} = if let Value::Int(i) = {} {{ i }} else {{ return Err(Value::String(\"Expected int for argument '{}'\".to_string().into())); }};\n", arg_var, arg_var, param_name));
+                         auto_wrappers.push_str(&format!("    let {} = if let Value::Int(i) = {} {{ i }} else {{ return Err(Value::String(\"Expected int for argument '{}'\".to_string().into()).into()); }};\n", arg_var, arg_var, param_name));
                          rust_args.push(arg_var);
                      },
                      pyro_core::ast::Type::Float => {
-                         auto_wrappers.push_str(&format!("    let {} = if let Value::Float(f) = {} {{ f }} else {{ return Err(Value::String(\"Expected float for argument '{}'\".to_string().into())); }};\n", arg_var, arg_var, param_name));
+                         auto_wrappers.push_str(&format!("    let {} = if let Value::Float(f) = {} {{ f }} else {{ return Err(Value::String(\"Expected float for argument '{}'\".to_string().into()).into()); }};\n", arg_var, arg_var, param_name));
                          rust_args.push(arg_var);
                      },
                      pyro_core::ast::Type::Bool => {
-                         auto_wrappers.push_str(&format!("    let {} = if let Value::Bool(b) = {} {{ b }} else {{ return Err(Value::String(\"Expected bool for argument '{}'\".to_string().into())); }};\n", arg_var, arg_var, param_name));
+                         auto_wrappers.push_str(&format!("    let {} = if let Value::Bool(b) = {} {{ b }} else {{ return Err(Value::String(\"Expected bool for argument '{}'\".to_string().into()).into()); }};\n", arg_var, arg_var, param_name));
                          rust_args.push(arg_var);
                      },
                      pyro_core::ast::Type::String => {
-                         auto_wrappers.push_str(&format!("    let {} = if let Value::String(s) = {} {{ s.to_string() }} else {{ return Err(Value::String(\"Expected string for argument '{}'\".to_string().into())); }};\n", arg_var, arg_var, param_name));
+                         auto_wrappers.push_str(&format!("    let {} = if let Value::String(s) = {} {{ s.to_string() }} else {{ return Err(Value::String(\"Expected string for argument '{}'\".to_string().into()).into()); }};\n", arg_var, arg_var, param_name));
+                         rust_args.push(arg_var);
+                     },
+                     pyro_core::ast::Type::Float32 => {
+                         auto_wrappers.push_str(&format!("    let {} = if let Value::Float(f) = {} {{ f as f32 }} else {{ return Err(Value::String(\"Expected float for argument '{}'\".to_string().into()).into()); }};\n", arg_var, arg_var, param_name));
+                         rust_args.push(arg_var);
+                     },
+                     // Sized ints are range checked rather than truncated
+                     sized if sized.rust_numeric().is_some() => {
+                         let rust_type = sized.rust_numeric().unwrap_or_default();
+                         auto_wrappers.push_str(&format!("    let {0} = if let Value::Int(i) = {0} {{ {1}::try_from(i).map_err(|_| Value::String(format!(\"Argument '{2}' does not fit in {1}: {{}}\", i).into()))? }} else {{ return Err(Value::String(\"Expected int for argument '{2}'\".to_string().into()).into()); }};\n", arg_var, rust_type, param_name));
                          rust_args.push(arg_var);
                      },
                      _ => {
@@ -211,9 +489,8 @@ anyhow = "1.0"
             let args_str = rust_args.join(", ");
             
             // Determine Rust return type for annotation
-            let rust_ret_type = match return_type {
-                pyro_core::ast::Type::Int => "i64",
-                pyro_core::ast::Type::Float => "f64",
+            let rust_ret_type = match &return_type {
+                numeric if numeric.rust_numeric().is_some() => numeric.rust_numeric().unwrap_or_default(),
                 pyro_core::ast::Type::Bool => "bool",
                 pyro_core::ast::Type::String => "String",
                 pyro_core::ast::Type::Void => "()",
@@ -224,9 +501,13 @@ anyhow = "1.0"
 
              match return_type {
                  pyro_core::ast::Type::Int => auto_wrappers.push_str("    Ok(Value::Int(result))\n"),
-                 pyro_core::ast::Type::Float => {
+                 pyro_core::ast::Type::Float | pyro_core::ast::Type::Float32 => {
                       auto_wrappers.push_str("    Ok(Value::Float(result as f64))\n")
                  },
+                 pyro_core::ast::Type::UInt64 => auto_wrappers.push_str(
+                     "    i64::try_from(result).map(Value::Int).map_err(|_| Value::String(format!(\"Result does not fit in int: {}\", result).into()).into())\n",
+                 ),
+                 sized if sized.rust_numeric().is_some() => auto_wrappers.push_str("    Ok(Value::Int(result as i64))\n"),
                  pyro_core::ast::Type::Bool => auto_wrappers.push_str("    Ok(Value::Bool(result))\n"),
                  pyro_core::ast::Type::String => auto_wrappers.push_str("    Ok(Value::String(result.into()))\n"), // Fixed: Wrap in Arc (via From/Into)
                  pyro_core::ast::Type::Void => auto_wrappers.push_str("    Ok(Value::Bool(true)) // Void -> True\n"),
@@ -241,6 +522,14 @@ anyhow = "1.0"
                 name, wrapper_name
             ));
 
+            // Functions of `import rust` paths also go into their module
+            if let Some((path, short)) = module_functions.get(&name) {
+                module_entries.entry(path.clone()).or_default().push_str(&format!(
+                    "        m.insert(\"{0}\".to_string(), Value::NativeFunction {{ name: \"{0}\".to_string(), func: NativeClosure(Arc::new(native_auto::{1})) }});\n",
+                    short, wrapper_name
+                ));
+            }
+
         } else if has_native {
             // Old behavior: assume function is in `native::`
             auto_registration.push_str(&format!(
@@ -252,6 +541,14 @@ anyhow = "1.0"
     
     fs::write(build_dir.join("src/native_auto.rs"), auto_wrappers)?;
 
+    for path in &rust_imports {
+        let entries = module_entries.remove(path).unwrap_or_default();
+        auto_registration.push_str(&format!(
+            "    {{\n        let mut m = std::collections::HashMap::new();\n{}        interpreter.register_native_module(\"rust.{}\", Value::NativeModule(Arc::new(m)));\n    }}\n",
+            entries, path
+        ));
+    }
+
     let native_mod = if has_native { "mod native;" } else { "" };
     
     // Combining manual `native::register` call if exists with auto-registration is tricky.
@@ -267,23 +564,24 @@ anyhow = "1.0"
     };
 
     let abs_file = fs::canonicalize(&file).unwrap_or(file.clone());
+    let banner = if output::quiet() { "" } else { r#"println!("Running with custom native support...");"# };
 
     let main_rs = format!(r#"
-use pyro_core::interpreter::Interpreter;
-use pyro_core::stdlib;
+use pyro_core::interpreter::{{Interpreter, NativeClosure, Value}};
+use pyro_core::prelude::Prelude;
+use std::sync::Arc;
 
 {}
 mod native_auto;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {{
-    let mut interpreter = Interpreter::new();
-    stdlib::register_std_libs(&mut interpreter);
+    let mut interpreter = Interpreter::with_prelude(&Prelude::full());
     
     // Register native modules
     {}
     
-    println!("Running with custom native support...");
+    {}
 
     let path = std::path::PathBuf::from({:?});
     let content = std::fs::read_to_string(&path)?;
@@ -292,22 +590,27 @@ async fn main() -> anyhow::Result<()> {{
     let program = pyro_core::parser::Parser::new(&tokens).parse()
         .map_err(|e| anyhow::anyhow!("Parser error: {{:?}}", e))?;
     
-    interpreter.run(program.statements).map_err(|e| anyhow::anyhow!("Runtime error: {{:?}}", e))?;
+    if let Err(e) = interpreter.run(program.statements) {{
+        eprintln!("{{}}: {{}}", path.display(), pyro_core::diagnostics::Diagnostic::from(&e));
+        std::process::exit(1);
+    }}
 
     Ok(())
 }}
-"#, native_mod, native_reg, abs_file);
+"#, native_mod, native_reg, banner, abs_file);
 
     fs::write(build_dir.join("src/main.rs"), main_rs)?;
 
     // 5. Run cargo run
-    println!("Compiling and running...");
-    let status = Command::new("cargo")
-        .arg("run")
-        .arg("--release")
-        .current_dir(&build_dir)
-        .status()
-        .context("Failed to run cargo run")?;
+    output::status("Compiling and running...");
+    let mut cargo = Command::new("cargo");
+    cargo.arg("run").arg("--release").current_dir(&build_dir);
+    network::cargo_args(&mut cargo);
+    if output::quiet() {
+        cargo.arg("--quiet");
+    }
+    output::command(&cargo);
+    let status = cargo.status().context("Failed to run cargo run")?;
 
     if !status.success() {
         anyhow::bail!("Execution failed");
@@ -0,0 +1,71 @@
+use crate::manifest::Manifest;
+use crate::output::{self, Style};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+// The shell a script's command line runs in. Extra arguments are appended
+// as separate words, so they reach the command unchanged.
+#[cfg(unix)]
+fn shell(script: &str, args: &[String]) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(format!("{} \"$@\"", script)).arg("sh").args(args);
+    command
+}
+
+#[cfg(windows)]
+fn shell(script: &str, args: &[String]) -> Command {
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(script).args(args);
+    command
+}
+
+fn list(manifest: &Manifest) {
+    if manifest.scripts.is_empty() {
+        output::status("No scripts in pyro.mod");
+        return;
+    }
+    for (name, script) in &manifest.scripts {
+        println!("{}  {}", output::styled(name, Style::Bold), script);
+    }
+}
+
+// Runs the `[scripts]` entry `name` from the project root, with the package's
+// name, version and root in PYRO_PACKAGE_* variables and the running pyro's
+// directory first on PATH. Exits with the script's status if it fails.
+// Without a name, lists the scripts.
+pub fn r#impl(name: Option<String>, args: Vec<String>) -> Result<()> {
+    let (manifest, root) = Manifest::locate_from(std::env::current_dir()?)
+        .context("Could not find pyro.mod. Run 'pyro mod init' first.")?;
+    let Some(name) = name else {
+        list(&manifest);
+        return Ok(());
+    };
+    let Some(script) = manifest.scripts.get(&name) else {
+        let known = manifest.scripts.keys().cloned().collect::<Vec<_>>().join(", ");
+        if known.is_empty() {
+            anyhow::bail!("No script named '{}': pyro.mod has no [scripts]", name);
+        }
+        anyhow::bail!("No script named '{}' in pyro.mod; scripts: {}", name, known);
+    };
+
+    let mut command = shell(script, &args);
+    command
+        .current_dir(&root)
+        .env("PYRO_PACKAGE_NAME", &manifest.package.name)
+        .env("PYRO_PACKAGE_VERSION", &manifest.package.version)
+        .env("PYRO_PACKAGE_ROOT", &root);
+    if let Some(dir) = std::env::current_exe().ok().as_deref().and_then(Path::parent) {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(dir.to_path_buf()).chain(std::env::split_paths(&path));
+        command.env("PATH", std::env::join_paths(paths).context("Could not set PATH")?);
+    }
+
+    output::status(format!("{} {}", output::styled(">", Style::Dim), script));
+    output::command(&command);
+    let status = command.status().with_context(|| format!("Could not run script '{}'", name))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
@@ -1,18 +1,67 @@
 use anyhow::Result;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
-use pyro_core::interpreter::{Interpreter, Value};
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::prelude::Prelude;
+use pyro_core::stdlib::time::Timings;
 use pyro_core::parser::Parser;
 use pyro_core::lexer::{Lexer, Token};
 use pyro_core::ast::Stmt;
+use pyro_core::diagnostics::Diagnostic;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use crate::output::{self, Style};
+use crate::session;
 use crate::util;
+use std::time::{Duration, Instant};
+
+// Global the shell binds the most recent uncaught error to
+const LAST_ERROR: &str = "_err";
 
 pub fn run() -> Result<()> {
     // 1. Initialize Interpreter
-    let mut interpreter = Interpreter::new();
+    let interpreter = Interpreter::with_prelude(&Prelude::full());
+
+    println!("Pyro Shell v0.1.0");
+    println!("Type 'exit' or Ctrl-D to exit");
+    repl(interpreter)
+}
+
+// `pyro run --post-mortem`: the shell over the interpreter a script failed
+// in, with the error as `_err` and the variables of the function it was
+// raised in defined as globals, shadowing the script's own.
+pub fn post_mortem(mut interpreter: Interpreter, e: RuntimeError) -> Result<()> {
+    let locals = interpreter.error_locals().map(|env| env.0.clone());
+    report_error(&mut interpreter, e);
+    match locals {
+        Some(locals) => {
+            let mut names: Vec<&String> = locals.keys().collect();
+            names.sort();
+            for name in &names {
+                interpreter.set_global(name, locals[*name].clone());
+            }
+            let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+            println!("Post-mortem: variables of the failing function are loaded ({})", names.join(", "));
+        }
+        None => println!("Post-mortem: the error was raised at the top level"),
+    }
+    println!("Type ':last_error' for the full error, 'exit' or Ctrl-D to exit");
+    repl(interpreter)
+}
+
+fn repl(mut interpreter: Interpreter) -> Result<()> {
     let mut loaded_files = HashSet::new();
+    let mut imports = Vec::new();
+    // Everything defined before the first prompt is left out of saved sessions
+    let builtin: HashSet<String> = interpreter.globals().into_iter().map(|(name, _)| name).collect();
+
+    // Ctrl-C while code is running cancels the evaluation rather than killing
+    // the shell. At the prompt rustyline reads it as a key press instead.
+    let interrupt = interpreter.interrupt_token();
+    ctrlc::set_handler({
+        let interrupt = interrupt.clone();
+        move || interrupt.interrupt()
+    })?;
 
     // 2. Initialize Rustyline Editor
     let mut rl = DefaultEditor::new()?;
@@ -20,9 +69,6 @@ pub fn run() -> Result<()> {
          let _ = rl.load_history(&format!("{}/.pyro_history", home));
     }
 
-    println!("Pyro Shell v0.1.0");
-    println!("Type 'exit' or Ctrl-D to exit");
-
     let mut buffer = String::new();
 
     loop {
@@ -36,6 +82,65 @@ pub fn run() -> Result<()> {
                     if input_part.trim() == "exit" {
                         break;
                     }
+                    if input_part.trim() == ":last_error" {
+                        match interpreter.global(LAST_ERROR) {
+                            Some(err) => print_error_details(&err),
+                            None => println!("No errors yet"),
+                        }
+                        continue;
+                    }
+                    if let Some(path) = meta_command(input_part, ":save") {
+                        let snapshot = session::snapshot(&interpreter, &imports, &builtin);
+                        match std::fs::write(path, snapshot.source) {
+                            Ok(_) => println!("Saved session to {}", path),
+                            Err(e) => println!("Error: Could not write {}: {}", path, e),
+                        }
+                        if !snapshot.skipped.is_empty() {
+                            println!("Not saved (no source form): {}", snapshot.skipped.join(", "));
+                        }
+                        continue;
+                    }
+                    if let Some(path) = meta_command(input_part, ":restore") {
+                        match std::fs::read_to_string(path) {
+                            Ok(source) => {
+                                interrupt.clear();
+                                execute(&mut interpreter, &mut loaded_files, &mut imports, &source);
+                                println!("Restored session from {}", path);
+                            }
+                            Err(e) => println!("Error: Could not read {}: {}", path, e),
+                        }
+                        continue;
+                    }
+                    if input_part.trim() == ":paste" {
+                        println!("Paste mode: end with a line containing only '.' or Ctrl-D");
+                        if let Some(source) = read_paste(&mut rl) {
+                            let _ = rl.add_history_entry(source.trim());
+                            interrupt.clear();
+                            execute(&mut interpreter, &mut loaded_files, &mut imports, &source);
+                        }
+                        continue;
+                    }
+                    if let Some(name) = input_part.trim().strip_prefix(":edit ") {
+                        let name = name.trim();
+                        match session::function_source(&interpreter, name) {
+                            Some(source) => match edit_in_editor(&source) {
+                                Ok(edited) if edited.trim() == source.trim() => println!("No changes"),
+                                Ok(edited) => {
+                                    interrupt.clear();
+                                    execute(&mut interpreter, &mut loaded_files, &mut imports, &edited);
+                                }
+                                Err(e) => println!("Error: {}", e),
+                            },
+                            None => println!("Error: No function named '{}'", name),
+                        }
+                        continue;
+                    }
+                    if let Some(source) = input_part.trim().strip_prefix(":time ") {
+                        let _ = rl.add_history_entry(input_part.trim());
+                        interrupt.clear();
+                        time_expression(&mut interpreter, source);
+                        continue;
+                    }
                     if input_part.trim().is_empty() {
                          continue;
                     }
@@ -48,86 +153,9 @@ pub fn run() -> Result<()> {
                     let input = buffer.trim();
                     if !input.is_empty() {
                         let _ = rl.add_history_entry(input);
+                        interrupt.clear();
                         
-                        // Parse the line
-                        let mut lexer = Lexer::new(input);
-                        let tokens = lexer.tokenize();
-                        
-                        // Check for lexer errors (like unclosed strings) if we want?
-                        // But parser will handle it.
-
-                        let mut parser = Parser::new(&tokens);
-                        
-                        match parser.parse() {
-                            Ok(program) => {
-                                for stmt in program.statements {
-                                    match stmt {
-                                        Stmt::Import(path) => {
-                                            if interpreter.has_native_module(&path) {
-                                                if let Err(e) = interpreter.run(vec![Stmt::Import(path.clone())]) {
-                                                    println!("Runtime Error: {:?}", e);
-                                                }
-                                                continue;
-                                            }
-
-                                            // Resolve and process file
-                                            let mut statements = Vec::new();
-                                            // Quick hack for resolution relative to CWD
-                                            let import_path = PathBuf::from(&path);
-                                            let resolved_path = if import_path.exists() {
-                                                    if import_path.is_absolute() {
-                                                        import_path
-                                                    } else {
-                                                        if let Ok(cwd) = std::env::current_dir() {
-                                                            cwd.join(import_path)
-                                                        } else {
-                                                            import_path
-                                                        }
-                                                    }
-                                            } else {
-                                                 if let Ok(home) = std::env::var("HOME") {
-                                                    let pkg_path = PathBuf::from(home).join(".pyro/pkg").join(&path);
-                                                    if pkg_path.exists() {
-                                                        pkg_path
-                                                    } else {
-                                                         println!("Error: Could not resolve import '{}'", path);
-                                                         continue;
-                                                    }
-                                                } else {
-                                                     println!("Error: Could not resolve import '{}'", path);
-                                                     continue;
-                                                }
-                                            };
-                                            
-                                            if let Err(e) = util::process_file(resolved_path, &mut loaded_files, &mut statements) {
-                                                 println!("Error importing file: {}", e);
-                                            } else {
-                                                if let Err(e) = interpreter.run(statements) {
-                                                    println!("Runtime Error: {:?}", e);
-                                                }
-                                            }
-                                        }
-                                        Stmt::Expr(expr) => {
-                                            match interpreter.evaluate(expr) {
-                                                Ok(val) => {
-                                                    match val {
-                                                        Value::Void => (),
-                                                        _ => println!("{:?}", val),
-                                                    }
-                                                }
-                                                Err(e) => println!("Runtime Error: {:?}", e),
-                                            }
-                                        }
-                                        _ => {
-                                            if let Err(e) = interpreter.run(vec![stmt]) {
-                                                println!("Runtime Error: {:?}", e);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => println!("Parse Error: {}", e),
-                        }
+                        execute(&mut interpreter, &mut loaded_files, &mut imports, input);
                     }
                     buffer.clear();
                 }
@@ -160,7 +188,252 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
-fn is_input_complete(input: &str) -> bool {
+// Parses and runs one complete input, printing results and errors
+fn execute(interpreter: &mut Interpreter, loaded_files: &mut HashSet<PathBuf>, imports: &mut Vec<String>, input: &str) {
+    // Parse the line
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize();
+
+    // Check for lexer errors (like unclosed strings) if we want?
+    // But parser will handle it.
+
+    let mut parser = Parser::new(&tokens);
+
+    match parser.parse() {
+        Ok(program) => {
+            for stmt in program.statements {
+                match stmt {
+                    Stmt::Import(path) => {
+                        if interpreter.has_native_module(&path) || path.starts_with(pyro_core::plugin::IMPORT_PREFIX) {
+                            match interpreter.run(vec![Stmt::Import(path.clone())]) {
+                                Ok(_) => record_import(imports, path),
+                                Err(e) => report_error(interpreter, e),
+                            }
+                            continue;
+                        }
+
+                        // Resolve and process file
+                        let mut statements = Vec::new();
+                        // Quick hack for resolution relative to CWD
+                        let import_path = PathBuf::from(&path);
+                        let resolved_path = if import_path.exists() {
+                                if import_path.is_absolute() {
+                                    import_path
+                                } else {
+                                    if let Ok(cwd) = std::env::current_dir() {
+                                        cwd.join(import_path)
+                                    } else {
+                                        import_path
+                                    }
+                                }
+                        } else {
+                             if let Ok(home) = std::env::var("HOME") {
+                                let pkg_path = PathBuf::from(home).join(".pyro/pkg").join(&path);
+                                if pkg_path.exists() {
+                                    pkg_path
+                                } else {
+                                     println!("Error: Could not resolve import '{}'", path);
+                                     continue;
+                                }
+                            } else {
+                                 println!("Error: Could not resolve import '{}'", path);
+                                 continue;
+                            }
+                        };
+
+                        if let Err(e) = util::process_import(resolved_path, loaded_files, &mut statements) {
+                             println!("Error importing file: {}", e);
+                        } else {
+                            match interpreter.run(statements) {
+                                Ok(_) => record_import(imports, path),
+                                Err(e) => report_error(interpreter, e),
+                            }
+                        }
+                    }
+                    Stmt::Expr(expr) => {
+                        match interpreter.evaluate(expr) {
+                            Ok(val) => {
+                                match val {
+                                    Value::Void => (),
+                                    _ => println!("{:?}", val),
+                                }
+                            }
+                            Err(e) => report_error(interpreter, e),
+                        }
+                    }
+                    _ => {
+                        if let Err(e) = interpreter.run(vec![stmt]) {
+                            report_error(interpreter, e);
+                        }
+                    }
+                }
+            }
+        }
+        Err(e) => println!("{}", Diagnostic::error("parse", e)),
+    }
+}
+
+// Total time `:time` spends on an expression before reporting
+const TIME_BUDGET: Duration = Duration::from_millis(500);
+
+// `:time expr` evaluates the expression repeatedly, for about TIME_BUDGET
+fn time_expression(interpreter: &mut Interpreter, source: &str) {
+    let tokens = Lexer::new(source).tokenize();
+    let expr = match Parser::new(&tokens).parse() {
+        Ok(program) => match <[Stmt; 1]>::try_from(program.statements) {
+            Ok([Stmt::Expr(expr)]) => expr,
+            _ => return println!("Error: :time takes a single expression"),
+        },
+        Err(e) => return println!("{}", Diagnostic::error("parse", e)),
+    };
+
+    let mut timings = Timings::default();
+    while timings.total < TIME_BUDGET && !interpreter.interrupt_token().interrupted() {
+        let start = Instant::now();
+        if let Err(e) = interpreter.evaluate(expr.clone()) {
+            return report_error(interpreter, e);
+        }
+        timings.record(start.elapsed());
+    }
+    println!("{}", timings);
+}
+
+// Reads lines verbatim until a lone "." or Ctrl-D. Ctrl-C discards them.
+fn read_paste(rl: &mut DefaultEditor) -> Option<String> {
+    let mut source = String::new();
+    loop {
+        match rl.readline("") {
+            Ok(line) if line.trim() == "." => break,
+            Ok(line) => {
+                source.push_str(&line);
+                source.push('\n');
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(_) => {
+                println!("Paste cancelled.");
+                return None;
+            }
+        }
+    }
+    Some(source)
+}
+
+// Opens the source in $EDITOR (vi if unset) and returns what was saved
+fn edit_in_editor(source: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!("pyro-edit-{}.pyro", std::process::id()));
+    std::fs::write(&path, source)?;
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program).args(parts).arg(&path).status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    match status {
+        Ok(status) if status.success() => Ok(edited?),
+        Ok(status) => Err(anyhow::anyhow!("{} exited with {}", editor, status)),
+        Err(e) => Err(anyhow::anyhow!("Could not start {}: {}", editor, e)),
+    }
+}
+
+// `:save` or `:save file`, returning the file to use
+fn meta_command<'a>(line: &'a str, command: &str) -> Option<&'a str> {
+    let rest = line.trim().strip_prefix(command)?;
+    if rest.is_empty() {
+        Some(session::DEFAULT_SESSION_FILE)
+    } else if rest.starts_with(char::is_whitespace) {
+        Some(rest.trim())
+    } else {
+        None
+    }
+}
+
+// Imports run so far, replayed first when a saved session is restored
+fn record_import(imports: &mut Vec<String>, path: String) {
+    if !imports.contains(&path) {
+        imports.push(path);
+    }
+}
+
+// Keeps the error as `_err` for inspection and prints a short summary
+fn report_error(interpreter: &mut Interpreter, e: RuntimeError) {
+    let err = interpreter.native_error(e).into_value();
+    print_error(&err);
+    interpreter.set_global(LAST_ERROR, err);
+}
+
+fn print_error(err: &Value) {
+    println!("{}", error_report(err, |summary| output::styled(summary, Style::Red)));
+}
+
+// An uncaught error as the shell and `pyro run` show it: the calls it
+// unwound through, outermost first, the error, passed through `highlight`,
+// and the errors it was raised from
+pub fn error_report(err: &Value, highlight: impl Fn(String) -> String) -> String {
+    let mut lines = Vec::new();
+    if let Some(Value::List(frames)) = error_field(err, "traceback") {
+        lines.push("Traceback (most recent call last):".to_string());
+        for frame in frames.iter() {
+            lines.push(format!("  {}", display(frame)));
+        }
+    }
+    lines.push(highlight(error_summary(err)));
+    // Follow `raise ... from ...` chains
+    let mut cause = error_field(err, "cause");
+    while let Some(c @ Value::Instance { .. }) = cause {
+        lines.push(format!("  caused by: {}", error_summary(&c)));
+        cause = error_field(&c, "cause");
+    }
+    lines.join("\n")
+}
+
+// `:last_error` shows every field of the error, not just the message
+fn print_error_details(err: &Value) {
+    match err {
+        Value::Instance { class_name, fields, .. } => {
+            println!("{}", class_name);
+            let fields = fields.read().unwrap();
+            let mut names: Vec<_> = fields.keys().collect();
+            names.sort();
+            for name in names {
+                println!("  {}: {}", name, display(&fields[name]));
+            }
+        }
+        other => println!("{}", display(other)),
+    }
+}
+
+// "ValueError: bad input", with the kind appended when it adds information
+fn error_summary(err: &Value) -> String {
+    match err {
+        Value::Instance { class_name, .. } => {
+            let message = error_field(err, "message").map(|m| display(&m)).unwrap_or_default();
+            match error_field(err, "kind") {
+                Some(Value::String(kind)) if kind.as_str() != "Error" && kind.as_str() != class_name => {
+                    format!("{} ({}): {}", class_name, kind, message)
+                }
+                _ => format!("{}: {}", class_name, message),
+            }
+        }
+        other => format!("Error: {}", display(other)),
+    }
+}
+
+fn error_field(err: &Value, name: &str) -> Option<Value> {
+    match err {
+        Value::Instance { fields, .. } => fields.read().unwrap().get(name).cloned(),
+        _ => None,
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+pub fn is_input_complete(input: &str) -> bool {
     // Quick checks
     if input.trim().is_empty() {
         return true;
@@ -0,0 +1,51 @@
+use crate::cmd::installer::{in_parallel, restore_package};
+use crate::manifest::{LockFile, Manifest};
+use crate::output::{self, Style};
+use crate::toolchain;
+use anyhow::{Context, Result};
+
+// Makes this machine match pyro.lock, without resolving pyro.mod again:
+// every locked package is put back at its locked commit, installing any that
+// are missing and discarding local changes. What pyro can't change itself, a
+// different pyro, platform or Rust toolchain, is reported with what to do.
+pub fn r#impl(jobs: usize) -> Result<()> {
+    let lockfile = LockFile::load()?;
+    let rust = Manifest::load().map(|manifest| manifest.rust.is_some()).unwrap_or(false);
+
+    let results = in_parallel(jobs, &lockfile.package, |pkg| {
+        restore_package(pkg).with_context(|| format!("Could not sync {} to pyro.lock", pkg.name))
+    });
+    let mut failed = 0;
+    for (pkg, result) in lockfile.package.iter().zip(results) {
+        match result {
+            Ok(()) => output::status(format!("{}  {}", output::styled("synced", Style::Green), pkg.name)),
+            Err(e) => {
+                output::error(format!("{:#}", e));
+                failed += 1;
+            }
+        }
+    }
+
+    match &lockfile.environment {
+        Some(locked) => {
+            for difference in toolchain::differences(locked, rust) {
+                output::warn(difference);
+            }
+            if let Some(rustc) = locked.rustc.as_ref().filter(|_| rust) {
+                if toolchain::current(true).rustc.as_ref() != Some(rustc) {
+                    output::warn(format!(
+                        "Native builds use the default Rust toolchain; run 'rustup toolchain install {0}' and 'rustup default {0}' to match pyro.lock",
+                        rustc
+                    ));
+                }
+            }
+        }
+        None => output::status("pyro.lock doesn't record an environment yet; 'pyro install' adds one"),
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} package(s) could not be synced", failed);
+    }
+    output::success(format!("{} package(s) match pyro.lock", lockfile.package.len()));
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::coverage::{self, Report};
+use crate::output::{self, Style};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+pub struct TestOptions {
+    pub coverage: bool,
+    pub lcov: PathBuf,
+    pub update_snapshots: bool,
+}
+
+// Test files in a directory are the ones named like `*_test.pyro` or
+// `test_*.pyro`; files given explicitly always run.
+fn is_test_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with("_test.pyro") || (name.starts_with("test_") && name.ends_with(".pyro"))
+}
+
+fn collect(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+            continue;
+        }
+        if !path.exists() {
+            anyhow::bail!("No such file or directory: {:?}", path);
+        }
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_test_file(entry.path()) {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    Ok(files)
+}
+
+// The paths to look for tests under: those given, or tests
+pub fn test_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    if paths.is_empty() { vec![PathBuf::from("tests")] } else { paths }
+}
+
+// Runs each test file in its own `pyro run` process. A file passes when it
+// exits cleanly and prints no line containing FAILED.
+pub fn r#impl(paths: Vec<PathBuf>, options: TestOptions) -> Result<()> {
+    let paths = test_paths(paths);
+    let files = collect(&paths)?;
+    if files.is_empty() {
+        anyhow::bail!("No test files found in {:?}", paths);
+    }
+
+    let exe = std::env::current_exe().context("Could not find the pyro executable")?;
+    let data_dir = std::env::temp_dir().join(format!("pyro-coverage-{}", std::process::id()));
+    if options.coverage {
+        fs::create_dir_all(&data_dir)?;
+    }
+
+    let mut report = Report::new();
+    let mut failed = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let mut command = Command::new(&exe);
+        command.arg("run").arg("--skip-build").arg(file);
+        if options.update_snapshots {
+            command.env("PYRO_UPDATE_SNAPSHOTS", "1");
+        }
+        let data = data_dir.join(format!("{}.json", i));
+        if options.coverage {
+            command.arg("--coverage-data").arg(&data);
+        }
+        output::command(&command);
+        let result = command.output().with_context(|| format!("Could not run {:?}", file))?;
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let passed = result.status.success() && !stdout.lines().any(|l| l.contains("FAILED"));
+
+        if passed {
+            output::status(format!("{}      {}", output::styled("ok", Style::Green), file.display()));
+            if output::verbose() {
+                print!("{}", stdout);
+            }
+        } else {
+            println!("{}  {}", output::styled("FAILED", Style::Red), file.display());
+            print!("{}", stdout);
+            eprint!("{}", String::from_utf8_lossy(&result.stderr));
+            failed.push(file);
+        }
+        if options.coverage {
+            if let Ok(data) = coverage::load(&data) {
+                for (name, lines) in &data {
+                    coverage::merge(&mut report, name, lines);
+                }
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&data_dir);
+
+    output::status("");
+    output::status(output::styled(format!("{} passed, {} failed", files.len() - failed.len(), failed.len()), Style::Bold));
+    if options.coverage {
+        output::status("");
+        output::status(coverage::summary(&report).trim_end());
+        fs::write(&options.lcov, coverage::lcov(&report))
+            .with_context(|| format!("Could not write {:?}", options.lcov))?;
+        output::status(format!("Wrote {}", options.lcov.display()));
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!("{} test file(s) failed", failed.len());
+    }
+    Ok(())
+}
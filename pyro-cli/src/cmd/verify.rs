@@ -0,0 +1,54 @@
+use crate::cmd::installer::{calculate_dir_checksum, head_commit, package_dir, restore_package};
+use crate::manifest::{LockFile, LockPackage};
+use crate::output::{self, Style};
+use anyhow::Result;
+
+// How an installed package differs from what pyro.lock records, if it does
+fn drift(pkg: &LockPackage) -> Result<Option<String>> {
+    let dest = package_dir(&pkg.name)?;
+    if !dest.exists() {
+        return Ok(Some("not installed".to_string()));
+    }
+    if let Some(locked) = &pkg.commit {
+        let commit = head_commit(&dest)?;
+        if &commit != locked {
+            return Ok(Some(format!("at commit {}, locked to {}", commit, locked)));
+        }
+    }
+    let checksum = calculate_dir_checksum(&dest)?;
+    if checksum != pkg.checksum {
+        return Ok(Some(format!("checksum {}, locked to {}", checksum, pkg.checksum)));
+    }
+    Ok(None)
+}
+
+// Checks every package in pyro.lock against its installed copy, reporting
+// any that are missing, on another commit or changed on disk. With
+// `restore`, puts those back as locked. Fails if any still differ.
+pub fn r#impl(restore: bool) -> Result<()> {
+    let lockfile = LockFile::load()?;
+    if lockfile.package.is_empty() {
+        output::status("pyro.lock has no packages");
+        return Ok(());
+    }
+
+    let mut drifted = 0;
+    for pkg in &lockfile.package {
+        let Some(problem) = drift(pkg)? else {
+            output::status(format!("{}      {}", output::styled("ok", Style::Green), pkg.name));
+            continue;
+        };
+        if restore {
+            restore_package(pkg)?;
+            output::status(format!("{}  {} ({})", output::styled("restored", Style::Yellow), pkg.name, problem));
+        } else {
+            output::status(format!("{}  {}: {}", output::styled("DRIFT", Style::Red), pkg.name, problem));
+            drifted += 1;
+        }
+    }
+
+    if drifted > 0 {
+        anyhow::bail!("{} package(s) differ from pyro.lock; run 'pyro mod verify --restore' to restore them", drifted);
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+// Line coverage for `pyro test`. Files are parsed with a probe call before
+// every statement (see `Parser::with_coverage_probes`), and a hook on the
+// interpreter bumps the hit count of a probe's line when it is called. A run
+// saves its counts as JSON, and `pyro test` merges the counts of every test
+// file into one report.
+
+use crate::diagnostics::ParseFailure;
+use anyhow::{Context, Result};
+use pyro_core::ast::Program;
+use pyro_core::interpreter::{Interpreter, InterpreterHooks, NativeClosure, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::{Parser, COVERAGE_PROBE};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Hit counts per line, for every line that has a statement
+pub type FileCoverage = BTreeMap<usize, u64>;
+
+// Keyed by file path
+pub type Report = BTreeMap<String, FileCoverage>;
+
+struct State {
+    files: Vec<(String, FileCoverage)>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+pub fn enable() {
+    *STATE.lock().unwrap() = Some(State { files: Vec::new() });
+}
+
+pub fn enabled() -> bool {
+    STATE.lock().unwrap().is_some()
+}
+
+// Parses a file with coverage probes and registers its statement lines.
+pub fn parse(path: &Path, content: &str) -> Result<Program> {
+    let mut guard = STATE.lock().unwrap();
+    let state = guard.as_mut().expect("coverage is not enabled");
+    let name = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().into_owned();
+
+    let (tokens, lines) = Lexer::new(content).tokenize_with_lines();
+    let mut parser = Parser::new(&tokens).with_coverage_probes(&lines, state.files.len() as i64);
+    let program = parser
+        .parse()
+        .map_err(|message| ParseFailure { path: path.to_path_buf(), message, imported_by: Vec::new() })?;
+    let statements = parser.probed_lines().iter().map(|&line| (line, 0)).collect();
+    state.files.push((name, statements));
+    Ok(program)
+}
+
+// Counts the probes' calls as the program makes them
+struct Collector;
+
+impl InterpreterHooks for Collector {
+    fn on_call(&mut self, function: &str, args: &[Value]) {
+        if function != COVERAGE_PROBE {
+            return;
+        }
+        if let [Value::Int(file), Value::Int(line)] = args {
+            let mut guard = STATE.lock().unwrap();
+            let hits = guard
+                .as_mut()
+                .and_then(|state| state.files.get_mut(*file as usize))
+                .and_then(|(_, lines)| lines.get_mut(&(*line as usize)));
+            if let Some(hits) = hits {
+                *hits += 1;
+            }
+        }
+    }
+}
+
+// Installs the collector, and defines the probe function as one that does
+// nothing so the probes can run.
+pub fn install(interpreter: &mut Interpreter) {
+    interpreter.replace_hooks(Some(Arc::new(Mutex::new(Collector))));
+    interpreter.set_global(
+        COVERAGE_PROBE,
+        Value::NativeFunction { name: COVERAGE_PROBE.to_string(), func: NativeClosure(Arc::new(|_| Ok(Value::Void))) },
+    );
+}
+
+pub fn save(path: &Path) -> Result<()> {
+    let guard = STATE.lock().unwrap();
+    let mut report = Report::new();
+    for (name, lines) in guard.iter().flat_map(|state| &state.files) {
+        merge(&mut report, name, lines);
+    }
+    fs::write(path, serde_json::to_vec(&report)?).with_context(|| format!("Could not write coverage data {:?}", path))
+}
+
+pub fn load(path: &Path) -> Result<Report> {
+    let data = fs::read(path).with_context(|| format!("Could not read coverage data {:?}", path))?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+pub fn merge(report: &mut Report, name: &str, lines: &FileCoverage) {
+    let file = report.entry(name.to_string()).or_default();
+    for (&line, &hits) in lines {
+        *file.entry(line).or_default() += hits;
+    }
+}
+
+// Paths under the current directory are shown relative to it
+fn display_path(name: &str) -> String {
+    let path = PathBuf::from(name);
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn percent(covered: usize, total: usize) -> f64 {
+    if total == 0 { 100.0 } else { covered as f64 * 100.0 / total as f64 }
+}
+
+pub fn summary(report: &Report) -> String {
+    let rows: Vec<(String, usize, usize)> = report
+        .iter()
+        .map(|(name, lines)| (display_path(name), lines.len(), lines.values().filter(|&&h| h > 0).count()))
+        .collect();
+    let width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0).max("Total".len());
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{:<width$}  {:>6}  {:>7}  {:>6}", "File", "Lines", "Covered", "Cover");
+    let (mut total, mut covered) = (0, 0);
+    for (name, lines, hit) in &rows {
+        let _ = writeln!(out, "{:<width$}  {:>6}  {:>7}  {:>5.1}%", name, lines, hit, percent(*hit, *lines));
+        total += lines;
+        covered += hit;
+    }
+    let _ = writeln!(out, "{:<width$}  {:>6}  {:>7}  {:>5.1}%", "Total", total, covered, percent(covered, total));
+    out
+}
+
+// https://github.com/linux-test-project/lcov/blob/master/man/geninfo.1 (TRACEFILE FORMAT)
+pub fn lcov(report: &Report) -> String {
+    let mut out = String::new();
+    for (name, lines) in report {
+        let _ = writeln!(out, "TN:");
+        let _ = writeln!(out, "SF:{}", display_path(name));
+        for (line, hits) in lines {
+            let _ = writeln!(out, "DA:{},{}", line, hits);
+        }
+        let _ = writeln!(out, "LF:{}", lines.len());
+        let _ = writeln!(out, "LH:{}", lines.values().filter(|&&h| h > 0).count());
+        let _ = writeln!(out, "end_of_record");
+    }
+    out
+}
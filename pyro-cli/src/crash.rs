@@ -0,0 +1,106 @@
+// Crash reports for panics in the interpreter itself, as opposed to errors
+// raised by the Pyro program. The report holds what is needed to reproduce
+// the crash: the top-level statement that was running, the script's source
+// and its token stream.
+
+use crate::output;
+use anyhow::Result;
+use pyro_core::ast::{to_source, Program, Stmt};
+use pyro_core::lexer::Lexer;
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ISSUES_URL: &str = "https://github.com/iamyohann/pyro/issues";
+
+// Message, location and backtrace of the most recent panic, recorded by the
+// hook because catch_unwind only hands back the payload
+static LAST_PANIC: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+// Records panic details while keeping the usual panic output
+pub fn install_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            *last = Some((describe(info), Backtrace::force_capture().to_string()));
+        }
+        previous(info);
+    }));
+}
+
+fn describe(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message,
+    }
+}
+
+// Writes a report to ~/.pyro/crash and tells the user how to file it
+pub fn report(file: &Path, statement: Option<&Stmt>) {
+    match write_report(file, statement) {
+        Ok(path) => {
+            eprintln!();
+            output::error("The Pyro interpreter crashed. This is a bug in Pyro, not in your program.");
+            eprintln!("A crash report was written to {}", path.display());
+            eprintln!("Please open an issue at {} and attach the report.", ISSUES_URL);
+            eprintln!("It contains your script's source, so remove anything private first.");
+        }
+        Err(e) => output::error(format!("The Pyro interpreter crashed, and the crash report could not be written: {}", e)),
+    }
+}
+
+fn write_report(file: &Path, statement: Option<&Stmt>) -> Result<PathBuf> {
+    let (panic, backtrace) = LAST_PANIC
+        .lock()
+        .ok()
+        .and_then(|mut p| p.take())
+        .unwrap_or_else(|| ("unknown panic".to_string(), String::new()));
+    let source = fs::read_to_string(file).unwrap_or_default();
+
+    let mut out = String::new();
+    writeln!(out, "Pyro crash report")?;
+    writeln!(out, "version: {} ({} {})", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH)?;
+    writeln!(out, "panic: {}", panic)?;
+    writeln!(out, "file: {}", file.display())?;
+    writeln!(out)?;
+    writeln!(out, "== statement being executed ==")?;
+    match statement {
+        Some(stmt) => out.push_str(&to_source(&Program { statements: vec![stmt.clone()] })),
+        None => writeln!(out, "(none, the crash happened while loading the program)")?,
+    }
+    writeln!(out)?;
+    writeln!(out, "== source ==")?;
+    writeln!(out, "{}", source)?;
+    writeln!(out, "== tokens ==")?;
+    // Lexing is repeated here, so it is guarded in case it is what crashed
+    let tokens = panic::catch_unwind(|| Lexer::new(&source).tokenize());
+    match tokens {
+        Ok(tokens) => {
+            for token in tokens {
+                writeln!(out, "{:?}", token)?;
+            }
+        }
+        Err(_) => writeln!(out, "(the lexer panicked)")?,
+    }
+    writeln!(out)?;
+    writeln!(out, "== backtrace ==")?;
+    writeln!(out, "{}", backtrace)?;
+
+    let home = std::env::var("HOME")?;
+    let dir = PathBuf::from(home).join(".pyro/crash");
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = dir.join(format!("crash-{}-{}.txt", stamp, std::process::id()));
+    fs::write(&path, out)?;
+    Ok(path)
+}
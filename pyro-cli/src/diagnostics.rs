@@ -0,0 +1,140 @@
+// Machine-readable errors for `pyro run --json-errors`.
+//
+// Each diagnostic is one JSON object per line on stderr, a pyro_core
+// diagnostic with the file it belongs to:
+//
+//     {"file":"main.pyro","severity":"error","code":"parse","span":{"line":3},"message":"..."}
+//
+// Parse errors carry the line of the statement that failed. Runtime errors
+// have no span yet and use the error's kind ("Error", "NotFound", ...) as
+// their code, with a note for each call they unwound through. Constants
+// reassigned anywhere in the program have the code "constant". Failures
+// before the program runs have the code "io" or "error". An error in an
+// imported file has a note with the chain of imports that led to it.
+
+use pyro_core::diagnostics::Diagnostic;
+use pyro_core::interpreter::RuntimeError;
+use pyro_core::parser::incremental::parse_recovering;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub struct FileDiagnostic {
+    pub file: String,
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+}
+
+// A file that failed to parse
+#[derive(Debug)]
+pub struct ParseFailure {
+    pub path: PathBuf,
+    pub message: String,
+    // The files whose imports led to this one, entry file first; empty for
+    // the entry file
+    pub imported_by: Vec<PathBuf>,
+}
+
+impl ParseFailure {
+    fn import_note(&self) -> Option<String> {
+        if self.imported_by.is_empty() {
+            return None;
+        }
+        Some(format!("import chain: {}", crate::util::import_chain(&self.imported_by, &self.path)))
+    }
+}
+
+impl fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Parse error in {:?}: {}", self.path, self.message)?;
+        if let Some(note) = self.import_note() {
+            write!(f, "\n  note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseFailure {}
+
+// Problems the checks that run before the program found, e.g. a constant
+// reassigned in another file
+#[derive(Debug)]
+pub struct CheckFailure {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<&str> = self.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        write!(f, "{}", messages.join("\n"))
+    }
+}
+
+impl std::error::Error for CheckFailure {}
+
+// An error raised by the program and not caught
+#[derive(Debug)]
+pub struct RuntimeFailure {
+    pub diagnostic: Diagnostic,
+    // The error as the shell shows it, with its traceback
+    report: String,
+}
+
+impl RuntimeFailure {
+    pub fn new(failure: &RuntimeError, report: String) -> Self {
+        RuntimeFailure { diagnostic: Diagnostic::from(failure), report }
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.report.push_str(&format!("\n  note: {}", note));
+        self.diagnostic.notes.push(note);
+        self
+    }
+}
+
+impl fmt::Display for RuntimeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.report)
+    }
+}
+
+impl std::error::Error for RuntimeFailure {}
+
+// The diagnostics for a failed run of `file`
+pub fn from_error(file: &Path, error: &anyhow::Error) -> Vec<FileDiagnostic> {
+    let in_file = |file: &Path, diagnostic| FileDiagnostic { file: file.display().to_string(), diagnostic };
+
+    if let Some(parse) = error.downcast_ref::<ParseFailure>() {
+        // Reparse with recovery to report every broken statement, with lines
+        let errors = std::fs::read_to_string(&parse.path)
+            .map(|source| parse_recovering(&source).1)
+            .unwrap_or_default();
+        let mut diagnostics = if errors.is_empty() {
+            vec![Diagnostic::error("parse", parse.message.clone())]
+        } else {
+            errors.iter().map(Diagnostic::from).collect()
+        };
+        if let Some(note) = parse.import_note() {
+            diagnostics.iter_mut().for_each(|d| d.notes.push(note.clone()));
+        }
+        return diagnostics.into_iter().map(|d| in_file(&parse.path, d)).collect();
+    }
+    if let Some(check) = error.downcast_ref::<CheckFailure>() {
+        return check.diagnostics.iter().map(|d| in_file(file, d.clone())).collect();
+    }
+    if let Some(runtime) = error.downcast_ref::<RuntimeFailure>() {
+        return vec![in_file(file, runtime.diagnostic.clone())];
+    }
+    // Anything else failed before the program ran, e.g. an unreadable file
+    let code = if error.root_cause().downcast_ref::<std::io::Error>().is_some() { "io" } else { "error" };
+    vec![in_file(file, Diagnostic::error(code, format!("{:#}", error)))]
+}
+
+pub fn emit(diagnostics: &[FileDiagnostic]) {
+    for diagnostic in diagnostics {
+        if let Ok(line) = serde_json::to_string(diagnostic) {
+            eprintln!("{}", line);
+        }
+    }
+}
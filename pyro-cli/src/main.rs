@@ -2,6 +2,7 @@ use clap::{Parser, Subcommand};
 // use pyro_core::ast::Stmt;
 use anyhow::{Context, Result};
 // use std::fs;
+use pyro_core::permissions::Permissions;
 use std::path::PathBuf;
 // use pyro_core::lexer::Lexer;
 // use pyro_core::parser::Parser as PyroParser;
@@ -9,12 +10,28 @@ use std::path::PathBuf;
 mod cmd;
 mod util;
 mod manifest;
+mod session;
+mod crash;
+mod diagnostics;
+mod coverage;
+mod output;
+mod network;
+mod toolchain;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// When to use colors in output
+    #[arg(long, global = true, value_enum, default_value_t = output::ColorChoice::Auto)]
+    color: output::ColorChoice,
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Also print details, such as the commands pyro runs
+    #[arg(short, long, global = true)]
+    verbose: bool,
 }
 
 #[derive(Subcommand)]
@@ -22,7 +39,60 @@ enum Commands {
     /// Run a Pyro script
     Run {
         /// The file to run
-        file: PathBuf,
+        #[arg(required_unless_present = "script")]
+        file: Option<PathBuf>,
+        /// Run a script from pyro.mod's [scripts] instead, like `pyro x`
+        #[arg(long, value_name = "NAME", conflicts_with = "file")]
+        script: Option<String>,
+        /// Reload function definitions when the script or its imports change
+        #[arg(long)]
+        watch: bool,
+        /// Print the files the script imports, transitively, as a JSON list and exit
+        #[arg(long)]
+        dump_imports: bool,
+        /// Accept `pyro shell --remote` connections on this address while running
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = cmd::inspect::DEFAULT_ADDR)]
+        inspect: Option<String>,
+        /// Log the script's nondeterministic inputs (time, random numbers, environment, subprocesses) to a file
+        #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+        record: Option<PathBuf>,
+        /// Feed inputs logged by --record back to the script instead of reading them
+        #[arg(long, value_name = "FILE")]
+        replay: Option<PathBuf>,
+        /// Deny filesystem, network and subprocess access not granted by --allow-* flags
+        #[arg(long)]
+        sandbox: bool,
+        /// Allow file access, anywhere or only under the given comma-separated paths (implies --sandbox)
+        #[arg(long, value_name = "PATHS", num_args = 0..=1, require_equals = true)]
+        allow_fs: Option<Option<String>>,
+        /// Allow network access, to any host or only the given comma-separated hosts (implies --sandbox)
+        #[arg(long, value_name = "HOSTS", num_args = 0..=1, require_equals = true)]
+        allow_net: Option<Option<String>>,
+        /// Allow process.exec, of any command or only the given comma-separated commands (implies --sandbox)
+        #[arg(long, value_name = "COMMANDS", num_args = 0..=1, require_equals = true)]
+        allow_run: Option<Option<String>>,
+        /// Report errors as JSON lines on stderr, for CI and editors
+        #[arg(long)]
+        json_errors: bool,
+        /// On an uncaught error, open the shell with the failing function's variables loaded
+        #[arg(long, conflicts_with = "json_errors")]
+        post_mortem: bool,
+        /// Record line coverage to this file (used by `pyro test --coverage`)
+        #[arg(long, value_name = "FILE", hide = true)]
+        coverage_data: Option<PathBuf>,
+        /// Don't run the project's build.pyro first
+        #[arg(long)]
+        skip_build: bool,
+        /// Don't download anything; use only cached packages and crates
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Run a script from pyro.mod's [scripts], or list them
+    X {
+        name: Option<String>,
+        /// Passed on to the script's command
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
     },
     /// Package management commands
     Mod {
@@ -32,6 +102,9 @@ enum Commands {
     /// Add a dependency
     Get {
         url: String,
+        /// Don't download anything; the package must already be in the cache
+        #[arg(long)]
+        offline: bool,
     },
     /// Compile to binary
     Build {
@@ -42,13 +115,66 @@ enum Commands {
         /// Target output format (binary or rust)
         #[arg(short, long, default_value = "binary")]
         target: BuildTarget,
+        /// Don't run the project's build.pyro first
+        #[arg(long)]
+        skip_build: bool,
     },
     /// Install dependencies
-    Install,
+    Install {
+        /// Don't download anything; install only from the package cache
+        #[arg(long)]
+        offline: bool,
+        /// How many packages to fetch at once
+        #[arg(short, long, value_name = "N", default_value_t = 8)]
+        jobs: usize,
+    },
     /// Generate extern definitions for Rust dependencies
     Externs,
     /// Run the interactive shell
-    Shell,
+    Shell {
+        /// Attach to a program started with `pyro run --inspect` instead
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = cmd::inspect::DEFAULT_ADDR)]
+        remote: Option<String>,
+    },
+    /// Parse every .pyro file under a directory ahead of time, filling the parse cache
+    Compile {
+        /// Package directory or single file
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Run test files: *_test.pyro and test_*.pyro under the given paths (default: tests)
+    Test {
+        paths: Vec<PathBuf>,
+        /// Report line coverage and write it in lcov format
+        #[arg(long)]
+        coverage: bool,
+        /// Where --coverage writes the lcov report
+        #[arg(long, value_name = "FILE", default_value = "lcov.info")]
+        lcov: PathBuf,
+        /// Overwrite std.testing snapshots that don't match instead of failing
+        #[arg(long)]
+        update_snapshots: bool,
+        /// Don't run the project's build.pyro first
+        #[arg(long)]
+        skip_build: bool,
+    },
+    /// Run the checks done before a program runs, without running it
+    Check {
+        file: PathBuf,
+        /// Also require annotations on public functions and flag implicitly typed values reaching typed code
+        #[arg(long)]
+        strict: bool,
+        /// Report problems as JSON lines on stderr, for CI and editors
+        #[arg(long)]
+        json_errors: bool,
+    },
+    /// Parse a file and print its syntax tree
+    Parse {
+        file: PathBuf,
+        /// Output format (json, source or debug)
+        #[arg(short, long, default_value = "json")]
+        format: ParseFormat,
+    },
 
 }
 
@@ -58,12 +184,71 @@ enum BuildTarget {
     Rust,
 }
 
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum ParseFormat {
+    Json,
+    Source,
+    Debug,
+}
+
 #[derive(Subcommand)]
 enum ModCommands {
     /// Initialize a new module
     Init {
         name: String,
     },
+    /// Check installed packages against the commits and checksums in pyro.lock
+    Verify {
+        /// Put packages that differ back to their locked state
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Install exactly what pyro.lock records and report how this machine differs from it
+    Sync {
+        /// How many packages to fetch at once
+        #[arg(short, long, value_name = "N", default_value_t = 8)]
+        jobs: usize,
+        /// Don't download anything; install only from the package cache
+        #[arg(long)]
+        offline: bool,
+    },
+    /// List packages in pyro.lock with newer release tags
+    Outdated {
+        /// Use the tags already in the package cache instead of asking each repository
+        #[arg(long)]
+        offline: bool,
+    },
+}
+
+// None unless sandboxing was asked for, in which case only what the --allow-*
+// flags name is granted
+fn permissions(
+    sandbox: bool,
+    fs: &Option<Option<String>>,
+    net: &Option<Option<String>>,
+    run: &Option<Option<String>>,
+) -> Option<Permissions> {
+    if !sandbox && fs.is_none() && net.is_none() && run.is_none() {
+        return None;
+    }
+    let list = |items: &str| items.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect::<Vec<_>>();
+    let mut permissions = Permissions::none();
+    match fs {
+        Some(None) => permissions = permissions.allow_all_fs(),
+        Some(Some(paths)) => permissions = list(paths).iter().fold(permissions, |p, path| p.allow_fs(path)),
+        None => {}
+    }
+    match net {
+        Some(None) => permissions = permissions.allow_all_net(),
+        Some(Some(hosts)) => permissions = list(hosts).iter().fold(permissions, |p, host| p.allow_net(host)),
+        None => {}
+    }
+    match run {
+        Some(None) => permissions = permissions.allow_all_run(),
+        Some(Some(commands)) => permissions = list(commands).iter().fold(permissions, |p, command| p.allow_run(command)),
+        None => {}
+    }
+    Some(permissions)
 }
 
 fn main() -> Result<()> {
@@ -78,37 +263,104 @@ fn main() -> Result<()> {
         .build()
         .context("Failed to build tokio runtime")?;
 
-    runtime.block_on(async_main())
+    let result = runtime.block_on(async_main());
+    pyro_core::stdlib::fs::remove_temp_paths();
+    result
 }
 
 async fn async_main() -> Result<()> {
     let cli = Cli::parse();
+    output::init(cli.color, cli.quiet, cli.verbose);
 
     match &cli.command {
-        Commands::Run { file } => {
-            cmd::run::r#impl(file.clone())?;
+        Commands::Run { file, script, watch, dump_imports, inspect, record, replay, sandbox, allow_fs, allow_net, allow_run, json_errors, post_mortem, coverage_data, skip_build, offline } => {
+            network::set_offline(*offline);
+            // clap makes sure there is one or the other
+            let Some(file) = file else {
+                return cmd::script::r#impl(script.clone(), Vec::new());
+            };
+            if *dump_imports {
+                cmd::run::dump_imports(file.clone())?;
+            } else {
+                if !*skip_build {
+                    cmd::hook::run(file)?;
+                }
+                let options = cmd::run::RunOptions {
+                    watch: *watch,
+                    inspect: inspect.clone(),
+                    record: record.clone(),
+                    replay: replay.clone(),
+                    permissions: permissions(*sandbox, allow_fs, allow_net, allow_run),
+                    json_errors: *json_errors,
+                    post_mortem: *post_mortem,
+                    coverage: coverage_data.clone(),
+                };
+                cmd::run::r#impl(file.clone(), options)?;
+            }
+        }
+        Commands::X { name, args } => {
+            cmd::script::r#impl(name.clone(), args.clone())?;
         }
         Commands::Mod { command } => {
             match command {
                 ModCommands::Init { name } => {
                     cmd::init::r#impl(name.clone())?;
                 }
+                ModCommands::Verify { restore } => {
+                    cmd::verify::r#impl(*restore)?;
+                }
+                ModCommands::Sync { jobs, offline } => {
+                    network::set_offline(*offline);
+                    cmd::sync::r#impl(*jobs)?;
+                }
+                ModCommands::Outdated { offline } => {
+                    network::set_offline(*offline);
+                    cmd::outdated::r#impl()?;
+                }
             }
         }
-        Commands::Get { url } => {
+        Commands::Get { url, offline } => {
+            network::set_offline(*offline);
             cmd::get::r#impl(url.clone())?;
         }
-        Commands::Install => {
-            cmd::installer::r#impl()?;
+        Commands::Install { offline, jobs } => {
+            network::set_offline(*offline);
+            cmd::installer::r#impl(*jobs)?;
         }
         Commands::Externs => {
             cmd::externs::run()?;
         }
-        Commands::Build { file, output, target } => {
+        Commands::Build { file, output, target, skip_build } => {
+            if !*skip_build {
+                cmd::hook::run(file)?;
+            }
             cmd::build::r#impl(file.clone(), output.clone(), target.clone())?;
         }
-        Commands::Shell => {
-            cmd::shell::run()?;
+        Commands::Shell { remote } => match remote {
+            Some(addr) => cmd::inspect::connect(addr)?,
+            None => cmd::shell::run()?,
+        },
+        Commands::Compile { path } => {
+            cmd::compile::r#impl(path.clone())?;
+        }
+        Commands::Test { paths, coverage, lcov, update_snapshots, skip_build } => {
+            // Once for the whole run rather than in each file's process, in
+            // the projects the tests are in
+            if !*skip_build {
+                cmd::hook::run_all(&cmd::test::test_paths(paths.clone()))?;
+            }
+            let options = cmd::test::TestOptions {
+                coverage: *coverage,
+                lcov: lcov.clone(),
+                update_snapshots: *update_snapshots,
+            };
+            cmd::test::r#impl(paths.clone(), options)?;
+        }
+        Commands::Check { file, strict, json_errors } => {
+            cmd::check::r#impl(file.clone(), *strict, *json_errors)?;
+        }
+        Commands::Parse { file, format } => {
+            cmd::parse::r#impl(file.clone(), format.clone())?;
         }
     }
 
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::fs;
 use anyhow::{Result, Context};
 
@@ -11,6 +11,26 @@ pub struct Manifest {
     pub dependencies: HashMap<String, String>, 
     #[serde(default)]
     pub rust: Option<RustConfig>,
+    // Named commands for `pyro x <name>`, e.g. `serve = "pyro run src/server.pyro"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scripts: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildConfig>,
+}
+
+// `[build]`: whether build.pyro runs before the program, and what it may
+// touch beyond the project directory
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildConfig {
+    #[serde(default)]
+    pub hook: bool,
+    #[serde(default)]
+    pub allow_fs: Vec<String>,
+    #[serde(default)]
+    pub allow_net: Vec<String>,
+    #[serde(default)]
+    pub allow_run: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -27,10 +47,26 @@ pub struct Package {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LockFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<LockEnvironment>,
     #[serde(default)]
     pub package: Vec<LockPackage>,
 }
 
+// `[environment]`: what pyro.lock was written with, so another machine can
+// tell when it differs. The Rust toolchain is only recorded for projects
+// with Rust dependencies.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockEnvironment {
+    pub pyro: String,
+    // "<arch>-<os>", e.g. "x86_64-linux"
+    pub platform: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rustc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LockPackage {
     pub name: String,
@@ -50,6 +86,8 @@ impl Manifest {
             },
             dependencies: HashMap::new(),
             rust: None,
+            scripts: BTreeMap::new(),
+            build: None,
         }
     }
 
@@ -58,6 +96,11 @@ impl Manifest {
     }
 
     pub fn resolve_from<P: AsRef<Path>>(start_path: P) -> Result<Self> {
+        Self::locate_from(start_path).map(|(manifest, _)| manifest)
+    }
+
+    // The manifest along with the directory holding it, the project root
+    pub fn locate_from<P: AsRef<Path>>(start_path: P) -> Result<(Self, PathBuf)> {
         let mut current_dir = start_path.as_ref().to_path_buf();
         if current_dir.is_file() {
             current_dir.pop();
@@ -68,7 +111,7 @@ impl Manifest {
             if path.exists() {
                let content = fs::read_to_string(&path).context("Failed to read pyro.mod")?;
                let manifest: Manifest = toml::from_str(&content).context("Failed to parse pyro.mod")?;
-               return Ok(manifest);
+               return Ok((manifest, current_dir));
             }
 
             if !current_dir.pop() {
@@ -86,11 +129,16 @@ impl Manifest {
 
 impl LockFile {
     pub fn load() -> Result<Self> {
-        let path = Path::new("pyro.lock");
+        Self::load_from(Path::new("."))
+    }
+
+    // The pyro.lock in a project root
+    pub fn load_from(root: &Path) -> Result<Self> {
+        let path = root.join("pyro.lock");
         if !path.exists() {
-            return Ok(LockFile { package: vec![] });
+            return Ok(LockFile { environment: None, package: vec![] });
         }
-        let content = fs::read_to_string(path).context("Failed to read pyro.lock")?;
+        let content = fs::read_to_string(&path).context("Failed to read pyro.lock")?;
         let lock: LockFile = toml::from_str(&content).context("Failed to parse pyro.lock")?;
         Ok(lock)
     }
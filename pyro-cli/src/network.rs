@@ -0,0 +1,52 @@
+// How package commands use the network. With `--offline`, get, install and
+// run use only packages already in ~/.pyro/pkg and cargo's cache: anything
+// missing is an error rather than a download. Otherwise network steps that
+// fail, such as a git clone or fetch, are retried a few times with backoff
+// before giving up.
+
+use crate::output;
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+// Tries for each network step, the first included
+const ATTEMPTS: u32 = 3;
+// Wait before the first retry, doubled before each one after
+const BACKOFF: Duration = Duration::from_millis(500);
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+// Runs `step` until it succeeds or has failed ATTEMPTS times, saying so
+// before each retry. `what` names the step in messages, e.g. "clone of x".
+pub fn retry<T>(what: &str, mut step: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match step() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < ATTEMPTS => {
+                output::warn(format!("{} failed ({:#}); retrying in {:.1}s", what, e, delay.as_secs_f64()));
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.context(format!("{} failed after {} attempts", what, ATTEMPTS))),
+        }
+    }
+}
+
+// Adds --offline to a cargo command when pyro is offline
+pub fn cargo_args(cargo: &mut std::process::Command) {
+    if offline() {
+        cargo.arg("--offline");
+    }
+}
@@ -0,0 +1,213 @@
+// Messages from the CLI itself: progress, results, warnings. Commands print
+// them through here rather than with println!, so the global --quiet,
+// --verbose and --color flags apply everywhere. What a command produces as
+// data (a script's prints, `pyro parse`, JSON) still goes straight to stdout.
+
+use pyro_core::diagnostics::{Diagnostic, Severity};
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ColorChoice {
+    // Color when writing to a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+const QUIET: u8 = 0;
+const NORMAL: u8 = 1;
+const VERBOSE: u8 = 2;
+
+static LEVEL: AtomicU8 = AtomicU8::new(NORMAL);
+static COLOR_STDOUT: AtomicBool = AtomicBool::new(false);
+static COLOR_STDERR: AtomicBool = AtomicBool::new(false);
+static STDERR_TERMINAL: AtomicBool = AtomicBool::new(false);
+// Whether a Progress line is on screen, to be cleared before other output
+static PROGRESS_DRAWN: AtomicBool = AtomicBool::new(false);
+
+pub fn init(color: ColorChoice, quiet: bool, verbose: bool) {
+    let level = if quiet { QUIET } else if verbose { VERBOSE } else { NORMAL };
+    LEVEL.store(level, Ordering::Relaxed);
+
+    let decide = |terminal: bool| match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && std::env::var_os("NO_COLOR").is_none(),
+    };
+    COLOR_STDOUT.store(decide(std::io::stdout().is_terminal()), Ordering::Relaxed);
+    COLOR_STDERR.store(decide(std::io::stderr().is_terminal()), Ordering::Relaxed);
+    STDERR_TERMINAL.store(std::io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    LEVEL.load(Ordering::Relaxed) == QUIET
+}
+
+pub fn verbose() -> bool {
+    LEVEL.load(Ordering::Relaxed) == VERBOSE
+}
+
+pub fn color_stdout() -> bool {
+    COLOR_STDOUT.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy)]
+pub enum Style {
+    Bold,
+    Red,
+    Green,
+    Yellow,
+    Dim,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Red => "1;31",
+            Style::Green => "1;32",
+            Style::Yellow => "1;33",
+            Style::Dim => "2",
+        }
+    }
+}
+
+fn paint(text: impl Display, style: Style, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+// Styles text for stdout, if colors are on
+pub fn styled(text: impl Display, style: Style) -> String {
+    paint(text, style, color_stdout())
+}
+
+// Styles text for stderr, if colors are on there
+pub fn styled_stderr(text: impl Display, style: Style) -> String {
+    paint(text, style, COLOR_STDERR.load(Ordering::Relaxed))
+}
+
+fn clear_progress() {
+    if PROGRESS_DRAWN.swap(false, Ordering::Relaxed) {
+        eprint!("\r\x1b[2K");
+    }
+}
+
+// Progress and results; hidden by --quiet
+pub fn status(message: impl Display) {
+    if !quiet() {
+        clear_progress();
+        println!("{}", message);
+    }
+}
+
+// A finished step, highlighted; hidden by --quiet
+pub fn success(message: impl Display) {
+    if !quiet() {
+        clear_progress();
+        println!("{}", styled(message, Style::Green));
+    }
+}
+
+// Details only shown with --verbose, such as the commands being run
+pub fn detail(message: impl Display) {
+    if verbose() {
+        eprintln!("{}", paint(message, Style::Dim, COLOR_STDERR.load(Ordering::Relaxed)));
+    }
+}
+
+// Always shown, on stderr
+pub fn warn(message: impl Display) {
+    clear_progress();
+    let color = COLOR_STDERR.load(Ordering::Relaxed);
+    eprintln!("{} {}", paint("warning:", Style::Yellow, color), message);
+}
+
+// Always shown, on stderr
+pub fn error(message: impl Display) {
+    clear_progress();
+    let color = COLOR_STDERR.load(Ordering::Relaxed);
+    eprintln!("{} {}", paint("error:", Style::Red, color), message);
+}
+
+// A problem in a program's source, on stderr: `file: error[code]: message`
+pub fn diagnostic(file: impl Display, diagnostic: &Diagnostic) {
+    let style = match diagnostic.severity {
+        Severity::Error => Style::Red,
+        Severity::Warning => Style::Yellow,
+    };
+    eprintln!("{}: {}", file, paint(diagnostic, style, COLOR_STDERR.load(Ordering::Relaxed)));
+}
+
+// Echoes an external command (cargo, git) with --verbose
+pub fn command(command: &std::process::Command) {
+    let args: Vec<_> = command.get_args().map(|a| a.to_string_lossy()).collect();
+    detail(format!("Running {} {}", command.get_program().to_string_lossy(), args.join(" ")));
+}
+
+// A line on stderr showing how far a long step has got, redrawn in place: a
+// bar and count when the number of items is known, and what is being worked
+// on. Only drawn on a terminal and without --quiet; elsewhere the status
+// lines printed through `status` are the whole record.
+pub struct Progress {
+    total: usize,
+    done: usize,
+    message: String,
+}
+
+const BAR_WIDTH: usize = 24;
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Progress { total, done: 0, message: String::new() }
+    }
+
+    fn visible() -> bool {
+        !quiet() && STDERR_TERMINAL.load(Ordering::Relaxed)
+    }
+
+    fn draw(&mut self) {
+        if !Self::visible() {
+            return;
+        }
+        let line = match (BAR_WIDTH * self.done.min(self.total)).checked_div(self.total) {
+            Some(filled) => {
+                format!("[{}{}] {}/{} {}", "=".repeat(filled), " ".repeat(BAR_WIDTH - filled), self.done, self.total, self.message)
+            }
+            None => self.message.clone(),
+        };
+        eprint!("\r\x1b[2K{}", paint(line, Style::Dim, COLOR_STDERR.load(Ordering::Relaxed)));
+        PROGRESS_DRAWN.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_total(&mut self, total: usize) {
+        self.total = total;
+        self.draw();
+    }
+
+    pub fn set_message(&mut self, message: impl Display) {
+        self.message = message.to_string();
+        self.draw();
+    }
+
+    pub fn inc(&mut self) {
+        self.done += 1;
+        self.draw();
+    }
+
+    // A status line, printed above the progress line
+    pub fn status(&mut self, message: impl Display) {
+        status(message);
+        self.draw();
+    }
+
+    pub fn finish(&mut self) {
+        clear_progress();
+    }
+}
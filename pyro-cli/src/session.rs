@@ -0,0 +1,168 @@
+// Saving and restoring shell sessions.
+//
+// A session file is plain Pyro source: the imports that were run, then the
+// classes and functions the user defined, then `let` bindings for globals
+// holding data. Restoring it is just running it.
+
+use pyro_core::ast::{to_source, Expr, Program, Stmt, Type};
+use pyro_core::interpreter::{Interpreter, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+pub const DEFAULT_SESSION_FILE: &str = "session.pyrorepl";
+
+pub struct Snapshot {
+    pub source: String,
+    // Globals that can't be written as source, e.g. channels or instances
+    pub skipped: Vec<String>,
+}
+
+// `builtin` holds the globals the shell started with, which are not saved
+pub fn snapshot(interpreter: &Interpreter, imports: &[String], builtin: &HashSet<String>) -> Snapshot {
+    let mut globals: Vec<(String, Value)> = interpreter
+        .globals()
+        .into_iter()
+        .filter(|(name, _)| !builtin.contains(name) && !name.starts_with('_'))
+        .collect();
+    globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut classes = Vec::new();
+    let mut functions = Vec::new();
+    let mut data = Vec::new();
+    let mut skipped = Vec::new();
+    let classes_by_name: HashMap<&str, &Value> = globals
+        .iter()
+        .filter(|(_, v)| matches!(v, Value::Class { .. }))
+        .map(|(name, value)| (name.as_str(), value))
+        .collect();
+
+    for (name, value) in &globals {
+        match value {
+            // Closures depend on a call that has returned, so they can't be saved
+            Value::Function { generics, params, body, partial_args, env: None } if partial_args.is_empty() => {
+                functions.push(fn_decl(name, generics, params, body));
+            }
+            Value::Class { .. } => {
+                // Declared below, parents first
+            }
+            Value::NativeModule(_) if imports.iter().any(|i| i.rsplit('.').next() == Some(name.as_str())) => {}
+            _ => match value_to_expr(value) {
+                Some(expr) => data.push(Stmt::VarDecl { name: name.to_string(), typ: None, value: expr, mutable: false }),
+                None => skipped.push(name.to_string()),
+            },
+        }
+    }
+
+    // Parents are declared before the classes that extend them
+    let mut declared = HashSet::new();
+    for name in classes_by_name.keys() {
+        declare_class(name, &classes_by_name, &mut declared, &mut classes);
+    }
+
+    let mut statements: Vec<Stmt> = imports.iter().map(|i| Stmt::Import(i.clone())).collect();
+    statements.extend(classes);
+    statements.extend(functions);
+    statements.extend(data);
+    skipped.sort();
+    Snapshot { source: to_source(&Program { statements }), skipped }
+}
+
+fn declare_class<'a>(
+    name: &'a str,
+    classes: &HashMap<&'a str, &Value>,
+    declared: &mut HashSet<&'a str>,
+    out: &mut Vec<Stmt>,
+) {
+    let Some(Value::Class { name: class_name, parent, methods }) = classes.get(name) else {
+        return;
+    };
+    if !declared.insert(name) {
+        return;
+    }
+    let parent_methods = match parent.as_deref().and_then(|p| classes.get_key_value(p)) {
+        Some((parent_name, Value::Class { methods, .. })) => {
+            declare_class(parent_name, classes, declared, out);
+            Some(methods.clone())
+        }
+        _ => None,
+    };
+
+    let mut names: Vec<&String> = methods.keys().collect();
+    names.sort();
+    let mut decls = Vec::new();
+    for method_name in names {
+        let Value::Function { generics, params, body, .. } = &methods[method_name] else {
+            continue;
+        };
+        // Inherited methods come back from the parent declaration
+        let inherited = parent_methods.as_ref().and_then(|m| m.get(method_name)).is_some_and(
+            |m| matches!(m, Value::Function { body: parent_body, .. } if Arc::ptr_eq(parent_body, body)),
+        );
+        if !inherited {
+            decls.push(fn_decl(method_name, generics, params, body));
+        }
+    }
+    out.push(Stmt::ClassDecl { name: class_name.clone(), parent: parent.clone(), methods: decls });
+}
+
+// Source of a function defined earlier, for `:edit`
+pub fn function_source(interpreter: &Interpreter, name: &str) -> Option<String> {
+    match &interpreter.global(name)? {
+        Value::Function { generics, params, body, partial_args, .. } if partial_args.is_empty() => {
+            Some(to_source(&Program { statements: vec![fn_decl(name, generics, params, body)] }))
+        }
+        _ => None,
+    }
+}
+
+fn fn_decl(name: &str, generics: &[String], params: &[(String, Type)], body: &Arc<Vec<Stmt>>) -> Stmt {
+    Stmt::FnDecl {
+        name: name.to_string(),
+        generics: generics.to_vec(),
+        params: params.to_vec(),
+        // Return types aren't kept at runtime and aren't checked
+        return_type: Type::Void,
+        body: body.as_ref().clone(),
+    }
+}
+
+// Source for plain data values; None for anything else
+fn value_to_expr(value: &Value) -> Option<Expr> {
+    let exprs = |items: &[Value]| items.iter().map(value_to_expr).collect::<Option<Vec<_>>>();
+    let pairs = |items: &[(Value, Value)]| {
+        items
+            .iter()
+            .map(|(k, v)| Some((value_to_expr(k)?, value_to_expr(v)?)))
+            .collect::<Option<Vec<_>>>()
+    };
+    let wrap = |constructor: &str, inner: Expr| Expr::Call {
+        function: Box::new(Expr::Identifier(constructor.to_string())),
+        generics: Vec::new(),
+        args: vec![inner],
+    };
+    Some(match value {
+        Value::Int(i) => Expr::LiteralInt(*i),
+        Value::Float(f) if f.is_finite() => Expr::LiteralFloat(*f),
+        Value::Bool(b) => Expr::LiteralBool(*b),
+        // There are no escape sequences, so a quote can't be written back
+        Value::String(s) if !s.contains('"') => Expr::LiteralString(s.to_string()),
+        Value::Decimal(d) => wrap("decimal", Expr::LiteralString(d.to_string())),
+        Value::Char(c) if *c != '\'' && *c != '\n' => Expr::LiteralChar(*c),
+        Value::List(items) => Expr::List(exprs(items)?),
+        Value::Tuple(items) => Expr::Tuple(exprs(items)?),
+        // `{}` reads back as a dict
+        Value::Set(items) if !items.is_empty() => Expr::Set(exprs(items)?),
+        Value::Dict(items) => Expr::Dict(pairs(items)?),
+        Value::ListMutable(items) => wrap("ListMutable", Expr::List(exprs(&items.read().ok()?)?)),
+        Value::TupleMutable(items) => wrap("TupleMutable", Expr::Tuple(exprs(&items.read().ok()?)?)),
+        Value::SetMutable(items) => {
+            let items = items.read().ok()?;
+            if items.is_empty() {
+                return None;
+            }
+            wrap("SetMutable", Expr::Set(exprs(&items)?))
+        }
+        Value::DictMutable(items) => wrap("DictMutable", Expr::Dict(pairs(&items.read().ok()?)?)),
+        _ => return None,
+    })
+}
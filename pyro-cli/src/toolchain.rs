@@ -0,0 +1,74 @@
+// The environment pyro.lock records: the pyro version and platform it was
+// written on and, for projects with Rust dependencies, the rustc and cargo
+// that built them. `pyro run` warns when this machine differs, and
+// `pyro mod sync` says what to change.
+
+use crate::manifest::LockEnvironment;
+use crate::output;
+use std::path::Path;
+use std::process::Command;
+
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+// The version a tool reports, e.g. "1.82.0" from "rustc 1.82.0 (f6e511eec
+// 2024-10-15)". None if it isn't installed.
+fn tool_version(tool: &str) -> Option<String> {
+    let result = Command::new(tool).arg("--version").output().ok()?;
+    if !result.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&result.stdout).split_whitespace().nth(1).map(String::from)
+}
+
+// This machine's environment, with the Rust toolchain if `rust`
+pub fn current(rust: bool) -> LockEnvironment {
+    LockEnvironment {
+        pyro: env!("CARGO_PKG_VERSION").to_string(),
+        platform: platform(),
+        rustc: if rust { tool_version("rustc") } else { None },
+        cargo: if rust { tool_version("cargo") } else { None },
+    }
+}
+
+// How this machine differs from `locked`, one message each. The toolchain is
+// only compared if `rust` and pyro.lock records it.
+pub fn differences(locked: &LockEnvironment, rust: bool) -> Vec<String> {
+    let current = current(rust && (locked.rustc.is_some() || locked.cargo.is_some()));
+    let mut differences = Vec::new();
+    let mut compare = |what: &str, locked: Option<&String>, current: Option<&String>| {
+        let Some(locked) = locked else { return };
+        if Some(locked) != current {
+            let current = current.map_or("nothing", String::as_str);
+            differences.push(format!("{} {} is locked, but this machine has {}", what, locked, current));
+        }
+    };
+    compare("pyro", Some(&locked.pyro), Some(&current.pyro));
+    compare("platform", Some(&locked.platform), Some(&current.platform));
+    if rust {
+        compare("rustc", locked.rustc.as_ref(), current.rustc.as_ref());
+        compare("cargo", locked.cargo.as_ref(), current.cargo.as_ref());
+    }
+    differences
+}
+
+// Warns if the project at `root` has a pyro.lock written in another
+// environment. `rust` says whether the program is built natively, so the
+// Rust toolchain matters.
+pub fn warn_if_changed(root: &Path, rust: bool) {
+    let Ok(lockfile) = crate::manifest::LockFile::load_from(root) else {
+        return;
+    };
+    let Some(locked) = lockfile.environment else {
+        return;
+    };
+    let differences = differences(&locked, rust);
+    if differences.is_empty() {
+        return;
+    }
+    for difference in &differences {
+        output::warn(format!("pyro.lock: {}", difference));
+    }
+    output::warn("Builds may differ from where pyro.lock was written; see 'pyro mod sync'");
+}
@@ -1,12 +1,130 @@
+use crate::diagnostics::{CheckFailure, ParseFailure};
+use crate::output;
 use anyhow::{Context, Result};
-use pyro_core::ast::Stmt;
+use pyro_core::ast::{Expr, Program, Stmt};
+use pyro_core::diagnostics::Severity;
+use pyro_core::interpreter::{MAIN_MODULE, MODULE_NAME};
 use pyro_core::lexer::Lexer;
+use pyro_core::plugin;
 use pyro_core::parser::Parser as PyroParser;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+// Parsed files are cached as JSON under ~/.pyro/cache/ast, keyed by a hash of
+// the source and the pyro version, so unchanged files skip lexing and parsing.
+// The cache is best effort: any problem reading or writing it falls back to
+// parsing. Coverage runs always parse, as they need instrumented programs.
+pub fn parse_cached(path: &Path, content: &str) -> Result<Program> {
+    if crate::coverage::enabled() {
+        return crate::coverage::parse(path, content);
+    }
+    let cache_file = ast_cache_dir().map(|dir| {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update([0]);
+        hasher.update(content.as_bytes());
+        dir.join(format!("{}.json", hex::encode(hasher.finalize())))
+    });
+
+    if let Some(cached) = cache_file.as_ref().and_then(|f| fs::read(f).ok()) {
+        if let Ok(program) = serde_json::from_slice(&cached) {
+            return Ok(program);
+        }
+    }
+
+    let mut lexer = Lexer::new(content);
+    let tokens = lexer.tokenize();
+    for diagnostic in lexer.diagnostics() {
+        output::diagnostic(&path.display(), diagnostic);
+    }
+    let program = PyroParser::new(&tokens)
+        .parse()
+        .map_err(|message| ParseFailure { path: path.to_path_buf(), message, imported_by: Vec::new() })?;
+
+    if let Some(cache_file) = cache_file {
+        if let Ok(json) = serde_json::to_vec(&program) {
+            // Write then rename so a concurrent run never reads half a file
+            let tmp = cache_file.with_extension(format!("tmp{}", std::process::id()));
+            if fs::create_dir_all(cache_file.parent().unwrap()).is_ok() && fs::write(&tmp, json).is_ok() {
+                let _ = fs::rename(&tmp, &cache_file);
+            }
+        }
+    }
+    Ok(program)
+}
+
+// Set PYRO_NO_CACHE to always parse
+fn ast_cache_dir() -> Option<PathBuf> {
+    if std::env::var_os("PYRO_NO_CACHE").is_some() {
+        return None;
+    }
+    let home = std::env::var("HOME").ok()?;
+    Some(PathBuf::from(home).join(".pyro/cache/ast"))
+}
+
+// Inlines the entry file of a program and everything it imports, without
+// checking it, for callers that only look at what it imports
+pub fn load_file(path: PathBuf, loaded: &mut HashSet<PathBuf>, statements: &mut Vec<Stmt>) -> Result<()> {
+    process_module(path, MAIN_MODULE, &mut Vec::new(), loaded, statements)
+}
+
+// Inlines the entry file of a program and everything it imports, then runs
+// the checks done before a program runs
 pub fn process_file(path: PathBuf, loaded: &mut HashSet<PathBuf>, statements: &mut Vec<Stmt>) -> Result<()> {
+    load_file(path.clone(), loaded, statements)?;
+    let mut diagnostics = pyro_core::constants::check(statements).err().unwrap_or_default();
+    diagnostics.extend(pyro_core::generics::check(statements).err().unwrap_or_default());
+    diagnostics.extend(pyro_core::aliases::check(statements).err().unwrap_or_default());
+    diagnostics.extend(pyro_core::interfaces::check(statements).err().unwrap_or_default());
+    diagnostics.extend(pyro_core::flow::check(statements).err().unwrap_or_default());
+    diagnostics.extend(pyro_core::stubs::check(statements).err().unwrap_or_default());
+    // Warnings, e.g. unreachable code, are shown but don't stop the program
+    let (warnings, diagnostics): (Vec<_>, Vec<_>) =
+        diagnostics.into_iter().partition(|d| d.severity == Severity::Warning);
+    for warning in &warnings {
+        output::diagnostic(path.display(), warning);
+    }
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(CheckFailure { diagnostics }.into())
+    }
+}
+
+// Inlines a file imported from the shell, which runs as the main module
+pub fn process_import(path: PathBuf, loaded: &mut HashSet<PathBuf>, statements: &mut Vec<Stmt>) -> Result<()> {
+    let name = module_name(&path.to_string_lossy());
+    process_module(path, &name, &mut Vec::new(), loaded, statements)?;
+    statements.push(set_module_name(MAIN_MODULE));
+    Ok(())
+}
+
+// `import "lib/utils.pyro"` runs with `__name__` set to "utils"
+fn module_name(import_path: &str) -> String {
+    Path::new(import_path).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn set_module_name(name: &str) -> Stmt {
+    Stmt::VarDecl {
+        name: MODULE_NAME.to_string(),
+        typ: None,
+        value: Expr::LiteralString(name.to_string()),
+        mutable: true,
+    }
+}
+
+// Imports are inlined, so `__name__` is set before a file's statements and
+// set back after each of its imports. `importers` are the files whose
+// imports led here, entry file first, for errors in imported files.
+fn process_module(
+    path: PathBuf,
+    name: &str,
+    importers: &mut Vec<PathBuf>,
+    loaded: &mut HashSet<PathBuf>,
+    statements: &mut Vec<Stmt>,
+) -> Result<()> {
     // Canonicalize path to handle relative paths correctly and deduplicate
     let canonical_path = if path.exists() {
         fs::canonicalize(&path)?
@@ -19,71 +137,172 @@ pub fn process_file(path: PathBuf, loaded: &mut HashSet<PathBuf>, statements: &m
     }
     loaded.insert(canonical_path);
 
-    let content = fs::read_to_string(&path)
-        .with_context(|| format!("Could not read file {:?}", path))?;
-    
-    // 1. Lex
-    let mut lexer = Lexer::new(&content);
-    let tokens = lexer.tokenize();
+    let content = fs::read_to_string(&path).with_context(|| {
+        if importers.is_empty() {
+            format!("Could not read file {:?}", path)
+        } else {
+            format!("Could not read file {:?} (import chain: {})", path, import_chain(importers, &path))
+        }
+    })?;
     
-    // 2. Parse
-    let mut parser = PyroParser::new(&tokens);
-    let program = parser.parse().map_err(|e| anyhow::anyhow!("Parse error in {:?}: {}", path, e))?;
+    let program = match parse_cached(&path, &content) {
+        Ok(program) => program,
+        Err(error) => {
+            return Err(match error.downcast::<ParseFailure>() {
+                Ok(failure) => ParseFailure { imported_by: importers.clone(), ..failure }.into(),
+                Err(error) => error,
+            })
+        }
+    };
 
+    statements.push(set_module_name(name));
+    importers.push(path.clone());
     for stmt in program.statements {
         if let Stmt::Import(import_path) = &stmt {
-            if import_path.starts_with("std.") {
-                statements.push(stmt.clone());
+            // A plugin library next to the importing file is found from anywhere
+            if let Some(library) = import_path.strip_prefix(plugin::IMPORT_PREFIX) {
+                let beside = path.parent().unwrap().join(library);
+                let library = if beside.exists() { beside.to_string_lossy().into_owned() } else { library.to_string() };
+                statements.push(Stmt::Import(format!("{}{}", plugin::IMPORT_PREFIX, library)));
                 continue;
             }
-            let mut dep_path = PathBuf::from(import_path);
-            
-            // Resume resolution logic:
-            // 1. Check relative to current file
-            let relative = path.parent().unwrap().join(import_path);
-            if relative.exists() {
-                dep_path = relative;
-            } else {
-                // 2. Check ~/.pyro/pkg
-                if let Ok(home) = std::env::var("HOME") {
-                    let pkg_path = PathBuf::from(home).join(".pyro/pkg").join(import_path);
-                    if pkg_path.exists() {
-                        dep_path = pkg_path;
-                    }
-                }
-                
-                // 3. Check .externs relative to possible pyro.mod locations
-                // This is a bit tricky as we don't know where pyro.mod is easily without searching up.
-                // But for now, let's assume it's in the same dir as the file, or parent.
-                // A better approach is to search up for .externs
-                if !dep_path.exists() {
-                    let mut current = path.parent().unwrap().to_path_buf();
-                    loop {
-                        let externs_path = current.join(".externs").join(import_path);
-                         // Check for .pyro extension if not present? The import_path usually implies .pyro or is bare.
-                         // The parser usually passes "foo.pyro" if it was `import "foo.pyro"`, or "foo" if `import foo`.
-                         // If "foo", we need to append .pyro
-                        let target = if externs_path.to_string_lossy().ends_with(".pyro") {
-                             externs_path
-                        } else {
-                             let mut p = externs_path.clone().into_os_string();
-                             p.push(".pyro");
-                             PathBuf::from(p)
-                        };
-
-                        if target.exists() {
-                            dep_path = target;
-                            break;
-                        }
-                        if !current.pop() { break; }
-                    }
-                }
+            // Native modules (stdlib, Rust crates) are resolved by the interpreter
+            if is_native_import(import_path) {
+                statements.push(stmt.clone());
+                continue;
             }
-            
-            process_file(dep_path, loaded, statements)?;
+            let dep_path = resolve_import(&path, import_path);
+            process_module(dep_path, &module_name(import_path), importers, loaded, statements)?;
+            statements.push(set_module_name(name));
         } else {
             statements.push(stmt);
         }
     }
+    importers.pop();
     Ok(())
 }
+
+fn is_native_import(import_path: &str) -> bool {
+    import_path.starts_with("std.") || import_path.starts_with("rust.") || import_path.starts_with(plugin::IMPORT_PREFIX)
+}
+
+// Where `import_path`, imported by the file at `importer`, is loaded from
+fn resolve_import(importer: &Path, import_path: &str) -> PathBuf {
+    let mut dep_path = PathBuf::from(import_path);
+    
+    // Resume resolution logic:
+    // 1. Check relative to current file
+    let relative = importer.parent().unwrap().join(import_path);
+    if relative.exists() {
+        dep_path = relative;
+    } else {
+        // 2. Check ~/.pyro/pkg
+        if let Ok(home) = std::env::var("HOME") {
+            let pkg_path = PathBuf::from(home).join(".pyro/pkg").join(import_path);
+            if pkg_path.exists() {
+                dep_path = pkg_path;
+            }
+        }
+        
+        // 3. Check .externs relative to possible pyro.mod locations
+        // This is a bit tricky as we don't know where pyro.mod is easily without searching up.
+        // But for now, let's assume it's in the same dir as the file, or parent.
+        // A better approach is to search up for .externs
+        if !dep_path.exists() {
+            let mut current = importer.parent().unwrap().to_path_buf();
+            loop {
+                let externs_path = current.join(".externs").join(import_path);
+                 // Check for .pyro extension if not present? The import_path usually implies .pyro or is bare.
+                 // The parser usually passes "foo.pyro" if it was `import "foo.pyro"`, or "foo" if `import foo`.
+                 // If "foo", we need to append .pyro
+                let target = if externs_path.to_string_lossy().ends_with(".pyro") {
+                     externs_path
+                } else {
+                     let mut p = externs_path.clone().into_os_string();
+                     p.push(".pyro");
+                     PathBuf::from(p)
+                };
+
+                if target.exists() {
+                    dep_path = target;
+                    break;
+                }
+                if !current.pop() { break; }
+            }
+        }
+    }
+    dep_path
+}
+
+// A path as shown in import chains, with the home directory as ~
+pub fn display_path(path: &Path) -> String {
+    let home = std::env::var("HOME").ok().filter(|home| !home.is_empty());
+    match home.and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+// "main.pyro -> utils.pyro -> ~/.pyro/pkg/json/json.pyro"
+pub fn import_chain(importers: &[PathBuf], file: &Path) -> String {
+    let mut files: Vec<String> = importers.iter().map(|path| display_path(path)).collect();
+    files.push(display_path(file));
+    files.join(" -> ")
+}
+
+// What an uncaught error came from, to find which imported file raised it
+pub enum Origin<'a> {
+    // The innermost function call it unwound through
+    Function(&'a str),
+    // Top-level code of a module, by its `__name__`
+    Module(&'a str),
+}
+
+// The files whose imports lead from `entry` to the imported file the origin
+// is in, entry file first, and that file. Imports are walked in the order
+// they're inlined, so as when running, a later definition of a function
+// replaces an earlier one. None if the origin is in the entry file itself or
+// can't be found.
+pub fn find_in_imports(entry: &Path, origin: &Origin) -> Option<(Vec<PathBuf>, PathBuf)> {
+    fn visit(
+        path: PathBuf,
+        origin: &Origin,
+        importers: &mut Vec<PathBuf>,
+        loaded: &mut HashSet<PathBuf>,
+        found: &mut Option<(Vec<PathBuf>, PathBuf)>,
+    ) {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if !loaded.insert(canonical) {
+            return;
+        }
+        // The program already loaded once, so this only fails if a file
+        // changed since; that file is skipped
+        let Ok(content) = fs::read_to_string(&path) else { return };
+        let Ok(program) = PyroParser::new(&Lexer::new(&content).tokenize()).parse() else { return };
+        if let Origin::Module(name) = origin {
+            if !importers.is_empty() && path.file_stem().is_some_and(|stem| stem.to_string_lossy() == *name) {
+                *found = Some((importers.clone(), path.clone()));
+            }
+        }
+        importers.push(path.clone());
+        for stmt in &program.statements {
+            match (stmt, origin) {
+                (Stmt::Import(import_path), _) if !is_native_import(import_path) => {
+                    visit(resolve_import(&path, import_path), origin, importers, loaded, found);
+                }
+                (Stmt::FnDecl { name, .. } | Stmt::ClassDecl { name, .. }, Origin::Function(function))
+                    if name == function =>
+                {
+                    let (importers, file) = importers.split_at(importers.len() - 1);
+                    *found = Some((importers.to_vec(), file[0].clone()));
+                }
+                _ => {}
+            }
+        }
+        importers.pop();
+    }
+
+    let mut found = None;
+    visit(entry.to_path_buf(), origin, &mut Vec::new(), &mut HashSet::new(), &mut found);
+    found.filter(|(importers, _)| !importers.is_empty())
+}
@@ -60,6 +60,154 @@ else
     exit 1
 fi
 
+# 6. Run a script from pyro.mod
+printf '\n[scripts]\ngreet = "echo hello from $PYRO_PACKAGE_NAME"\n' >> pyro.mod
+if "$PYRO_BIN" x greet | grep -q "hello from consumer"; then
+    echo "pyro x runs scripts"
+else
+    echo "pyro x FAILED to run the script"
+    exit 1
+fi
+
+# 7. Run build.pyro before the program
+printf '\n[build]\nhook = true\n' >> pyro.mod
+echo 'import std.fs
+fs.write("generated.pyro", "let GENERATED = 1")' > build.pyro
+echo 'import "generated.pyro"
+print(GENERATED)' > app.pyro
+if "$PYRO_BIN" run app.pyro | grep -q "1"; then
+    echo "build.pyro runs before the program"
+else
+    echo "build.pyro did NOT run"
+    exit 1
+fi
+
+# 8. pyro mod verify notices changes to installed packages and restores them
+"$PYRO_BIN" mod verify
+INSTALLED="$HOME/.pyro/pkg/${PKG_URL//:\/\//:/}"
+echo "# local edit" >> "$INSTALLED/main.pyro"
+if "$PYRO_BIN" mod verify; then
+    echo "pyro mod verify MISSED a modified package"
+    exit 1
+fi
+"$PYRO_BIN" mod verify --restore
+"$PYRO_BIN" mod verify
+echo "pyro mod verify restores packages"
+
+# 9. Dependencies of dependencies are installed and locked
+cd ..
+mkdir -p leaf-pkg
+cd leaf-pkg
+git init -q
+echo "def leaf(): return 1" > leaf.pyro
+git add . && git commit -q -m "Leaf"
+LEAF_URL="file://$(pwd)"
+cd "$DUMMY_PKG_PATH"
+printf '[package]\nname = "dummy"\nversion = "0.1.0"\n\n[dependencies]\n"%s" = "HEAD"\n' "$LEAF_URL" > pyro.mod
+git add . && git commit -q -m "Depend on leaf"
+cd "$TEST_DIR/consumer"
+# Resolving HEAD doesn't fetch into an existing clone
+rm -rf pyro.lock "$INSTALLED"
+"$PYRO_BIN" install
+if grep -q "$LEAF_URL" pyro.lock; then
+    echo "pyro.lock contains the transitive dependency"
+else
+    echo "pyro.lock MISSING the transitive dependency"
+    cat pyro.lock
+    exit 1
+fi
+
+# 10. --offline installs from the cache and refuses to download
+if "$PYRO_BIN" install --offline | grep -q "0 installed, 0 updated, 2 up to date, 0 failed"; then
+    echo "pyro install --offline uses the cache and summarizes"
+else
+    echo "pyro install --offline did NOT install from the cache"
+    exit 1
+fi
+if "$PYRO_BIN" get --offline "file://$TEST_DIR/not-cached" 2>&1 | grep -q "offline"; then
+    echo "pyro get --offline refuses to download"
+else
+    echo "pyro get --offline did NOT refuse to download"
+    exit 1
+fi
+
+# 11. Packages install side by side, and every failure is reported
+cp pyro.mod pyro.mod.bak
+sed -i.tmp "s|^\[dependencies\]\$|[dependencies]\n\"file://$TEST_DIR/missing-a\" = \"HEAD\"\n\"file://$TEST_DIR/missing-b\" = \"HEAD\"|" pyro.mod
+if "$PYRO_BIN" install --offline --jobs 2 2>&1 | grep -q "2 package(s) failed"; then
+    echo "pyro install reports every failed package"
+else
+    echo "pyro install did NOT report both failed packages"
+    exit 1
+fi
+mv pyro.mod.bak pyro.mod
+
+# 12. pyro mod outdated lists newer release tags with upgrade hints
+cd ..
+mkdir -p tagged-pkg
+cd tagged-pkg
+git init -q
+echo "def tagged(): return 1" > tagged.pyro
+git add . && git commit -q -m "Tagged"
+git tag v1.0.0 && git tag v1.2.0 && git tag v2.0.0 && git tag v2.1.0-beta
+TAGGED_URL="file://$(pwd)"
+cd "$TEST_DIR/consumer"
+"$PYRO_BIN" get "$TAGGED_URL@v1.0.0"
+OUTDATED=$("$PYRO_BIN" mod outdated)
+if echo "$OUTDATED" | grep -Eq "v1.0.0 +v1.2.0 +v2.0.0 +pyro get $TAGGED_URL@v1.2.0"; then
+    echo "pyro mod outdated lists compatible and latest versions"
+else
+    echo "pyro mod outdated did NOT list the newer versions. Found:"
+    echo "$OUTDATED"
+    exit 1
+fi
+"$PYRO_BIN" get "$TAGGED_URL@v2.0.0"
+if "$PYRO_BIN" mod outdated --offline 2>&1 | grep -q "All packages are up to date"; then
+    echo "pyro mod outdated ignores pre-releases"
+else
+    echo "pyro mod outdated did NOT find the packages up to date"
+    exit 1
+fi
+
+# 13. pyro.lock records the environment, run warns when it differs, and
+# pyro mod sync reinstalls exactly what is locked
+if grep -q "^\[environment\]" pyro.lock && grep -q "platform = " pyro.lock; then
+    echo "pyro.lock records the environment"
+else
+    echo "pyro.lock MISSING the environment. Found:"
+    cat pyro.lock
+    exit 1
+fi
+sed -i.tmp 's/^pyro = .*/pyro = "0.0.0-old"/' pyro.lock
+if "$PYRO_BIN" run app.pyro 2>&1 | grep -q "pyro 0.0.0-old is locked"; then
+    echo "pyro run warns about a different pyro version"
+else
+    echo "pyro run did NOT warn about the pyro version"
+    exit 1
+fi
+echo "# local edit" >> "$INSTALLED/main.pyro"
+"$PYRO_BIN" mod sync --offline
+"$PYRO_BIN" mod verify
+echo "pyro mod sync restores locked packages"
+
+# 14. build.pyro runs interpreted and sandboxed in a project with [rust]
+# dependencies
+mkdir -p "$TEST_DIR/native"
+cd "$TEST_DIR/native"
+printf '[package]\nname = "native"\nversion = "0.1.0"\n\n[rust]\nrand = "0.8"\n\n[build]\nhook = true\n' > pyro.mod
+echo 'import std.fs
+fs.write("generated.pyro", "let GENERATED = 1")' > build.pyro
+# No tests, so only the hook does anything. Run from outside the project,
+# whose hook is found from the test path.
+cd "$TEST_DIR"
+"$PYRO_BIN" test native || true
+if [ -f native/generated.pyro ]; then
+    echo "build.pyro runs in a project with [rust] dependencies"
+else
+    echo "build.pyro did NOT run in a project with [rust] dependencies"
+    exit 1
+fi
+
 echo "Verification Passed!"
 # Cleanup
 rm -rf "$TEST_DIR"
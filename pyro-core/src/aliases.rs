@@ -0,0 +1,135 @@
+// Type aliases. `type ID = int | string` names a type; wherever `ID` is
+// written, the aliased type is meant. `check` rejects aliases that expand to
+// themselves (`type Tree = int | Pair<Tree>`), which could never be written
+// out, and `resolve` replaces every use of an alias with what it stands for,
+// for passes such as the transpiler that need real types. A record's fields
+// can still mention the record, since a record is a type of its own rather
+// than an expansion.
+
+use crate::ast::visit::{walk_block, walk_block_mut, Visitor, VisitorMut};
+use crate::ast::{Stmt, Type};
+use crate::diagnostics::Diagnostic;
+use crate::generics::substitute;
+use std::collections::{HashMap, HashSet};
+
+struct Alias {
+    generics: Vec<String>,
+    alias: Type,
+}
+
+#[derive(Default)]
+struct Declarations {
+    aliases: HashMap<String, Alias>,
+    // In declaration order, so problems are reported in that order
+    order: Vec<String>,
+}
+
+impl Visitor for Declarations {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::TypeAlias { name, generics, alias } = stmt {
+            self.order.push(name.clone());
+            self.aliases.insert(name.clone(), Alias { generics: generics.clone(), alias: alias.clone() });
+        }
+    }
+}
+
+fn declarations(statements: &[Stmt]) -> Declarations {
+    let mut declarations = Declarations::default();
+    walk_block(&mut declarations, statements);
+    declarations
+}
+
+// Names of the aliases a type mentions
+fn mentions(typ: &Type, aliases: &HashMap<String, Alias>, found: &mut Vec<String>) {
+    match typ {
+        Type::UserDefined(name, args) => {
+            if aliases.contains_key(name) && !found.contains(name) {
+                found.push(name.clone());
+            }
+            args.iter().for_each(|arg| mentions(arg, aliases, found));
+        }
+        Type::Union(types) => types.iter().for_each(|t| mentions(t, aliases, found)),
+        Type::Variadic(each) => mentions(each, aliases, found),
+        _ => {}
+    }
+}
+
+// The aliases from `name` back to `target`, if expanding `name` reaches it
+fn path_to(name: &str, target: &str, aliases: &HashMap<String, Alias>, visited: &mut HashSet<String>) -> Option<Vec<String>> {
+    if !visited.insert(name.to_string()) {
+        return None;
+    }
+    let mut next = Vec::new();
+    mentions(&aliases[name].alias, aliases, &mut next);
+    for mentioned in next {
+        if mentioned == target {
+            return Some(vec![name.to_string(), mentioned]);
+        }
+        if let Some(mut path) = path_to(&mentioned, target, aliases, visited) {
+            path.insert(0, name.to_string());
+            return Some(path);
+        }
+    }
+    None
+}
+
+// Returns every problem found, with the code "alias"
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let declarations = declarations(statements);
+    let mut errors = Vec::new();
+    for name in &declarations.order {
+        if let Some(path) = path_to(name, name, &declarations.aliases, &mut HashSet::new()) {
+            errors.push(
+                Diagnostic::error("alias", format!("Type alias '{}' expands to itself", name))
+                    .with_note(path.join(" -> "))
+                    .with_note("declare the recursive part as a record instead"),
+            );
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+struct Resolver<'a> {
+    aliases: &'a HashMap<String, Alias>,
+    // Aliases being expanded, so a recursive one that wasn't checked stops
+    expanding: Vec<String>,
+}
+
+impl VisitorMut for Resolver<'_> {
+    fn visit_type_mut(&mut self, typ: &mut Type) {
+        match typ {
+            Type::UserDefined(name, args) => {
+                args.iter_mut().for_each(|arg| self.visit_type_mut(arg));
+                let Some(alias) = self.aliases.get(name.as_str()) else { return };
+                if self.expanding.contains(name) {
+                    return;
+                }
+                // Parameters given no argument (`Pair` for `Pair<T>`) stay as they are
+                let bindings = alias.generics.iter().cloned().zip(args.iter().cloned()).collect();
+                self.expanding.push(name.clone());
+                let mut expanded = substitute(&alias.alias, &bindings);
+                self.visit_type_mut(&mut expanded);
+                self.expanding.pop();
+                *typ = expanded;
+            }
+            Type::Union(types) => types.iter_mut().for_each(|t| self.visit_type_mut(t)),
+            Type::Variadic(each) => self.visit_type_mut(each),
+            _ => {}
+        }
+    }
+}
+
+// Expands every alias and drops their declarations
+pub fn resolve(statements: Vec<Stmt>) -> Vec<Stmt> {
+    let declarations = declarations(&statements);
+    if declarations.aliases.is_empty() {
+        return statements;
+    }
+    let mut statements: Vec<Stmt> = statements.into_iter().filter(|stmt| !matches!(stmt, Stmt::TypeAlias { .. })).collect();
+    walk_block_mut(&mut Resolver { aliases: &declarations.aliases, expanding: Vec::new() }, &mut statements);
+    statements
+}
@@ -1,7 +1,24 @@
-#[derive(Debug, Clone, PartialEq)]
+pub mod visit;
+mod printer;
+
+pub use printer::{expr_to_source, to_source, type_to_source};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Type {
     Int,
     Float,
+    // Fixed-size numbers for extern signatures. At runtime they are ints
+    // and floats; the size applies where values cross into Rust.
+    Int8,
+    Int16,
+    Int32,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Char,
     Bool,
     String,
     Void,
@@ -15,9 +32,108 @@ pub enum Type {
     DictMutable,
     UserDefined(String, Vec<Type>),
     Union(Vec<Type>),
+    // Any value, checked only as the program runs. `Any` is written as
+    // `any`; `ImplicitAny` is what a parameter without an annotation gets,
+    // which is the same except that `pyro check --strict` reports it.
+    Any,
+    ImplicitAny,
+    // The last parameter written `*name: T`, taking any number of
+    // arguments, each a T. The function sees them as a list.
+    Variadic(Box<Type>),
+}
+
+// The parameter the argument at `index` is passed as, with the type that
+// argument must have, if there is one
+pub fn parameter_for(params: &[(String, Type)], index: usize) -> Option<(&str, &Type)> {
+    match params.last() {
+        Some((name, Type::Variadic(each))) if index + 1 >= params.len() => Some((name, each)),
+        _ => params.get(index).map(|(name, typ)| (name.as_str(), typ)),
+    }
+}
+
+// What a record gets from `derives (...)` instead of hand-written code:
+// `==` by field values, ordering field by field, use as a dict key or set
+// member, and printing as `Point(x=1, y=2)`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Derives {
+    pub eq: bool,
+    pub ord: bool,
+    pub hash: bool,
+    pub show: bool,
+}
+
+impl Derives {
+    pub const NAMES: [&'static str; 4] = ["eq", "ord", "hash", "show"];
+
+    // Adds one by name. Ordering and hashing compare with eq, so they need it.
+    pub fn add(&mut self, name: &str) -> Result<(), String> {
+        let flag = match name {
+            "eq" => &mut self.eq,
+            "ord" => &mut self.ord,
+            "hash" => &mut self.hash,
+            "show" => &mut self.show,
+            _ => return Err(format!("Unknown derive '{}', expected one of {}", name, Self::NAMES.join(", "))),
+        };
+        if std::mem::replace(flag, true) {
+            return Err(format!("'{}' is derived more than once", name));
+        }
+        Ok(())
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        for (name, set) in [("ord", self.ord), ("hash", self.hash)] {
+            if set && !self.eq {
+                return Err(format!("Deriving '{}' also needs 'eq'", name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        let set = [self.eq, self.ord, self.hash, self.show];
+        Self::NAMES.iter().zip(set).filter(|(_, set)| *set).map(|(name, _)| *name).collect()
+    }
+}
+
+// The name that binds nothing: `let _ = f()` runs f and drops the result.
+pub const DISCARD: &str = "_";
+
+// The name an import binds: the last segment of its path, so "std.math" binds
+// `math` and `import rust "serde_json::value"` binds `value`
+pub fn import_name(path: &str) -> &str {
+    path.rsplit(['.', ':']).next().unwrap_or(path)
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Numeric types with their Pyro and Rust names. `int` and `float` are 64 bits.
+pub const NUMERIC_TYPES: &[(&str, Type, &str)] = &[
+    ("int", Type::Int, "i64"),
+    ("float", Type::Float, "f64"),
+    ("int8", Type::Int8, "i8"),
+    ("int16", Type::Int16, "i16"),
+    ("int32", Type::Int32, "i32"),
+    ("uint8", Type::UInt8, "u8"),
+    ("uint16", Type::UInt16, "u16"),
+    ("uint32", Type::UInt32, "u32"),
+    ("uint64", Type::UInt64, "u64"),
+    ("float32", Type::Float32, "f32"),
+];
+
+impl Type {
+    pub fn numeric(name: &str) -> Option<Type> {
+        NUMERIC_TYPES.iter().find(|(n, ..)| *n == name).map(|(_, t, _)| t.clone())
+    }
+
+    // The Rust type externs use for this numeric type
+    pub fn rust_numeric(&self) -> Option<&'static str> {
+        NUMERIC_TYPES.iter().find(|(_, t, _)| t == self).map(|(.., r)| *r)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float | Type::Float32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum BinaryOp {
     Add,
     Sub,
@@ -29,14 +145,17 @@ pub enum BinaryOp {
     Gt,
     Lte,
     Gte,
+    // Membership: `x in items`
+    In,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expr {
     LiteralInt(i64),
     LiteralFloat(f64),
     LiteralBool(bool),
     LiteralString(String),
+    LiteralChar(char),
     Identifier(String),
     Binary {
         left: Box<Expr>,
@@ -57,13 +176,105 @@ pub enum Expr {
         args: Vec<Expr>,
     },
     List(Vec<Expr>),
+    // `*items` in a call's arguments or a list literal: the elements of a
+    // list or tuple, in place
+    Spread(Box<Expr>),
+    // `value?`: the value inside an `Option.Some` or `Result.Ok`. An
+    // `Option.None` or `Result.Err` is returned from the function instead.
+    Propagate(Box<Expr>),
     Tuple(Vec<Expr>),
     Set(Vec<Expr>),
     Dict(Vec<(Expr, Expr)>),
+    // `start..end` leaves out end, `start..=end` includes it
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    // An anonymous function. The parser only produces one for the block
+    // after a call, `each(xs) as x: ...`, which becomes the last argument.
+    Lambda {
+        params: Vec<(String, Type)>,
+        body: Vec<Stmt>,
+    },
+    // f"hello {name}": the text with each expression's value put in as
+    // str() would show it
+    InterpolatedString(Vec<StringPart>),
+    // `then_expr if cond else else_expr`: only the branch taken is evaluated
+    Conditional {
+        cond: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StringPart {
+    Text(String),
+    Expr(Expr),
+}
+
+// What a `case` of a `match` compares the value with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    // `_`: matches anything without binding it
+    Wildcard,
+    // A name: matches anything and binds it, like `let`
+    Binding(String),
+    // An int, float, string, char or bool literal: matches an equal value
+    Literal(Expr),
+    // `(a, b)`: a tuple or list with one element per pattern
+    Tuple(Vec<Pattern>),
+    // `Point(x, y)`: a record of that type, its fields in declaration order
+    Record {
+        name: String,
+        fields: Vec<Pattern>,
+    },
+    // `Shape.Circle(r)` or `Shape.Empty`: that variant of an enum, its fields
+    // in declaration order
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Vec<Pattern>,
+    },
+    // `1 | 2 | 3`: matches if any alternative does. Every alternative binds
+    // the same names.
+    Or(Vec<Pattern>),
+}
 
+impl Pattern {
+    // The names the pattern binds when it matches, in order
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            Pattern::Binding(name) => vec![name.as_str()],
+            Pattern::Tuple(patterns)
+            | Pattern::Record { fields: patterns, .. }
+            | Pattern::Variant { fields: patterns, .. } => {
+                patterns.iter().flat_map(Pattern::names).collect()
+            }
+            Pattern::Or(alternatives) => alternatives.first().map(Pattern::names).unwrap_or_default(),
+            Pattern::Wildcard | Pattern::Literal(_) => Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// One alternative of an `enum`, with the names of the fields it carries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    // `case n if n > 10:`: the case only runs if this is also true, with the
+    // pattern's names bound
+    pub guard: Option<Expr>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Stmt {
     VarDecl {
         name: String,
@@ -71,6 +282,20 @@ pub enum Stmt {
         value: Expr,
         mutable: bool,
     },
+    // `const NAME = value`: bound once, never reassigned
+    ConstDecl {
+        name: String,
+        typ: Option<Type>,
+        value: Expr,
+    },
+    // `let a, _ = value` or `let (a, _) = value`: binds the elements of a
+    // tuple or list in order. Names that are DISCARD take an element without
+    // binding it.
+    Destructure {
+        names: Vec<String>,
+        value: Expr,
+        mutable: bool,
+    },
     Expr(Expr),
     If {
         cond: Expr,
@@ -81,15 +306,29 @@ pub enum Stmt {
         cond: Expr,
         body: Vec<Stmt>,
     },
+    // `for k, v in items:` unpacks each item into the names, as `let k, v`
+    // would; with one name the item is bound whole
     For {
-        item_name: String,
+        names: Vec<String>,
         iterable: Expr,
         body: Vec<Stmt>,
     },
+    // `match subject:` runs the body of the first `case` whose pattern
+    // matches, binding the names in it. Nothing runs if none does.
+    Match {
+        subject: Expr,
+        cases: Vec<MatchCase>,
+    },
     Assign {
         name: String,
         value: Expr,
     },
+    // `a, b = b, a`: assigns the elements of a tuple or list to existing
+    // variables in order, skipping DISCARD names
+    DestructureAssign {
+        names: Vec<String>,
+        value: Expr,
+    },
     Set {
         object: Expr,
         name: String,
@@ -110,8 +349,13 @@ pub enum Stmt {
         name: String,
         generics: Vec<String>,
         fields: Vec<(String, Type)>,
+        derives: Derives,
         methods: Vec<Stmt>,
     },
+    EnumDef {
+        name: String,
+        variants: Vec<EnumVariant>,
+    },
     InterfaceDef {
         name: String,
         generics: Vec<String>,
@@ -133,6 +377,11 @@ pub enum Stmt {
         catch_body: Option<Vec<Stmt>>,
         finally_body: Option<Vec<Stmt>>,
     },
+    With {
+        value: Expr,
+        name: Option<String>,
+        body: Vec<Stmt>,
+    },
     Raise {
         error: Expr,
         cause: Option<Expr>,
@@ -148,7 +397,7 @@ pub enum Stmt {
 
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Program {
     pub statements: Vec<Stmt>,
 }
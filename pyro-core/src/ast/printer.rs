@@ -0,0 +1,522 @@
+// Pretty-printer from AST back to Pyro source.
+//
+// The output is normalised (4-space indentation, minimal parentheses, one
+// statement per line) and parses back to the same AST.
+
+use crate::ast::{BinaryOp, Derives, Expr, Pattern, Program, Stmt, StringPart, Type, DISCARD};
+
+const INDENT: &str = "    ";
+
+pub fn to_source(program: &Program) -> String {
+    let mut printer = Printer { out: String::new(), depth: 0 };
+    printer.print_stmts(&program.statements);
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+fn is_declaration(stmt: &Stmt) -> bool {
+    matches!(
+        stmt,
+        Stmt::FnDecl { .. }
+            | Stmt::ClassDecl { .. }
+            | Stmt::InterfaceDef { .. }
+            | Stmt::RecordDef { .. }
+            | Stmt::EnumDef { .. }
+    )
+}
+
+// Ranges bind looser than arithmetic and tighter than comparisons
+const RANGE_PRECEDENCE: u8 = 3;
+
+fn precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Eq | BinaryOp::Neq => 1,
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Lte | BinaryOp::Gte | BinaryOp::In => 2,
+        BinaryOp::Add | BinaryOp::Sub => 4,
+        BinaryOp::Mul | BinaryOp::Div => 5,
+    }
+}
+
+fn op_str(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Lte => "<=",
+        BinaryOp::Gte => ">=",
+        BinaryOp::In => "in",
+    }
+}
+
+fn float_to_source(f: f64) -> String {
+    // Display never uses exponent notation (which the lexer can't read), but
+    // drops the fractional part of whole numbers, which would lex as an int.
+    let s = format!("{}", f);
+    if f.is_finite() && !s.contains('.') {
+        format!("{}.0", s)
+    } else {
+        s
+    }
+}
+
+fn is_plain_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+pub fn type_to_source(typ: &Type) -> String {
+    match typ {
+        Type::Int => "int".to_string(),
+        Type::Float => "float".to_string(),
+        Type::Int8 => "int8".to_string(),
+        Type::Int16 => "int16".to_string(),
+        Type::Int32 => "int32".to_string(),
+        Type::UInt8 => "uint8".to_string(),
+        Type::UInt16 => "uint16".to_string(),
+        Type::UInt32 => "uint32".to_string(),
+        Type::UInt64 => "uint64".to_string(),
+        Type::Float32 => "float32".to_string(),
+        Type::Char => "char".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::String => "string".to_string(),
+        Type::Void => "void".to_string(),
+        Type::List => "list".to_string(),
+        Type::Tuple => "tuple".to_string(),
+        Type::Set => "set".to_string(),
+        Type::Dict => "dict".to_string(),
+        Type::ListMutable => "list_mut".to_string(),
+        Type::TupleMutable => "tuple_mut".to_string(),
+        Type::SetMutable => "set_mut".to_string(),
+        Type::DictMutable => "dict_mut".to_string(),
+        Type::UserDefined(name, generics) => {
+            if generics.is_empty() {
+                name.clone()
+            } else {
+                format!("{}<{}>", name, types_to_source(generics))
+            }
+        }
+        Type::Union(types) => types.iter().map(type_to_source).collect::<Vec<_>>().join(" | "),
+        Type::Any | Type::ImplicitAny => "any".to_string(),
+        Type::Variadic(each) => type_to_source(each),
+    }
+}
+
+fn types_to_source(types: &[Type]) -> String {
+    types.iter().map(type_to_source).collect::<Vec<_>>().join(", ")
+}
+
+fn generic_params(generics: &[String]) -> String {
+    if generics.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", generics.join(", "))
+    }
+}
+
+fn params_to_source(params: &[(String, Type)]) -> String {
+    params
+        .iter()
+        .map(|(name, typ)| match typ {
+            // The parser fills these in when the annotation is omitted.
+            Type::UserDefined(t, g) if g.is_empty() && name == "self" && t == "Self" => name.clone(),
+            Type::ImplicitAny if name != "self" => name.clone(),
+            Type::Variadic(each) if **each == Type::ImplicitAny => format!("*{}", name),
+            Type::Variadic(each) => format!("*{}: {}", name, type_to_source(each)),
+            _ => format!("{}: {}", name, type_to_source(typ)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn typed_params_to_source(params: &[(String, Type)]) -> String {
+    params
+        .iter()
+        .map(|(name, typ)| match typ {
+            Type::Variadic(each) => format!("*{}: {}", name, type_to_source(each)),
+            _ => format!("{}: {}", name, type_to_source(typ)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn return_type(typ: &Type) -> String {
+    match typ {
+        Type::Void => String::new(),
+        t => format!(" -> {}", type_to_source(t)),
+    }
+}
+
+pub fn expr_to_source(expr: &Expr) -> String {
+    match expr {
+        Expr::LiteralInt(i) => i.to_string(),
+        Expr::LiteralFloat(f) => float_to_source(*f),
+        Expr::LiteralBool(b) => b.to_string(),
+        // Triple quotes keep inner quotes, unless the text would read back
+        // as a heredoc
+        Expr::LiteralString(s) if s.contains('"') && !s.contains("\"\"\"") && !s.starts_with('\n') => {
+            format!("\"\"\"{}\"\"\"", s)
+        }
+        Expr::LiteralString(s) => format!("\"{}\"", s),
+        Expr::InterpolatedString(parts) => {
+            let text: String = parts
+                .iter()
+                .map(|part| match part {
+                    StringPart::Text(text) => text.replace('{', "{{").replace('}', "}}"),
+                    StringPart::Expr(e) => format!("{{{}}}", expr_to_source(e)),
+                })
+                .collect();
+            if text.contains('"') && !text.contains("\"\"\"") && !text.starts_with('\n') {
+                format!("f\"\"\"{}\"\"\"", text)
+            } else {
+                format!("f\"{}\"", text)
+            }
+        }
+        Expr::LiteralChar(c) => format!("'{}'", c),
+        Expr::Identifier(name) => name.clone(),
+        Expr::Binary { left, op, right } => {
+            let prec = precedence(op);
+            // Operators are left-associative, so the right operand needs
+            // parentheses at equal precedence too.
+            let l = operand(left, |p| p < prec);
+            let r = operand(right, |p| p <= prec);
+            format!("{} {} {}", l, op_str(op), r)
+        }
+        Expr::Get { object, name } => format!("{}.{}", postfix_target(object), name),
+        Expr::Index { object, index } => {
+            format!("{}[{}]", postfix_target(object), expr_to_source(index))
+        }
+        Expr::Call { function, generics, args } => {
+            let generics = if generics.is_empty() {
+                String::new()
+            } else {
+                format!("<{}>", types_to_source(generics))
+            };
+            match args.split_last() {
+                Some((Expr::Lambda { params, .. }, rest)) => {
+                    format!("{}{}({}){}", postfix_target(function), generics, exprs_to_source(rest), block_header(params))
+                }
+                _ => format!("{}{}({})", postfix_target(function), generics, exprs_to_source(args)),
+            }
+        }
+        Expr::List(elements) => format!("[{}]", exprs_to_source(elements)),
+        Expr::Spread(items) => format!("*{}", expr_to_source(items)),
+        Expr::Propagate(inner) => format!("{}?", postfix_target(inner)),
+        Expr::Tuple(elements) => match elements.len() {
+            1 => format!("({},)", expr_to_source(&elements[0])),
+            _ => format!("({})", exprs_to_source(elements)),
+        },
+        Expr::Set(elements) => format!("{{{}}}", exprs_to_source(elements)),
+        Expr::Dict(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", expr_to_source(k), expr_to_source(v)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{{{}}}", entries)
+        }
+        Expr::Range { start, end, inclusive } => {
+            // Neither side may be another range
+            let s = operand(start, |p| p <= RANGE_PRECEDENCE);
+            let e = operand(end, |p| p <= RANGE_PRECEDENCE);
+            format!("{}{}{}", s, if *inclusive { "..=" } else { ".." }, e)
+        }
+        // Only valid after a call; the statement prints the body
+        Expr::Lambda { params, .. } => block_header(params),
+        Expr::Conditional { cond, then_expr, else_expr } => format!(
+            "{} if {} else {}",
+            operand(then_expr, |_| false),
+            operand(cond, |_| false),
+            expr_to_source(else_expr)
+        ),
+    }
+}
+
+fn block_header(params: &[(String, Type)]) -> String {
+    if params.is_empty() {
+        ":".to_string()
+    } else {
+        format!(" as {}:", params_to_source(params))
+    }
+}
+
+fn pattern_to_source(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => DISCARD.to_string(),
+        Pattern::Binding(name) => name.clone(),
+        Pattern::Literal(value) => expr_to_source(value),
+        Pattern::Tuple(elements) => match elements.len() {
+            1 => format!("({},)", pattern_to_source(&elements[0])),
+            _ => format!("({})", patterns_to_source(elements)),
+        },
+        Pattern::Record { name, fields } => format!("{}({})", name, patterns_to_source(fields)),
+        Pattern::Variant { enum_name, variant, fields } if fields.is_empty() => format!("{}.{}", enum_name, variant),
+        Pattern::Variant { enum_name, variant, fields } => {
+            format!("{}.{}({})", enum_name, variant, patterns_to_source(fields))
+        }
+        Pattern::Or(alternatives) => alternatives.iter().map(pattern_to_source).collect::<Vec<_>>().join(" | "),
+    }
+}
+
+fn patterns_to_source(patterns: &[Pattern]) -> String {
+    patterns.iter().map(pattern_to_source).collect::<Vec<_>>().join(", ")
+}
+
+fn exprs_to_source(exprs: &[Expr]) -> String {
+    exprs.iter().map(expr_to_source).collect::<Vec<_>>().join(", ")
+}
+
+fn operand(expr: &Expr, needs_parens: impl Fn(u8) -> bool) -> String {
+    match expr {
+        Expr::Binary { op, .. } if needs_parens(precedence(op)) => format!("({})", expr_to_source(expr)),
+        Expr::Range { .. } if needs_parens(RANGE_PRECEDENCE) => format!("({})", expr_to_source(expr)),
+        // Binds looser than any operator
+        Expr::Conditional { .. } => format!("({})", expr_to_source(expr)),
+        _ => expr_to_source(expr),
+    }
+}
+
+fn postfix_target(expr: &Expr) -> String {
+    match expr {
+        // Number literals would swallow a following `.` or bind a leading `-`
+        // to the whole postfix expression.
+        Expr::Binary { .. } | Expr::Range { .. } | Expr::Conditional { .. } | Expr::LiteralInt(_) | Expr::LiteralFloat(_) => {
+            format!("({})", expr_to_source(expr))
+        }
+        _ => expr_to_source(expr),
+    }
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    // A call with a trailing block prints its header on the line, the block
+    // below it
+    fn line_with_block(&mut self, text: &str, expr: &Expr) {
+        self.line(text);
+        if let Expr::Call { args, .. } = expr
+            && let Some(Expr::Lambda { body, .. }) = args.last()
+        {
+            self.print_block(body);
+        }
+    }
+
+    fn print_stmts(&mut self, stmts: &[Stmt]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            if i > 0 && (is_declaration(stmt) || is_declaration(&stmts[i - 1])) {
+                self.out.push('\n');
+            }
+            self.print_stmt(stmt);
+        }
+    }
+
+    fn print_block(&mut self, stmts: &[Stmt]) {
+        self.depth += 1;
+        if stmts.is_empty() {
+            // There is no `pass`; a comment-only line still opens the block.
+            self.line("# empty");
+        }
+        self.print_stmts(stmts);
+        self.depth -= 1;
+    }
+
+    fn print_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl { name, typ, value, mutable } => {
+                let keyword = if *mutable { "mut" } else { "let" };
+                let annotation = match typ {
+                    Some(t) => format!(": {}", type_to_source(t)),
+                    None => String::new(),
+                };
+                self.line_with_block(&format!("{} {}{} = {}", keyword, name, annotation, expr_to_source(value)), value);
+            }
+            Stmt::ConstDecl { name, typ, value } => {
+                let annotation = match typ {
+                    Some(t) => format!(": {}", type_to_source(t)),
+                    None => String::new(),
+                };
+                self.line_with_block(&format!("const {}{} = {}", name, annotation, expr_to_source(value)), value);
+            }
+            Stmt::Destructure { names, value, mutable } => {
+                let keyword = if *mutable { "mut" } else { "let" };
+                self.line_with_block(&format!("{} {} = {}", keyword, names.join(", "), expr_to_source(value)), value);
+            }
+            Stmt::Expr(expr) => self.line_with_block(&expr_to_source(expr), expr),
+            Stmt::If { cond, then_block, else_block } => {
+                self.line(&format!("if {}:", expr_to_source(cond)));
+                self.print_block(then_block);
+                if let Some(else_stmts) = else_block {
+                    self.line("else:");
+                    self.print_block(else_stmts);
+                }
+            }
+            Stmt::While { cond, body } => {
+                self.line(&format!("while {}:", expr_to_source(cond)));
+                self.print_block(body);
+            }
+            Stmt::For { names, iterable, body } => {
+                self.line(&format!("for {} in {}:", names.join(", "), expr_to_source(iterable)));
+                self.print_block(body);
+            }
+            Stmt::Match { subject, cases } => {
+                self.line(&format!("match {}:", expr_to_source(subject)));
+                self.depth += 1;
+                for case in cases {
+                    match &case.guard {
+                        Some(guard) => self.line(&format!("case {} if {}:", pattern_to_source(&case.pattern), expr_to_source(guard))),
+                        None => self.line(&format!("case {}:", pattern_to_source(&case.pattern))),
+                    }
+                    self.print_block(&case.body);
+                }
+                self.depth -= 1;
+            }
+            Stmt::Assign { name, value } => {
+                self.line_with_block(&format!("{} = {}", name, expr_to_source(value)), value);
+            }
+            Stmt::DestructureAssign { names, value } => {
+                self.line_with_block(&format!("{} = {}", names.join(", "), expr_to_source(value)), value);
+            }
+            Stmt::Set { object, name, value } => {
+                self.line_with_block(&format!("{}.{} = {}", postfix_target(object), name, expr_to_source(value)), value);
+            }
+            Stmt::FnDecl { name, generics, params, return_type: ret, body } => {
+                self.line(&format!(
+                    "def {}{}({}){}:",
+                    name,
+                    generic_params(generics),
+                    params_to_source(params),
+                    return_type(ret)
+                ));
+                self.print_block(body);
+            }
+            Stmt::Return(expr) => match expr {
+                Some(e) => self.line_with_block(&format!("return {}", expr_to_source(e)), e),
+                None => self.line("return"),
+            },
+            Stmt::Break => self.line("break"),
+            Stmt::Continue => self.line("continue"),
+            Stmt::Import(path) => {
+                if let Some(krate) = path.strip_prefix("rust.") {
+                    self.line(&format!("import rust \"{}\"", krate));
+                } else if let Some(library) = path.strip_prefix(crate::plugin::IMPORT_PREFIX) {
+                    self.line(&format!("import plugin \"{}\"", library));
+                } else if path.split('.').all(is_plain_identifier) {
+                    self.line(&format!("import {}", path));
+                } else {
+                    self.line(&format!("import \"{}\"", path));
+                }
+            }
+            Stmt::RecordDef { name, generics, fields, derives, methods } => {
+                let mut header = format!(
+                    "record {}{}({})",
+                    name,
+                    generic_params(generics),
+                    typed_params_to_source(fields)
+                );
+                if *derives != Derives::default() {
+                    header.push_str(&format!(" derives ({})", derives.names().join(", ")));
+                }
+                if methods.is_empty() {
+                    self.line(&header);
+                } else {
+                    self.line(&format!("{}:", header));
+                    self.print_block(methods);
+                }
+            }
+            Stmt::EnumDef { name, variants } => {
+                self.line(&format!("enum {}:", name));
+                self.depth += 1;
+                for variant in variants {
+                    if variant.fields.is_empty() {
+                        self.line(&variant.name);
+                    } else {
+                        self.line(&format!("{}({})", variant.name, params_to_source(&variant.fields)));
+                    }
+                }
+                self.depth -= 1;
+            }
+            Stmt::InterfaceDef { name, generics, methods } => {
+                if methods.is_empty() {
+                    self.line(&format!("interface {}{} {{}}", name, generic_params(generics)));
+                    return;
+                }
+                self.line(&format!("interface {}{} {{", name, generic_params(generics)));
+                self.depth += 1;
+                for (method, params, ret) in methods {
+                    self.line(&format!("def {}({}){}", method, typed_params_to_source(params), return_type(ret)));
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+            Stmt::TypeAlias { name, generics, alias } => {
+                self.line(&format!("type {}{} = {}", name, generic_params(generics), type_to_source(alias)));
+            }
+            Stmt::ClassDecl { name, parent, methods } => {
+                match parent {
+                    Some(p) => self.line(&format!("class {}({}):", name, p)),
+                    None => self.line(&format!("class {}:", name)),
+                }
+                self.print_block(methods);
+            }
+            Stmt::Try { body, catch_var, catch_body, finally_body } => {
+                self.line("try:");
+                self.print_block(body);
+                if let Some(b) = catch_body {
+                    match catch_var {
+                        Some(var) => self.line(&format!("except {}:", var)),
+                        None => self.line("except:"),
+                    }
+                    self.print_block(b);
+                }
+                if let Some(b) = finally_body {
+                    self.line("finally:");
+                    self.print_block(b);
+                }
+            }
+            Stmt::With { value, name, body } => {
+                match name {
+                    Some(name) => self.line(&format!("with {} as {}:", expr_to_source(value), name)),
+                    None => self.line(&format!("with {}:", expr_to_source(value))),
+                }
+                self.print_block(body);
+            }
+            Stmt::Raise { error, cause } => match cause {
+                Some(c) => self.line(&format!("raise {} from {}", expr_to_source(error), expr_to_source(c))),
+                None => self.line(&format!("raise {}", expr_to_source(error))),
+            },
+            Stmt::Go(expr) => self.line(&format!("go {}", expr_to_source(expr))),
+            Stmt::Extern { func_name, generics, params, return_type: ret, rust_path } => {
+                let path = match rust_path {
+                    Some(p) => format!("\"{}\" ", p),
+                    None => String::new(),
+                };
+                self.line(&format!(
+                    "extern {}def {}{}({}){}",
+                    path,
+                    func_name,
+                    generic_params(generics),
+                    typed_params_to_source(params),
+                    return_type(ret)
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,581 @@
+// Generic traversal over the AST.
+//
+// `Visitor` walks a tree by reference, `VisitorMut` walks it in place and
+// `Folder` consumes it and rebuilds a (possibly different) tree. Every hook has
+// a default implementation that recurses into children through the matching
+// `walk_*` / `fold_*` function, so implementors only override the nodes they
+// care about and call back into the walker to keep descending.
+
+use crate::ast::{EnumVariant, Expr, MatchCase, Program, Stmt, StringPart, Type};
+
+pub trait Visitor {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_type(&mut self, _typ: &Type) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    walk_block(visitor, &program.statements);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, stmts: &[Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+fn walk_params<V: Visitor + ?Sized>(visitor: &mut V, params: &[(String, Type)]) {
+    for (_, typ) in params {
+        visitor.visit_type(typ);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::VarDecl { typ, value, .. } | Stmt::ConstDecl { typ, value, .. } => {
+            if let Some(t) = typ {
+                visitor.visit_type(t);
+            }
+            visitor.visit_expr(value);
+        }
+        Stmt::Destructure { value, .. } => visitor.visit_expr(value),
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+        Stmt::If { cond, then_block, else_block } => {
+            visitor.visit_expr(cond);
+            walk_block(visitor, then_block);
+            if let Some(else_stmts) = else_block {
+                walk_block(visitor, else_stmts);
+            }
+        }
+        Stmt::While { cond, body } => {
+            visitor.visit_expr(cond);
+            walk_block(visitor, body);
+        }
+        Stmt::For { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            walk_block(visitor, body);
+        }
+        Stmt::Match { subject, cases } => {
+            visitor.visit_expr(subject);
+            for case in cases {
+                if let Some(guard) = &case.guard {
+                    visitor.visit_expr(guard);
+                }
+                walk_block(visitor, &case.body);
+            }
+        }
+        Stmt::Assign { value, .. } | Stmt::DestructureAssign { value, .. } => visitor.visit_expr(value),
+        Stmt::Set { object, value, .. } => {
+            visitor.visit_expr(object);
+            visitor.visit_expr(value);
+        }
+        Stmt::FnDecl { params, return_type, body, .. } => {
+            walk_params(visitor, params);
+            visitor.visit_type(return_type);
+            walk_block(visitor, body);
+        }
+        Stmt::Return(expr) => {
+            if let Some(e) = expr {
+                visitor.visit_expr(e);
+            }
+        }
+        Stmt::Break | Stmt::Continue | Stmt::Import(_) => {}
+        Stmt::RecordDef { fields, methods, .. } => {
+            walk_params(visitor, fields);
+            walk_block(visitor, methods);
+        }
+        Stmt::EnumDef { variants, .. } => {
+            for variant in variants {
+                walk_params(visitor, &variant.fields);
+            }
+        }
+        Stmt::InterfaceDef { methods, .. } => {
+            for (_, params, ret) in methods {
+                walk_params(visitor, params);
+                visitor.visit_type(ret);
+            }
+        }
+        Stmt::TypeAlias { alias, .. } => visitor.visit_type(alias),
+        Stmt::ClassDecl { methods, .. } => walk_block(visitor, methods),
+        Stmt::Try { body, catch_body, finally_body, .. } => {
+            walk_block(visitor, body);
+            if let Some(b) = catch_body {
+                walk_block(visitor, b);
+            }
+            if let Some(b) = finally_body {
+                walk_block(visitor, b);
+            }
+        }
+        Stmt::With { value, body, .. } => {
+            visitor.visit_expr(value);
+            walk_block(visitor, body);
+        }
+        Stmt::Raise { error, cause } => {
+            visitor.visit_expr(error);
+            if let Some(c) = cause {
+                visitor.visit_expr(c);
+            }
+        }
+        Stmt::Go(expr) => visitor.visit_expr(expr),
+        Stmt::Extern { params, return_type, .. } => {
+            walk_params(visitor, params);
+            visitor.visit_type(return_type);
+        }
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::LiteralInt(_)
+        | Expr::LiteralFloat(_)
+        | Expr::LiteralBool(_)
+        | Expr::LiteralString(_)
+        | Expr::LiteralChar(_)
+        | Expr::Identifier(_) => {}
+        Expr::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Get { object, .. } | Expr::Spread(object) | Expr::Propagate(object) => visitor.visit_expr(object),
+        Expr::Index { object, index } => {
+            visitor.visit_expr(object);
+            visitor.visit_expr(index);
+        }
+        Expr::Call { function, generics, args } => {
+            visitor.visit_expr(function);
+            for t in generics {
+                visitor.visit_type(t);
+            }
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::List(elements) | Expr::Tuple(elements) | Expr::Set(elements) => {
+            for e in elements {
+                visitor.visit_expr(e);
+            }
+        }
+        Expr::Dict(entries) => {
+            for (k, v) in entries {
+                visitor.visit_expr(k);
+                visitor.visit_expr(v);
+            }
+        }
+        Expr::Range { start, end, .. } => {
+            visitor.visit_expr(start);
+            visitor.visit_expr(end);
+        }
+        Expr::Lambda { params, body } => {
+            walk_params(visitor, params);
+            walk_block(visitor, body);
+        }
+        Expr::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expr(e) = part {
+                    visitor.visit_expr(e);
+                }
+            }
+        }
+        Expr::Conditional { cond, then_expr, else_expr } => {
+            visitor.visit_expr(cond);
+            visitor.visit_expr(then_expr);
+            visitor.visit_expr(else_expr);
+        }
+    }
+}
+
+pub trait VisitorMut {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_type_mut(&mut self, _typ: &mut Type) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    walk_block_mut(visitor, &mut program.statements);
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmts: &mut [Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+fn walk_params_mut<V: VisitorMut + ?Sized>(visitor: &mut V, params: &mut [(String, Type)]) {
+    for (_, typ) in params {
+        visitor.visit_type_mut(typ);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::VarDecl { typ, value, .. } | Stmt::ConstDecl { typ, value, .. } => {
+            if let Some(t) = typ {
+                visitor.visit_type_mut(t);
+            }
+            visitor.visit_expr_mut(value);
+        }
+        Stmt::Destructure { value, .. } => visitor.visit_expr_mut(value),
+        Stmt::Expr(expr) => visitor.visit_expr_mut(expr),
+        Stmt::If { cond, then_block, else_block } => {
+            visitor.visit_expr_mut(cond);
+            walk_block_mut(visitor, then_block);
+            if let Some(else_stmts) = else_block {
+                walk_block_mut(visitor, else_stmts);
+            }
+        }
+        Stmt::While { cond, body } => {
+            visitor.visit_expr_mut(cond);
+            walk_block_mut(visitor, body);
+        }
+        Stmt::For { iterable, body, .. } => {
+            visitor.visit_expr_mut(iterable);
+            walk_block_mut(visitor, body);
+        }
+        Stmt::Match { subject, cases } => {
+            visitor.visit_expr_mut(subject);
+            for case in cases {
+                if let Some(guard) = &mut case.guard {
+                    visitor.visit_expr_mut(guard);
+                }
+                walk_block_mut(visitor, &mut case.body);
+            }
+        }
+        Stmt::Assign { value, .. } | Stmt::DestructureAssign { value, .. } => visitor.visit_expr_mut(value),
+        Stmt::Set { object, value, .. } => {
+            visitor.visit_expr_mut(object);
+            visitor.visit_expr_mut(value);
+        }
+        Stmt::FnDecl { params, return_type, body, .. } => {
+            walk_params_mut(visitor, params);
+            visitor.visit_type_mut(return_type);
+            walk_block_mut(visitor, body);
+        }
+        Stmt::Return(expr) => {
+            if let Some(e) = expr {
+                visitor.visit_expr_mut(e);
+            }
+        }
+        Stmt::Break | Stmt::Continue | Stmt::Import(_) => {}
+        Stmt::RecordDef { fields, methods, .. } => {
+            walk_params_mut(visitor, fields);
+            walk_block_mut(visitor, methods);
+        }
+        Stmt::EnumDef { variants, .. } => {
+            for variant in variants {
+                walk_params_mut(visitor, &mut variant.fields);
+            }
+        }
+        Stmt::InterfaceDef { methods, .. } => {
+            for (_, params, ret) in methods {
+                walk_params_mut(visitor, params);
+                visitor.visit_type_mut(ret);
+            }
+        }
+        Stmt::TypeAlias { alias, .. } => visitor.visit_type_mut(alias),
+        Stmt::ClassDecl { methods, .. } => walk_block_mut(visitor, methods),
+        Stmt::Try { body, catch_body, finally_body, .. } => {
+            walk_block_mut(visitor, body);
+            if let Some(b) = catch_body {
+                walk_block_mut(visitor, b);
+            }
+            if let Some(b) = finally_body {
+                walk_block_mut(visitor, b);
+            }
+        }
+        Stmt::With { value, body, .. } => {
+            visitor.visit_expr_mut(value);
+            walk_block_mut(visitor, body);
+        }
+        Stmt::Raise { error, cause } => {
+            visitor.visit_expr_mut(error);
+            if let Some(c) = cause {
+                visitor.visit_expr_mut(c);
+            }
+        }
+        Stmt::Go(expr) => visitor.visit_expr_mut(expr),
+        Stmt::Extern { params, return_type, .. } => {
+            walk_params_mut(visitor, params);
+            visitor.visit_type_mut(return_type);
+        }
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::LiteralInt(_)
+        | Expr::LiteralFloat(_)
+        | Expr::LiteralBool(_)
+        | Expr::LiteralString(_)
+        | Expr::LiteralChar(_)
+        | Expr::Identifier(_) => {}
+        Expr::Binary { left, right, .. } => {
+            visitor.visit_expr_mut(left);
+            visitor.visit_expr_mut(right);
+        }
+        Expr::Get { object, .. } | Expr::Spread(object) | Expr::Propagate(object) => visitor.visit_expr_mut(object),
+        Expr::Index { object, index } => {
+            visitor.visit_expr_mut(object);
+            visitor.visit_expr_mut(index);
+        }
+        Expr::Call { function, generics, args } => {
+            visitor.visit_expr_mut(function);
+            for t in generics {
+                visitor.visit_type_mut(t);
+            }
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        Expr::List(elements) | Expr::Tuple(elements) | Expr::Set(elements) => {
+            for e in elements {
+                visitor.visit_expr_mut(e);
+            }
+        }
+        Expr::Dict(entries) => {
+            for (k, v) in entries {
+                visitor.visit_expr_mut(k);
+                visitor.visit_expr_mut(v);
+            }
+        }
+        Expr::Range { start, end, .. } => {
+            visitor.visit_expr_mut(start);
+            visitor.visit_expr_mut(end);
+        }
+        Expr::Lambda { params, body } => {
+            walk_params_mut(visitor, params);
+            walk_block_mut(visitor, body);
+        }
+        Expr::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Expr(e) = part {
+                    visitor.visit_expr_mut(e);
+                }
+            }
+        }
+        Expr::Conditional { cond, then_expr, else_expr } => {
+            visitor.visit_expr_mut(cond);
+            visitor.visit_expr_mut(then_expr);
+            visitor.visit_expr_mut(else_expr);
+        }
+    }
+}
+
+// Folders take ownership so a pass can replace a node with a different kind of
+// node (e.g. constant folding `Binary` into a literal).
+pub trait Folder {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        fold_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+
+    fn fold_type(&mut self, typ: Type) -> Type {
+        typ
+    }
+}
+
+pub fn fold_program<F: Folder + ?Sized>(folder: &mut F, program: Program) -> Program {
+    Program { statements: fold_block(folder, program.statements) }
+}
+
+pub fn fold_block<F: Folder + ?Sized>(folder: &mut F, stmts: Vec<Stmt>) -> Vec<Stmt> {
+    stmts.into_iter().map(|s| folder.fold_stmt(s)).collect()
+}
+
+fn fold_params<F: Folder + ?Sized>(folder: &mut F, params: Vec<(String, Type)>) -> Vec<(String, Type)> {
+    params.into_iter().map(|(n, t)| (n, folder.fold_type(t))).collect()
+}
+
+fn fold_box<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Box<Expr> {
+    Box::new(folder.fold_expr(expr))
+}
+
+pub fn fold_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: Stmt) -> Stmt {
+    match stmt {
+        Stmt::VarDecl { name, typ, value, mutable } => Stmt::VarDecl {
+            name,
+            typ: typ.map(|t| folder.fold_type(t)),
+            value: folder.fold_expr(value),
+            mutable,
+        },
+        Stmt::ConstDecl { name, typ, value } => Stmt::ConstDecl {
+            name,
+            typ: typ.map(|t| folder.fold_type(t)),
+            value: folder.fold_expr(value),
+        },
+        Stmt::Destructure { names, value, mutable } => Stmt::Destructure { names, value: folder.fold_expr(value), mutable },
+        Stmt::Expr(expr) => Stmt::Expr(folder.fold_expr(expr)),
+        Stmt::If { cond, then_block, else_block } => Stmt::If {
+            cond: folder.fold_expr(cond),
+            then_block: fold_block(folder, then_block),
+            else_block: else_block.map(|b| fold_block(folder, b)),
+        },
+        Stmt::While { cond, body } => Stmt::While {
+            cond: folder.fold_expr(cond),
+            body: fold_block(folder, body),
+        },
+        Stmt::For { names, iterable, body } => Stmt::For {
+            names,
+            iterable: folder.fold_expr(iterable),
+            body: fold_block(folder, body),
+        },
+        Stmt::Match { subject, cases } => Stmt::Match {
+            subject: folder.fold_expr(subject),
+            cases: cases
+                .into_iter()
+                .map(|case| MatchCase {
+                    pattern: case.pattern,
+                    guard: case.guard.map(|guard| folder.fold_expr(guard)),
+                    body: fold_block(folder, case.body),
+                })
+                .collect(),
+        },
+        Stmt::Assign { name, value } => Stmt::Assign { name, value: folder.fold_expr(value) },
+        Stmt::DestructureAssign { names, value } => Stmt::DestructureAssign { names, value: folder.fold_expr(value) },
+        Stmt::Set { object, name, value } => Stmt::Set {
+            object: folder.fold_expr(object),
+            name,
+            value: folder.fold_expr(value),
+        },
+        Stmt::FnDecl { name, generics, params, return_type, body } => Stmt::FnDecl {
+            name,
+            generics,
+            params: fold_params(folder, params),
+            return_type: folder.fold_type(return_type),
+            body: fold_block(folder, body),
+        },
+        Stmt::Return(expr) => Stmt::Return(expr.map(|e| folder.fold_expr(e))),
+        Stmt::Break => Stmt::Break,
+        Stmt::Continue => Stmt::Continue,
+        Stmt::Import(path) => Stmt::Import(path),
+        Stmt::RecordDef { name, generics, fields, derives, methods } => Stmt::RecordDef {
+            name,
+            generics,
+            fields: fold_params(folder, fields),
+            derives,
+            methods: fold_block(folder, methods),
+        },
+        Stmt::EnumDef { name, variants } => Stmt::EnumDef {
+            name,
+            variants: variants
+                .into_iter()
+                .map(|variant| EnumVariant { name: variant.name, fields: fold_params(folder, variant.fields) })
+                .collect(),
+        },
+        Stmt::InterfaceDef { name, generics, methods } => Stmt::InterfaceDef {
+            name,
+            generics,
+            methods: methods
+                .into_iter()
+                .map(|(n, params, ret)| (n, fold_params(folder, params), folder.fold_type(ret)))
+                .collect(),
+        },
+        Stmt::TypeAlias { name, generics, alias } => Stmt::TypeAlias {
+            name,
+            generics,
+            alias: folder.fold_type(alias),
+        },
+        Stmt::ClassDecl { name, parent, methods } => Stmt::ClassDecl {
+            name,
+            parent,
+            methods: fold_block(folder, methods),
+        },
+        Stmt::Try { body, catch_var, catch_body, finally_body } => Stmt::Try {
+            body: fold_block(folder, body),
+            catch_var,
+            catch_body: catch_body.map(|b| fold_block(folder, b)),
+            finally_body: finally_body.map(|b| fold_block(folder, b)),
+        },
+        Stmt::With { value, name, body } => Stmt::With {
+            value: folder.fold_expr(value),
+            name,
+            body: fold_block(folder, body),
+        },
+        Stmt::Raise { error, cause } => Stmt::Raise {
+            error: folder.fold_expr(error),
+            cause: cause.map(|c| folder.fold_expr(c)),
+        },
+        Stmt::Go(expr) => Stmt::Go(fold_box(folder, *expr)),
+        Stmt::Extern { func_name, generics, params, return_type, rust_path } => Stmt::Extern {
+            func_name,
+            generics,
+            params: fold_params(folder, params),
+            return_type: folder.fold_type(return_type),
+            rust_path,
+        },
+    }
+}
+
+pub fn fold_expr<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::LiteralInt(_)
+        | Expr::LiteralFloat(_)
+        | Expr::LiteralBool(_)
+        | Expr::LiteralString(_)
+        | Expr::LiteralChar(_)
+        | Expr::Identifier(_) => expr,
+        Expr::Binary { left, op, right } => Expr::Binary {
+            left: fold_box(folder, *left),
+            op,
+            right: fold_box(folder, *right),
+        },
+        Expr::Get { object, name } => Expr::Get { object: fold_box(folder, *object), name },
+        Expr::Spread(items) => Expr::Spread(fold_box(folder, *items)),
+        Expr::Propagate(inner) => Expr::Propagate(fold_box(folder, *inner)),
+        Expr::Index { object, index } => Expr::Index {
+            object: fold_box(folder, *object),
+            index: fold_box(folder, *index),
+        },
+        Expr::Call { function, generics, args } => Expr::Call {
+            function: fold_box(folder, *function),
+            generics: generics.into_iter().map(|t| folder.fold_type(t)).collect(),
+            args: args.into_iter().map(|a| folder.fold_expr(a)).collect(),
+        },
+        Expr::List(elements) => Expr::List(elements.into_iter().map(|e| folder.fold_expr(e)).collect()),
+        Expr::Tuple(elements) => Expr::Tuple(elements.into_iter().map(|e| folder.fold_expr(e)).collect()),
+        Expr::Set(elements) => Expr::Set(elements.into_iter().map(|e| folder.fold_expr(e)).collect()),
+        Expr::Dict(entries) => Expr::Dict(
+            entries
+                .into_iter()
+                .map(|(k, v)| (folder.fold_expr(k), folder.fold_expr(v)))
+                .collect(),
+        ),
+        Expr::Range { start, end, inclusive } => Expr::Range {
+            start: fold_box(folder, *start),
+            end: fold_box(folder, *end),
+            inclusive,
+        },
+        Expr::Lambda { params, body } => Expr::Lambda {
+            params: fold_params(folder, params),
+            body: fold_block(folder, body),
+        },
+        Expr::InterpolatedString(parts) => Expr::InterpolatedString(
+            parts
+                .into_iter()
+                .map(|part| match part {
+                    StringPart::Expr(e) => StringPart::Expr(folder.fold_expr(e)),
+                    text => text,
+                })
+                .collect(),
+        ),
+        Expr::Conditional { cond, then_expr, else_expr } => Expr::Conditional {
+            cond: fold_box(folder, *cond),
+            then_expr: fold_box(folder, *then_expr),
+            else_expr: fold_box(folder, *else_expr),
+        },
+    }
+}
@@ -0,0 +1,90 @@
+// Checks that nothing rebinds a name declared with `const`. It runs over the
+// whole program once imports are inlined, so a constant from one file can't
+// be reassigned from another before anything executes. The interpreter
+// repeats the check at runtime for code it is handed piecemeal, e.g. in the
+// shell.
+
+use crate::ast::visit::{walk_block, walk_stmt, Visitor};
+use crate::ast::Stmt;
+use crate::diagnostics::Diagnostic;
+use std::collections::HashSet;
+
+#[derive(Default)]
+struct Declarations {
+    constants: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Visitor for Declarations {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::ConstDecl { name, .. } = stmt
+            && !self.constants.insert(name.clone())
+        {
+            self.errors.push(format!("Constant '{}' is declared more than once", name));
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+struct Bindings<'a> {
+    constants: &'a HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Bindings<'_> {
+    fn bind(&mut self, name: &str) {
+        if self.constants.contains(name) {
+            self.errors.push(format!("Cannot reassign constant '{}'", name));
+        }
+    }
+}
+
+impl Visitor for Bindings<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl { name, .. }
+            | Stmt::Assign { name, .. }
+            | Stmt::FnDecl { name, .. }
+            | Stmt::RecordDef { name, .. }
+            | Stmt::EnumDef { name, .. }
+            | Stmt::ClassDecl { name, .. }
+            | Stmt::Extern { func_name: name, .. } => self.bind(name),
+            Stmt::Destructure { names, .. } | Stmt::DestructureAssign { names, .. } | Stmt::For { names, .. } => {
+                names.iter().for_each(|name| self.bind(name))
+            }
+            Stmt::Match { cases, .. } => cases.iter().flat_map(|case| case.pattern.names()).for_each(|name| self.bind(name)),
+            Stmt::Try { catch_var: Some(name), .. } | Stmt::With { name: Some(name), .. } => self.bind(name),
+            _ => {}
+        }
+        match stmt {
+            // Method names belong to the class, not the program's scope
+            Stmt::RecordDef { methods, .. } | Stmt::ClassDecl { methods, .. } => {
+                for method in methods {
+                    match method {
+                        Stmt::FnDecl { body, .. } => walk_block(self, body),
+                        other => self.visit_stmt(other),
+                    }
+                }
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+}
+
+// Returns every problem found, duplicate declarations first, with the code
+// "constant"
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut declarations = Declarations::default();
+    walk_block(&mut declarations, statements);
+
+    let mut bindings = Bindings { constants: &declarations.constants, errors: Vec::new() };
+    walk_block(&mut bindings, statements);
+
+    let mut errors = declarations.errors;
+    errors.extend(bindings.errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.into_iter().map(|message| Diagnostic::error("constant", message)).collect())
+    }
+}
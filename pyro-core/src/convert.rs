@@ -59,6 +59,8 @@ impl FromPyroValue for String {
     fn from_value(v: &Value) -> Result<Self, String> {
         match v {
             Value::String(s) => Ok(s.to_string()),
+            Value::Char(c) => Ok(c.to_string()),
+            Value::Path(p) => Ok(p.to_string_lossy().into_owned()),
             _ => Err("Expected String".to_string()),
         }
     }
@@ -70,6 +72,37 @@ impl ToPyroValue for String {
     }
 }
 
+impl FromPyroValue for char {
+    fn from_value(v: &Value) -> Result<Self, String> {
+        match v {
+            Value::Char(c) => Ok(*c),
+            _ => Err("Expected Char".to_string()),
+        }
+    }
+}
+
+impl ToPyroValue for char {
+    fn to_value(self) -> Value {
+        Value::Char(self)
+    }
+}
+
+impl FromPyroValue for rust_decimal::Decimal {
+    fn from_value(v: &Value) -> Result<Self, String> {
+        match v {
+            Value::Decimal(d) => Ok(*d),
+            Value::Int(i) => Ok((*i).into()),
+            _ => Err("Expected Decimal or Int".to_string()),
+        }
+    }
+}
+
+impl ToPyroValue for rust_decimal::Decimal {
+    fn to_value(self) -> Value {
+        Value::Decimal(self)
+    }
+}
+
 // Implement for Vec<T>
 impl<T: FromPyroValue> FromPyroValue for Vec<T> {
     fn from_value(v: &Value) -> Result<Self, String> {
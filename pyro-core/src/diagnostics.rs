@@ -0,0 +1,111 @@
+// Problems found in a program, in one shape whichever phase found them: the
+// lexer, the parser, the checks run before execution, or the interpreter.
+// Hosts such as the CLI, the shell and editor tooling render diagnostics
+// without knowing where they came from.
+
+use crate::interpreter::{RuntimeError, Value};
+use crate::parser::incremental::ParseError;
+use serde::Serialize;
+use std::fmt;
+
+// Where in the source a diagnostic points, 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Span {
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // What went wrong, for tools to match on: "parse", "indentation",
+    // "constant", "generics", "alias", "interface", "unassigned",
+    // "missing-return", "unreachable", "stdlib", "annotation" and
+    // "implicit-any" (with --strict), or the kind of a runtime error
+    // ("Error", "NotFound", ...)
+    pub code: String,
+    pub span: Option<Span>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Diagnostic { severity: Severity::Error, code: code.into(), span: None, message: message.into(), notes: Vec::new() }
+    }
+
+    pub fn warning(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Diagnostic { severity: Severity::Warning, ..Diagnostic::error(code, message) }
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.span = Some(Span { line: line as u32 });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+// error[parse]: Expected expression
+//   at line 3
+//   note: ...
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(span) = self.span {
+            write!(f, "\n  at line {}", span.line)?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Diagnostic::error("parse", error.message.clone()).at_line(error.line)
+    }
+}
+
+// The code is the error's kind. Each call the error unwound through becomes
+// a note, innermost first.
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        let field = |name: &str| match &error.value {
+            Value::Instance { fields, .. } => match fields.read().unwrap().get(name) {
+                Some(Value::String(s)) => Some(s.to_string()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let message = match &error.value {
+            Value::String(s) => s.to_string(),
+            other => field("message").unwrap_or_else(|| format!("{:?}", other)),
+        };
+        Diagnostic {
+            span: error.span,
+            notes: error.trace.iter().map(|frame| format!("in {}", frame.function)).collect(),
+            ..Diagnostic::error(field("kind").unwrap_or_else(|| "Error".to_string()), message)
+        }
+    }
+}
@@ -0,0 +1,438 @@
+// Checks how control moves through the top level and each function body:
+//
+// - a name the code binds itself isn't read (or reassigned) on a path where
+//   nothing has bound it yet, which would fail as the program runs. Names
+//   bound only by an enclosing scope, or not at all (builtins, globals of a
+//   function), aren't this check's business, nor are builtins a program may
+//   redefine.
+// - a function declaring a return type can't reach the end of its body, which
+//   would quietly give back void.
+// - nothing follows a `return`, `raise`, `break` or `continue`, or a statement
+//   every path through which ends in one. This is a warning, not an error.
+//
+// Blocks don't open scopes, so a name bound in both branches of an `if` is
+// bound after it, while one bound in a loop body isn't, as the loop may not
+// run at all. A `try` body may stop at any statement, so its `catch` only
+// relies on what was bound before the `try`. A `match` is like an `if` with
+// one branch per case, plus one for no case matching unless a case matches
+// anything, or the cases between them match every variant of an enum.
+
+use crate::ast::visit::{walk_expr, walk_stmt, Visitor};
+use crate::ast::{import_name, type_to_source, Expr, MatchCase, Pattern, Stmt, Type, DISCARD};
+use crate::diagnostics::Diagnostic;
+use crate::interpreter::{is_overridable_builtin, PRELUDE_ENUMS};
+use std::collections::{HashMap, HashSet};
+
+type Assigned = HashSet<String>;
+
+// The names a statement binds in the scope it runs in
+fn binds(stmt: &Stmt) -> Vec<&str> {
+    match stmt {
+        Stmt::VarDecl { name, .. }
+        | Stmt::ConstDecl { name, .. }
+        | Stmt::FnDecl { name, .. }
+        | Stmt::RecordDef { name, .. }
+        | Stmt::EnumDef { name, .. }
+        | Stmt::ClassDecl { name, .. }
+        | Stmt::Extern { func_name: name, .. } => vec![name.as_str()],
+        Stmt::Destructure { names, .. } => names.iter().map(String::as_str).collect(),
+        // What a plugin binds is only known once it's loaded
+        Stmt::Import(path) if path.starts_with(crate::plugin::IMPORT_PREFIX) => Vec::new(),
+        Stmt::Import(path) => vec![import_name(path)],
+        _ => Vec::new(),
+    }
+}
+
+// Every name a block binds, nested blocks included but not nested functions
+fn declared(block: &[Stmt], names: &mut HashSet<String>) {
+    for stmt in block {
+        names.extend(binds(stmt).into_iter().map(str::to_string));
+        match stmt {
+            Stmt::If { then_block, else_block, .. } => {
+                declared(then_block, names);
+                if let Some(else_block) = else_block {
+                    declared(else_block, names);
+                }
+            }
+            Stmt::While { body, .. } => declared(body, names),
+            Stmt::For { names: items, body, .. } => {
+                names.extend(items.iter().cloned());
+                declared(body, names);
+            }
+            Stmt::Match { cases, .. } => {
+                for case in cases {
+                    names.extend(case.pattern.names().into_iter().map(str::to_string));
+                    declared(&case.body, names);
+                }
+            }
+            Stmt::With { name, body, .. } => {
+                names.extend(name.clone());
+                declared(body, names);
+            }
+            Stmt::Try { body, catch_var, catch_body, finally_body } => {
+                declared(body, names);
+                names.extend(catch_var.clone());
+                for block in [catch_body, finally_body].into_iter().flatten() {
+                    declared(block, names);
+                }
+            }
+            _ => {}
+        }
+    }
+    names.remove(DISCARD);
+}
+
+fn needs_value(return_type: &Type) -> bool {
+    match return_type {
+        Type::Void | Type::Any | Type::ImplicitAny => false,
+        Type::Union(types) => types.iter().all(needs_value),
+        _ => true,
+    }
+}
+
+// The variants of every enum the program declares, by enum name
+struct Enums(HashMap<String, Vec<String>>);
+
+impl Visitor for Enums {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::EnumDef { name, variants } = stmt {
+            self.0.insert(name.clone(), variants.iter().map(|variant| variant.name.clone()).collect());
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+fn matches_anything(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::Wildcard | Pattern::Binding(_))
+}
+
+impl Enums {
+    // Just Option and Result, which every program has
+    fn prelude() -> Self {
+        let variants = PRELUDE_ENUMS
+            .iter()
+            .map(|(name, variants)| (name.to_string(), variants.iter().map(|(variant, _)| variant.to_string()).collect()));
+        Enums(variants.collect())
+    }
+
+    // Whether some case always matches, or together they match any value of
+    // one enum
+    fn exhaustive(&self, cases: &[MatchCase]) -> bool {
+        let mut covered: HashMap<&str, HashSet<&str>> = HashMap::new();
+        // A guarded case may not run, so it covers nothing
+        let patterns = cases.iter().filter(|case| case.guard.is_none()).flat_map(|case| match &case.pattern {
+            Pattern::Or(alternatives) => alternatives.iter().collect(),
+            pattern => vec![pattern],
+        });
+        for pattern in patterns {
+            match pattern {
+                pattern if matches_anything(pattern) => return true,
+                Pattern::Variant { enum_name, variant, fields } if fields.iter().all(matches_anything) => {
+                    covered.entry(enum_name.as_str()).or_default().insert(variant.as_str());
+                }
+                _ => {}
+            }
+        }
+        covered.iter().any(|(enum_name, variants)| {
+            self.0.get(*enum_name).is_some_and(|all| all.iter().all(|variant| variants.contains(variant.as_str())))
+        })
+    }
+}
+
+fn merge(paths: Vec<Assigned>) -> Option<Assigned> {
+    paths.into_iter().reduce(|a, b| a.intersection(&b).cloned().collect())
+}
+
+struct Scope<'a> {
+    // Where the code is, for messages: "function 'f'" or "the top level"
+    place: String,
+    // Names this scope binds that no enclosing one does
+    locals: HashSet<String>,
+    // Names bound here or in an enclosing scope
+    visible: HashSet<String>,
+    // What was bound at each `break` of the loops being walked, innermost last
+    loops: Vec<Vec<Assigned>>,
+    // Names already reported, so each is reported once
+    reported: HashSet<String>,
+    enums: &'a Enums,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(
+        place: String,
+        body: &[Stmt],
+        params: &[(String, Type)],
+        outer: &HashSet<String>,
+        enums: &'a Enums,
+        diagnostics: &'a mut Vec<Diagnostic>,
+    ) -> Self {
+        let mut names: HashSet<String> = params.iter().map(|(name, _)| name.clone()).collect();
+        declared(body, &mut names);
+        Scope {
+            place,
+            locals: names.difference(outer).cloned().collect(),
+            visible: names.union(outer).cloned().collect(),
+            loops: Vec::new(),
+            reported: HashSet::new(),
+            enums,
+            diagnostics,
+        }
+    }
+
+    fn read(&mut self, name: &str, assigned: &Assigned) {
+        // Until the program binds e.g. `retry` itself, it means the builtin
+        if is_overridable_builtin(name) {
+            return;
+        }
+        if self.locals.contains(name) && !assigned.contains(name) && self.reported.insert(name.to_string()) {
+            self.diagnostics.push(Diagnostic::error(
+                "unassigned",
+                format!("'{}' may be used before it is assigned in {}", name, self.place),
+            ));
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, assigned: &Assigned) {
+        Reads { scope: self, assigned }.visit_expr(expr);
+    }
+
+    // Walks a function or lambda body as a scope of its own. `function` is
+    // the kind and name of a declared function, whose return type is checked.
+    fn nested(&mut self, place: String, function: Option<(&str, &str, &Type)>, params: &[(String, Type)], body: &[Stmt]) {
+        let mut scope = Scope::new(place, body, params, &self.visible, self.enums, self.diagnostics);
+        let mut assigned: Assigned = params.iter().map(|(name, _)| name.clone()).collect();
+        let falls_through = scope.block(body, &mut assigned);
+        if let Some((kind, name, return_type)) = function
+            && falls_through
+            && needs_value(return_type)
+        {
+            self.diagnostics.push(Diagnostic::error(
+                "missing-return",
+                format!(
+                    "{} '{}' is declared to return {} but can reach the end of its body without returning",
+                    kind,
+                    name,
+                    type_to_source(return_type)
+                ),
+            ));
+        }
+    }
+
+    fn function(&mut self, kind: &str, name: &str, params: &[(String, Type)], return_type: &Type, body: &[Stmt]) {
+        let place = format!("{} '{}'", kind.to_lowercase(), name);
+        self.nested(place, Some((kind, name, return_type)), params, body);
+    }
+
+    // Walks a block, adding what it binds to `assigned`. Returns whether
+    // control can reach the end of it.
+    fn block(&mut self, block: &[Stmt], assigned: &mut Assigned) -> bool {
+        for (i, stmt) in block.iter().enumerate() {
+            if !self.stmt(stmt, assigned) {
+                if i + 1 < block.len() {
+                    let after = match stmt {
+                        Stmt::Return(_) => "it follows a 'return'",
+                        Stmt::Raise { .. } => "it follows a 'raise'",
+                        Stmt::Break => "it follows a 'break'",
+                        Stmt::Continue => "it follows a 'continue'",
+                        _ => "every path through the statement before it returns, raises or leaves the loop",
+                    };
+                    self.diagnostics.push(
+                        Diagnostic::warning("unreachable", format!("Unreachable code in {}", self.place)).with_note(after),
+                    );
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, assigned: &mut Assigned) -> bool {
+        match stmt {
+            Stmt::VarDecl { value, .. } | Stmt::ConstDecl { value, .. } | Stmt::Destructure { value, .. } => {
+                self.expr(value, assigned)
+            }
+            Stmt::Assign { name, value } => {
+                self.expr(value, assigned);
+                self.read(name, assigned);
+            }
+            Stmt::DestructureAssign { names, value } => {
+                self.expr(value, assigned);
+                for name in names.iter().filter(|name| *name != DISCARD) {
+                    self.read(name, assigned);
+                }
+            }
+            Stmt::Expr(expr) => self.expr(expr, assigned),
+            Stmt::Go(expr) => self.expr(expr, assigned),
+            Stmt::Set { object, value, .. } => {
+                self.expr(object, assigned);
+                self.expr(value, assigned);
+            }
+            Stmt::FnDecl { name, params, return_type, body, .. } => {
+                self.function("Function", name, params, return_type, body)
+            }
+            Stmt::RecordDef { methods, .. } | Stmt::ClassDecl { methods, .. } => {
+                for method in methods {
+                    if let Stmt::FnDecl { name, params, return_type, body, .. } = method {
+                        self.function("Method", name, params, return_type, body);
+                    }
+                }
+            }
+            Stmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value, assigned);
+                }
+                return false;
+            }
+            Stmt::Raise { error, cause } => {
+                self.expr(error, assigned);
+                if let Some(cause) = cause {
+                    self.expr(cause, assigned);
+                }
+                return false;
+            }
+            Stmt::Break => {
+                if let Some(exits) = self.loops.last_mut() {
+                    exits.push(assigned.clone());
+                }
+                return false;
+            }
+            Stmt::Continue => return false,
+            Stmt::If { cond, then_block, else_block } => {
+                self.expr(cond, assigned);
+                let mut paths = Vec::new();
+                let mut then_assigned = assigned.clone();
+                if self.block(then_block, &mut then_assigned) {
+                    paths.push(then_assigned);
+                }
+                let mut else_assigned = assigned.clone();
+                if else_block.as_ref().is_none_or(|block| self.block(block, &mut else_assigned)) {
+                    paths.push(else_assigned);
+                }
+                match merge(paths) {
+                    Some(after) => *assigned = after,
+                    None => return false,
+                }
+            }
+            Stmt::While { cond, body } => {
+                self.expr(cond, assigned);
+                self.loops.push(Vec::new());
+                self.block(body, &mut assigned.clone());
+                let exits = self.loops.pop().unwrap_or_default();
+                // `while true:` is only left through a `break`, with what
+                // was bound at every one of them
+                if *cond == Expr::LiteralBool(true) {
+                    match merge(exits) {
+                        Some(after) => *assigned = after,
+                        None => return false,
+                    }
+                }
+            }
+            Stmt::For { names, iterable, body } => {
+                self.expr(iterable, assigned);
+                let mut body_assigned = assigned.clone();
+                body_assigned.extend(names.iter().cloned());
+                self.loops.push(Vec::new());
+                self.block(body, &mut body_assigned);
+                self.loops.pop();
+            }
+            Stmt::Match { subject, cases } => {
+                self.expr(subject, assigned);
+                let mut paths = Vec::new();
+                for case in cases {
+                    let mut case_assigned = assigned.clone();
+                    case_assigned.extend(case.pattern.names().into_iter().map(str::to_string));
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard, &case_assigned);
+                    }
+                    if self.block(&case.body, &mut case_assigned) {
+                        paths.push(case_assigned);
+                    }
+                }
+                // Unless the cases cover every value, none may run
+                if !self.enums.exhaustive(cases) {
+                    paths.push(assigned.clone());
+                }
+                match merge(paths) {
+                    Some(after) => *assigned = after,
+                    None => return false,
+                }
+            }
+            // The body runs once, after the value is bound
+            Stmt::With { value, name, body } => {
+                self.expr(value, assigned);
+                assigned.extend(name.clone());
+                if !self.block(body, assigned) {
+                    return false;
+                }
+            }
+            Stmt::Try { body, catch_var, catch_body, finally_body } => {
+                let before = assigned.clone();
+                let mut paths = Vec::new();
+                let mut body_assigned = before.clone();
+                if self.block(body, &mut body_assigned) {
+                    paths.push(body_assigned);
+                }
+                if let Some(catch_body) = catch_body {
+                    let mut catch_assigned = before.clone();
+                    catch_assigned.extend(catch_var.clone());
+                    if self.block(catch_body, &mut catch_assigned) {
+                        paths.push(catch_assigned);
+                    }
+                }
+                let after = merge(paths);
+                if let Some(finally_body) = finally_body {
+                    let mut finally_assigned = before;
+                    if !self.block(finally_body, &mut finally_assigned) {
+                        return false;
+                    }
+                    assigned.extend(finally_assigned);
+                }
+                match after {
+                    Some(after) => assigned.extend(after),
+                    None => return false,
+                }
+            }
+            Stmt::Import(_) | Stmt::Extern { .. } | Stmt::EnumDef { .. } | Stmt::InterfaceDef { .. } | Stmt::TypeAlias { .. } => {}
+        }
+        assigned.extend(binds(stmt).into_iter().map(str::to_string));
+        true
+    }
+}
+
+// Reports reads of unbound names in an expression, and walks the lambdas in
+// it as scopes of their own
+struct Reads<'s, 'a> {
+    scope: &'s mut Scope<'a>,
+    assigned: &'s Assigned,
+}
+
+impl Visitor for Reads<'_, '_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier(name) => self.scope.read(name, self.assigned),
+            Expr::Lambda { params, body } => {
+                let place = format!("a block lambda in {}", self.scope.place);
+                self.scope.nested(place, None, params, body)
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+// Returns every problem found: errors with the codes "unassigned" and
+// "missing-return", and warnings with the code "unreachable"
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let mut enums = Enums::prelude();
+    for stmt in statements {
+        enums.visit_stmt(stmt);
+    }
+    let mut top = Scope::new("the top level".to_string(), statements, &[], &HashSet::new(), &enums, &mut diagnostics);
+    top.block(statements, &mut Assigned::new());
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(diagnostics)
+    }
+}
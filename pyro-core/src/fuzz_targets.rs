@@ -0,0 +1,36 @@
+// Entry points for fuzzing with cargo-fuzz, enabled by the `fuzzing` feature.
+// None of them should panic on any input; a panic (or an abort from a stack
+// overflow) is a bug to fix in the lexer, parser or interpreter.
+//
+//     fuzz_target!(|data: &[u8]| {
+//         let _ = pyro_core::fuzz_targets::interpret_limited(data, Duration::from_millis(200));
+//     });
+
+use crate::ast::Program;
+use crate::interpreter::{Interpreter, RuntimeError, Value};
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::permissions::Permissions;
+use crate::prelude::Prelude;
+use std::time::{Duration, Instant};
+
+// Invalid UTF-8 is replaced rather than rejected, so every input reaches the lexer
+pub fn lex_bytes(data: &[u8]) -> Vec<Token> {
+    Lexer::new(&String::from_utf8_lossy(data)).tokenize()
+}
+
+pub fn parse_tokens(tokens: &[Token]) -> Result<Program, String> {
+    Parser::new(tokens).parse()
+}
+
+// Parses and runs `data` with no std modules, no filesystem, network or
+// subprocess access, and at most `time_limit` of wall-clock time, checked in
+// loops and function calls. Receiving from an empty channel still blocks.
+pub fn interpret_limited(data: &[u8], time_limit: Duration) -> Result<(), RuntimeError> {
+    let program = parse_tokens(&lex_bytes(data)).map_err(|e| Value::String(e.into()))?;
+
+    let mut interpreter = Interpreter::with_prelude(&Prelude::minimal());
+    interpreter.set_permissions(Some(Permissions::none()));
+    interpreter.interrupt_token().set_deadline(Some(Instant::now() + time_limit));
+    interpreter.run(program.statements).map(|_| ())
+}
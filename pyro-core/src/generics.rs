@@ -0,0 +1,158 @@
+// Checks how generic functions and types are used: each gets as many type
+// arguments as it declares parameters, and only generic ones get any. Type
+// arguments are erased when the interpreter runs a program, so this is where
+// `identity<int, string>(x)` is caught; the transpiler relies on it when it
+// compiles one copy of a generic per instantiation.
+
+use crate::ast::visit::{walk_block, walk_expr, walk_stmt, Visitor, VisitorMut};
+use crate::ast::{Expr, Stmt, Type};
+use crate::diagnostics::Diagnostic;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+struct Declarations {
+    // Generic parameter counts of functions and records, which can be called
+    callables: HashMap<String, usize>,
+    // ... and of everything that can be named in a type
+    types: HashMap<String, usize>,
+    errors: Vec<String>,
+}
+
+impl Declarations {
+    fn check_params(&mut self, name: &str, generics: &[String]) {
+        let mut seen = HashSet::new();
+        for param in generics {
+            if !seen.insert(param) {
+                self.errors.push(format!("Generic parameter '{}' of '{}' is declared more than once", param, name));
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, generics: &[String], callable: bool) {
+        self.check_params(name, generics);
+        if callable {
+            self.callables.insert(name.to_string(), generics.len());
+        }
+        self.types.insert(name.to_string(), generics.len());
+    }
+}
+
+impl Visitor for Declarations {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FnDecl { name, generics, .. } | Stmt::Extern { func_name: name, generics, .. } => {
+                self.declare(name, generics, true)
+            }
+            Stmt::RecordDef { name, generics, .. } => self.declare(name, generics, true),
+            Stmt::InterfaceDef { name, generics, .. } | Stmt::TypeAlias { name, generics, .. } => {
+                self.declare(name, generics, false)
+            }
+            Stmt::ClassDecl { name, .. } => self.declare(name, &[], true),
+            Stmt::EnumDef { name, .. } => self.declare(name, &[], false),
+            _ => {}
+        }
+        match stmt {
+            // Methods are reached through their object, so they don't shadow
+            // top-level names
+            Stmt::RecordDef { methods, .. } | Stmt::ClassDecl { methods, .. } => {
+                for method in methods {
+                    if let Stmt::FnDecl { name, generics, body, .. } = method {
+                        self.check_params(name, generics);
+                        walk_block(self, body);
+                    }
+                }
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+}
+
+struct Uses<'a> {
+    declarations: &'a Declarations,
+    errors: Vec<String>,
+}
+
+impl Uses<'_> {
+    fn check_count(&mut self, name: &str, expected: Option<&usize>, given: usize) {
+        match expected {
+            Some(0) => self.errors.push(format!("'{}' is not generic but is given type arguments", name)),
+            Some(&n) if n != given => self.errors.push(format!(
+                "'{}' takes {} type argument{}, got {}",
+                name,
+                n,
+                if n == 1 { "" } else { "s" },
+                given
+            )),
+            _ => {}
+        }
+    }
+}
+
+impl Visitor for Uses<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { function, generics, .. } = expr
+            && let Expr::Identifier(name) = &**function
+            && !generics.is_empty()
+        {
+            self.check_count(name, self.declarations.callables.get(name), generics.len());
+        }
+        walk_expr(self, expr);
+    }
+
+    // A bare `Box` in an annotation leaves the arguments open
+    fn visit_type(&mut self, typ: &Type) {
+        match typ {
+            Type::UserDefined(name, args) => {
+                if !args.is_empty() {
+                    self.check_count(name, self.declarations.types.get(name), args.len());
+                }
+                args.iter().for_each(|arg| self.visit_type(arg));
+            }
+            Type::Union(types) => types.iter().for_each(|t| self.visit_type(t)),
+            Type::Variadic(each) => self.visit_type(each),
+            _ => {}
+        }
+    }
+}
+
+// Returns every problem found, with the code "generics"
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut declarations = Declarations::default();
+    walk_block(&mut declarations, statements);
+
+    let mut uses = Uses { declarations: &declarations, errors: Vec::new() };
+    walk_block(&mut uses, statements);
+
+    let mut errors = declarations.errors.clone();
+    errors.extend(uses.errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.into_iter().map(|message| Diagnostic::error("generics", message)).collect())
+    }
+}
+
+// Replaces type parameters with what they stand for, in a type or, walked over
+// a statement, everywhere in it
+pub(crate) struct Substitute<'a>(pub &'a HashMap<String, Type>);
+
+impl VisitorMut for Substitute<'_> {
+    fn visit_type_mut(&mut self, typ: &mut Type) {
+        match typ {
+            Type::UserDefined(name, args) if args.is_empty() => {
+                if let Some(bound) = self.0.get(name) {
+                    *typ = bound.clone();
+                }
+            }
+            Type::UserDefined(_, types) | Type::Union(types) => types.iter_mut().for_each(|t| self.visit_type_mut(t)),
+            Type::Variadic(each) => self.visit_type_mut(each),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn substitute(typ: &Type, bindings: &HashMap<String, Type>) -> Type {
+    let mut typ = typ.clone();
+    Substitute(bindings).visit_type_mut(&mut typ);
+    typ
+}
@@ -0,0 +1,332 @@
+// Checks that values given where an interface is expected satisfy it.
+// Interfaces are structural: a record or class satisfies one when it has
+// every method the interface declares, taking the same number of parameters
+// (not counting `self`) of the same types and returning the same type, where
+// a record or class also stands for any interface it satisfies. No
+// `implements` is needed. Unannotated parameters and return types match
+// anything, as do the interface's own type parameters unless arguments are
+// given (`Container<int>`).
+//
+// Values are checked where their type is known before running: record and
+// class constructor calls, and variables annotated or initialized with one.
+// They are checked when bound to a variable annotated with an interface,
+// passed for such a parameter of a top-level function, or returned from a
+// function declared to return one.
+
+use crate::aliases;
+use crate::ast::visit::{walk_block, walk_expr, walk_stmt, Visitor};
+use crate::ast::{parameter_for, type_to_source, Expr, Stmt, Type};
+use crate::diagnostics::Diagnostic;
+use crate::generics::substitute;
+use std::collections::HashMap;
+
+type Signature = (Vec<(String, Type)>, Type);
+
+struct Interface {
+    generics: Vec<String>,
+    methods: Vec<(String, Signature)>,
+}
+
+// A record or class
+struct Implementor {
+    methods: HashMap<String, Signature>,
+    parent: Option<String>,
+    // Arguments a record's constructor needs before it gives a record rather
+    // than a partially applied constructor; classes take any
+    fields: Option<usize>,
+}
+
+#[derive(Default)]
+struct Declarations {
+    interfaces: HashMap<String, Interface>,
+    implementors: HashMap<String, Implementor>,
+    // Parameters of top-level functions
+    functions: HashMap<String, Vec<(String, Type)>>,
+}
+
+fn is_any(typ: &Type) -> bool {
+    matches!(typ, Type::Any | Type::ImplicitAny)
+}
+
+fn method_signatures(methods: &[Stmt]) -> HashMap<String, Signature> {
+    let mut signatures = HashMap::new();
+    for method in methods {
+        if let Stmt::FnDecl { name, params, return_type, .. } = method {
+            let params = match params.split_first() {
+                Some(((first, _), rest)) if first == "self" => rest.to_vec(),
+                _ => params.clone(),
+            };
+            signatures.insert(name.clone(), (params, return_type.clone()));
+        }
+    }
+    signatures
+}
+
+impl Visitor for Declarations {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::InterfaceDef { name, generics, methods } => {
+                let methods = methods.iter().map(|(m, params, ret)| (m.clone(), (params.clone(), ret.clone()))).collect();
+                self.interfaces.insert(name.clone(), Interface { generics: generics.clone(), methods });
+            }
+            Stmt::RecordDef { name, fields, methods, .. } => {
+                let methods = method_signatures(methods);
+                self.implementors.insert(name.clone(), Implementor { methods, parent: None, fields: Some(fields.len()) });
+            }
+            Stmt::ClassDecl { name, parent, methods } => {
+                let methods = method_signatures(methods);
+                self.implementors.insert(name.clone(), Implementor { methods, parent: parent.clone(), fields: None });
+            }
+            Stmt::FnDecl { name, params, .. } => {
+                self.functions.insert(name.clone(), params.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Declarations {
+    // Methods of a class include those it inherits
+    fn find_method(&self, implementor: &str, method: &str) -> Option<&Signature> {
+        let mut current = Some(implementor);
+        let mut depth = 0;
+        while let Some(name) = current
+            && depth <= self.implementors.len()
+        {
+            let found = self.implementors.get(name)?;
+            if let Some(signature) = found.methods.get(method) {
+                return Some(signature);
+            }
+            current = found.parent.as_deref();
+            depth += 1;
+        }
+        None
+    }
+
+    // Whether a method's `actual` type can stand where `expected` is
+    // declared. A record or class stands for an interface it satisfies.
+    fn compatible(&self, expected: &Type, actual: &Type, generics: &[String], assumed: &mut Vec<(String, String)>) -> bool {
+        if is_any(expected) || is_any(actual) || expected == actual {
+            return true;
+        }
+        match (expected, actual) {
+            // Type parameters left unbound match anything
+            (Type::UserDefined(name, args), _) if args.is_empty() && generics.contains(name) => true,
+            (Type::UserDefined(interface, args), Type::UserDefined(implementor, _))
+                if self.interfaces.contains_key(interface) && self.implementors.contains_key(implementor) =>
+            {
+                // Interfaces mentioning themselves (`def scale() -> Shape`)
+                // are satisfied unless something else is missing
+                let pair = (implementor.clone(), interface.clone());
+                if assumed.contains(&pair) {
+                    return true;
+                }
+                assumed.push(pair);
+                let satisfied = self.mismatches(implementor, interface, args, assumed).is_empty();
+                assumed.pop();
+                satisfied
+            }
+            _ => false,
+        }
+    }
+
+    // Why `implementor` doesn't satisfy `interface<args>`, one note per
+    // method, or nothing when it does
+    fn mismatches(&self, implementor: &str, interface: &str, args: &[Type], assumed: &mut Vec<(String, String)>) -> Vec<String> {
+        let interface = &self.interfaces[interface];
+        let bindings: HashMap<String, Type> = interface.generics.iter().cloned().zip(args.iter().cloned()).collect();
+        let mut matches = |expected: &Type, actual: &Type| {
+            self.compatible(&substitute(expected, &bindings), actual, &interface.generics, assumed)
+        };
+
+        let mut notes = Vec::new();
+        for (method, (params, ret)) in &interface.methods {
+            let Some((actual_params, actual_ret)) = self.find_method(implementor, method) else {
+                notes.push(format!("missing method '{}'", method));
+                continue;
+            };
+            if actual_params.len() != params.len() {
+                notes.push(format!(
+                    "'{}' takes {} parameter{}, the interface expects {}",
+                    method,
+                    actual_params.len(),
+                    if actual_params.len() == 1 { "" } else { "s" },
+                    params.len()
+                ));
+                continue;
+            }
+            for ((name, actual), (_, expected)) in actual_params.iter().zip(params) {
+                if !matches(expected, actual) {
+                    notes.push(format!(
+                        "'{}' parameter '{}' is {}, the interface expects {}",
+                        method,
+                        name,
+                        type_to_source(actual),
+                        type_to_source(&substitute(expected, &bindings))
+                    ));
+                }
+            }
+            if *ret != Type::Void && *actual_ret != Type::Void && !matches(ret, actual_ret) {
+                notes.push(format!(
+                    "'{}' returns {}, the interface expects {}",
+                    method,
+                    type_to_source(actual_ret),
+                    type_to_source(&substitute(ret, &bindings))
+                ));
+            }
+        }
+        notes
+    }
+}
+
+struct Uses<'a> {
+    declarations: &'a Declarations,
+    // Records and classes held by variables in scope, where known
+    locals: HashMap<String, String>,
+    // Annotations of variables in scope, which later assignments must match
+    annotations: HashMap<String, Type>,
+    // The function being checked and what it declares it returns
+    returns: Option<(String, Type)>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Uses<'_> {
+    fn implementor_of(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Identifier(name) => self.locals.get(name).cloned(),
+            Expr::Call { function, args, .. } => {
+                let Expr::Identifier(name) = &**function else { return None };
+                let implementor = self.declarations.implementors.get(name)?;
+                match implementor.fields {
+                    Some(fields) if args.len() < fields => None,
+                    _ => Some(name.clone()),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn check_value(&mut self, expected: &Type, value: &Expr, site: String) {
+        let Type::UserDefined(name, args) = expected else { return };
+        if !self.declarations.interfaces.contains_key(name) {
+            return;
+        }
+        let Some(implementor) = self.implementor_of(value) else { return };
+        let notes = self.declarations.mismatches(&implementor, name, args, &mut Vec::new());
+        if !notes.is_empty() {
+            let message = format!("'{}' {} does not satisfy interface '{}'", implementor, site, type_to_source(expected));
+            let diagnostic = notes.into_iter().fold(Diagnostic::error("interface", message), Diagnostic::with_note);
+            self.errors.push(diagnostic);
+        }
+    }
+
+    fn bind(&mut self, name: &str, typ: Option<&Type>, value: Option<&Expr>) {
+        match typ {
+            Some(typ) => self.annotations.insert(name.to_string(), typ.clone()),
+            None => self.annotations.remove(name),
+        };
+        let known = match typ {
+            Some(Type::UserDefined(class, _)) if self.declarations.implementors.contains_key(class) => Some(class.clone()),
+            Some(_) => None,
+            None => value.and_then(|value| self.implementor_of(value)),
+        };
+        match known {
+            Some(class) => self.locals.insert(name.to_string(), class),
+            None => self.locals.remove(name),
+        };
+    }
+}
+
+impl Visitor for Uses<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl { name, typ, value, .. } | Stmt::ConstDecl { name, typ, value } => {
+                if let Some(typ) = typ {
+                    self.check_value(typ, value, format!("given to '{}'", name));
+                }
+                walk_stmt(self, stmt);
+                self.bind(name, typ.as_ref(), Some(value));
+            }
+            Stmt::Return(Some(value)) => {
+                if let Some((function, returns)) = self.returns.clone() {
+                    self.check_value(&returns, value, format!("returned from '{}'", function));
+                }
+                walk_stmt(self, stmt);
+            }
+            Stmt::Assign { name, value } => {
+                let annotation = self.annotations.get(name).cloned();
+                if let Some(typ) = &annotation {
+                    self.check_value(typ, value, format!("assigned to '{}'", name));
+                }
+                walk_stmt(self, stmt);
+                self.bind(name, annotation.as_ref(), Some(value));
+            }
+            Stmt::FnDecl { name, params, return_type, body, .. } => {
+                let outer_locals = std::mem::take(&mut self.locals);
+                let outer_annotations = std::mem::take(&mut self.annotations);
+                let outer_returns = self.returns.replace((name.clone(), return_type.clone()));
+                for (name, typ) in params {
+                    self.bind(name, Some(typ), None);
+                }
+                walk_block(self, body);
+                self.locals = outer_locals;
+                self.annotations = outer_annotations;
+                self.returns = outer_returns;
+            }
+            Stmt::For { names, .. } => {
+                walk_stmt(self, stmt);
+                for name in names {
+                    self.bind(name, None, None);
+                }
+            }
+            Stmt::With { name: Some(name), .. } => {
+                self.bind(name, None, None);
+                walk_stmt(self, stmt);
+            }
+            Stmt::Match { cases, .. } => {
+                for name in cases.iter().flat_map(|case| case.pattern.names()) {
+                    self.bind(name, None, None);
+                }
+                walk_stmt(self, stmt);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { function, args, .. } = expr
+            && let Expr::Identifier(name) = &**function
+            && let Some(params) = self.declarations.functions.get(name)
+        {
+            for (i, arg) in args.iter().take_while(|arg| !matches!(arg, Expr::Spread(_))).enumerate() {
+                if let Some((_, param)) = parameter_for(params, i) {
+                    self.check_value(param, arg, format!("passed as argument {} of '{}'", i + 1, name));
+                }
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
+
+// Returns every value found not to satisfy the interface it is used as, with
+// the code "interface" and a note per mismatched method
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let statements = aliases::resolve(statements.to_vec());
+    let mut declarations = Declarations::default();
+    walk_block(&mut declarations, &statements);
+
+    let mut uses = Uses {
+        declarations: &declarations,
+        locals: HashMap::new(),
+        annotations: HashMap::new(),
+        returns: None,
+        errors: Vec::new(),
+    };
+    walk_block(&mut uses, &statements);
+
+    if uses.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(uses.errors)
+    }
+}
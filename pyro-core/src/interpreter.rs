@@ -1,11 +1,19 @@
-use crate::ast::{BinaryOp, Expr, Stmt, Type};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use crate::ast::{import_name, BinaryOp, Derives, Expr, Pattern, Stmt, StringPart, Type, DISCARD};
+use crate::diagnostics::Span;
+use crate::stdlib::runtime::{InterruptToken, Requests, INTERRUPT_POLL};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::io::Write;
 use tokio;
 use async_channel;
+use unicode_segmentation::UnicodeSegmentation;
+use rust_decimal::Decimal;
+use num_complex::Complex64;
+use rust_decimal::prelude::{FromStr, ToPrimitive};
 
 #[derive(Clone)]
-pub struct NativeClosure(pub Arc<dyn Fn(Vec<Value>) -> Result<Value, Value> + Send + Sync>);
+pub struct NativeClosure(pub Arc<dyn Fn(Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync>);
 
 impl std::fmt::Debug for NativeClosure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -19,18 +27,114 @@ impl PartialEq for NativeClosure {
     }
 }
 
+// Native function that runs against the live interpreter, for builtins that
+// need to execute Pyro code or change interpreter state (e.g. std.runtime).
+pub type InterpreterFn = dyn Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync;
+
+#[derive(Clone)]
+pub struct InterpreterClosure(pub Arc<InterpreterFn>);
+
+impl std::fmt::Debug for InterpreterClosure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+impl PartialEq for InterpreterClosure {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// The variables of a call by name, as they were when an error left it
+#[derive(Clone)]
+pub struct Env(pub Arc<HashMap<String, Value>>);
+
+impl std::fmt::Debug for Env {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        write!(f, "<locals {:?}>", names)
+    }
+}
+
+// A variable of a call. Functions defined in the call share it rather than
+// copy it, so an assignment on either side is seen by the other.
+type Cell = Arc<RwLock<Value>>;
+
+fn cell(value: Value) -> Cell {
+    Arc::new(RwLock::new(value))
+}
+
+// Variables a function captured where it was defined
+#[derive(Clone)]
+pub struct Captures(Arc<HashMap<String, Cell>>);
+
+impl Captures {
+    // What each captured variable holds now
+    fn values(&self) -> HashMap<String, Value> {
+        self.0.iter().map(|(name, cell)| (name.clone(), cell.read().unwrap().clone())).collect()
+    }
+}
+
+impl std::fmt::Debug for Captures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        write!(f, "<captures {:?}>", names)
+    }
+}
+
+// Makes sends on an unbuffered channel wait for a receiver. One send at a
+// time holds `turn`, and returns once a receive acknowledges its value.
+#[derive(Debug)]
+pub struct Rendezvous {
+    turn: Mutex<()>,
+    ack_tx: async_channel::Sender<()>,
+    ack_rx: async_channel::Receiver<()>,
+}
+
+impl Rendezvous {
+    fn new() -> Self {
+        let (ack_tx, ack_rx) = async_channel::bounded(1);
+        Rendezvous { turn: Mutex::new(()), ack_tx, ack_rx }
+    }
+}
+
+// Sends a value on a channel, blocking while it's full or, if unbuffered,
+// until a receiver takes it. False if the channel is closed.
+fn send(tx: &async_channel::Sender<Value>, rendezvous: &Option<Arc<Rendezvous>>, value: Value) -> bool {
+    let Some(rendezvous) = rendezvous else {
+        return tokio::task::block_in_place(|| tx.send_blocking(value)).is_ok();
+    };
+    let _turn = rendezvous.turn.lock().unwrap();
+    if tokio::task::block_in_place(|| tx.send_blocking(value)).is_err() {
+        return false;
+    }
+    // Closing the channel also ends the wait
+    let _ = tokio::task::block_in_place(|| rendezvous.ack_rx.recv_blocking());
+    true
+}
+
+
 #[derive(Debug, Clone)]
 pub enum Value {
     Int(i64),
     Float(f64),
+    // Exact base-10 numbers for money math, created with decimal(...)
+    Decimal(Decimal),
     Bool(bool),
     // Managed by Arc
     String(Arc<String>), 
+    // A single code point, from 'a' literals, chr() and iterating a string
+    Char(char),
     Function {
         generics: Vec<String>,
         params: Vec<(String, Type)>,
         body: Arc<Vec<Stmt>>,
         partial_args: Vec<Value>, // For currying
+        // Locals of the call the function was defined in, if any
+        env: Option<Captures>,
     },
     List(Arc<Vec<Value>>), // Immutable
     Tuple(Arc<Vec<Value>>),
@@ -57,13 +161,27 @@ pub enum Value {
         fields: Arc<Vec<String>>, 
         values: Arc<Vec<Value>>,
         methods: Arc<HashMap<String, Value>>,
+        derives: Derives,
     },
     RecordConstructor {
         name: String,
-        fields: Vec<String>, // Field names
+        fields: Arc<Vec<String>>, // Field names
         methods: Arc<HashMap<String, Value>>,
+        derives: Derives,
         partial_args: Vec<Value>, // For currying
     },
+    // Enums: the type lists its variants with their field names, and a
+    // value is one of them with a value per field
+    Enum {
+        name: String,
+        variants: Arc<Vec<(String, Arc<Vec<String>>)>>,
+    },
+    EnumVariant {
+        enum_name: String,
+        variant: String,
+        fields: Arc<Vec<String>>,
+        values: Arc<Vec<Value>>,
+    },
 
     // Mutable
     ListMutable(Arc<RwLock<Vec<Value>>>),
@@ -80,12 +198,36 @@ pub enum Value {
         name: String,
         func: NativeClosure,
     },
+    InterpreterFunction {
+        name: String,
+        func: InterpreterClosure,
+    },
     NativeModule(Arc<HashMap<String, Value>>),
+
+    // Monotonic clock readings and elapsed time from std.time
+    Instant(std::time::Instant),
+    Duration(std::time::Duration),
+
+    // Complex numbers from std.math
+    Complex(Complex64),
+
+    // Integers from `start..end`, `start..=end` or `range()`, produced as
+    // they're used. `step` is 1 except from `range()`, and never 0.
+    Range { start: i64, end: i64, inclusive: bool, step: i64 },
+
+    // Dense f64 vectors and matrices from std.linalg
+    Matrix(Arc<crate::stdlib::linalg::Matrix>),
+
+    // Filesystem paths from std.path; functions taking a string path take
+    // these too
+    Path(Arc<std::path::PathBuf>),
     
     Channel {
         sender: Option<Arc<async_channel::Sender<Value>>>,
         receiver: Option<Arc<async_channel::Receiver<Value>>>,
         typ: Type,
+        // Only for unbuffered channels, whose sends wait for a receive
+        rendezvous: Option<Arc<Rendezvous>>,
     },
 
     Void,
@@ -96,8 +238,10 @@ impl PartialEq for Value {
         match (self, other) {
             (Value::Int(a), Value::Int(b)) => a == b,
             (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
             (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::String(a), Value::String(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
             (Value::Function { .. }, Value::Function { .. }) => false, // Functions not equitable
             (Value::List(a), Value::List(b)) => a == b,
             (Value::Tuple(a), Value::Tuple(b)) => a == b,
@@ -105,6 +249,20 @@ impl PartialEq for Value {
             (Value::Dict(a), Value::Dict(b)) => a == b, // Arc<Vec> equality
             
             (Value::Class { name: n1, .. }, Value::Class { name: n2, .. }) => n1 == n2,
+            // By value if the record derives eq, otherwise only the same record
+            (
+                Value::Record { name: n1, values: v1, derives, .. },
+                Value::Record { name: n2, values: v2, .. },
+            ) => if derives.eq { n1 == n2 && v1 == v2 } else { Arc::ptr_eq(v1, v2) },
+            (
+                Value::RecordConstructor { name: n1, partial_args: a1, .. },
+                Value::RecordConstructor { name: n2, partial_args: a2, .. },
+            ) => n1 == n2 && a1 == a2,
+            (Value::Enum { name: n1, .. }, Value::Enum { name: n2, .. }) => n1 == n2,
+            (
+                Value::EnumVariant { enum_name: e1, variant: v1, values: x1, .. },
+                Value::EnumVariant { enum_name: e2, variant: v2, values: x2, .. },
+            ) => e1 == e2 && v1 == v2 && x1 == x2,
             
             (Value::Instance { fields: f1, .. }, Value::Instance { fields: f2, .. }) => {
                  if Arc::ptr_eq(f1, f2) { return true; }
@@ -147,6 +305,19 @@ impl PartialEq for Value {
              },
              
              (Value::Void, Value::Void) => true,
+             (Value::Complex(a), Value::Complex(b)) => a == b,
+             // Equal when they hold the same ints, so 1..4 == 1..=3
+             (
+                 Value::Range { start: s1, end: e1, inclusive: i1, step: t1 },
+                 Value::Range { start: s2, end: e2, inclusive: i2, step: t2 },
+             ) => {
+                 let (len1, len2) = (range_len(*s1, *e1, *i1, *t1), range_len(*s2, *e2, *i2, *t2));
+                 len1 == len2 && (len1 == 0 || (s1 == s2 && (len1 == 1 || t1 == t2)))
+             }
+             (Value::Instant(a), Value::Instant(b)) => a == b,
+             (Value::Duration(a), Value::Duration(b)) => a == b,
+             (Value::Matrix(a), Value::Matrix(b)) => a == b,
+             (Value::Path(a), Value::Path(b)) => a == b,
              (Value::Channel { sender: s1, .. }, Value::Channel { sender: s2, .. }) => {
                  match (s1, s2) {
                      (Some(a), Some(b)) => Arc::ptr_eq(a, b),
@@ -160,6 +331,14 @@ impl PartialEq for Value {
     }
 }
 
+// What a for loop walks: values taken from a collection as they're needed,
+// or an object whose `__next__` method gives each one
+enum Iteration {
+    Values(Box<dyn Iterator<Item = Value>>),
+    Next(Value),
+    Channel(Arc<async_channel::Receiver<Value>>, Option<Arc<Rendezvous>>),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Flow {
     Return(Value),
@@ -168,93 +347,1331 @@ pub enum Flow {
     None,
 }
 
-#[derive(Clone)]
-pub struct Interpreter {
-    // Nested scopes: push hashmap on entry, pop on exit
-    // optimizing to single scope for now for simplicity
-    globals: HashMap<String, Value>,
-    native_modules: HashMap<String, Value>,
-}
+// A Pyro function call an error unwound through, named as the caller wrote it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+}
+
+// What running code fails with: the raised value, the calls it unwound
+// through, innermost first, and where it was raised, once statements carry
+// their positions. Any value converts into one with an empty trace.
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub value: Value,
+    pub trace: Vec<Frame>,
+    pub span: Option<Span>,
+}
+
+impl From<Value> for RuntimeError {
+    fn from(value: Value) -> Self {
+        RuntimeError { value, trace: Vec::new(), span: None }
+    }
+}
+
+impl RuntimeError {
+    // The value a `catch` binds. An Error instance gets the trace as a
+    // `traceback` field, outermost call first, unless it has one from an
+    // earlier catch.
+    pub fn into_value(self) -> Value {
+        if let Value::Instance { fields, .. } = &self.value
+            && !self.trace.is_empty()
+        {
+            let mut fields = fields.write().unwrap();
+            if !fields.contains_key("traceback") {
+                let frames = self.trace.iter().rev().map(|frame| Value::String(Arc::new(frame.function.clone())));
+                fields.insert("traceback".to_string(), Value::List(Arc::new(frames.collect())));
+            }
+        }
+        self.value
+    }
+}
+
+// An error for native code to return with a machine-readable kind, so scripts
+// can branch on `e.kind` instead of parsing messages.
+pub fn error_with_kind(kind: &str, message: impl Into<String>) -> RuntimeError {
+    let mut fields = HashMap::new();
+    fields.insert("message".to_string(), Value::String(Arc::new(message.into())));
+    fields.insert("kind".to_string(), Value::String(Arc::new(kind.to_string())));
+    RuntimeError::from(Value::Instance {
+        class_name: "Error".to_string(),
+        fields: Arc::new(RwLock::new(fields)),
+        methods: Arc::new(HashMap::new()),
+    })
+}
+
+// Maps an io::Error to an error whose kind is the io::ErrorKind name, e.g.
+// "NotFound", "PermissionDenied" or "AlreadyExists".
+pub fn io_error(e: &std::io::Error) -> RuntimeError {
+    error_with_kind(&format!("{:?}", e.kind()), e.to_string())
+}
+
+// The name `type(x)` returns for values that aren't instances or records.
+// Builtin collections use their type annotation names.
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Int(_) => "int",
+        Value::Float(_) => "float",
+        Value::Decimal(_) => "decimal",
+        Value::Bool(_) => "bool",
+        Value::String(_) => "string",
+        Value::Char(_) => "char",
+        Value::List(_) => "list",
+        Value::Tuple(_) => "tuple",
+        Value::Set(_) => "set",
+        Value::Dict(_) => "dict",
+        Value::ListMutable(_) => "list_mut",
+        Value::TupleMutable(_) => "tuple_mut",
+        Value::SetMutable(_) => "set_mut",
+        Value::DictMutable(_) => "dict_mut",
+        Value::Function { .. }
+        | Value::BoundMethod { .. }
+        | Value::BuiltinMethod { .. }
+        | Value::NativeFunction { .. }
+        | Value::InterpreterFunction { .. } => "function",
+        Value::Class { .. } | Value::RecordConstructor { .. } | Value::Enum { .. } => "type",
+        Value::Instance { .. } => "instance",
+        Value::Record { .. } => "record",
+        Value::EnumVariant { .. } => "enum",
+        Value::NativeModule(_) => "module",
+        Value::Instant(_) => "instant",
+        Value::Duration(_) => "duration",
+        Value::Complex(_) => "complex",
+        Value::Range { .. } => "range",
+        Value::Matrix(_) => "matrix",
+        Value::Path(_) => "path",
+        Value::Channel { .. } => "channel",
+        Value::Void => "void",
+    }
+}
+
+// The ints in a range, as an inclusive std range. It's empty when the range
+// is, including `i64::MIN..i64::MIN`.
+pub fn range_ints(start: i64, end: i64, inclusive: bool) -> std::ops::RangeInclusive<i64> {
+    match if inclusive { Some(end) } else { end.checked_sub(1) } {
+        Some(last) => start..=last,
+        None => {
+            let mut empty = 0..=0;
+            empty.next();
+            empty
+        }
+    }
+}
+
+// to_list() refuses ranges longer than this rather than exhaust memory
+const MAX_RANGE_LIST: i64 = 100_000_000;
+
+// How many ints a range holds. They are `start`, `start + step` and so on,
+// stopping before `end`, or at it when inclusive.
+fn range_len(start: i64, end: i64, inclusive: bool, step: i64) -> i64 {
+    let ints = if step > 0 {
+        range_ints(start, end, inclusive)
+    } else {
+        // Counting down, the ints are those of the range from `end` up
+        match if inclusive { Some(end) } else { end.checked_add(1) } {
+            Some(last) => last..=start,
+            None => range_ints(0, 0, false),
+        }
+    };
+    if ints.is_empty() {
+        return 0;
+    }
+    let count = *ints.end() as i128 - *ints.start() as i128 + 1;
+    let stride = (step as i128).abs();
+    ((count + stride - 1) / stride).min(i64::MAX as i128) as i64
+}
+
+// The int at `index` of a range, if it has that many. Wrapping arithmetic
+// gives the right answer, as the int itself is always in bounds.
+fn range_get(start: i64, end: i64, inclusive: bool, step: i64, index: i64) -> Option<i64> {
+    (0..range_len(start, end, inclusive, step))
+        .contains(&index)
+        .then(|| start.wrapping_add(index.wrapping_mul(step)))
+}
+
+fn range_contains(start: i64, end: i64, inclusive: bool, step: i64, value: i64) -> bool {
+    let offset = value as i128 - start as i128;
+    offset % step as i128 == 0
+        && i64::try_from(offset / step as i128).is_ok_and(|index| range_get(start, end, inclusive, step, index).is_some())
+}
+
+pub fn range_values(start: i64, end: i64, inclusive: bool, step: i64) -> impl Iterator<Item = i64> {
+    (0..range_len(start, end, inclusive, step)).map(move |index| start.wrapping_add(index.wrapping_mul(step)))
+}
+
+// `Shape.Circle`: the value itself for a variant without fields, otherwise a
+// function taking one argument per field and returning the value
+fn enum_variant(enum_name: &str, variant: &str, fields: &Arc<Vec<String>>) -> Value {
+    let (enum_name, variant, fields) = (enum_name.to_string(), variant.to_string(), fields.clone());
+    if fields.is_empty() {
+        return Value::EnumVariant { enum_name, variant, fields, values: Arc::new(Vec::new()) };
+    }
+    Value::NativeFunction {
+        name: format!("{}.{}", enum_name, variant),
+        func: NativeClosure(Arc::new(move |args: Vec<Value>| {
+            if args.len() != fields.len() {
+                let noun = if fields.len() == 1 { "argument" } else { "arguments" };
+                return Err(Value::String(Arc::new(format!(
+                    "'{}.{}' takes {} {}, {} given",
+                    enum_name,
+                    variant,
+                    fields.len(),
+                    noun,
+                    args.len()
+                )))
+                .into());
+            }
+            Ok(Value::EnumVariant {
+                enum_name: enum_name.clone(),
+                variant: variant.clone(),
+                fields: fields.clone(),
+                values: Arc::new(args),
+            })
+        })),
+    }
+}
+
+// How print and str show a value: `Point(x=1, y=2)` for a record that
+// derives show, `Shape.Circle(radius=2)` for an enum value, and the debug
+// form for anything else
+fn show(value: &Value) -> String {
+    match value {
+        Value::Record { name, fields, values, derives, .. } if derives.show => {
+            let fields: Vec<String> = fields.iter().zip(values.iter()).map(|(f, v)| format!("{}={}", f, show_field(v))).collect();
+            format!("{}({})", name, fields.join(", "))
+        }
+        Value::EnumVariant { enum_name, variant, fields, .. } if fields.is_empty() => format!("{}.{}", enum_name, variant),
+        Value::EnumVariant { enum_name, variant, fields, values } => {
+            let fields: Vec<String> = fields.iter().zip(values.iter()).map(|(f, v)| format!("{}={}", f, show_field(v))).collect();
+            format!("{}.{}({})", enum_name, variant, fields.join(", "))
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+// What str(x) and f-strings give
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::Char(c) => c.to_string(),
+        Value::Complex(z) => z.to_string(),
+        Value::Duration(d) => crate::stdlib::time::format_duration(*d),
+        Value::Path(p) => p.display().to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => show(other),
+    }
+}
+
+fn show_field(value: &Value) -> String {
+    match value {
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::String(s) => format!("{:?}", s.as_str()),
+        Value::Char(c) => format!("{:?}", c),
+        other => show(other),
+    }
+}
+
+// Runs a native function, turning a panic into an Error with kind "Panic" so
+// a bug in one stdlib function can't take down the host process or the REPL.
+fn catch_panic<T>(name: &str, f: impl FnOnce() -> T) -> Result<T, RuntimeError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).map_err(|payload| {
+        let detail = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        error_with_kind("Panic", format!("Native function '{}' panicked: {}", name, detail))
+    })
+}
+
+// Upper bound in seconds for a single retry() wait, however many attempts
+const MAX_RETRY_DELAY: f64 = 30.0;
+
+// Deeper recursion raises a RecursionError rather than exhausting memory,
+// unless the host or script sets another limit
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+// Statements and expressions nest by recursion, so the stack is grown in
+// STACK_SEGMENT steps whenever less than RED_ZONE of it is left. This keeps
+// deep recursion from overflowing the stack, even on small thread stacks.
+const RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+#[derive(Clone)]
+pub struct Interpreter {
+    // Top-level variables, shared with the goroutines and jobs the program
+    // starts; each call keeps its own in a CallFrame
+    globals: Arc<RwLock<HashMap<String, Value>>>,
+    native_modules: HashMap<String, Value>,
+    // Pyro sources for modules written in Pyro, loaded on first import
+    embedded_modules: HashMap<String, &'static str>,
+    // Embedded sources already merged into native_modules
+    loaded_embedded: HashSet<String>,
+    // Pyro function calls in progress, innermost last
+    frames: Vec<CallFrame>,
+    max_call_depth: usize,
+    // Where print writes, if not to stdout
+    output: Option<Output>,
+    // Where runtime error reports (failed goroutines, jobs) go, if not to stderr
+    error_output: Option<Output>,
+    // Names declared with `const`, which nothing may rebind
+    constants: HashSet<String>,
+    // Instrumentation told about calls, statements and errors, if any
+    hooks: Option<Hooks>,
+    // Whether an error reported to on_raise is still propagating, so each
+    // error is reported once rather than by every statement it unwinds
+    unwinding: bool,
+    // Whether the error coming back from a statement is an `Option.None` or
+    // `Result.Err` that `?` is returning from the function, not an error
+    returning: bool,
+    // Variables of the innermost Pyro function the propagating error left,
+    // captures included, for post-mortem inspection
+    error_locals: Option<Env>,
+    // Cancels the running code; shared with goroutines
+    interrupt: InterruptToken,
+    // What the stdlib may access, if restricted; shared with goroutines
+    permissions: Option<Arc<crate::permissions::Permissions>>,
+    // Work queued for this interpreter by a host, once it has asked for the
+    // handle; goroutines and jobs start without one
+    requests: Option<Requests>,
+}
+
+// A running Pyro function call: its parameters and the variables it binds,
+// and what the function captured where it was defined
+#[derive(Clone)]
+struct CallFrame {
+    locals: HashMap<String, Cell>,
+    env: Option<Captures>,
+}
+
+pub type Output = Arc<Mutex<dyn std::io::Write + Send>>;
+
+// Observes a running program, for profilers, tracers, debuggers and coverage.
+// Every method does nothing by default. Calls are the ones written in the
+// program, native functions included, named as at the call site.
+pub trait InterpreterHooks {
+    fn on_call(&mut self, _function: &str, _args: &[Value]) {}
+    fn on_return(&mut self, _function: &str, _result: &Result<Value, RuntimeError>) {}
+    // Before each statement runs
+    fn on_stmt(&mut self, _stmt: &Stmt) {}
+    // Once per error, where it is raised, before any `except` sees it
+    fn on_raise(&mut self, _error: &RuntimeError) {}
+}
+
+// Shared by the goroutines a program starts, which clone the interpreter
+pub type Hooks = Arc<Mutex<dyn InterpreterHooks + Send>>;
+
+// The global holding the name of the module whose top-level code is running
+pub const MODULE_NAME: &str = "__name__";
+// `__name__` in the file a program starts from
+pub const MAIN_MODULE: &str = "main";
+
+// A variant's name and the names of its fields
+pub type VariantFields = (&'static str, &'static [&'static str]);
+
+// Enums every program starts with, and the fields of their variants. `?`
+// unwraps `Option.Some` and `Result.Ok` and returns the other variants.
+pub const PRELUDE_ENUMS: &[(&str, &[VariantFields])] = &[
+    ("Option", &[("Some", &["value"]), ("None", &[])]),
+    ("Result", &[("Ok", &["value"]), ("Err", &["error"])]),
+];
+
+// Builtins added since programs could define names of their own, which give
+// way to a user definition of the same name once it is bound
+const OVERRIDABLE_BUILTINS: &[&str] = &["decimal", "retry", "timeit", "type", "isinstance", "ord", "chr", "sorted"];
+
+pub fn is_overridable_builtin(name: &str) -> bool {
+    OVERRIDABLE_BUILTINS.contains(&name) || Type::numeric(name).is_some()
+}
+
+impl Interpreter {
+    // An interpreter with the full standard library
+    pub fn new() -> Self {
+        Self::with_prelude(&crate::prelude::Prelude::full())
+    }
+
+    // An interpreter with only the std modules in `prelude`. The Error class
+    // and the builtins (print, range, str, ...) are always available.
+    pub fn with_prelude(prelude: &crate::prelude::Prelude) -> Self {
+        let mut globals = HashMap::new();
+
+        // Define built-in Error class
+        // class Error:
+        //     def __init__(self, message):
+        //         self.message = message
+        //         self.kind = "Error"
+        let init_body = vec![
+            Stmt::Set {
+                object: Expr::Identifier("self".to_string()),
+                name: "message".to_string(),
+                value: Expr::Identifier("message".to_string()),
+            },
+            Stmt::Set {
+                object: Expr::Identifier("self".to_string()),
+                name: "kind".to_string(),
+                value: Expr::LiteralString("Error".to_string()),
+            }
+        ];
+
+        let init_func = Value::Function {
+            generics: Vec::new(),
+            params: vec![("self".to_string(), Type::Void), ("message".to_string(), Type::String)],
+            body: Arc::new(init_body),
+            partial_args: Vec::new(),
+            env: None,
+        };
+
+        let mut error_methods = HashMap::new();
+        error_methods.insert("__init__".to_string(), init_func);
+
+        globals.insert("Error".to_string(), Value::Class {
+            name: "Error".to_string(),
+            parent: None,
+            methods: Arc::new(error_methods),
+        });
+        for (name, variants) in PRELUDE_ENUMS {
+            let variants = variants
+                .iter()
+                .map(|(variant, fields)| (variant.to_string(), Arc::new(fields.iter().map(|f| f.to_string()).collect())))
+                .collect();
+            globals.insert(name.to_string(), Value::Enum { name: name.to_string(), variants: Arc::new(variants) });
+        }
+        // Module loaders change this while an imported file runs, so
+        // `if __name__ == "main":` only runs in the entry file
+        globals.insert(MODULE_NAME.to_string(), Value::String(Arc::new(MAIN_MODULE.to_string())));
+
+        let mut interpreter = Self {
+            globals: Arc::new(RwLock::new(globals)),
+            native_modules: HashMap::new(),
+            embedded_modules: HashMap::new(),
+            loaded_embedded: HashSet::new(),
+            frames: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            output: None,
+            error_output: None,
+            hooks: None,
+            unwinding: false,
+            returning: false,
+            error_locals: None,
+            interrupt: InterruptToken::default(),
+            requests: None,
+            permissions: None,
+            constants: HashSet::new(),
+        };
+        prelude.install(&mut interpreter);
+        interpreter
+    }
+
+    // Makes `import name` bind `module`, usually a Value::NativeModule,
+    // replacing any native module of the same name. Programs that imported
+    // the old one keep it; imports after this get the new one.
+    pub fn register_native_module(&mut self, name: &str, module: Value) {
+        self.native_modules.insert(name.to_string(), module);
+    }
+
+    // Removes the module `import name` would load, native, embedded or both,
+    // and returns whether there was one. Like registering, this only changes
+    // later imports: a program that already imported it keeps its binding.
+    pub fn unregister_native_module(&mut self, name: &str) -> bool {
+        let native = self.native_modules.remove(name).is_some();
+        let embedded = self.embedded_modules.remove(name).is_some();
+        self.loaded_embedded.remove(name);
+        native || embedded
+    }
+
+    // The names of every module `import` can load without a file, sorted
+    pub fn list_native_modules(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> =
+            self.native_modules.keys().chain(self.embedded_modules.keys()).map(String::as_str).collect();
+        names.into_iter().collect()
+    }
+
+    pub fn register_native_function<F>(&mut self, name: &str, func: F)
+    where F: Fn(Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static 
+    {
+        self.globals.write().unwrap().insert(name.to_string(), Value::NativeFunction {
+            name: name.to_string(),
+            func: NativeClosure(Arc::new(func)),
+        });
+    }
+
+    pub fn register_embedded_module(&mut self, name: &str, source: &'static str) {
+        self.embedded_modules.insert(name.to_string(), source);
+    }
+
+    // Registers the modules of the plugin library at `path` and returns
+    // their names. See plugin.rs for how plugins are built.
+    pub fn load_plugin(&mut self, path: &str) -> Result<Vec<String>, RuntimeError> {
+        crate::permissions::check_plugin(self, path).map_err(|e| self.native_error(e))?;
+        let modules = crate::plugin::load(path)
+            .map_err(|e| self.make_error(&format!("Could not load plugin '{}': {}", path, e)))?;
+        let mut names = Vec::new();
+        for descriptor in modules {
+            self.register_native_module(&descriptor.name, descriptor.module);
+            names.push(descriptor.name);
+        }
+        Ok(names)
+    }
+
+    // Whether `import name` loads a module without a file
+    pub fn has_native_module(&self, name: &str) -> bool {
+        self.native_modules.contains_key(name) || self.embedded_modules.contains_key(name)
+    }
+
+    // Reads a global variable, e.g. to get results back after running a script
+    pub fn global(&self, name: &str) -> Option<Value> {
+        self.globals.read().unwrap().get(name).cloned()
+    }
+
+    // Every global variable and its value, at this moment
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        self.globals.read().unwrap().iter().map(|(name, value)| (name.clone(), value.clone())).collect()
+    }
+
+    // The variables of the innermost Pyro function the last uncaught error
+    // was raised in or unwound through, or None if it came from the top level
+    pub fn error_locals(&self) -> Option<&Env> {
+        self.error_locals.as_ref()
+    }
+
+    // The token that cancels what this interpreter and its goroutines run,
+    // for a host's Ctrl-C handler or time limit
+    pub fn interrupt_token(&self) -> InterruptToken {
+        self.interrupt.clone()
+    }
+
+    // Restricts the filesystem, network and subprocess access of the code
+    // this interpreter runs from now on, or with None lifts the restriction
+    pub fn set_permissions(&mut self, permissions: Option<crate::permissions::Permissions>) {
+        self.permissions = permissions.map(Arc::new);
+    }
+
+    pub fn permissions(&self) -> Option<&crate::permissions::Permissions> {
+        self.permissions.as_deref()
+    }
+
+    // Where other threads queue work for this interpreter, such as reloads
+    // from a file watcher
+    pub fn requests(&mut self) -> Requests {
+        self.requests.get_or_insert_with(Requests::default).clone()
+    }
+
+    // A copy for code that runs alongside this interpreter, such as a
+    // goroutine or a job. It shares the globals and the interrupt token but
+    // leaves queued requests to this interpreter.
+    pub(crate) fn fork(&self) -> Interpreter {
+        Interpreter { requests: None, ..self.clone() }
+    }
+
+    // Does any queued requests, at the top level whatever call is running
+    fn poll_requests(&mut self) {
+        if let Some(requests) = self.requests.clone().filter(Requests::pending) {
+            let frames = std::mem::take(&mut self.frames);
+            requests.apply(self);
+            self.frames = frames;
+        }
+    }
+
+    // Sleeps like thread::sleep, but returns early with an error on
+    // interrupt and does queued requests while it waits
+    pub(crate) fn sleep(&mut self, duration: std::time::Duration) -> Result<(), RuntimeError> {
+        let deadline = std::time::Instant::now() + duration;
+        loop {
+            self.interrupt.check()?;
+            self.poll_requests();
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            std::thread::sleep((deadline - now).min(INTERRUPT_POLL));
+        }
+    }
+
+    // Takes the next value off a channel, blocking until there is one. None
+    // once the channel is closed and drained. An interpreter with queued
+    // requests wakes up to do them while it waits.
+    fn receive(&mut self, rx: &async_channel::Receiver<Value>, rendezvous: &Option<Arc<Rendezvous>>) -> Option<Value> {
+        let value = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) if self.requests.is_some() => loop {
+                let wait = tokio::time::timeout(INTERRUPT_POLL, rx.recv());
+                match tokio::task::block_in_place(|| runtime.block_on(wait)) {
+                    Ok(received) => break received.ok()?,
+                    Err(_) => self.poll_requests(),
+                }
+            },
+            _ => tokio::task::block_in_place(|| rx.recv_blocking()).ok()?,
+        };
+        if let Some(rendezvous) = rendezvous {
+            let _ = rendezvous.ack_tx.try_send(());
+        }
+        Some(value)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.write().unwrap().insert(name.to_string(), value);
+    }
+
+    // Sends the output of print to `output` instead of stdout, e.g. to
+    // capture it in tests. Goroutines started afterwards share it.
+    pub fn set_output(&mut self, output: Output) {
+        self.output = Some(output);
+    }
+
+    // Like set_output, for errors reported outside the normal flow, such as
+    // a goroutine or scheduled job that fails.
+    pub fn set_error_output(&mut self, output: Output) {
+        self.error_output = Some(output);
+    }
+
+    // Swaps the print output, returning the previous one so a caller that
+    // captures output for a while can put it back.
+    pub fn replace_output(&mut self, output: Option<Output>) -> Option<Output> {
+        std::mem::replace(&mut self.output, output)
+    }
+
+    // Swaps the instrumentation hooks, returning the previous ones
+    pub fn replace_hooks(&mut self, hooks: Option<Hooks>) -> Option<Hooks> {
+        std::mem::replace(&mut self.hooks, hooks)
+    }
+
+    fn hooks(&self) -> Option<std::sync::MutexGuard<'_, dyn InterpreterHooks + Send + 'static>> {
+        self.hooks.as_ref().map(|hooks| hooks.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    // Writes a line to the print output
+    pub fn write_output(&self, line: &str) -> std::io::Result<()> {
+        match &self.output {
+            Some(output) => writeln!(output.lock().unwrap_or_else(|e| e.into_inner()), "{}", line),
+            None => writeln!(std::io::stdout(), "{}", line),
+        }
+    }
+
+    // Writes a line to the error output
+    pub fn write_error(&self, line: &str) -> std::io::Result<()> {
+        match &self.error_output {
+            Some(output) => writeln!(output.lock().unwrap_or_else(|e| e.into_inner()), "{}", line),
+            None => writeln!(std::io::stderr(), "{}", line),
+        }
+    }
+
+    // Builds the module value for `import path`: the native module, the
+    // embedded Pyro source of the same name, or both merged. Embedded sources
+    // run once in their own scope; the result is cached as a native module.
+    fn load_module(&mut self, path: &str) -> Result<Option<Value>, RuntimeError> {
+        let native = self.native_modules.get(path).cloned();
+        let Some(source) = self.embedded_modules.get(path).copied() else {
+            return Ok(native);
+        };
+        if self.loaded_embedded.contains(path) {
+            return Ok(native);
+        }
+
+        let tokens = crate::lexer::Lexer::new(source).tokenize();
+        let program = crate::parser::Parser::new(&tokens)
+            .parse()
+            .map_err(|e| self.make_error(&format!("Parse error in embedded module '{}': {}", path, e)))?;
+
+        // The module's own globals, not shared with the importer's
+        let mut globals: HashMap<String, Value> = self.globals.read().unwrap().iter()
+            .filter(|(name, _)| *name == "Error" || PRELUDE_ENUMS.iter().any(|(e, _)| e == *name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        globals.insert(MODULE_NAME.to_string(), Value::String(Arc::new(path.to_string())));
+        let baseline: Vec<String> = globals.keys().cloned().collect();
+        let mut module_scope = self.fork();
+        module_scope.frames.clear();
+        module_scope.globals = Arc::new(RwLock::new(globals));
+        module_scope.constants.clear();
+        module_scope.run(program.statements)?;
+
+        let mut exports = match native {
+            Some(Value::NativeModule(e)) => (*e).clone(),
+            _ => HashMap::new(),
+        };
+        for (name, value) in module_scope.globals() {
+            if !baseline.contains(&name) && !name.starts_with('_') {
+                exports.insert(name, value);
+            }
+        }
+        let module = Value::NativeModule(Arc::new(exports));
+        self.native_modules.insert(path.to_string(), module.clone());
+        self.loaded_embedded.insert(path.to_string());
+        Ok(Some(module))
+    }
+    
+    // A function defined while another runs shares that call's variables, so
+    // it sees later assignments to them, even after the call returns.
+    // Top-level functions capture nothing and see globals as they are when
+    // called.
+    fn capture(&self) -> Option<Captures> {
+        let frame = self.frames.last()?;
+        let mut captured = frame.env.as_ref().map(|env| (*env.0).clone()).unwrap_or_default();
+        captured.extend(frame.locals.iter().map(|(name, cell)| (name.clone(), cell.clone())));
+        Some(Captures(Arc::new(captured)))
+    }
+
+    // The variable `name` is in the running call, its own or captured
+    fn local_cell(&self, name: &str) -> Option<&Cell> {
+        let frame = self.frames.last()?;
+        frame.locals.get(name).or_else(|| frame.env.as_ref()?.0.get(name))
+    }
+
+    // A name resolves to the innermost call's variable, then to what that
+    // function captured, then to the calls around it, and last to a global
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.locals.get(name).or_else(|| frame.env.as_ref()?.0.get(name)))
+            .map(|cell| cell.read().unwrap().clone())
+            .or_else(|| self.global(name))
+    }
+
+    // `let`, `def` and the like bind a new variable in the running call, or
+    // the globals
+    fn define(&mut self, name: String, value: Value) {
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.locals.insert(name, cell(value));
+            }
+            None => {
+                self.globals.write().unwrap().insert(name, value);
+            }
+        }
+    }
+
+    // Removes a variable `define` bound, returning its value
+    fn undefine(&mut self, name: &str) -> Option<Value> {
+        match self.frames.last_mut() {
+            Some(frame) => frame.locals.remove(name).map(|cell| cell.read().unwrap().clone()),
+            None => self.globals.write().unwrap().remove(name),
+        }
+    }
+
+    // Call arguments or list elements, with the items of each `*items` in place
+    fn evaluate_items(&mut self, exprs: Vec<Expr>) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::new();
+        for expr in exprs {
+            match expr {
+                Expr::Spread(items) => match self.evaluate(*items)? {
+                    Value::Tuple(items) | Value::List(items) => values.extend(items.iter().cloned()),
+                    Value::TupleMutable(items) | Value::ListMutable(items) => values.extend(items.read().unwrap().iter().cloned()),
+                    other => return Err(self.make_error(&format!("Cannot spread {}; only lists and tuples spread", type_name(&other)))),
+                },
+                expr => values.push(self.evaluate(expr)?),
+            }
+        }
+        Ok(values)
+    }
+
+    // Calls the method `name` of an instance or record with no arguments, if
+    // it has one
+    fn call_own_method(&mut self, object: &Value, name: &str) -> Option<Result<Value, RuntimeError>> {
+        let method = match object {
+            Value::Instance { methods, .. } | Value::Record { methods, .. } => methods.get(name)?.clone(),
+            _ => return None,
+        };
+        let bound = Value::BoundMethod { object: Box::new(object.clone()), method: Box::new(method) };
+        Some(self.apply(bound, Vec::new(), Vec::new()))
+    }
+
+    // Collections give their elements (a dict its keys, a string its chars),
+    // channels what they receive until closed, and objects follow the
+    // iteration protocol: `__iter__()` returns something iterable, and
+    // `__next__()` returns the next value, or nothing (void) when there are
+    // no more.
+    fn iterate(&mut self, value: Value) -> Result<Iteration, RuntimeError> {
+        let values: Box<dyn Iterator<Item = Value>> = match value {
+            Value::List(items) | Value::Tuple(items) | Value::Set(items) => {
+                Box::new((0..items.len()).map(move |i| items[i].clone()))
+            }
+            Value::ListMutable(items) | Value::TupleMutable(items) | Value::SetMutable(items) => {
+                Box::new(items.read().unwrap().clone().into_iter())
+            }
+            Value::Dict(pairs) => Box::new((0..pairs.len()).map(move |i| pairs[i].0.clone())),
+            Value::DictMutable(pairs) => Box::new(pairs.read().unwrap().iter().map(|(k, _)| k.clone()).collect::<Vec<_>>().into_iter()),
+            Value::String(s) => Box::new(s.chars().map(Value::Char).collect::<Vec<_>>().into_iter()),
+            // Ranges are walked without building a list
+            Value::Range { start, end, inclusive, step } => Box::new(range_values(start, end, inclusive, step).map(Value::Int)),
+            Value::Channel { receiver: Some(rx), rendezvous, .. } => return Ok(Iteration::Channel(rx, rendezvous)),
+            Value::Channel { receiver: None, .. } => return Err(self.make_error("Cannot iterate over a send-only channel")),
+            object @ (Value::Instance { .. } | Value::Record { .. }) => {
+                if let Some(iterable) = self.call_own_method(&object, "__iter__") {
+                    return match iterable? {
+                        iterator @ (Value::Instance { .. } | Value::Record { .. }) => {
+                            if self.has_own_method(&iterator, "__next__") {
+                                Ok(Iteration::Next(iterator))
+                            } else {
+                                Err(self.make_error("__iter__ must return a collection or an object with __next__"))
+                            }
+                        }
+                        other => self.iterate(other),
+                    };
+                }
+                if self.has_own_method(&object, "__next__") {
+                    return Ok(Iteration::Next(object));
+                }
+                return Err(self.make_error(&format!("Cannot iterate over {}; it has no __iter__ or __next__ method", type_name(&object))));
+            }
+            other => return Err(self.make_error(&format!("Cannot iterate over {}", type_name(&other)))),
+        };
+        Ok(Iteration::Values(values))
+    }
+
+    fn has_own_method(&self, object: &Value, name: &str) -> bool {
+        matches!(object, Value::Instance { methods, .. } | Value::Record { methods, .. } if methods.contains_key(name))
+    }
+
+    // A context manager's `__enter__` or `__exit__`, ready to call. Native
+    // objects such as file handles carry them as functions.
+    fn context_method(&self, object: &Value, name: &str) -> Option<Value> {
+        match object {
+            Value::Instance { methods, .. } | Value::Record { methods, .. } => {
+                let method = methods.get(name)?.clone();
+                Some(Value::BoundMethod { object: Box::new(object.clone()), method: Box::new(method) })
+            }
+            Value::NativeModule(methods) => methods.get(name).cloned(),
+            _ => None,
+        }
+    }
+
+    fn next_item(&mut self, iteration: &mut Iteration) -> Result<Option<Value>, RuntimeError> {
+        match iteration {
+            Iteration::Values(values) => Ok(values.next()),
+            Iteration::Channel(rx, rendezvous) => Ok(self.receive(rx, rendezvous)),
+            Iteration::Next(iterator) => match self.call_own_method(iterator, "__next__") {
+                Some(Ok(Value::Void)) | None => Ok(None),
+                Some(result) => result.map(Some),
+            },
+        }
+    }
+
+    // The elements of a tuple or list being unpacked into `count` names
+    fn unpack(&self, value: &Value, count: usize) -> Result<Vec<Value>, RuntimeError> {
+        let items: Vec<Value> = match value {
+            Value::Tuple(items) | Value::List(items) => items.to_vec(),
+            Value::TupleMutable(items) | Value::ListMutable(items) => items.read().unwrap().clone(),
+            other => return Err(self.make_error(&format!("Cannot unpack {} into {} names", type_name(other), count))),
+        };
+        if items.len() != count {
+            return Err(self.make_error(&format!("Cannot unpack {} values into {} names", items.len(), count)));
+        }
+        Ok(items)
+    }
+
+    // Assignment updates the variable the name reads: one of the running
+    // call, which functions defined in the call share, one the call's
+    // function captured, or a global. A variable of an outer call is copied
+    // into the running call instead.
+    fn assign(&mut self, name: String, value: Value) {
+        if let Some(cell) = self.local_cell(&name) {
+            *cell.write().unwrap() = value;
+            return;
+        }
+        let reads_global = self.frames.iter().all(|frame| {
+            !frame.locals.contains_key(&name) && !frame.env.as_ref().is_some_and(|env| env.0.contains_key(&name))
+        });
+        if reads_global {
+            self.globals.write().unwrap().insert(name, value);
+        } else {
+            self.define(name, value);
+        }
+    }
+
+    // Calls nested deeper than this raise a RecursionError
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    // Runs statements at the top level even while a call is in progress, as
+    // for definitions swapped in by a reload
+    pub fn run_global(&mut self, statements: Vec<Stmt>) -> Result<Flow, RuntimeError> {
+        let frames = std::mem::take(&mut self.frames);
+        let result = self.run(statements);
+        self.frames = frames;
+        result
+    }
+
+    fn check_not_constant(&self, name: &str) -> Result<(), RuntimeError> {
+        if self.constants.contains(name) {
+            return Err(self.make_error(&format!("Cannot reassign constant '{}'", name)));
+        }
+        Ok(())
+    }
+
+    fn make_error(&self, msg: &str) -> RuntimeError {
+        // Construct an instance of Error
+        let mut fields = HashMap::new();
+        fields.insert("message".to_string(), Value::String(Arc::new(msg.to_string())));
+        fields.insert("kind".to_string(), Value::String(Arc::new("Error".to_string())));
+        
+        let methods = if let Some(Value::Class { methods, .. }) = self.global("Error") {
+            methods
+        } else {
+             Arc::new(HashMap::new())
+        };
+
+        RuntimeError::from(Value::Instance {
+            class_name: "Error".to_string(),
+            fields: Arc::new(RwLock::new(fields)),
+            methods,
+        })
+    }
+
+    // Turns an error returned by native code into an Error instance. Plain
+    // strings become the message; errors from error_with_kind get the Error
+    // class methods so they behave like ones raised in Pyro.
+    pub fn native_error(&self, e: RuntimeError) -> RuntimeError {
+        let value = match e.value {
+            Value::String(s) => self.make_error(&s).value,
+            Value::Instance { class_name, fields, methods } if class_name == "Error" && methods.is_empty() => {
+                let methods = match self.global("Error") {
+                    Some(Value::Class { methods, .. }) => methods,
+                    _ => methods,
+                };
+                Value::Instance { class_name, fields, methods }
+            }
+            other => other,
+        };
+        RuntimeError { value, ..e }
+    }
+
+    fn decimal_op(&self, a: Decimal, op: BinaryOp, b: Decimal) -> Result<Value, RuntimeError> {
+        let result = match op {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Sub => a.checked_sub(b),
+            BinaryOp::Mul => a.checked_mul(b),
+            BinaryOp::Div => {
+                if b.is_zero() {
+                    return Err(self.make_error("Division by zero"));
+                }
+                a.checked_div(b)
+            }
+            BinaryOp::Eq => return Ok(Value::Bool(a == b)),
+            BinaryOp::Neq => return Ok(Value::Bool(a != b)),
+            BinaryOp::Lt => return Ok(Value::Bool(a < b)),
+            BinaryOp::Gt => return Ok(Value::Bool(a > b)),
+            BinaryOp::Lte => return Ok(Value::Bool(a <= b)),
+            BinaryOp::Gte => return Ok(Value::Bool(a >= b)),
+            BinaryOp::In => return Err(self.make_error("Unsupported operation")),
+        };
+        result.map(Value::Decimal).ok_or_else(|| self.make_error("Decimal overflow"))
+    }
+
+    fn duration_op(&self, a: std::time::Duration, op: BinaryOp, b: std::time::Duration) -> Result<Value, RuntimeError> {
+        match op {
+            BinaryOp::Add => a.checked_add(b).map(Value::Duration).ok_or_else(|| self.make_error("Duration overflow")),
+            BinaryOp::Sub => Ok(Value::Duration(a.saturating_sub(b))),
+            // Ratio of two durations, e.g. for speedups
+            BinaryOp::Div => {
+                if b.is_zero() {
+                    return Err(self.make_error("Division by zero"));
+                }
+                Ok(Value::Float(a.as_secs_f64() / b.as_secs_f64()))
+            }
+            BinaryOp::Eq => Ok(Value::Bool(a == b)),
+            BinaryOp::Neq => Ok(Value::Bool(a != b)),
+            BinaryOp::Lt => Ok(Value::Bool(a < b)),
+            BinaryOp::Gt => Ok(Value::Bool(a > b)),
+            BinaryOp::Lte => Ok(Value::Bool(a <= b)),
+            BinaryOp::Gte => Ok(Value::Bool(a >= b)),
+            BinaryOp::Mul | BinaryOp::In => Err(self.make_error("Unsupported operation")),
+        }
+    }
+
+    // `item in container`: elements of lists, tuples and sets, keys of dicts,
+    // substrings and chars of strings, and ints of ranges
+    fn contains(&self, container: &Value, item: &Value) -> Result<bool, RuntimeError> {
+        match (container, item) {
+            (Value::Range { start, end, inclusive, step }, Value::Int(i)) => Ok(range_contains(*start, *end, *inclusive, *step, *i)),
+            (Value::Range { .. }, _) => Ok(false),
+            (Value::List(items) | Value::Tuple(items) | Value::Set(items), _) => Ok(items.contains(item)),
+            (Value::ListMutable(items) | Value::TupleMutable(items) | Value::SetMutable(items), _) => {
+                Ok(items.read().unwrap().contains(item))
+            }
+            (Value::Dict(pairs), _) => Ok(pairs.iter().any(|(k, _)| k == item)),
+            (Value::DictMutable(pairs), _) => Ok(pairs.read().unwrap().iter().any(|(k, _)| k == item)),
+            (Value::String(s), Value::String(sub)) => Ok(s.contains(sub.as_str())),
+            (Value::String(s), Value::Char(c)) => Ok(s.contains(*c)),
+            (Value::String(_), other) => Err(self.make_error(&format!("Cannot look for {} in a string", type_name(other)))),
+            (other, _) => Err(self.make_error(&format!("Cannot use 'in' with {}", type_name(other)))),
+        }
+    }
+
+    // ==, != and ordering between records, for the records that derive them
+    fn record_op(&self, a: &Value, op: BinaryOp, b: &Value) -> Result<Value, RuntimeError> {
+        let Value::Record { name, derives, .. } = a else {
+            return Err(self.make_error("Unsupported operation"));
+        };
+        let needs = |derived: bool, what: &str| {
+            if derived { Ok(()) } else { Err(self.make_error(&format!("Record '{}' does not derive {}", name, what))) }
+        };
+        match op {
+            BinaryOp::Eq | BinaryOp::Neq => {
+                needs(derives.eq, "eq")?;
+                Ok(Value::Bool((a == b) == (op == BinaryOp::Eq)))
+            }
+            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Lte | BinaryOp::Gte => {
+                needs(derives.ord, "ord")?;
+                let ordering = self.compare(a, b)?;
+                Ok(Value::Bool(match op {
+                    BinaryOp::Lt => ordering.is_lt(),
+                    BinaryOp::Gt => ordering.is_gt(),
+                    BinaryOp::Lte => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                }))
+            }
+            _ => Err(self.make_error("Unsupported operation")),
+        }
+    }
+
+    // The order sorted() and derived ord use. Numbers, chars, strings and
+    // bools compare by value; lists, tuples and records that derive ord
+    // element by element.
+    fn compare(&self, a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+        let ordering = match (a, b) {
+            (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+            (Value::Float(x), Value::Float(y)) => x.partial_cmp(y),
+            (Value::Decimal(x), Value::Decimal(y)) => Some(x.cmp(y)),
+            (Value::Char(x), Value::Char(y)) => Some(x.cmp(y)),
+            (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+            (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+            (Value::Duration(x), Value::Duration(y)) => Some(x.cmp(y)),
+            (Value::Path(x), Value::Path(y)) => Some(x.cmp(y)),
+            (Value::List(x), Value::List(y)) | (Value::Tuple(x), Value::Tuple(y)) => return self.compare_all(x, y),
+            (Value::Record { name: n1, values: x, derives, .. }, Value::Record { name: n2, values: y, .. })
+                if derives.ord && n1 == n2 =>
+            {
+                return self.compare_all(x, y);
+            }
+            _ => None,
+        };
+        let describe = |value: &Value| match value {
+            Value::Record { name, .. } => name.clone(),
+            other => type_name(other).to_string(),
+        };
+        ordering.ok_or_else(|| self.make_error(&format!("Cannot order {} and {}", describe(a), describe(b))))
+    }
+
+    fn compare_all(&self, a: &[Value], b: &[Value]) -> Result<Ordering, RuntimeError> {
+        for (x, y) in a.iter().zip(b) {
+            let ordering = self.compare(x, y)?;
+            if ordering.is_ne() {
+                return Ok(ordering);
+            }
+        }
+        Ok(a.len().cmp(&b.len()))
+    }
+
+    // sorted(items) -> a new list in ascending order
+    fn sorted(&self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut items = match args.as_slice() {
+            [Value::List(items) | Value::Tuple(items) | Value::Set(items)] => items.to_vec(),
+            [Value::ListMutable(items) | Value::TupleMutable(items) | Value::SetMutable(items)] => items.read().unwrap().clone(),
+            [Value::Range { start, end, inclusive, step }] => range_values(*start, *end, *inclusive, *step).map(Value::Int).collect(),
+            _ => return Err(self.make_error("sorted expects a list, tuple, set or range")),
+        };
+        let mut error = None;
+        items.sort_by(|a, b| {
+            self.compare(a, b).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                Ordering::Equal
+            })
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(Value::List(Arc::new(items))),
+        }
+    }
+
+    // Records are dict keys and set members only if they derive hash
+    fn check_key(&self, key: &Value) -> Result<(), RuntimeError> {
+        match key {
+            Value::Record { name, derives, .. } if !derives.hash => Err(self.make_error(&format!(
+                "Record '{}' must derive hash to be a dict key or set member",
+                name
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // items[start..end]: a copy of the elements or chars in the range
+    fn slice(&self, object: Value, start: i64, end: i64, inclusive: bool) -> Result<Value, RuntimeError> {
+        let ints = range_ints(start, end, inclusive);
+        let len = match &object {
+            Value::List(items) | Value::Tuple(items) => items.len(),
+            Value::ListMutable(items) | Value::TupleMutable(items) => items.read().unwrap().len(),
+            Value::String(s) => s.chars().count(),
+            other => return Err(self.make_error(&format!("Cannot slice {}", type_name(other)))),
+        };
+        let range = if ints.is_empty() && start >= 0 && start as usize <= len {
+            start as usize..start as usize
+        } else if *ints.start() >= 0 && (*ints.end() as usize) < len {
+            *ints.start() as usize..*ints.end() as usize + 1
+        } else {
+            let shown = if inclusive { format!("{}..={}", start, end) } else { format!("{}..{}", start, end) };
+            return Err(self.make_error(&format!("Slice {} out of bounds for length {}", shown, len)));
+        };
+        Ok(match object {
+            Value::List(items) => Value::List(Arc::new(items[range].to_vec())),
+            Value::Tuple(items) => Value::Tuple(Arc::new(items[range].to_vec())),
+            Value::ListMutable(items) => Value::ListMutable(Arc::new(RwLock::new(items.read().unwrap()[range].to_vec()))),
+            Value::TupleMutable(items) => Value::TupleMutable(Arc::new(RwLock::new(items.read().unwrap()[range].to_vec()))),
+            Value::String(s) => Value::String(Arc::new(s.chars().skip(range.start).take(range.len()).collect())),
+            _ => unreachable!(),
+        })
+    }
+
+    fn scale_duration(&self, d: std::time::Duration, factor: f64) -> Result<Value, RuntimeError> {
+        std::time::Duration::try_from_secs_f64(d.as_secs_f64() * factor)
+            .map(Value::Duration)
+            .map_err(|_| self.make_error("Duration must be finite and not negative"))
+    }
 
-impl Interpreter {
-    pub fn new() -> Self {
-        let mut globals = HashMap::new();
+    // type(x): the class of an instance, the constructor of a record, and
+    // otherwise the name used in type annotations ("int", "list", "dict_mut").
+    fn type_of(&self, value: &Value) -> Value {
+        match value {
+            Value::Instance { class_name, methods, .. } => match self.lookup(class_name).as_ref() {
+                Some(class @ Value::Class { name, .. }) if name == class_name => class.clone(),
+                // Defined in a module scope we can't see
+                _ => Value::Class { name: class_name.clone(), parent: None, methods: methods.clone() },
+            },
+            Value::Record { name, fields, methods, derives, .. } => match self.lookup(name).as_ref() {
+                Some(record @ Value::RecordConstructor { name: n, .. }) if n == name => record.clone(),
+                _ => Value::RecordConstructor {
+                    name: name.clone(),
+                    fields: fields.clone(),
+                    methods: methods.clone(),
+                    derives: *derives,
+                    partial_args: Vec::new(),
+                },
+            },
+            Value::EnumVariant { enum_name, .. } => match self.lookup(enum_name).as_ref() {
+                Some(typ @ Value::Enum { name, .. }) if name == enum_name => typ.clone(),
+                _ => Value::String(Arc::new(type_name(value).to_string())),
+            },
+            other => Value::String(Arc::new(type_name(other).to_string())),
+        }
+    }
 
-        // Define built-in Error class
-        // class Error:
-        //     def __init__(self, message):
-        //         self.message = message
-        let init_body = vec![
-            Stmt::Set {
-                object: Expr::Identifier("self".to_string()),
-                name: "message".to_string(),
-                value: Expr::Identifier("message".to_string()),
+    // Class names from `class_name` up through its parents
+    fn class_chain(&self, class_name: &str) -> Vec<String> {
+        let mut chain = vec![class_name.to_string()];
+        while let Some(Value::Class { parent: Some(parent), .. }) = self.lookup(chain.last().unwrap()) {
+            // Guards against a class redefined as its own ancestor
+            if chain.contains(&parent) {
+                break;
             }
-        ];
+            chain.push(parent);
+        }
+        chain
+    }
 
-        let init_func = Value::Function {
-            generics: Vec::new(),
-            params: vec![("self".to_string(), Type::Void), ("message".to_string(), Type::String)],
-            body: Arc::new(init_body),
-            partial_args: Vec::new(),
+    // isinstance(x, T): T is a type name, a class (matching subclasses too),
+    // a record constructor, an enum, or a list or tuple of those.
+    fn is_instance(&self, value: &Value, typ: &Value) -> Result<bool, RuntimeError> {
+        let names = match value {
+            Value::Instance { class_name, .. } => self.class_chain(class_name),
+            Value::Record { name, .. } => vec![name.clone()],
+            Value::EnumVariant { enum_name, .. } => vec![enum_name.clone()],
+            _ => Vec::new(),
         };
+        match typ {
+            Value::String(t) => Ok(type_name(value) == t.as_str() || names.iter().any(|n| n == t.as_str())),
+            Value::Class { name, .. } => Ok(matches!(value, Value::Instance { .. }) && names.contains(name)),
+            Value::RecordConstructor { name, .. } => Ok(matches!(value, Value::Record { .. }) && names.contains(name)),
+            Value::Enum { name, .. } => Ok(matches!(value, Value::EnumVariant { .. }) && names.contains(name)),
+            Value::List(types) | Value::Tuple(types) => {
+                for t in types.iter() {
+                    if self.is_instance(value, t)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            other => Err(self.make_error(&format!("isinstance expects a type, got {:?}", other))),
+        }
+    }
 
-        let mut error_methods = HashMap::new();
-        error_methods.insert("__init__".to_string(), init_func);
+    // int32(x), float32(x) and the other numeric type names convert a number
+    // or numeric string, raising OverflowError if it doesn't fit. Floats
+    // become integers by truncating toward zero.
+    fn cast_numeric(&self, name: &str, target: &Type, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if args.len() != 1 {
+            return Err(self.make_error(&format!("{} takes 1 arg", name)));
+        }
+        let overflow = |shown: &dyn std::fmt::Display| {
+            self.native_error(error_with_kind("OverflowError", format!("{} does not fit in {}", shown, name)))
+        };
+        let invalid = || self.make_error(&format!("Cannot convert {:?} to {}", args[0], name));
 
-        globals.insert("Error".to_string(), Value::Class {
-            name: "Error".to_string(),
-            parent: None,
-            methods: Arc::new(error_methods),
-        });
+        if target.is_float() {
+            let f = match &args[0] {
+                Value::Int(i) => *i as f64,
+                Value::Float(f) => *f,
+                Value::Decimal(d) => d.to_f64().ok_or_else(invalid)?,
+                Value::String(s) => s.trim().parse::<f64>().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            };
+            if *target == Type::Float32 {
+                let narrowed = f as f32;
+                if narrowed.is_infinite() && f.is_finite() {
+                    return Err(overflow(&f));
+                }
+                return Ok(Value::Float(narrowed as f64));
+            }
+            return Ok(Value::Float(f));
+        }
 
-        let mut interpreter = Self {
-            globals,
-            native_modules: HashMap::new(),
+        let i: i128 = match &args[0] {
+            Value::Int(i) => *i as i128,
+            Value::Float(f) if f.is_finite() => f.trunc() as i128,
+            Value::Decimal(d) => d.trunc().to_i128().ok_or_else(|| overflow(d))?,
+            Value::String(s) => s.trim().parse::<i128>().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
         };
-        crate::stdlib::register_std_libs(&mut interpreter);
-        interpreter
+        // uint64 stops at the largest int, as that is what a Pyro int holds
+        let (min, max) = match target {
+            Type::Int8 => (i8::MIN as i128, i8::MAX as i128),
+            Type::Int16 => (i16::MIN as i128, i16::MAX as i128),
+            Type::Int32 => (i32::MIN as i128, i32::MAX as i128),
+            Type::UInt8 => (0, u8::MAX as i128),
+            Type::UInt16 => (0, u16::MAX as i128),
+            Type::UInt32 => (0, u32::MAX as i128),
+            _ => (i64::MIN as i128, i64::MAX as i128),
+        };
+        let min = if *target == Type::UInt64 { 0 } else { min };
+        if i < min || i > max {
+            return Err(overflow(&i));
+        }
+        Ok(Value::Int(i as i64))
     }
 
-    pub fn register_native_module(&mut self, name: &str, module: Value) {
-        self.native_modules.insert(name.to_string(), module);
-    }
+    // retry(fn, attempts = 5, backoff = "exponential", jitter = true, delay = 0.1)
+    // Calls fn until it returns without raising, sleeping between attempts.
+    // The last error is re-raised once the attempts run out.
+    fn retry(&mut self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if args.is_empty() || args.len() > 5 {
+            return Err(self.make_error("retry expects 1 to 5 arguments (fn, attempts, backoff, jitter, delay)"));
+        }
+        let attempts = match args.get(1) {
+            None => 5,
+            Some(Value::Int(n)) if *n >= 1 => *n,
+            Some(_) => return Err(self.make_error("retry attempts must be a positive int")),
+        };
+        let backoff = match args.get(2) {
+            None => "exponential".to_string(),
+            Some(Value::String(s)) if matches!(s.as_str(), "exponential" | "linear" | "constant") => s.to_string(),
+            Some(_) => return Err(self.make_error("retry backoff must be \"exponential\", \"linear\" or \"constant\"")),
+        };
+        let jitter = match args.get(3) {
+            None => true,
+            Some(Value::Bool(b)) => *b,
+            Some(_) => return Err(self.make_error("retry jitter must be a bool")),
+        };
+        let delay = match args.get(4) {
+            None => 0.1,
+            Some(Value::Duration(d)) => d.as_secs_f64(),
+            Some(Value::Float(f)) if *f >= 0.0 => *f,
+            Some(Value::Int(i)) if *i >= 0 => *i as f64,
+            Some(_) => return Err(self.make_error("retry delay must be a duration or a non-negative number of seconds")),
+        };
 
-    pub fn register_native_function<F>(&mut self, name: &str, func: F)
-    where F: Fn(Vec<Value>) -> Result<Value, Value> + Send + Sync + 'static 
-    {
-        self.globals.insert(name.to_string(), Value::NativeFunction {
-            name: name.to_string(),
-            func: NativeClosure(Arc::new(func)),
-        });
+        let func = args[0].clone();
+        let mut attempt = 1;
+        loop {
+            match self.apply(func.clone(), Vec::new(), Vec::new()) {
+                Ok(v) => return Ok(v),
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(_) => {}
+            }
+            let mut wait = match backoff.as_str() {
+                "exponential" => delay * 2f64.powi((attempt - 1).min(30) as i32),
+                "linear" => delay * attempt as f64,
+                _ => delay,
+            };
+            // Full jitter spreads out retries from many clients failing at once
+            if jitter {
+                wait *= rand::random::<f64>();
+            }
+            // Inside a goroutine this runs on a tokio worker, which is handed
+            // back to the runtime for the wait
+            let wait = std::time::Duration::from_secs_f64(wait.min(MAX_RETRY_DELAY));
+            tokio::task::block_in_place(|| self.sleep(wait))
+                .map_err(|e| self.native_error(e))?;
+            attempt += 1;
+        }
     }
 
-    pub fn has_native_module(&self, name: &str) -> bool {
-        self.native_modules.contains_key(name)
-    }
-    
-    fn make_error(&self, msg: &str) -> Value {
-        // Construct an instance of Error
-        let mut fields = HashMap::new();
-        fields.insert("message".to_string(), Value::String(Arc::new(msg.to_string())));
-        
-        let methods = if let Some(Value::Class { methods, .. }) = self.globals.get("Error") {
-            methods.clone()
-        } else {
-             Arc::new(HashMap::new())
+    // timeit(fn, n = 1000) -> {best, mean, runs}, calling fn with no arguments
+    fn timeit(&mut self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        if args.is_empty() || args.len() > 2 {
+            return Err(self.make_error("timeit expects 1 or 2 arguments (fn, n)"));
+        }
+        let runs = match args.get(1) {
+            None => 1000,
+            Some(Value::Int(n)) if *n >= 1 && *n <= u32::MAX as i64 => *n as u32,
+            Some(_) => return Err(self.make_error("timeit n must be a positive int")),
         };
+        let func = args[0].clone();
+        let mut timings = crate::stdlib::time::Timings::default();
+        for _ in 0..runs {
+            self.interrupt.check().map_err(|e| self.native_error(e))?;
+            let start = std::time::Instant::now();
+            self.apply(func.clone(), Vec::new(), Vec::new())?;
+            timings.record(start.elapsed());
+        }
+        Ok(timings.to_value())
+    }
 
-        Value::Instance {
-            class_name: "Error".to_string(),
-            fields: Arc::new(RwLock::new(fields)),
-            methods,
+    fn complex_op(&self, a: Complex64, op: BinaryOp, b: Complex64) -> Result<Value, RuntimeError> {
+        match op {
+            BinaryOp::Add => Ok(Value::Complex(a + b)),
+            BinaryOp::Sub => Ok(Value::Complex(a - b)),
+            BinaryOp::Mul => Ok(Value::Complex(a * b)),
+            BinaryOp::Div => Ok(Value::Complex(a / b)),
+            BinaryOp::Eq => Ok(Value::Bool(a == b)),
+            BinaryOp::Neq => Ok(Value::Bool(a != b)),
+            // Complex numbers have no ordering
+            _ => Err(self.make_error("Unsupported operation")),
         }
     }
 
 
-    pub fn run(&mut self, statements: Vec<Stmt>) -> Result<Flow, Value> {
+    pub fn run(&mut self, statements: Vec<Stmt>) -> Result<Flow, RuntimeError> {
         for stmt in statements {
-            let flow = self.execute_stmt(stmt)?;
+            // Where `pyro run --watch` swaps in new code and `pyro run
+            // --inspect` evaluates what it's sent
+            self.poll_requests();
+            if let Some(mut hooks) = self.hooks() {
+                hooks.on_stmt(&stmt);
+            }
+            self.unwinding = false;
+            self.returning = false;
+            let flow = match stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.execute_stmt(stmt)) {
+                Err(e) if std::mem::take(&mut self.returning) => Ok(Flow::Return(e.value)),
+                result => result,
+            };
+            let flow = flow.inspect_err(|e| {
+                if !self.unwinding {
+                    self.error_locals = None;
+                    if let Some(mut hooks) = self.hooks() {
+                        hooks.on_raise(e);
+                    }
+                }
+                self.unwinding = true;
+            })?;
             match flow {
                 Flow::None => continue,
                 _ => return Ok(flow),
@@ -263,7 +1680,99 @@ impl Interpreter {
         Ok(Flow::None)
     }
 
-    fn execute_stmt(&mut self, stmt: Stmt) -> Result<Flow, Value> {
+    // Whether `value` matches `pattern`, adding the names it binds to
+    // `bindings`. Only a record pattern with the wrong number of fields is
+    // an error; anything else that doesn't fit just doesn't match.
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> Result<bool, RuntimeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    let mut bound = Vec::new();
+                    if self.match_pattern(alternative, value, &mut bound)? {
+                        bindings.extend(bound);
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal(literal) => Ok(self.evaluate(literal.clone())? == *value),
+            Pattern::Tuple(patterns) => {
+                let items: Vec<Value> = match value {
+                    Value::Tuple(items) | Value::List(items) => items.to_vec(),
+                    Value::TupleMutable(items) | Value::ListMutable(items) => items.read().unwrap().clone(),
+                    _ => return Ok(false),
+                };
+                if items.len() != patterns.len() {
+                    return Ok(false);
+                }
+                for (pattern, item) in patterns.iter().zip(&items) {
+                    if !self.match_pattern(pattern, item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::Record { name, fields } => {
+                let Value::Record { name: record, values, .. } = value else { return Ok(false) };
+                if record != name {
+                    return Ok(false);
+                }
+                if values.len() != fields.len() {
+                    return Err(self.make_error(&format!(
+                        "Record '{}' has {} fields, but the pattern gives {}",
+                        name,
+                        values.len(),
+                        fields.len()
+                    )));
+                }
+                for (pattern, item) in fields.iter().zip(values.iter()) {
+                    if !self.match_pattern(pattern, item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                // A misspelt variant is an error rather than a case that
+                // never matches
+                let expected = match self.lookup(enum_name) {
+                    Some(Value::Enum { variants, .. }) => match variants.iter().find(|(name, _)| name == variant) {
+                        Some((_, declared)) => declared.len(),
+                        None => {
+                            return Err(self.make_error(&format!("Enum '{}' has no variant '{}'", enum_name, variant)))
+                        }
+                    },
+                    _ => return Err(self.make_error(&format!("'{}' is not an enum", enum_name))),
+                };
+                if expected != fields.len() {
+                    return Err(self.make_error(&format!(
+                        "Variant '{}.{}' has {} fields, but the pattern gives {}",
+                        enum_name,
+                        variant,
+                        expected,
+                        fields.len()
+                    )));
+                }
+                let Value::EnumVariant { enum_name: e, variant: v, values, .. } = value else { return Ok(false) };
+                if e != enum_name || v != variant {
+                    return Ok(false);
+                }
+                for (pattern, item) in fields.iter().zip(values.iter()) {
+                    if !self.match_pattern(pattern, item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn execute_stmt(&mut self, stmt: Stmt) -> Result<Flow, RuntimeError> {
         match stmt {
             Stmt::Try { body, catch_var, catch_body, finally_body } => {
                 let result = self.run(body);
@@ -273,17 +1782,21 @@ impl Interpreter {
                 if let Err(e) = result {
                     // Exception occurred
                     if let Some(catch_block) = catch_body {
-                         // Enter implicit scope (simplified for now)
-                         let old_globals = self.globals.clone(); // inefficient but works for now as scope push
-                         
-                         if let Some(var_name) = catch_var {
-                             self.globals.insert(var_name, e);
-                         }
+                         // The error variable only lasts for the except block
+                         let shadowed = catch_var.map(|name| {
+                             let previous = self.undefine(&name);
+                             self.define(name.clone(), e.into_value());
+                             (name, previous)
+                         });
 
                          let catch_res = self.run(catch_block);
                          
-                         // Restore scope
-                         self.globals = old_globals;
+                         if let Some((name, previous)) = shadowed {
+                             self.undefine(&name);
+                             if let Some(value) = previous {
+                                 self.define(name, value);
+                             }
+                         }
 
                          if let Err(new_e) = catch_res {
                              flow_result = Err(new_e);
@@ -306,6 +1819,8 @@ impl Interpreter {
                      match fin_res {
                          Ok(Flow::None) => {
                              // Finally finished normally, return previous result
+                             // and carry on unwinding an error it interrupted
+                             self.unwinding = flow_result.is_err();
                              return flow_result;
                          }
                          _ => {
@@ -320,26 +1835,79 @@ impl Interpreter {
             Stmt::Raise { error, cause: _ } => {
                 let err_val = self.evaluate(error)?;
                 // Ignoring cause for now or wrap it
-                return Err(err_val);
+                return Err(err_val.into());
             }
             Stmt::Go(expr) => {
-                // We need to clone the interpreter to move it into the thread
-                // Since everything is Arc<RwLock>, this is a shallow clone of references
-                let mut new_interpreter = self.clone();
-                let expr_clone = expr.clone();
-                
-                tokio::spawn(async move {
-                     // In a real implementation, we might want to handle the error
-                     // Or provide a way to join the handle.
+                // The goroutine runs on a fork of the interpreter, which
+                // shares the globals, so what either assigns the other sees
+                let mut new_interpreter = self.fork();
+                let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+                    return Err(self.make_error("'go' needs a tokio runtime, but none is running"));
+                };
+
+                // A call's function and arguments are evaluated here, so
+                // their side effects happen in order and their errors raise
+                // at the `go`; only the call itself runs concurrently.
+                let call = match *expr {
+                    Expr::Call { function, generics, args } => {
+                        let func_val = self.evaluate(*function)?;
+                        let evaluated_args = self.evaluate_items(args)?;
+                        Ok((func_val, evaluated_args, generics))
+                    }
+                    other => Err(other),
+                };
+
+                runtime.spawn(async move {
                      // For 'go' fire and forget style:
-                     if let Err(e) = new_interpreter.evaluate(*expr_clone) {
-                         eprintln!("Error in go routine: {:?}", e);
+                     let result = match call {
+                         Ok((func_val, evaluated_args, generics)) => new_interpreter.apply(func_val, evaluated_args, generics),
+                         Err(expr) => new_interpreter.evaluate(expr),
+                     };
+                     if let Err(e) = result {
+                         let _ = new_interpreter.write_error(&format!("Error in go routine: {:?}", e));
                      }
                 });
             }
             Stmt::VarDecl { name, value, .. } => {
+                self.check_not_constant(&name)?;
+                let val = self.evaluate(value)?;
+                if name != DISCARD {
+                    self.define(name, val);
+                }
+            }
+            Stmt::Destructure { names, value, .. } => {
+                for name in &names {
+                    self.check_not_constant(name)?;
+                }
+                let val = self.evaluate(value)?;
+                let items = self.unpack(&val, names.len())?;
+                for (name, item) in names.into_iter().zip(items) {
+                    if name != DISCARD {
+                        self.define(name, item);
+                    }
+                }
+            }
+            Stmt::DestructureAssign { names, value } => {
+                for name in names.iter().filter(|name| *name != DISCARD) {
+                    self.check_not_constant(name)?;
+                    if self.lookup(name).is_none() {
+                        return Err(self.make_error(&format!("Undefined variable '{}' in assignment", name)));
+                    }
+                }
+                // Every element is read before any name changes, so `a, b = b, a` swaps
+                let val = self.evaluate(value)?;
+                let items = self.unpack(&val, names.len())?;
+                for (name, item) in names.into_iter().zip(items) {
+                    if name != DISCARD {
+                        self.assign(name, item);
+                    }
+                }
+            }
+            Stmt::ConstDecl { name, value, .. } => {
+                self.check_not_constant(&name)?;
                 let val = self.evaluate(value)?;
-                self.globals.insert(name, val);
+                self.define(name.clone(), val);
+                self.constants.insert(name);
             }
             Stmt::Expr(expr) => {
                 self.evaluate(expr)?;
@@ -372,6 +1940,7 @@ impl Interpreter {
             }
             Stmt::While { cond, body } => {
                 while let Value::Bool(true) = self.evaluate(cond.clone())? {
+                    self.interrupt.check().map_err(|e| self.native_error(e))?;
                     let flow = self.run(body.clone())?;
                     match flow {
                         Flow::Return(v) => return Ok(Flow::Return(v)),
@@ -382,11 +1951,12 @@ impl Interpreter {
                 }
             }
             Stmt::Assign { name, value } => {
-                if !self.globals.contains_key(&name) {
+                self.check_not_constant(&name)?;
+                if self.lookup(&name).is_none() {
                     return Err(self.make_error(&format!("Undefined variable '{}' in assignment", name)));
                 }
                 let val = self.evaluate(value)?;
-                self.globals.insert(name, val);
+                self.assign(name, val);
             }
             Stmt::Set { object, name, value } => {
                 let obj_val = self.evaluate(object)?;
@@ -400,19 +1970,26 @@ impl Interpreter {
                 }
             }
             Stmt::FnDecl { name, generics, params, body, .. } => {
-                self.globals.insert(name, Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new() });
+                self.check_not_constant(&name)?;
+                let env = self.capture();
+                self.define(name, Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new(), env });
             }
             Stmt::Import(path) => {
-                if let Some(module) = self.native_modules.get(&path) {
-                    // Simple binding strategy: use the last segment as the name
-                    // e.g. "std.math" -> "math"
-                    let name = path.split('.').last().unwrap_or(&path).to_string();
-                    self.globals.insert(name, module.clone());
+                if let Some(library) = path.strip_prefix(crate::plugin::IMPORT_PREFIX) {
+                    // Each of the plugin's modules is bound as if imported
+                    for name in self.load_plugin(library)? {
+                        let module = self.native_modules[&name].clone();
+                        let binding = name.rsplit('.').next().unwrap_or(&name).to_string();
+                        self.define(binding, module);
+                    }
+                } else if let Some(module) = self.load_module(&path)? {
+                    self.define(import_name(&path).to_string(), module.clone());
                 } else {
-                     println!("Warning: Module '{}' not found", path);
+                    return Err(self.native_error(error_with_kind("NotFound", format!("Module '{}' not found", path))));
                 }
             }
-            Stmt::RecordDef { name, generics: _, fields, methods } => {
+            Stmt::RecordDef { name, generics: _, fields, derives, methods } => {
+                self.check_not_constant(&name)?;
                 let mut field_names = Vec::new();
                 for (n, _) in fields {
                     field_names.push(n);
@@ -421,32 +1998,50 @@ impl Interpreter {
                 let mut method_map = HashMap::new();
                 for method in methods {
                     if let Stmt::FnDecl { name, generics, params, return_type: _, body } = method {
-                         method_map.insert(name, Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new() });
+                         method_map.insert(name, Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new(), env: None });
                     }
                 }
 
-                self.globals.insert(name.clone(), Value::RecordConstructor { 
+                self.define(name.clone(), Value::RecordConstructor { 
                     name, 
-                    fields: field_names, 
+                    fields: Arc::new(field_names), 
                     methods: Arc::new(method_map),
+                    derives,
                     partial_args: Vec::new() 
                 });
             }
+            Stmt::EnumDef { name, variants } => {
+                self.check_not_constant(&name)?;
+                let variants = variants
+                    .into_iter()
+                    .map(|variant| {
+                        let fields = variant.fields.into_iter().map(|(field, _)| field).collect();
+                        (variant.name, Arc::new(fields))
+                    })
+                    .collect();
+                self.define(name.clone(), Value::Enum { name, variants: Arc::new(variants) });
+            }
             Stmt::InterfaceDef { .. } | Stmt::TypeAlias { .. } => {
                 // Not yet supported
             }
-            Stmt::For { item_name, iterable, body } => {
+            Stmt::For { names, iterable, body } => {
+                for name in &names {
+                    self.check_not_constant(name)?;
+                }
                 let iterable_val = self.evaluate(iterable)?;
-                let items = match iterable_val {
-                    Value::List(items) => items,
-                    Value::ListMutable(items) => items.read().unwrap().clone().into(),
-                    Value::Tuple(items) => items,
-                    Value::Set(items) => items,
-                    _ => return Err(self.make_error("For loop expects iterable")),
-                };
-
-                for item in items.iter() {
-                    self.globals.insert(item_name.clone(), item.clone());
+                let mut iteration = self.iterate(iterable_val)?;
+                while let Some(item) = self.next_item(&mut iteration)? {
+                    self.interrupt.check().map_err(|e| self.native_error(e))?;
+                    if let [name] = names.as_slice() {
+                        self.define(name.clone(), item);
+                    } else {
+                        let items = self.unpack(&item, names.len())?;
+                        for (name, item) in names.iter().zip(items) {
+                            if name != DISCARD {
+                                self.define(name.clone(), item);
+                            }
+                        }
+                    }
                     let flow = self.run(body.clone())?;
                     match flow {
                         Flow::Return(v) => return Ok(Flow::Return(v)),
@@ -456,11 +2051,76 @@ impl Interpreter {
                     }
                 }
             }
+            // `__enter__()`, if the value has one, gives what `as` binds, or
+            // else the value itself is bound. `__exit__(error)` runs however
+            // the body ends, with the error it raised or void, and returning
+            // true from it swallows the error.
+            Stmt::With { value, name, body } => {
+                if let Some(name) = &name {
+                    self.check_not_constant(name)?;
+                }
+                let manager = self.evaluate(value)?;
+                let entered = match self.context_method(&manager, "__enter__") {
+                    Some(enter) => self.apply(enter, Vec::new(), Vec::new())?,
+                    None => manager.clone(),
+                };
+                if let Some(name) = name {
+                    self.define(name, entered);
+                }
+                let result = self.run(body);
+                let Some(exit) = self.context_method(&manager, "__exit__") else {
+                    return result;
+                };
+                return match result {
+                    Ok(flow) => {
+                        self.apply(exit, vec![Value::Void], Vec::new())?;
+                        Ok(flow)
+                    }
+                    Err(e) => {
+                        let handled = self.apply(exit, vec![e.value.clone()], Vec::new())?;
+                        if handled == Value::Bool(true) {
+                            Ok(Flow::None)
+                        } else {
+                            // Still the body's error, already reported
+                            self.unwinding = true;
+                            Err(e)
+                        }
+                    }
+                };
+            }
+            Stmt::Match { subject, cases } => {
+                let value = self.evaluate(subject)?;
+                for case in cases {
+                    let mut bindings = Vec::new();
+                    if !self.match_pattern(&case.pattern, &value, &mut bindings)? {
+                        continue;
+                    }
+                    for (name, _) in &bindings {
+                        self.check_not_constant(name)?;
+                    }
+                    for (name, item) in bindings {
+                        self.define(name, item);
+                    }
+                    // The names stay bound even when the guard fails, as
+                    // after a `let`
+                    if let Some(guard) = case.guard {
+                        match self.evaluate(guard)? {
+                            Value::Bool(true) => {}
+                            Value::Bool(false) => continue,
+                            _ => return Err(self.make_error("Match guard must be boolean")),
+                        }
+                    }
+                    let flow = self.run(case.body)?;
+                    if flow != Flow::None { return Ok(flow); }
+                    break;
+                }
+            }
             Stmt::ClassDecl { name, parent, methods } => {
+                self.check_not_constant(&name)?;
                 let mut method_map = HashMap::new();
                 
                 if let Some(parent_name) = &parent {
-                     if let Some(Value::Class { methods: parent_methods, .. }) = self.globals.get(parent_name) {
+                     if let Some(Value::Class { methods: parent_methods, .. }) = self.lookup(parent_name) {
                          for (k, v) in parent_methods.iter() {
                              method_map.insert(k.clone(), v.clone());
                          }
@@ -471,27 +2131,43 @@ impl Interpreter {
 
                 for method in methods {
                     if let Stmt::FnDecl { name, generics, params, body, .. } = method {
-                        method_map.insert(name.clone(), Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new() });
+                        method_map.insert(name.clone(), Value::Function { generics, params, body: Arc::new(body), partial_args: Vec::new(), env: None });
                     }
                 }
-                self.globals.insert(name.clone(), Value::Class { name, parent, methods: Arc::new(method_map) });
+                self.define(name.clone(), Value::Class { name, parent, methods: Arc::new(method_map) });
             }
         }
         Ok(Flow::None)
     }
 
-    pub fn evaluate(&mut self, expr: Expr) -> Result<Value, Value> {
+    pub fn evaluate(&mut self, expr: Expr) -> Result<Value, RuntimeError> {
+        stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.evaluate_expr(expr))
+    }
+
+    fn evaluate_expr(&mut self, expr: Expr) -> Result<Value, RuntimeError> {
         match expr {
             Expr::LiteralInt(i) => Ok(Value::Int(i)),
             Expr::LiteralFloat(f) => Ok(Value::Float(f)),
             Expr::LiteralBool(b) => Ok(Value::Bool(b)),
             Expr::LiteralString(s) => Ok(Value::String(Arc::new(s))),
-            Expr::List(elements) => {
-                let mut vals = Vec::new();
-                for e in elements {
-                    vals.push(self.evaluate(e)?);
+            Expr::LiteralChar(c) => Ok(Value::Char(c)),
+            Expr::List(elements) => Ok(Value::List(Arc::new(self.evaluate_items(elements)?))),
+            Expr::Spread(_) => Err(self.make_error("'*' can only spread into call arguments and list literals")),
+            Expr::Propagate(inner) => {
+                let value = self.evaluate(*inner)?;
+                match &value {
+                    Value::EnumVariant { enum_name, variant, values, .. } if enum_name == "Option" || enum_name == "Result" => {
+                        if variant == "Some" || variant == "Ok" {
+                            return Ok(values[0].clone());
+                        }
+                        if self.frames.is_empty() {
+                            return Err(self.make_error(&format!("'?' got {} outside a function, so there is nothing to return from", show(&value))));
+                        }
+                        self.returning = true;
+                        Err(value.into())
+                    }
+                    other => Err(self.make_error(&format!("'?' needs an Option or a Result, not {}", type_name(other)))),
                 }
-                Ok(Value::List(Arc::new(vals)))
             }
             Expr::Tuple(elements) => {
                 let mut vals = Vec::new();
@@ -503,7 +2179,9 @@ impl Interpreter {
             Expr::Set(elements) => {
                 let mut vals = Vec::new();
                 for e in elements {
-                    vals.push(self.evaluate(e)?);
+                    let val = self.evaluate(e)?;
+                    self.check_key(&val)?;
+                    vals.push(val);
                 }
                 Ok(Value::Set(Arc::new(vals)))
             }
@@ -511,11 +2189,43 @@ impl Interpreter {
                 let mut vals = Vec::new();
                 for (k, v) in elements {
                     let key = self.evaluate(k)?;
+                    self.check_key(&key)?;
                     let val = self.evaluate(v)?;
                     vals.push((key, val));
                 }
                 Ok(Value::Dict(Arc::new(vals)))
             }
+            Expr::Range { start, end, inclusive } => {
+                match (self.evaluate(*start)?, self.evaluate(*end)?) {
+                    (Value::Int(start), Value::Int(end)) => Ok(Value::Range { start, end, inclusive, step: 1 }),
+                    (a, b) => Err(self.make_error(&format!(
+                        "Range bounds must be ints, not {} and {}",
+                        type_name(&a),
+                        type_name(&b)
+                    ))),
+                }
+            }
+            Expr::InterpolatedString(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        StringPart::Text(s) => text.push_str(&s),
+                        StringPart::Expr(e) => {
+                            let value = self.evaluate(e)?;
+                            text.push_str(&stringify(&value));
+                        }
+                    }
+                }
+                Ok(Value::String(Arc::new(text)))
+            }
+            Expr::Conditional { cond, then_expr, else_expr } => match self.evaluate(*cond)? {
+                Value::Bool(true) => self.evaluate(*then_expr),
+                Value::Bool(false) => self.evaluate(*else_expr),
+                _ => Err(self.make_error("Condition must be boolean")),
+            },
+            Expr::Lambda { params, body } => {
+                Ok(Value::Function { generics: Vec::new(), params, body: Arc::new(body), partial_args: Vec::new(), env: self.capture() })
+            }
             Expr::Identifier(name) => {
                 if name == "print" 
                    || name == "range"
@@ -528,8 +2238,12 @@ impl Interpreter {
                     // special hack for built-ins
                    return Ok(Value::String(Arc::new(name))); 
                 }
+                // Newer builtins give way to user definitions of the same name
+                if is_overridable_builtin(&name) && self.lookup(&name).is_none() {
+                    return Ok(Value::String(Arc::new(name)));
+                }
                 
-                self.globals.get(&name).cloned().ok_or_else(|| self.make_error(&format!("Undefined variable: {}", name)))
+                self.lookup(&name).ok_or_else(|| self.make_error(&format!("Undefined variable: {}", name)))
             }
             Expr::Get { object, name } => {
                 let obj_val = self.evaluate(*object)?;
@@ -551,7 +2265,7 @@ impl Interpreter {
                             });
                         }
                     }
-                    Value::Record { name: ref rec_name, fields, values, methods } => {
+                    Value::Record { name: ref rec_name, fields, values, methods, derives } => {
                         // Check fields
                          if let Some(pos) = fields.iter().position(|f| f == &name) {
                              return Ok(values[pos].clone());
@@ -559,7 +2273,7 @@ impl Interpreter {
                          // Check methods
                          if let Some(func) = methods.get(&name) {
                              return Ok(Value::BoundMethod {
-                                    object: Box::new(Value::Record { name: rec_name.clone(), fields: fields.clone(), values: values.clone(), methods: methods.clone() }),
+                                    object: Box::new(Value::Record { name: rec_name.clone(), fields: fields.clone(), values: values.clone(), methods: methods.clone(), derives }),
                                     method: Box::new(func.clone()),
                              });
                         }
@@ -568,6 +2282,25 @@ impl Interpreter {
                     _ => {}
                 }
 
+                match &obj_val {
+                    Value::Enum { name: enum_name, variants } => {
+                        let Some((_, fields)) = variants.iter().find(|(variant, _)| *variant == name) else {
+                            return Err(self.make_error(&format!("Enum '{}' has no variant '{}'", enum_name, name)));
+                        };
+                        return Ok(enum_variant(enum_name, &name, fields));
+                    }
+                    Value::EnumVariant { enum_name, variant, fields, values } => {
+                        return match fields.iter().position(|field| *field == name) {
+                            Some(pos) => Ok(values[pos].clone()),
+                            None => Err(self.make_error(&format!(
+                                "Variant '{}.{}' has no field '{}'",
+                                enum_name, variant, name
+                            ))),
+                        };
+                    }
+                    _ => {}
+                }
+
                 if let Value::NativeModule(exports) = &obj_val {
                     if let Some(val) = exports.get(&name) {
                         return Ok(val.clone());
@@ -586,6 +2319,7 @@ impl Interpreter {
                 let r = self.evaluate(*right)?;
                 
                 match (l, op, r) {
+                    (item, BinaryOp::In, container) => self.contains(&container, &item).map(Value::Bool),
                     (Value::Int(a), BinaryOp::Add, Value::Int(b)) => Ok(Value::Int(a + b)),
                     (Value::Int(a), BinaryOp::Sub, Value::Int(b)) => Ok(Value::Int(a - b)),
                     (Value::Int(a), BinaryOp::Mul, Value::Int(b)) => Ok(Value::Int(a * b)),
@@ -604,6 +2338,16 @@ impl Interpreter {
                     (Value::String(a), BinaryOp::Add, Value::String(b)) => Ok(Value::String(Arc::new(format!("{}{}", a, b)))),
                     (Value::String(a), BinaryOp::Eq, Value::String(b)) => Ok(Value::Bool(a == b)),
                     (Value::String(a), BinaryOp::Neq, Value::String(b)) => Ok(Value::Bool(a != b)),
+
+                    // Char ops. Chars compare by code point and join strings
+                    (Value::Char(a), BinaryOp::Eq, Value::Char(b)) => Ok(Value::Bool(a == b)),
+                    (Value::Char(a), BinaryOp::Neq, Value::Char(b)) => Ok(Value::Bool(a != b)),
+                    (Value::Char(a), BinaryOp::Lt, Value::Char(b)) => Ok(Value::Bool(a < b)),
+                    (Value::Char(a), BinaryOp::Gt, Value::Char(b)) => Ok(Value::Bool(a > b)),
+                    (Value::Char(a), BinaryOp::Lte, Value::Char(b)) => Ok(Value::Bool(a <= b)),
+                    (Value::Char(a), BinaryOp::Gte, Value::Char(b)) => Ok(Value::Bool(a >= b)),
+                    (Value::String(a), BinaryOp::Add, Value::Char(b)) => Ok(Value::String(Arc::new(format!("{}{}", a, b)))),
+                    (Value::Char(a), BinaryOp::Add, Value::String(b)) => Ok(Value::String(Arc::new(format!("{}{}", a, b)))),
                     
                     // Float ops
                     (Value::Float(a), BinaryOp::Add, Value::Float(b)) => Ok(Value::Float(a + b)),
@@ -621,12 +2365,75 @@ impl Interpreter {
                     (Value::Bool(a), BinaryOp::Eq, Value::Bool(b)) => Ok(Value::Bool(a == b)),
                     (Value::Bool(a), BinaryOp::Neq, Value::Bool(b)) => Ok(Value::Bool(a != b)),
 
+                    // Decimal ops. Ints are promoted; mixing with floats is an error
+                    // so precision is never lost silently.
+                    (Value::Decimal(a), op, Value::Decimal(b)) => self.decimal_op(a, op, b),
+                    (Value::Decimal(a), op, Value::Int(b)) => self.decimal_op(a, op, Decimal::from(b)),
+                    (Value::Int(a), op, Value::Decimal(b)) => self.decimal_op(Decimal::from(a), op, b),
+
+                    // Time ops. Durations are never negative, so subtraction saturates at zero.
+                    (Value::Instant(a), BinaryOp::Sub, Value::Instant(b)) => Ok(Value::Duration(a.saturating_duration_since(b))),
+                    (Value::Instant(a), BinaryOp::Add, Value::Duration(d)) => a.checked_add(d).map(Value::Instant).ok_or_else(|| self.make_error("Instant overflow")),
+                    (Value::Instant(a), BinaryOp::Sub, Value::Duration(d)) => a.checked_sub(d).map(Value::Instant).ok_or_else(|| self.make_error("Instant overflow")),
+                    (Value::Instant(a), BinaryOp::Eq, Value::Instant(b)) => Ok(Value::Bool(a == b)),
+                    (Value::Instant(a), BinaryOp::Neq, Value::Instant(b)) => Ok(Value::Bool(a != b)),
+                    (Value::Instant(a), BinaryOp::Lt, Value::Instant(b)) => Ok(Value::Bool(a < b)),
+                    (Value::Instant(a), BinaryOp::Gt, Value::Instant(b)) => Ok(Value::Bool(a > b)),
+                    (Value::Instant(a), BinaryOp::Lte, Value::Instant(b)) => Ok(Value::Bool(a <= b)),
+                    (Value::Instant(a), BinaryOp::Gte, Value::Instant(b)) => Ok(Value::Bool(a >= b)),
+                    (Value::Duration(a), op, Value::Duration(b)) => self.duration_op(a, op, b),
+                    (Value::Duration(d), BinaryOp::Mul, Value::Int(n)) | (Value::Int(n), BinaryOp::Mul, Value::Duration(d)) => self.scale_duration(d, n as f64),
+                    (Value::Duration(d), BinaryOp::Mul, Value::Float(f)) | (Value::Float(f), BinaryOp::Mul, Value::Duration(d)) => self.scale_duration(d, f),
+                    (Value::Duration(d), BinaryOp::Div, Value::Int(n)) => self.scale_duration(d, 1.0 / n as f64),
+                    (Value::Duration(d), BinaryOp::Div, Value::Float(f)) => self.scale_duration(d, 1.0 / f),
+
+                    // Complex ops. Real operands are promoted.
+                    (Value::Complex(a), op, Value::Complex(b)) => self.complex_op(a, op, b),
+                    (Value::Complex(a), op, Value::Float(b)) => self.complex_op(a, op, Complex64::from(b)),
+                    (Value::Complex(a), op, Value::Int(b)) => self.complex_op(a, op, Complex64::from(b as f64)),
+                    (Value::Float(a), op, Value::Complex(b)) => self.complex_op(Complex64::from(a), op, b),
+                    (Value::Int(a), op, Value::Complex(b)) => self.complex_op(Complex64::from(a as f64), op, b),
+
+                    // Vector/matrix ops
+                    (Value::Matrix(a), BinaryOp::Add, Value::Matrix(b)) => a.add(&b).map(|m| Value::Matrix(Arc::new(m))).map_err(|e| self.make_error(&e)),
+                    (Value::Matrix(a), BinaryOp::Sub, Value::Matrix(b)) => a.sub(&b).map(|m| Value::Matrix(Arc::new(m))).map_err(|e| self.make_error(&e)),
+                    (Value::Matrix(a), BinaryOp::Mul, Value::Matrix(b)) => a.mul(&b).map(|m| Value::Matrix(Arc::new(m))).map_err(|e| self.make_error(&e)),
+                    (Value::Matrix(m), BinaryOp::Mul, Value::Float(f)) | (Value::Float(f), BinaryOp::Mul, Value::Matrix(m)) => Ok(Value::Matrix(Arc::new(m.scale(f)))),
+                    (Value::Matrix(m), BinaryOp::Mul, Value::Int(i)) | (Value::Int(i), BinaryOp::Mul, Value::Matrix(m)) => Ok(Value::Matrix(Arc::new(m.scale(i as f64)))),
+                    (Value::Matrix(a), BinaryOp::Eq, Value::Matrix(b)) => Ok(Value::Bool(a == b)),
+                    (Value::Matrix(a), BinaryOp::Neq, Value::Matrix(b)) => Ok(Value::Bool(a != b)),
+
+                    // Path ops. `/` joins; a string on either side is taken as a path.
+                    (Value::Path(a), BinaryOp::Div, b @ (Value::Path(_) | Value::String(_))) => crate::stdlib::path::join_value(&a, &b).map_err(|e| self.native_error(e)),
+                    (Value::String(a), BinaryOp::Div, b @ Value::Path(_)) => crate::stdlib::path::join_value(std::path::Path::new(a.as_str()), &b).map_err(|e| self.native_error(e)),
+                    (Value::Path(a), BinaryOp::Eq, Value::Path(b)) => Ok(Value::Bool(a == b)),
+                    (Value::Path(a), BinaryOp::Neq, Value::Path(b)) => Ok(Value::Bool(a != b)),
+                    (Value::Path(a), BinaryOp::Lt, Value::Path(b)) => Ok(Value::Bool(a < b)),
+                    (Value::Path(a), BinaryOp::Gt, Value::Path(b)) => Ok(Value::Bool(a > b)),
+                    (Value::Path(a), BinaryOp::Lte, Value::Path(b)) => Ok(Value::Bool(a <= b)),
+                    (Value::Path(a), BinaryOp::Gte, Value::Path(b)) => Ok(Value::Bool(a >= b)),
+
+                    (a @ Value::Range { .. }, BinaryOp::Eq, b @ Value::Range { .. }) => Ok(Value::Bool(a == b)),
+                    (a @ Value::Range { .. }, BinaryOp::Neq, b @ Value::Range { .. }) => Ok(Value::Bool(a != b)),
+
+                    (a @ Value::Record { .. }, op, b) => self.record_op(&a, op, &b),
+
+                    // Types, e.g. `type(x) == Point`, and enum values
+                    (a @ (Value::Class { .. } | Value::RecordConstructor { .. } | Value::Enum { .. } | Value::EnumVariant { .. }), BinaryOp::Eq, b) => Ok(Value::Bool(a == b)),
+                    (a @ (Value::Class { .. } | Value::RecordConstructor { .. } | Value::Enum { .. } | Value::EnumVariant { .. }), BinaryOp::Neq, b) => Ok(Value::Bool(a != b)),
+
                     _ => Err(self.make_error("Unsupported operation")),
                 }
             }
             Expr::Index { object, index } => {
                 let obj_val = self.evaluate(*object)?;
                 let idx_val = self.evaluate(*index)?;
+                if let Value::Range { start, end, inclusive, step } = idx_val {
+                    if step != 1 {
+                        return Err(self.make_error("Cannot slice with a range that has a step"));
+                    }
+                    return self.slice(obj_val, start, end, inclusive);
+                }
 
                 match obj_val {
                     Value::List(l) => {
@@ -662,9 +2469,10 @@ impl Interpreter {
                     Value::String(s) => {
                          if let Value::Int(i) = idx_val {
                              let idx = i as usize;
-                             if i < 0 || idx >= s.len() { return Err(self.make_error("Index out of bounds")); }
+                             if i < 0 { return Err(self.make_error("Index out of bounds")); }
+                             // A char, as iterating a string gives
                              if let Some(c) = s.chars().nth(idx) {
-                                 Ok(Value::String(Arc::new(c.to_string())))
+                                 Ok(Value::Char(c))
                              } else {
                                   Err(self.make_error("Index out of bounds"))
                              }
@@ -687,25 +2495,45 @@ impl Interpreter {
                         }
                         Err(self.make_error("Key error"))
                     }
+                    Value::Range { start, end, inclusive, step } => {
+                        if let Value::Int(i) = idx_val {
+                            range_get(start, end, inclusive, step, i)
+                                .map(Value::Int)
+                                .ok_or_else(|| self.make_error("Index out of bounds"))
+                        } else { Err(self.make_error("Range index must be integer")) }
+                    }
                     _ => Err(self.make_error("Type is not indexable")),
                 }
             }
             Expr::Call { function, generics, args } => {
+                let callee = match &*function {
+                    Expr::Identifier(name) | Expr::Get { name, .. } => name.clone(),
+                    _ => "<function>".to_string(),
+                };
                 let func_val = self.evaluate(*function)?;
                 
-                let mut evaluated_args = Vec::new();
-                for arg_expr in args {
-                     evaluated_args.push(self.evaluate(arg_expr)?);
-                }
+                let evaluated_args = self.evaluate_items(args)?;
                 
-                return self.apply(func_val, evaluated_args, generics);
-
-
+                // Only calls into Pyro code show up in a trace
+                let traced = matches!(func_val, Value::Function { .. } | Value::BoundMethod { .. } | Value::Class { .. });
+                if let Some(mut hooks) = self.hooks() {
+                    hooks.on_call(&callee, &evaluated_args);
+                }
+                let result = self.apply(func_val, evaluated_args, generics);
+                if let Some(mut hooks) = self.hooks() {
+                    hooks.on_return(&callee, &result);
+                }
+                return result.map_err(|mut e| {
+                    if traced {
+                        e.trace.push(Frame { function: callee });
+                    }
+                    e
+                });
             }
         }
     }
 
-    fn call_method(&mut self, object: Value, name: &str, args: Vec<Value>) -> Result<Value, Value> {
+    fn call_method(&mut self, object: Value, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
         match object {
             Value::ListMutable(list_rc) => {
                 let mut list = list_rc.write().unwrap();
@@ -837,6 +2665,7 @@ impl Interpreter {
                 match name {
                     "add" => {
                          if args.len() != 1 { return Err(self.make_error("add expects 1 argument")); }
+                         self.check_key(&args[0])?;
                          if !set.contains(&args[0]) {
                              set.push(args[0].clone());
                          }
@@ -869,7 +2698,20 @@ impl Interpreter {
             }
             Value::String(s) => {
                 match name {
-                    "len" => Ok(Value::Int(s.len() as i64)),
+                    // Strings are measured and indexed in code points
+                    "len" | "chars_len" => Ok(Value::Int(s.chars().count() as i64)),
+                    "byte_len" => Ok(Value::Int(s.len() as i64)),
+                    "graphemes" => {
+                        let parts = s.graphemes(true)
+                            .map(|g| Value::String(Arc::new(g.to_string())))
+                            .collect();
+                        Ok(Value::List(Arc::new(parts)))
+                    }
+                    "code_points" => {
+                        let points = s.chars().map(|c| Value::Int(c as i64)).collect();
+                        Ok(Value::List(Arc::new(points)))
+                    }
+                    "chars" => Ok(Value::List(Arc::new(s.chars().map(Value::Char).collect()))),
                     "upper" => Ok(Value::String(Arc::new(s.to_uppercase()))),
                     "lower" => Ok(Value::String(Arc::new(s.to_lowercase()))),
                     "split" => {
@@ -895,31 +2737,103 @@ impl Interpreter {
                 }
             }
 
-            Value::Channel { sender, receiver, typ } => {
+            Value::Char(c) => {
                 match name {
-                    "push" => {
-                        if args.len() != 1 { return Err(self.make_error("push expects 1 argument")); }
+                    "is_digit" => Ok(Value::Bool(c.is_ascii_digit())),
+                    "is_alpha" => Ok(Value::Bool(c.is_alphabetic())),
+                    "is_alnum" => Ok(Value::Bool(c.is_alphanumeric())),
+                    "is_space" => Ok(Value::Bool(c.is_whitespace())),
+                    "is_upper" => Ok(Value::Bool(c.is_uppercase())),
+                    "is_lower" => Ok(Value::Bool(c.is_lowercase())),
+                    // Some characters change length, e.g. 'ß'.upper() is "SS"
+                    "upper" => Ok(Value::String(Arc::new(c.to_uppercase().collect()))),
+                    "lower" => Ok(Value::String(Arc::new(c.to_lowercase().collect()))),
+                    _ => Err(self.make_error(&format!("Method '{}' not found on Char", name))),
+                }
+            }
+
+            Value::Range { start, end, inclusive, step } => {
+                let len = range_len(start, end, inclusive, step);
+                match name {
+                    "len" => Ok(Value::Int(len)),
+                    "contains" => {
+                        if args.len() != 1 { return Err(self.make_error("contains expects 1 argument")); }
+                        Ok(Value::Bool(matches!(args[0], Value::Int(i) if range_contains(start, end, inclusive, step, i))))
+                    }
+                    "to_list" => {
+                        if len > MAX_RANGE_LIST {
+                            return Err(self.make_error(&format!("Range of {} ints is too long for a list", len)));
+                        }
+                        Ok(Value::List(Arc::new(range_values(start, end, inclusive, step).map(Value::Int).collect())))
+                    }
+                    _ => Err(self.make_error(&format!("Method '{}' not found on Range", name))),
+                }
+            }
+
+            Value::Duration(d) => {
+                match name {
+                    "seconds" => Ok(Value::Float(d.as_secs_f64())),
+                    "millis" => Ok(Value::Int(d.as_millis() as i64)),
+                    "micros" => Ok(Value::Int(d.as_micros() as i64)),
+                    "nanos" => Ok(Value::Int(d.as_nanos() as i64)),
+                    _ => Err(self.make_error(&format!("Method '{}' not found on Duration", name))),
+                }
+            }
+
+            Value::Path(p) => crate::stdlib::path::method(self, &p, name, &args).map_err(|e| self.native_error(e)),
+
+            Value::Instant(i) => {
+                match name {
+                    "elapsed" => Ok(Value::Duration(i.elapsed())),
+                    _ => Err(self.make_error(&format!("Method '{}' not found on Instant", name))),
+                }
+            }
+
+            Value::Decimal(d) => {
+                match name {
+                    "round" => {
+                        if args.len() > 1 { return Err(self.make_error("round expects 0 or 1 arguments (places)")); }
+                        let places = match args.first() {
+                            Some(Value::Int(p)) if *p >= 0 => *p as u32,
+                            Some(_) => return Err(self.make_error("round places must be a non-negative int")),
+                            None => 0,
+                        };
+                        // Half away from zero, as expected for currency amounts
+                        Ok(Value::Decimal(d.round_dp_with_strategy(places, rust_decimal::RoundingStrategy::MidpointAwayFromZero)))
+                    }
+                    "to_float" => Ok(Value::Float(d.to_f64().unwrap_or(f64::NAN))),
+                    "scale" => Ok(Value::Int(d.scale() as i64)),
+                    _ => Err(self.make_error(&format!("Method '{}' not found on Decimal", name))),
+                }
+            }
+
+            Value::Channel { sender, receiver, typ, rendezvous } => {
+                match name {
+                    // send and recv are the Go-style names
+                    "push" | "send" => {
+                        if args.len() != 1 { return Err(self.make_error(&format!("{} expects 1 argument", name))); }
                          if let Some(tx) = sender {
-                             match tokio::task::block_in_place(|| tx.send_blocking(args[0].clone())) {
-                                 Ok(_) => Ok(Value::Void),
-                                 Err(_) => return Err(self.make_error("Channel closed")),
+                             if send(&tx, &rendezvous, args[0].clone()) {
+                                 Ok(Value::Void)
+                             } else {
+                                 Err(self.make_error("Channel closed"))
                              }
                         } else {
                             Err(self.make_error("Channel is receive-only"))
                         }
                     }
-                    "collect" => {
-                         if !args.is_empty() { return Err(self.make_error("collect expects 0 arguments")); }
+                    "collect" | "recv" => {
+                         if !args.is_empty() { return Err(self.make_error(&format!("{} expects 0 arguments", name))); }
                          if let Some(rx) = receiver {
-                             match tokio::task::block_in_place(|| rx.recv_blocking()) {
-                                 Ok(v) => Ok(v),
-                                 Err(_) => Err(self.make_error("Channel closed or empty")),
-                             }
+                             self.receive(&rx, &rendezvous).ok_or_else(|| self.make_error("Channel closed or empty"))
                         } else {
                             Err(self.make_error("Channel is send-only"))
                         }
                     }
                     "close" => {
+                        if let Some(rendezvous) = &rendezvous {
+                            rendezvous.ack_tx.close();
+                        }
                         if let Some(tx) = sender {
                             tx.close();
                             Ok(Value::Void)
@@ -940,6 +2854,7 @@ impl Interpreter {
                                  sender: sender.clone(),
                                  receiver: None,
                                  typ: typ.clone(),
+                                 rendezvous: rendezvous.clone(),
                              })
                         } else {
                             Err(self.make_error("Channel does not have a sender"))
@@ -951,6 +2866,7 @@ impl Interpreter {
                                  sender: None,
                                  receiver: receiver.clone(),
                                  typ: typ.clone(),
+                                 rendezvous: rendezvous.clone(),
                              })
                         } else {
                             Err(self.make_error("Channel does not have a receiver"))
@@ -963,13 +2879,28 @@ impl Interpreter {
         }
     }
 
+    // Calls a Pyro callable from native code, e.g. a callback handed to a
+    // stdlib function through an InterpreterFunction.
+    pub fn call(&mut self, func: Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.apply(func, args, Vec::new())
+    }
+
     // Helper for applying arguments with currying support
-    fn apply(&mut self, func: Value, args: Vec<Value>, call_generics: Vec<Type>) -> Result<Value, Value> {
+    fn apply(&mut self, func: Value, args: Vec<Value>, call_generics: Vec<Type>) -> Result<Value, RuntimeError> {
         match func {
-            Value::Function { generics, params, body, partial_args } => {
+            Value::Function { generics, params, body, partial_args, env } => {
                 let mut all_args = partial_args.clone();
                 all_args.extend(args);
 
+                // A `*rest` parameter takes whatever is left once the others
+                // have an argument, so these functions are never over-applied
+                if let Some((_, Type::Variadic(_))) = params.last()
+                    && all_args.len() >= params.len() - 1
+                {
+                    let rest = all_args.split_off(params.len() - 1);
+                    all_args.push(Value::List(Arc::new(rest)));
+                }
+
                 if all_args.len() < params.len() {
                     // Partial application
                     return Ok(Value::Function {
@@ -977,25 +2908,38 @@ impl Interpreter {
                         params,
                         body,
                         partial_args: all_args,
+                        env,
                     });
                 } else if all_args.len() == params.len() {
                     // Full execution
-                    let mut new_env = self.globals.clone();
-                    for (i, val) in all_args.iter().enumerate() {
-                        new_env.insert(params[i].0.clone(), val.clone());
+                    if self.frames.len() >= self.max_call_depth {
+                        return Err(self.native_error(error_with_kind(
+                            "RecursionError",
+                            format!("Maximum call depth of {} exceeded", self.max_call_depth),
+                        )));
                     }
+                    self.interrupt.check().map_err(|e| self.native_error(e))?;
+                    let locals = params.iter().map(|(name, _)| name.clone()).zip(all_args.into_iter().map(cell)).collect();
                     
-                    let old_globals = self.globals.clone();
-                    self.globals = new_env;
+                    self.frames.push(CallFrame { locals, env });
                     let result = self.run(body.to_vec());
-                    self.globals = old_globals;
+                    let frame = self.frames.pop();
                     
                     match result {
                         Ok(Flow::Return(v)) => Ok(v),
                         Ok(Flow::None) => Ok(Value::Void),
                         Ok(Flow::Break) => Err(self.make_error("Unexpected 'break' outside of loop")),
                         Ok(Flow::Continue) => Err(self.make_error("Unexpected 'continue' outside of loop")),
-                        Err(e) => Err(e),
+                        Err(e) => {
+                            if self.error_locals.is_none()
+                                && let Some(CallFrame { locals, env }) = frame
+                            {
+                                let mut variables = env.map(|env| env.values()).unwrap_or_default();
+                                variables.extend(locals.into_iter().map(|(name, cell)| (name, cell.read().unwrap().clone())));
+                                self.error_locals = Some(Env(Arc::new(variables)));
+                            }
+                            Err(e)
+                        }
                     }
                 } else {
                     // Over-application
@@ -1005,12 +2949,13 @@ impl Interpreter {
                         params: params.clone(),
                         body: body.clone(),
                         partial_args: needed.to_vec(),
+                        env,
                     }, Vec::new(), call_generics.clone())?;
                     
                     self.apply(result, remaining.to_vec(), Vec::new())
                 }
             }
-            Value::RecordConstructor { name, fields, methods, partial_args } => {
+            Value::RecordConstructor { name, fields, methods, derives, partial_args } => {
                 let mut all_args = partial_args.clone();
                 all_args.extend(args);
                 
@@ -1019,14 +2964,16 @@ impl Interpreter {
                         name,
                         fields,
                         methods,
+                        derives,
                         partial_args: all_args,
                     });
                 } else if all_args.len() == fields.len() {
                     return Ok(Value::Record {
                         name,
-                        fields: Arc::new(fields),
+                        fields,
                         values: Arc::new(all_args),
                         methods,
+                        derives,
                     });
                 } else {
                      // Over-application
@@ -1035,6 +2982,7 @@ impl Interpreter {
                         name: name.clone(), 
                         fields: fields.clone(), 
                         methods: methods.clone(),
+                        derives,
                         partial_args: needed.to_vec() 
                     }, Vec::new(), call_generics.clone())?;
                      self.apply(result, remaining.to_vec(), Vec::new())
@@ -1047,7 +2995,7 @@ impl Interpreter {
                      methods: methods.clone(),
                  };
                  if let Some(init_method) = methods.get("__init__") {
-                     if let Value::Function { generics, params, body, partial_args } = init_method {
+                     if let Value::Function { generics, params, body, partial_args, env } = init_method {
                          let mut init_args = vec![instance.clone()];
                          init_args.extend(args);
                          
@@ -1056,6 +3004,7 @@ impl Interpreter {
                              params: params.clone(),
                              body: body.clone(),
                              partial_args: partial_args.clone(),
+                             env: env.clone(),
                          }, init_args, call_generics)?;
                      }
                  }
@@ -1063,12 +3012,12 @@ impl Interpreter {
             }
             Value::BoundMethod { object, method } => {
                 let call_args = args;
-                if let Value::Function { ref generics, ref params, ref body, ref partial_args } = *method {
+                if let Value::Function { ref generics, ref params, ref body, ref partial_args, ref env } = *method {
                      if partial_args.is_empty() && !params.is_empty() {
                          let mut new_partial = vec![*object.clone()];
                          new_partial.extend(partial_args.clone()); 
                          return self.apply(Value::Function {
-                             generics: generics.clone(), params: params.clone(), body: body.clone(), partial_args: new_partial
+                             generics: generics.clone(), params: params.clone(), body: body.clone(), partial_args: new_partial, env: env.clone()
                          }, call_args, call_generics);
                      } else {
                          return self.apply(*method, call_args, call_generics);
@@ -1079,19 +3028,19 @@ impl Interpreter {
             Value::BuiltinMethod { object, name } => {
                  self.call_method(*object, &name, args)
             }
-            Value::NativeFunction { name: _, func } => {
-                (func.0)(args).map_err(|e| {
-                    if let Value::String(s) = &e {
-                        return self.make_error(s);
-                    }
-                    e
-                })
+            Value::NativeFunction { name, func } => {
+                let result = catch_panic(&name, || (func.0)(args)).map_err(|e| self.native_error(e))?;
+                result.map_err(|e| self.native_error(e))
+            }
+            Value::InterpreterFunction { name, func } => {
+                let result = catch_panic(&name, || (func.0)(self, args)).map_err(|e| self.native_error(e))?;
+                result.map_err(|e| self.native_error(e))
             }
             Value::String(s) => {
                  let name = s.as_str();
                  if name == "print" {
                     for arg in args {
-                        println!("{:?}", arg);
+                        self.write_output(&show(&arg)).map_err(|e| self.native_error(io_error(&e)))?;
                     }
                     Ok(Value::Void)
                  } else if name == "range" {
@@ -1099,21 +3048,57 @@ impl Interpreter {
                         let start = if args.len() == 1 { 0 } else { match args[0] { Value::Int(i) => i, _ => return Err(self.make_error("start int")) } };
                         let end = if args.len() == 1 { match args[0] { Value::Int(i) => i, _ => return Err(self.make_error("end int")) } } else { match args[1] { Value::Int(i) => i, _ => return Err(self.make_error("end int")) } };
                         let step = if args.len() == 3 { match args[2] { Value::Int(i) => i, _ => return Err(self.make_error("step int")) } } else { 1 };
-                        
-                        let mut vals = Vec::new();
-                        let mut current = start;
-                        if step > 0 { while current < end { vals.push(Value::Int(current)); current += step; } }
-                        else { while current > end { vals.push(Value::Int(current)); current += step; } }
-                        Ok(Value::List(Arc::new(vals)))
+                        if step == 0 { return Err(self.make_error("range step must not be zero")); }
+                        Ok(Value::Range { start, end, inclusive: false, step })
                   } else if name == "str" {
                       if args.len() != 1 { return Err(self.make_error("str takes 1 arg")); }
                       match &args[0] {
                           Value::String(s) => Ok(Value::String(s.clone())),
-                          Value::Int(i) => Ok(Value::String(Arc::new(i.to_string()))),
-                          Value::Float(f) => Ok(Value::String(Arc::new(f.to_string()))),
-                          Value::Bool(b) => Ok(Value::String(Arc::new(b.to_string()))),
-                          _ => Ok(Value::String(Arc::new(format!("{:?}", args[0])))),
+                          other => Ok(Value::String(Arc::new(stringify(other)))),
+                      }
+                  } else if name == "type" {
+                      if args.len() != 1 { return Err(self.make_error("type takes 1 arg")); }
+                      Ok(self.type_of(&args[0]))
+                  } else if name == "isinstance" {
+                      if args.len() != 2 { return Err(self.make_error("isinstance takes 2 args (value, type)")); }
+                      self.is_instance(&args[0], &args[1]).map(Value::Bool)
+                  } else if name == "ord" {
+                      match args.as_slice() {
+                          [Value::Char(c)] => Ok(Value::Int(*c as i64)),
+                          [Value::String(s)] if s.chars().count() == 1 => Ok(Value::Int(s.chars().next().unwrap_or_default() as i64)),
+                          _ => Err(self.make_error("ord expects a char or a one-character string")),
                       }
+                  } else if name == "chr" {
+                      match args.as_slice() {
+                          [Value::Int(i)] => u32::try_from(*i)
+                              .ok()
+                              .and_then(char::from_u32)
+                              .map(Value::Char)
+                              .ok_or_else(|| self.make_error(&format!("{} is not a valid code point", i))),
+                          _ => Err(self.make_error("chr expects an int")),
+                      }
+                  } else if let Some(target) = Type::numeric(name) {
+                      self.cast_numeric(name, &target, args)
+                  } else if name == "retry" {
+                      self.retry(args)
+                  } else if name == "timeit" {
+                      self.timeit(args)
+                  } else if name == "sorted" {
+                      self.sorted(args)
+                  } else if name == "decimal" {
+                      if args.len() != 1 { return Err(self.make_error("decimal takes 1 arg")); }
+                      let parsed = match &args[0] {
+                          Value::Decimal(d) => Ok(*d),
+                          Value::Int(i) => Ok(Decimal::from(*i)),
+                          // Go through the shortest decimal representation so
+                          // decimal(0.1) is 0.1 rather than 0.1000000000000000055...
+                          Value::Float(f) => Decimal::from_str(&f.to_string()).map_err(|e| e.to_string()),
+                          Value::String(s) => Decimal::from_str(s.trim())
+                              .or_else(|_| Decimal::from_scientific(s.trim()))
+                              .map_err(|e| format!("Invalid decimal '{}': {}", s, e)),
+                          other => Err(format!("Cannot convert {:?} to decimal", other)),
+                      };
+                      parsed.map(Value::Decimal).map_err(|e| self.make_error(&e))
                   } else if name == "ListMutable" {
                      if args.len() != 1 { return Err(self.make_error("ListMutable takes 1 arg")); }
                      match &args[0] { Value::List(l) => Ok(Value::ListMutable(Arc::new(RwLock::new((**l).clone())))), _ => Err(self.make_error("Expects List")) }
@@ -1127,15 +3112,21 @@ impl Interpreter {
                       if args.len() != 1 { return Err(self.make_error("DictMutable takes 1 arg")); }
                       match &args[0] { Value::Dict(l) => Ok(Value::DictMutable(Arc::new(RwLock::new((**l).clone())))), _ => Err(self.make_error("Expects Dict")) }
                   } else if name == "chan" {
-                     let capacity = if args.len() == 1 {
-                         match args[0] { Value::Int(i) => i as usize, _ => 1 } 
-                     } else { 1 };
-                     // async-channel requires capacity >= 1
-                     let cap = if capacity < 1 { 1 } else { capacity };
-                     let (tx, rx) = async_channel::bounded(cap);
+                     // Without a capacity, or with 0, the channel is unbuffered
+                     let capacity = match args.as_slice() {
+                         [] => 0,
+                         [Value::Int(i)] if *i >= 0 => *i as usize,
+                         [Value::Int(_)] => return Err(self.make_error("chan capacity can't be negative")),
+                         [_] => return Err(self.make_error("chan capacity must be an integer")),
+                         _ => return Err(self.make_error("chan takes at most 1 argument")),
+                     };
+                     // async-channel requires capacity >= 1, so an unbuffered
+                     // channel holds one value while its send waits
+                     let (tx, rx) = async_channel::bounded(capacity.max(1));
+                     let rendezvous = (capacity == 0).then(|| Arc::new(Rendezvous::new()));
                      
                      let channel_type = if call_generics.is_empty() {
-                         Type::UserDefined("Any".to_string(), Vec::new())
+                         Type::Any
                      } else {
                          call_generics[0].clone()
                      };
@@ -1144,6 +3135,7 @@ impl Interpreter {
                         sender: Some(Arc::new(tx)),
                         receiver: Some(Arc::new(rx)),
                         typ: channel_type,
+                        rendezvous,
                      })
                   } else {
                      Err(self.make_error(&format!("Unknown builtin function: {}", name)))
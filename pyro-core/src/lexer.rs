@@ -1,3 +1,4 @@
+use crate::diagnostics::Diagnostic;
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -6,6 +7,7 @@ pub enum Token {
     // Keywords
     Let,
     Mut,
+    Const,
     Def,
     Return,
     If,
@@ -26,6 +28,7 @@ pub enum Token {
     Try,
     Except,
     Finally,
+    With,
     Raise,
     From,
     Go,
@@ -37,6 +40,9 @@ pub enum Token {
     Integer(i64),
     Float(f64),
     StringLiteral(String),
+    // The text of f"...", braces and all; the parser reads the expressions
+    InterpolatedString(String),
+    Char(char),
     Bool(bool),
 
     // Symbols
@@ -53,8 +59,11 @@ pub enum Token {
     GreaterEqual,// >=
     Colon,
     Dot,         // .
+    DotDot,      // ..
+    DotDotEqual, // ..=
     Arrow,       // ->
     Pipe,        // |
+    Question,    // ?
     Comma,
     LParen,
     RParen,
@@ -68,12 +77,17 @@ pub enum Token {
     Dedent,
     Newline,
 
+    // Text that can't be a token, with the reason; the parser reports it
+    Invalid(String),
+
     EOF,
 }
 
 pub struct Lexer<'a> {
     input: Peekable<Chars<'a>>,
     indent_stack: Vec<usize>,
+    // Problems that don't stop tokenizing, such as inconsistent indentation
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> Lexer<'a> {
@@ -81,13 +95,28 @@ impl<'a> Lexer<'a> {
         Self {
             input: input.chars().peekable(),
             indent_stack: vec![0],
+            diagnostics: Vec::new(),
         }
     }
 
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     pub fn tokenize(&mut self) -> Vec<Token> {
+        self.tokenize_with_lines().0
+    }
+
+    // Also returns the 1-based source line each token starts on, parallel to
+    // the tokens. Indent and Dedent tokens take the line of the newline that
+    // produced them.
+    pub fn tokenize_with_lines(&mut self) -> (Vec<Token>, Vec<usize>) {
         let mut tokens = Vec::new();
+        let mut lines = Vec::new();
+        let mut line = 1;
         
         while let Some(&c) = self.input.peek() {
+            let start_line = line;
             match c {
                 ' ' | '\t' => {
                     // Skip whitespace inside lines, indentation handled by Newline logic
@@ -95,8 +124,9 @@ impl<'a> Lexer<'a> {
                 }
                 '\n' => {
                     self.input.next();
+                    line += 1;
                     tokens.push(Token::Newline);
-                    self.handle_indentation(&mut tokens);
+                    self.handle_indentation(&mut tokens, line);
                 }
                 '#' => {
                     // Skip to end of line
@@ -192,9 +222,16 @@ impl<'a> Lexer<'a> {
                      // For simplicity, let's treat `.` as Dot token unless we implement specific float parsing here.
                      // Users can write `0.5`.
                      self.input.next(); 
-                     tokens.push(Token::Dot); 
+                     if self.input.next_if_eq(&'.').is_none() {
+                         tokens.push(Token::Dot);
+                     } else if self.input.next_if_eq(&'=').is_some() {
+                         tokens.push(Token::DotDotEqual);
+                     } else {
+                         tokens.push(Token::DotDot);
+                     }
                 }
                 '|' => { self.input.next(); tokens.push(Token::Pipe); }
+                '?' => { self.input.next(); tokens.push(Token::Question); }
                 ',' => { self.input.next(); tokens.push(Token::Comma); }
                 '(' => { self.input.next(); tokens.push(Token::LParen); }
                 ')' => { self.input.next(); tokens.push(Token::RParen); }
@@ -203,7 +240,21 @@ impl<'a> Lexer<'a> {
                 '{' => { self.input.next(); tokens.push(Token::LBrace); }
                 '}' => { self.input.next(); tokens.push(Token::RBrace); }
                 '"' => {
-                    tokens.push(self.read_string());
+                    let (token, newlines) = self.read_string();
+                    tokens.push(token);
+                    line += newlines;
+                }
+                '\'' => {
+                    tokens.push(self.read_char());
+                }
+                'f' if self.input.clone().nth(1) == Some('"') => {
+                    self.input.next(); // skip f
+                    let (token, newlines) = self.read_string();
+                    tokens.push(match token {
+                        Token::StringLiteral(s) => Token::InterpolatedString(s),
+                        other => other,
+                    });
+                    line += newlines;
                 }
                 c if c.is_alphabetic() || c == '_' => {
                     tokens.push(self.read_identifier());
@@ -216,6 +267,7 @@ impl<'a> Lexer<'a> {
                     self.input.next();
                 }
             }
+            lines.resize(tokens.len(), start_line);
         }
         
         // Handle remaining dedents at EOF
@@ -225,10 +277,11 @@ impl<'a> Lexer<'a> {
         }
         
         tokens.push(Token::EOF);
-        tokens
+        lines.resize(tokens.len(), line);
+        (tokens, lines)
     }
 
-    fn handle_indentation(&mut self, tokens: &mut Vec<Token>) {
+    fn handle_indentation(&mut self, tokens: &mut Vec<Token>, line: usize) {
         let mut spaces = 0;
         while let Some(&c) = self.input.peek() {
             if c == ' ' {
@@ -255,8 +308,10 @@ impl<'a> Lexer<'a> {
                 tokens.push(Token::Dedent);
             }
             if spaces != *self.indent_stack.last().unwrap() {
-                // Indentation error
-                eprintln!("Indentation Error");
+                self.diagnostics.push(
+                    Diagnostic::warning("indentation", "Dedent does not match any enclosing indentation level")
+                        .at_line(line),
+                );
             }
         }
     }
@@ -275,6 +330,7 @@ impl<'a> Lexer<'a> {
         match ident.as_str() {
             "let" => Token::Let,
             "mut" => Token::Mut,
+            "const" => Token::Const,
             "def" => Token::Def,
             "if" => Token::If,
             "else" => Token::Else,
@@ -297,6 +353,7 @@ impl<'a> Lexer<'a> {
             "try" => Token::Try,
             "except" => Token::Except,
             "finally" => Token::Finally,
+            "with" => Token::With,
             "raise" => Token::Raise,
             "from" => Token::From,
             "go" => Token::Go,
@@ -316,7 +373,8 @@ impl<'a> Lexer<'a> {
             if c.is_digit(10) {
                 number_str.push(c);
                 self.input.next();
-            } else if c == '.' && !is_float {
+            } else if c == '.' && !is_float && self.input.clone().nth(1) != Some('.') {
+                // `1..5` is a range, not the float `1.` followed by `.5`
                 // We need to be careful here. If we have `1.method()`, is that float `1.` or integer `1` then `.`?
                 // Usually `1.` is float. `1..` is range (not supported yet). `1.method()` is float method?
                 // Most langs require `(1).method()` or `1.0.method()`.
@@ -361,23 +419,105 @@ impl<'a> Lexer<'a> {
 
         if is_float {
             // Check if it ends with `.`. If so, it might be ambiguous but for now it's float 1.0
-            Token::Float(number_str.parse().unwrap())
+            Token::Float(number_str.parse().unwrap_or(f64::INFINITY))
         } else {
-            Token::Integer(number_str.parse().unwrap())
+            match number_str.parse() {
+                Ok(n) => Token::Integer(n),
+                Err(_) => Token::Invalid(format!("Integer literal {} is too large", number_str)),
+            }
         }
     }
 
-    fn read_string(&mut self) -> Token {
+    // Also returns how many source lines the literal spans, which for a
+    // heredoc is not the number of newlines left in its value
+    fn read_string(&mut self) -> (Token, usize) {
         self.input.next(); // skip opening "
+        let mut lookahead = self.input.clone();
+        if lookahead.next() == Some('"') && lookahead.next() == Some('"') {
+            self.input.next();
+            self.input.next();
+            return self.read_triple_quoted();
+        }
         let mut s = String::new();
         while let Some(&c) = self.input.peek() {
             if c == '"' {
                 self.input.next();
-                return Token::StringLiteral(s);
+                break;
+            }
+            s.push(c);
+            self.input.next();
+        }
+        let newlines = s.matches('\n').count();
+        (Token::StringLiteral(s), newlines) // EOF or unterminated if the loop ran out
+    }
+
+    // """...""" may contain single quotes. Quotes right before the closing
+    // three belong to the text, so `"""say "hi""""` ends in a quote.
+    fn read_triple_quoted(&mut self) -> (Token, usize) {
+        let mut s = String::new();
+        while let Some(c) = self.input.next() {
+            if c == '"' {
+                let mut lookahead = self.input.clone();
+                if lookahead.next() == Some('"') && lookahead.next() == Some('"') && lookahead.next() != Some('"') {
+                    self.input.next();
+                    self.input.next();
+                    break;
+                }
             }
             s.push(c);
+        }
+        let newlines = s.matches('\n').count();
+        let s = match s.strip_prefix('\n') {
+            Some(body) => dedent(body),
+            None => s,
+        };
+        (Token::StringLiteral(s), newlines)
+    }
+
+    // 'a': exactly one code point between single quotes
+    fn read_char(&mut self) -> Token {
+        self.input.next(); // skip opening '
+        let mut s = String::new();
+        while let Some(&c) = self.input.peek() {
             self.input.next();
+            if c == '\'' {
+                let mut chars = s.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c), None) => Token::Char(c),
+                    _ => Token::Invalid(format!("Character literal '{}' must hold exactly one character", s)),
+                };
+            }
+            if c == '\n' {
+                break;
+            }
+            s.push(c);
         }
-        Token::StringLiteral(s) // EOF or unterminated
+        Token::Invalid("Unterminated character literal".to_string())
     }
 }
+
+// A heredoc starts with a newline right after the opening quotes. Its text
+// begins on the next line and loses the indentation its non-blank lines
+// share. Blank lines, including the one holding the closing quotes, come out
+// empty, so
+//
+//     query = """
+//         SELECT *
+//           FROM users
+//         """
+//
+// is "SELECT *\n  FROM users\n".
+fn dedent(body: &str) -> String {
+    let lines: Vec<&str> = body.split('\n').collect();
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| if l.trim().is_empty() { "" } else { &l[indent..] })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -5,3 +5,17 @@ pub mod interpreter;
 pub mod transpiler;
 pub mod convert;
 pub mod stdlib;
+pub mod prelude;
+pub mod replay;
+pub mod permissions;
+pub mod plugin;
+pub mod constants;
+pub mod generics;
+pub mod aliases;
+pub mod interfaces;
+pub mod flow;
+pub mod strict;
+pub mod stubs;
+pub mod diagnostics;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz_targets;
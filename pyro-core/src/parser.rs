@@ -1,19 +1,153 @@
-use crate::ast::{BinaryOp, Expr, Stmt, Type, Program};
-use crate::lexer::Token;
+pub mod incremental;
+
+use crate::ast::{BinaryOp, Derives, EnumVariant, Expr, MatchCase, Pattern, Stmt, StringPart, Type, Program, DISCARD};
+use crate::lexer::{Lexer, Token};
 use std::iter::Peekable;
 use std::slice::Iter;
 
 pub struct Parser<'a> {
     tokens: Peekable<Iter<'a, Token>>,
+    probes: Option<Probes<'a>>,
+    // Statements, expressions and types being parsed, one inside the other
+    depth: usize,
+}
+
+// Nesting is parsed (and later evaluated) recursively, so deeper input is
+// rejected instead of risking the stack. The stack is also grown on demand,
+// as a few hundred levels can already overflow a small thread stack.
+const MAX_NESTING: usize = 256;
+const RED_ZONE: usize = 64 * 1024;
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+// Name of the function coverage probes call as `__coverage__(file, line)`.
+pub const COVERAGE_PROBE: &str = "__coverage__";
+
+// Coverage instrumentation: the token lines from `Lexer::tokenize_with_lines`
+// and the lines a probe was inserted for so far.
+struct Probes<'a> {
+    lines: &'a [usize],
+    file: i64,
+    probed: Vec<usize>,
+}
+
+// Keywords that can't start an expression after '.' are allowed as property
+// names, so native modules can expose methods like `glob.match`.
+fn property_name(token: &Token) -> Option<String> {
+    let name = match token {
+        Token::Identifier(s) => return Some(s.clone()),
+        Token::Match => "match",
+        Token::Case => "case",
+        Token::Type => "type",
+        Token::From => "from",
+        Token::In => "in",
+        Token::Enum => "enum",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+// Splits the text of f"..." into text and the expressions in braces.
+// `{{` and `}}` stand for literal braces.
+fn parse_interpolation(source: &str) -> Result<Vec<StringPart>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                text.push(c);
+            }
+            '}' => return Err("Single '}' in f-string, write '}}' for a brace".to_string()),
+            '{' => {
+                // Braces inside the expression, as in a dict literal, nest
+                let mut inner = String::new();
+                let mut depth = 0;
+                loop {
+                    match chars.next() {
+                        None => return Err("Unclosed '{' in f-string".to_string()),
+                        Some('}') if depth == 0 => break,
+                        Some(c) => {
+                            match c {
+                                '{' => depth += 1,
+                                '}' => depth -= 1,
+                                _ => {}
+                            }
+                            inner.push(c);
+                        }
+                    }
+                }
+                if !text.is_empty() {
+                    parts.push(StringPart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(StringPart::Expr(parse_embedded(inner.trim())?));
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(StringPart::Text(text));
+    }
+    Ok(parts)
+}
+
+fn parse_embedded(source: &str) -> Result<Expr, String> {
+    if source.is_empty() {
+        return Err("Empty expression in f-string".to_string());
+    }
+    let tokens = Lexer::new(source).tokenize();
+    let mut parser = Parser::new(&tokens);
+    let expr = parser.parse_expression()?;
+    match parser.tokens.find(|t| **t != Token::Newline) {
+        None | Some(Token::EOF) => Ok(expr),
+        Some(_) => Err(format!("Unexpected text after '{}' in f-string", source)),
+    }
 }
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
         Self {
             tokens: tokens.iter().peekable(),
+            probes: None,
+            depth: 0,
         }
     }
 
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth >= MAX_NESTING {
+            return Err(format!("Code is nested more than {} levels deep", MAX_NESTING));
+        }
+        self.depth += 1;
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || parse(self));
+        self.depth -= 1;
+        result
+    }
+
+    // Inserts a call to `__coverage__(file, line)` before every statement
+    // that can run on its own, for line coverage. Class and record bodies
+    // only hold methods, so their methods get probes but the bodies don't.
+    pub fn with_coverage_probes(mut self, lines: &'a [usize], file: i64) -> Self {
+        self.probes = Some(Probes { lines, file, probed: Vec::new() });
+        self
+    }
+
+    // Lines that received a probe, in the order they were parsed.
+    pub fn probed_lines(&self) -> &[usize] {
+        self.probes.as_ref().map_or(&[], |p| &p.probed)
+    }
+
+    fn push_probe(&mut self, stmts: &mut Vec<Stmt>) {
+        let position = self.tokens.len();
+        let Some(probes) = self.probes.as_mut() else { return };
+        let line = probes.lines[probes.lines.len() - position];
+        probes.probed.push(line);
+        stmts.push(Stmt::Expr(Expr::Call {
+            function: Box::new(Expr::Identifier(COVERAGE_PROBE.to_string())),
+            generics: Vec::new(),
+            args: vec![Expr::LiteralInt(probes.file), Expr::LiteralInt(line as i64)],
+        }));
+    }
+
     pub fn parse(&mut self) -> Result<Program, String> {
         let mut statements = Vec::new();
         while let Some(token) = self.tokens.peek() {
@@ -24,15 +158,23 @@ impl<'a> Parser<'a> {
                 self.tokens.next();
                 continue;
             }
+            self.push_probe(&mut statements);
             statements.push(self.parse_statement()?);
         }
         Ok(Program { statements })
     }
 
     fn parse_statement(&mut self) -> Result<Stmt, String> {
+        self.nested(Self::parse_statement_inner)
+    }
+
+    fn parse_statement_inner(&mut self) -> Result<Stmt, String> {
+        // `type Name = ...` declares an alias; `type(x)` is a call
+        let type_alias = matches!(self.tokens.clone().nth(1), Some(Token::Identifier(_)));
         match self.tokens.peek() {
             Some(Token::Let) => self.parse_var_decl(false),
             Some(Token::Mut) => self.parse_var_decl(true),
+            Some(Token::Const) => self.parse_const_decl(),
             Some(Token::Def) => self.parse_fn_decl(),
             Some(Token::Return) => self.parse_return(),
             Some(Token::Break) => {
@@ -53,21 +195,50 @@ impl<'a> Parser<'a> {
 
             Some(Token::While) => self.parse_while(),
             Some(Token::For) => self.parse_for(),
+            Some(Token::Match) => self.parse_match(),
             Some(Token::Import) => self.parse_import(),
             Some(Token::Record) => self.parse_record_decl(),
+            Some(Token::Enum) => self.parse_enum_decl(),
             Some(Token::Class) => self.parse_class_decl(),
             Some(Token::Interface) => self.parse_interface_decl(),
-            Some(Token::Type) => self.parse_type_alias(),
+            Some(Token::Type) if type_alias => self.parse_type_alias(),
             Some(Token::Try) => self.parse_try(),
+            Some(Token::With) => self.parse_with(),
             Some(Token::Raise) => self.parse_raise(),
             Some(Token::Go) => self.parse_go(),
             Some(Token::Extern) => self.parse_extern(),
             _ => {
                 let expr = self.parse_expression()?;
+
+                // a, b = b, a
+                if let (Expr::Identifier(first), Some(Token::Comma)) = (&expr, self.tokens.peek()) {
+                    let names = self.parse_more_names(first.clone())?;
+                    if self.tokens.next() != Some(&Token::Equal) {
+                        return Err("Expected '=' after the names to assign".to_string());
+                    }
+                    let value = self.parse_unpack_value()?;
+                    let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+                    return Ok(Stmt::DestructureAssign { names, value });
+                }
                 
                 if let Some(Token::Equal) = self.tokens.peek() {
                     self.tokens.next(); // consume '='
+                    // (a, b) = b, a
+                    if let Expr::Tuple(elements) = &expr {
+                        let names: Option<Vec<String>> = elements
+                            .iter()
+                            .map(|e| match e {
+                                Expr::Identifier(name) => Some(name.clone()),
+                                _ => None,
+                            })
+                            .collect();
+                        let names = names.ok_or_else(|| "Invalid assignment target".to_string())?;
+                        let value = self.parse_unpack_value()?;
+                        let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+                        return Ok(Stmt::DestructureAssign { names, value });
+                    }
                     let value = self.parse_expression()?;
+                    let value = self.parse_trailing_block(value)?;
                     if let Some(Token::Newline) = self.tokens.peek() {
                         self.tokens.next();
                     }
@@ -77,6 +248,7 @@ impl<'a> Parser<'a> {
                         _ => Err("Invalid assignment target".to_string()),
                     }
                 } else {
+                    let expr = self.parse_trailing_block(expr)?;
                     // Consume optional newline after expression statement
                     if let Some(Token::Newline) = self.tokens.peek() {
                        self.tokens.next();
@@ -87,15 +259,63 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // `first` and any names after it separated by commas
+    fn parse_more_names(&mut self, first: String) -> Result<Vec<String>, String> {
+        let mut names = vec![first];
+        while self.tokens.next_if(|t| matches!(t, Token::Comma)).is_some() {
+            match self.tokens.next() {
+                Some(Token::Identifier(s)) => names.push(s.clone()),
+                _ => return Err("Expected identifier after ','".to_string()),
+            }
+        }
+        Ok(names)
+    }
+
+    // What's unpacked by a destructuring; `b, a` is the tuple (b, a)
+    fn parse_unpack_value(&mut self) -> Result<Expr, String> {
+        let value = self.parse_expression()?;
+        if !matches!(self.tokens.peek(), Some(Token::Comma)) {
+            return self.parse_trailing_block(value);
+        }
+        let mut elements = vec![value];
+        while self.tokens.next_if(|t| matches!(t, Token::Comma)).is_some() {
+            elements.push(self.parse_expression()?);
+        }
+        Ok(Expr::Tuple(elements))
+    }
+
+    // An argument or list element, which can be `*items`
+    fn parse_element(&mut self) -> Result<Expr, String> {
+        if self.tokens.next_if(|t| matches!(t, Token::Star)).is_some() {
+            return Ok(Expr::Spread(Box::new(self.parse_expression()?)));
+        }
+        self.parse_expression()
+    }
+
     // let x: int = 10
     fn parse_var_decl(&mut self, is_mut: bool) -> Result<Stmt, String> {
         self.tokens.next(); // consume let/mut
         
+        let parenthesized = self.tokens.next_if(|t| matches!(t, Token::LParen)).is_some();
         let name = match self.tokens.next() {
             Some(Token::Identifier(s)) => s.clone(),
             _ => return Err("Expected identifier".to_string()),
         };
 
+        // let value, _ = pair  or  let (value, _) = pair
+        if parenthesized || matches!(self.tokens.peek(), Some(Token::Comma)) {
+            let names = self.parse_more_names(name)?;
+            if parenthesized && self.tokens.next() != Some(&Token::RParen) {
+                return Err("Expected ')' after the names to unpack".to_string());
+            }
+            if self.tokens.next() != Some(&Token::Equal) {
+                return Err("Expected '=' after the names to unpack".to_string());
+            }
+            let value = self.parse_unpack_value()?;
+            let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+            return Ok(Stmt::Destructure { names, value, mutable: is_mut });
+        }
+
         let mut typ = None;
         if let Some(Token::Colon) = self.tokens.peek() {
             self.tokens.next();
@@ -109,6 +329,7 @@ impl<'a> Parser<'a> {
         }
 
         let value = self.parse_expression()?;
+        let value = self.parse_trailing_block(value)?;
 
         if let Some(Token::Newline) = self.tokens.peek() {
             self.tokens.next();
@@ -122,7 +343,74 @@ impl<'a> Parser<'a> {
         })
     }
 
+    // A call that ends a statement may take an indented block as its last
+    // argument, a function of the names after `as`:
+    //
+    //     each(xs) as x:
+    //         print(x)
+    fn parse_trailing_block(&mut self, expr: Expr) -> Result<Expr, String> {
+        let opens_block = match self.tokens.peek() {
+            Some(Token::Colon) => true,
+            Some(Token::Identifier(s)) => s == "as",
+            _ => false,
+        };
+        match expr {
+            Expr::Call { function, generics, mut args } if opens_block => {
+                let params = self.parse_block_params()?;
+                let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+                let body = self.parse_block()?;
+                args.push(Expr::Lambda { params, body });
+                Ok(Expr::Call { function, generics, args })
+            }
+            expr => Ok(expr),
+        }
+    }
+
+    // `as x, y: int:` up to and including the colon that opens the block.
+    // Blocks always start on a new line, so a colon before the line break is
+    // the block's and any other is an annotation.
+    fn parse_block_params(&mut self) -> Result<Vec<(String, Type)>, String> {
+        let mut params = Vec::new();
+        if let Some(Token::Identifier(s)) = self.tokens.peek()
+            && s == "as"
+        {
+            self.tokens.next();
+            loop {
+                let name = match self.tokens.next() {
+                    Some(Token::Identifier(s)) => s.clone(),
+                    _ => return Err("Expected parameter name after 'as'".to_string()),
+                };
+                let mut typ = Type::ImplicitAny;
+                if let Some(Token::Colon) = self.tokens.peek()
+                    && !matches!(self.tokens.clone().nth(1), Some(Token::Newline))
+                {
+                    self.tokens.next();
+                    typ = self.parse_type()?;
+                }
+                params.push((name, typ));
+                if self.tokens.next_if(|t| matches!(t, Token::Comma)).is_none() {
+                    break;
+                }
+            }
+        }
+        match self.tokens.next() {
+            Some(Token::Colon) => Ok(params),
+            _ => Err("Expected ':' before block argument".to_string()),
+        }
+    }
+
+    fn parse_const_decl(&mut self) -> Result<Stmt, String> {
+        match self.parse_var_decl(false)? {
+            Stmt::VarDecl { name, typ, value, .. } => Ok(Stmt::ConstDecl { name, typ, value }),
+            _ => Err("A constant can't unpack several values".to_string()),
+        }
+    }
+
     fn parse_type(&mut self) -> Result<Type, String> {
+        self.nested(Self::parse_union_type)
+    }
+
+    fn parse_union_type(&mut self) -> Result<Type, String> {
         let first_type = self.parse_single_type()?;
         
         // Check for Union |
@@ -142,12 +430,17 @@ impl<'a> Parser<'a> {
         match self.tokens.next() {
             Some(Token::Identifier(s)) => {
                 let name = s.clone();
+                if let Some(numeric) = Type::numeric(&name) {
+                    return Ok(numeric);
+                }
                 match name.as_str() {
-                    "int" => Ok(Type::Int),
-                    "float" => Ok(Type::Float),
+                    "int64" => Ok(Type::Int),
+                    "float64" => Ok(Type::Float),
+                    "char" => Ok(Type::Char),
                     "bool" => Ok(Type::Bool),
                     "string" => Ok(Type::String),
                     "void" => Ok(Type::Void),
+                    "any" | "Any" => Ok(Type::Any),
                     "list" => Ok(Type::List),
                     "tuple" => Ok(Type::Tuple),
                     "set" => Ok(Type::Set),
@@ -181,6 +474,28 @@ impl<'a> Parser<'a> {
         }
     }
     
+    // `identity<int>(x)`: type arguments for a call. Only taken as such when
+    // the `>` is right before the call's `(`; otherwise nothing is consumed
+    // and `<` parses as a comparison.
+    fn type_args_before_call(&mut self) -> Option<Vec<Type>> {
+        if self.tokens.peek() != Some(&&Token::Less) {
+            return None;
+        }
+        let saved = (self.tokens.clone(), self.depth);
+        self.tokens.next();
+        let mut types = Vec::new();
+        while let Ok(typ) = self.parse_type() {
+            types.push(typ);
+            match self.tokens.next() {
+                Some(Token::Comma) => {}
+                Some(Token::Greater) if self.tokens.peek() == Some(&&Token::LParen) => return Some(types),
+                _ => break,
+            }
+        }
+        (self.tokens, self.depth) = saved;
+        None
+    }
+
     // Parse generic parameters definition: <T, U>
     fn parse_generic_params(&mut self) -> Result<Vec<String>, String> {
         let mut params = Vec::new();
@@ -206,7 +521,23 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self) -> Result<Expr, String> {
-        self.parse_equality()
+        self.nested(Self::parse_conditional)
+    }
+
+    // `a if cond else b` binds loosest; `a if x else b if y else c` groups
+    // to the right
+    fn parse_conditional(&mut self) -> Result<Expr, String> {
+        let then_expr = self.parse_equality()?;
+        if !matches!(self.tokens.peek(), Some(Token::If)) {
+            return Ok(then_expr);
+        }
+        self.tokens.next();
+        let cond = self.parse_equality()?;
+        if !matches!(self.tokens.next(), Some(Token::Else)) {
+            return Err("Expected 'else' in conditional expression".to_string());
+        }
+        let else_expr = self.parse_expression()?;
+        Ok(Expr::Conditional { cond: Box::new(cond), then_expr: Box::new(then_expr), else_expr: Box::new(else_expr) })
     }
 
     fn parse_equality(&mut self) -> Result<Expr, String> {
@@ -231,7 +562,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_comparison(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_term()?;
+        let mut left = self.parse_range()?;
 
         while let Some(&token) = self.tokens.peek() {
             let op = match token {
@@ -239,11 +570,12 @@ impl<'a> Parser<'a> {
                 Token::LessEqual => BinaryOp::Lte,
                 Token::Greater => BinaryOp::Gt,
                 Token::GreaterEqual => BinaryOp::Gte,
+                Token::In => BinaryOp::In,
                 _ => break,
             };
             self.tokens.next();
             while let Some(Token::Newline) = self.tokens.peek() { self.tokens.next(); }
-            let right = self.parse_term()?;
+            let right = self.parse_range()?;
             left = Expr::Binary {
                 left: Box::new(left),
                 op,
@@ -253,6 +585,20 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
+    // 1..10, 1..=10; ranges don't chain
+    fn parse_range(&mut self) -> Result<Expr, String> {
+        let start = self.parse_term()?;
+        let inclusive = match self.tokens.peek() {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEqual) => true,
+            _ => return Ok(start),
+        };
+        self.tokens.next();
+        while let Some(Token::Newline) = self.tokens.peek() { self.tokens.next(); }
+        let end = self.parse_term()?;
+        Ok(Expr::Range { start: Box::new(start), end: Box::new(end), inclusive })
+    }
+
     fn parse_term(&mut self) -> Result<Expr, String> {
         let mut left = self.parse_factor()?;
 
@@ -301,6 +647,10 @@ impl<'a> Parser<'a> {
 
     fn parse_primary(&mut self) -> Result<Expr, String> {
         let mut expr = self.parse_atom()?;
+        let mut generics = match expr {
+            Expr::Identifier(_) => self.type_args_before_call().unwrap_or_default(),
+            _ => Vec::new(),
+        };
 
         loop {
             if let Some(Token::LParen) = self.tokens.peek() {
@@ -311,7 +661,7 @@ impl<'a> Parser<'a> {
                 } else {
                     loop {
                         while let Some(Token::Newline) = self.tokens.peek() { self.tokens.next(); }
-                        let arg = self.parse_expression()?;
+                        let arg = self.parse_element()?;
                         args.push(arg);
                         // println!("Parsed arg: {:?}, Next token: {:?}", args.last(), self.tokens.peek());
                         match self.tokens.peek() {
@@ -336,14 +686,14 @@ impl<'a> Parser<'a> {
                 }
                 expr = Expr::Call {
                     function: Box::new(expr),
-                    generics: Vec::new(),
+                    generics: std::mem::take(&mut generics),
                     args,
                 };
             } else if let Some(Token::Dot) = self.tokens.peek() {
                 self.tokens.next(); // consume .
-                let name = match self.tokens.next() {
-                    Some(Token::Identifier(s)) => s.clone(),
-                    _ => return Err("Expected property name after '.'".to_string()),
+                let name = match self.tokens.next().and_then(property_name) {
+                    Some(name) => name,
+                    None => return Err("Expected property name after '.'".to_string()),
                 };
                 expr = Expr::Get {
                     object: Box::new(expr),
@@ -359,6 +709,8 @@ impl<'a> Parser<'a> {
                     object: Box::new(expr),
                     index: Box::new(index),
                 };
+            } else if self.tokens.next_if(|t| matches!(t, Token::Question)).is_some() {
+                expr = Expr::Propagate(Box::new(expr));
             } else {
                 break;
             }
@@ -368,6 +720,7 @@ impl<'a> Parser<'a> {
 
     fn parse_atom(&mut self) -> Result<Expr, String> {
         match self.tokens.peek() {
+            Some(Token::Invalid(reason)) => Err(reason.clone()),
             Some(Token::Integer(i)) => {
                 let val = *i;
                 self.tokens.next();
@@ -383,11 +736,27 @@ impl<'a> Parser<'a> {
                 self.tokens.next();
                 Ok(Expr::LiteralString(val))
             }
+            Some(Token::InterpolatedString(s)) => {
+                let parts = parse_interpolation(s)?;
+                self.tokens.next();
+                Ok(Expr::InterpolatedString(parts))
+            }
             Some(Token::Identifier(s)) => {
                 let name = s.clone();
                 self.tokens.next();
                 Ok(Expr::Identifier(name))
             }
+            // The `type(x)` builtin; the keyword only declares aliases at the
+            // start of a statement
+            Some(Token::Type) => {
+                self.tokens.next();
+                Ok(Expr::Identifier("type".to_string()))
+            }
+            Some(Token::Char(c)) => {
+                let val = *c;
+                self.tokens.next();
+                Ok(Expr::LiteralChar(val))
+            }
             Some(Token::Bool(b)) => {
                 let val = *b;
                 self.tokens.next();
@@ -591,7 +960,7 @@ impl<'a> Parser<'a> {
                     self.tokens.next();
                 } else {
                     loop {
-                        elements.push(self.parse_expression()?);
+                        elements.push(self.parse_element()?);
                         match self.tokens.peek() {
                             Some(Token::Comma) => { self.tokens.next(); }
                             Some(Token::RBracket) => {
@@ -659,10 +1028,15 @@ impl<'a> Parser<'a> {
     fn parse_for(&mut self) -> Result<Stmt, String> {
         self.tokens.next(); // consume for
         
-        let item_name = match self.tokens.next() {
-            Some(Token::Identifier(s)) => s.clone(),
+        // for k, v in ...  or  for (k, v) in ...
+        let parenthesized = self.tokens.next_if(|t| matches!(t, Token::LParen)).is_some();
+        let names = match self.tokens.next() {
+            Some(Token::Identifier(s)) => self.parse_more_names(s.clone())?,
             _ => return Err("Expected identifier after 'for'".to_string()),
         };
+        if parenthesized && self.tokens.next() != Some(&Token::RParen) {
+            return Err("Expected ')' after the loop variables".to_string());
+        }
 
         if let Some(Token::In) = self.tokens.next() {} else {
             return Err("Expected 'in' after loop variable".to_string());
@@ -680,7 +1054,148 @@ impl<'a> Parser<'a> {
 
         let body = self.parse_block()?;
 
-        Ok(Stmt::For { item_name, iterable, body })
+        Ok(Stmt::For { names, iterable, body })
+    }
+
+    // match subject:
+    //     case pattern [if guard]:
+    //         ...
+    fn parse_match(&mut self) -> Result<Stmt, String> {
+        self.tokens.next(); // consume match
+        let subject = self.parse_expression()?;
+        if let Some(Token::Colon) = self.tokens.next() {} else {
+            return Err("Expected ':' after match subject".to_string());
+        }
+        let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+        if let Some(Token::Indent) = self.tokens.next() {} else {
+            return Err("Expected indented 'case' blocks after 'match'".to_string());
+        }
+
+        let mut cases = Vec::new();
+        while let Some(token) = self.tokens.peek() {
+            match token {
+                Token::Dedent => {
+                    self.tokens.next();
+                    break;
+                }
+                Token::EOF => break,
+                Token::Newline => { self.tokens.next(); }
+                Token::Case => {
+                    self.tokens.next();
+                    let pattern = self.parse_pattern()?;
+                    let guard = match self.tokens.next_if(|t| matches!(t, Token::If)) {
+                        Some(_) => Some(self.parse_expression()?),
+                        None => None,
+                    };
+                    if let Some(Token::Colon) = self.tokens.next() {} else {
+                        return Err("Expected ':' after case pattern".to_string());
+                    }
+                    let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+                    let body = self.parse_block()?;
+                    cases.push(MatchCase { pattern, guard, body });
+                }
+                _ => return Err("Expected 'case' in match".to_string()),
+            }
+        }
+        if cases.is_empty() {
+            return Err("Expected at least one 'case' in match".to_string());
+        }
+        Ok(Stmt::Match { subject, cases })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, String> {
+        self.nested(Self::parse_or_pattern)
+    }
+
+    // One pattern, or alternatives separated by '|'
+    fn parse_or_pattern(&mut self) -> Result<Pattern, String> {
+        let first = self.parse_single_pattern()?;
+        if !matches!(self.tokens.peek(), Some(Token::Pipe)) {
+            return Ok(first);
+        }
+        let mut alternatives = vec![first];
+        while self.tokens.next_if(|t| matches!(t, Token::Pipe)).is_some() {
+            alternatives.push(self.parse_single_pattern()?);
+        }
+        // A name bound by only some alternatives would be unset after others
+        let mut expected = alternatives[0].names();
+        expected.sort();
+        for alternative in &alternatives[1..] {
+            let mut names = alternative.names();
+            names.sort();
+            if names != expected {
+                return Err("Every alternative of a '|' pattern must bind the same names".to_string());
+            }
+        }
+        Ok(Pattern::Or(alternatives))
+    }
+
+    fn parse_single_pattern(&mut self) -> Result<Pattern, String> {
+        match self.tokens.next() {
+            Some(Token::Identifier(name)) if name == DISCARD => Ok(Pattern::Wildcard),
+            Some(Token::Identifier(name)) if matches!(self.tokens.peek(), Some(Token::Dot)) => {
+                self.tokens.next();
+                let variant = match self.tokens.next() {
+                    Some(Token::Identifier(s)) => s.clone(),
+                    _ => return Err("Expected variant name after '.' in pattern".to_string()),
+                };
+                let fields = if self.tokens.next_if(|t| matches!(t, Token::LParen)).is_some() {
+                    self.parse_patterns()?.0
+                } else {
+                    Vec::new()
+                };
+                Ok(Pattern::Variant { enum_name: name.clone(), variant, fields })
+            }
+            Some(Token::Identifier(name)) => {
+                if let Some(Token::LParen) = self.tokens.peek() {
+                    self.tokens.next();
+                    let (fields, _) = self.parse_patterns()?;
+                    Ok(Pattern::Record { name: name.clone(), fields })
+                } else {
+                    Ok(Pattern::Binding(name.clone()))
+                }
+            }
+            Some(Token::LParen) => {
+                // `(p)` only groups; `(p,)` is a one-element tuple
+                let (mut elements, trailing_comma) = self.parse_patterns()?;
+                if elements.len() == 1 && !trailing_comma {
+                    Ok(elements.remove(0))
+                } else {
+                    Ok(Pattern::Tuple(elements))
+                }
+            }
+            Some(Token::Integer(i)) => Ok(Pattern::Literal(Expr::LiteralInt(*i))),
+            Some(Token::Float(f)) => Ok(Pattern::Literal(Expr::LiteralFloat(*f))),
+            Some(Token::StringLiteral(s)) => Ok(Pattern::Literal(Expr::LiteralString(s.clone()))),
+            Some(Token::Char(c)) => Ok(Pattern::Literal(Expr::LiteralChar(*c))),
+            Some(Token::Bool(b)) => Ok(Pattern::Literal(Expr::LiteralBool(*b))),
+            Some(Token::Minus) => match self.tokens.next() {
+                Some(Token::Integer(i)) => Ok(Pattern::Literal(Expr::LiteralInt(-i))),
+                Some(Token::Float(f)) => Ok(Pattern::Literal(Expr::LiteralFloat(-f))),
+                _ => Err("Expected a number after '-' in pattern".to_string()),
+            },
+            _ => Err("Expected a pattern after 'case'".to_string()),
+        }
+    }
+
+    // The patterns up to a closing ')', the '(' already consumed, and
+    // whether a comma came last
+    fn parse_patterns(&mut self) -> Result<(Vec<Pattern>, bool), String> {
+        let mut patterns = Vec::new();
+        let mut trailing_comma = false;
+        loop {
+            if let Some(Token::RParen) = self.tokens.peek() {
+                self.tokens.next();
+                return Ok((patterns, trailing_comma));
+            }
+            patterns.push(self.parse_pattern()?);
+            trailing_comma = false;
+            match self.tokens.next() {
+                Some(Token::Comma) => trailing_comma = true,
+                Some(Token::RParen) => return Ok((patterns, trailing_comma)),
+                _ => return Err("Expected ',' or ')' in pattern".to_string()),
+            }
+        }
     }
 
     fn parse_while(&mut self) -> Result<Stmt, String> {
@@ -715,6 +1230,8 @@ impl<'a> Parser<'a> {
             self.tokens.next();
         } else {
             loop {
+                // *rest: int
+                let variadic = self.tokens.next_if(|t| matches!(t, Token::Star)).is_some();
                 let param_name = match self.tokens.next() {
                     Some(Token::Identifier(s)) => s.clone(),
                     _ => return Err("Expected parameter name".to_string()),
@@ -732,14 +1249,18 @@ impl<'a> Parser<'a> {
                         self.tokens.next();
                         self.parse_type()?
                     } else {
-                         Type::UserDefined("Any".to_string(), Vec::new())
+                         Type::ImplicitAny
                     }
                 };
+                let param_type = if variadic { Type::Variadic(Box::new(param_type)) } else { param_type };
                 params.push((param_name, param_type));
 
                 match self.tokens.peek() {
-                    Some(Token::Comma) => { self.tokens.next(); }
                     Some(Token::RParen) => { self.tokens.next(); break; }
+                    Some(Token::Comma) if variadic => {
+                        return Err(format!("'*{}' must be the last parameter", params[params.len() - 1].0));
+                    }
+                    Some(Token::Comma) => { self.tokens.next(); }
                     _ => return Err("Expected ',' or ')'".to_string()),
                 }
             }
@@ -765,7 +1286,8 @@ impl<'a> Parser<'a> {
         let expr = if let Some(Token::Newline) | Some(Token::EOF) | Some(Token::Dedent) = self.tokens.peek() {
             None
         } else {
-            Some(self.parse_expression()?)
+            let value = self.parse_expression()?;
+            Some(self.parse_trailing_block(value)?)
         };
 
         // Consume optional newline
@@ -777,6 +1299,10 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.parse_body(true)
+    }
+
+    fn parse_body(&mut self, probed: bool) -> Result<Vec<Stmt>, String> {
         if let Some(Token::Indent) = self.tokens.next() {} else {
             return Err("Expected indentation".to_string());
         }
@@ -791,6 +1317,9 @@ impl<'a> Parser<'a> {
                 Token::EOF => break,
                 Token::Newline => { self.tokens.next(); continue; }
                 _ => {
+                    if probed {
+                        self.push_probe(&mut stmts);
+                    }
                     stmts.push(self.parse_statement()?);
                 }
             }
@@ -823,6 +1352,14 @@ impl<'a> Parser<'a> {
                     return Err("Expected identifier in import path".to_string());
                 }
 
+                // import rust "crate" -> rust.crate, import plugin "lib.so" -> plugin.lib.so
+                if (path == "rust" || path == "plugin") && let Some(Token::StringLiteral(s)) = self.tokens.peek() {
+                    path.push('.');
+                    path.push_str(s);
+                    self.tokens.next();
+                    break;
+                }
+
                 if let Some(Token::Dot) = self.tokens.peek() {
                     self.tokens.next();
                 } else {
@@ -880,6 +1417,8 @@ impl<'a> Parser<'a> {
             }
         }
 
+        let derives = self.parse_derives()?;
+
         let mut methods = Vec::new();
         if let Some(Token::Colon) = self.tokens.peek() {
             self.tokens.next(); // consume ':'
@@ -888,7 +1427,7 @@ impl<'a> Parser<'a> {
             } else {
                  return Err("Expected newline after ':'".to_string());
             }
-             methods = self.parse_block()?;
+             methods = self.parse_body(false)?;
         } else {
              // Optional newline if no body
              if let Some(Token::Newline) = self.tokens.peek() {
@@ -896,7 +1435,113 @@ impl<'a> Parser<'a> {
              }
         }
 
-        Ok(Stmt::RecordDef { name, generics, fields, methods })
+        Ok(Stmt::RecordDef { name, generics, fields, derives, methods })
+    }
+
+    // `enum Name: A | B(x)` on one line, or one variant per line in an
+    // indented block. Fields may be annotated like function parameters.
+    fn parse_enum_decl(&mut self) -> Result<Stmt, String> {
+        self.tokens.next(); // consume enum
+        let name = match self.tokens.next() {
+            Some(Token::Identifier(s)) => s.clone(),
+            _ => return Err("Expected enum name".to_string()),
+        };
+        if let Some(Token::Colon) = self.tokens.next() {} else {
+            return Err("Expected ':' after enum name".to_string());
+        }
+
+        let mut variants = Vec::new();
+        if self.tokens.next_if(|t| matches!(t, Token::Newline)).is_some() {
+            if let Some(Token::Indent) = self.tokens.next() {} else {
+                return Err("Expected indented variants after 'enum'".to_string());
+            }
+            while let Some(token) = self.tokens.peek() {
+                match token {
+                    Token::Dedent => {
+                        self.tokens.next();
+                        break;
+                    }
+                    Token::EOF => break,
+                    Token::Newline => { self.tokens.next(); }
+                    _ => variants.push(self.parse_enum_variant()?),
+                }
+            }
+        } else {
+            loop {
+                variants.push(self.parse_enum_variant()?);
+                if self.tokens.next_if(|t| matches!(t, Token::Pipe)).is_none() {
+                    break;
+                }
+            }
+            let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+        }
+
+        if variants.is_empty() {
+            return Err("Expected at least one variant in enum".to_string());
+        }
+        for (i, variant) in variants.iter().enumerate() {
+            if variants[..i].iter().any(|v| v.name == variant.name) {
+                return Err(format!("Variant '{}' is declared more than once in enum '{}'", variant.name, name));
+            }
+        }
+        Ok(Stmt::EnumDef { name, variants })
+    }
+
+    // `Name` or `Name(field, field: type)`
+    fn parse_enum_variant(&mut self) -> Result<EnumVariant, String> {
+        let name = match self.tokens.next() {
+            Some(Token::Identifier(s)) => s.clone(),
+            _ => return Err("Expected variant name".to_string()),
+        };
+        let mut fields = Vec::new();
+        if self.tokens.next_if(|t| matches!(t, Token::LParen)).is_some() {
+            loop {
+                if self.tokens.next_if(|t| matches!(t, Token::RParen)).is_some() {
+                    break;
+                }
+                let field_name = match self.tokens.next() {
+                    Some(Token::Identifier(s)) => s.clone(),
+                    _ => return Err("Expected field name".to_string()),
+                };
+                let field_type = if self.tokens.next_if(|t| matches!(t, Token::Colon)).is_some() {
+                    self.parse_type()?
+                } else {
+                    Type::ImplicitAny
+                };
+                fields.push((field_name, field_type));
+                match self.tokens.next() {
+                    Some(Token::Comma) => {}
+                    Some(Token::RParen) => break,
+                    _ => return Err("Expected ',' or ')'".to_string()),
+                }
+            }
+        }
+        Ok(EnumVariant { name, fields })
+    }
+
+    // `derives (eq, ord, hash, show)` after a record's fields
+    fn parse_derives(&mut self) -> Result<Derives, String> {
+        let mut derives = Derives::default();
+        if !matches!(self.tokens.peek(), Some(Token::Identifier(s)) if s == "derives") {
+            return Ok(derives);
+        }
+        self.tokens.next();
+        if self.tokens.next() != Some(&Token::LParen) {
+            return Err("Expected '(' after 'derives'".to_string());
+        }
+        loop {
+            match self.tokens.next() {
+                Some(Token::Identifier(name)) => derives.add(name)?,
+                _ => return Err("Expected a name to derive".to_string()),
+            }
+            match self.tokens.next() {
+                Some(Token::Comma) => {}
+                Some(Token::RParen) => break,
+                _ => return Err("Expected ',' or ')'".to_string()),
+            }
+        }
+        derives.check()?;
+        Ok(derives)
     }
 
     fn parse_interface_decl(&mut self) -> Result<Stmt, String> {
@@ -1118,6 +1763,32 @@ impl<'a> Parser<'a> {
         })
     }
 
+    // with value as name:
+    //     ...
+    fn parse_with(&mut self) -> Result<Stmt, String> {
+        self.tokens.next(); // consume with
+        let value = self.parse_expression()?;
+
+        let mut name = None;
+        if let Some(Token::Identifier(s)) = self.tokens.peek()
+            && s == "as"
+        {
+            self.tokens.next();
+            match self.tokens.next() {
+                Some(Token::Identifier(s)) => name = Some(s.clone()),
+                _ => return Err("Expected a name after 'as'".to_string()),
+            }
+        }
+
+        if let Some(Token::Colon) = self.tokens.next() {} else {
+            return Err("Expected ':' after with".to_string());
+        }
+        let _ = self.tokens.next_if(|t| matches!(t, Token::Newline));
+        let body = self.parse_block()?;
+
+        Ok(Stmt::With { value, name, body })
+    }
+
     fn parse_raise(&mut self) -> Result<Stmt, String> {
         self.tokens.next(); // consume raise
         let error = self.parse_expression()?;
@@ -1166,9 +1837,11 @@ impl<'a> Parser<'a> {
             return Err("Expected 'def' after extern".to_string());
         }
 
-        let name = match self.tokens.next() {
-            Some(Token::Identifier(s)) => s.clone(),
-            _ => return Err("Expected external function name".to_string()),
+        // Keywords allowed after '.' can name one too, for the declarations
+        // of native module functions like `glob.match`
+        let name = match self.tokens.next().and_then(property_name) {
+            Some(name) => name,
+            None => return Err("Expected external function name".to_string()),
         };
 
         let generics = self.parse_generic_params()?;
@@ -0,0 +1,172 @@
+// Incremental, error-recovering parsing for editor tooling.
+//
+// Source is split into top-level chunks: a chunk starts at every line that
+// begins in column 0 (outside of any open bracket) and runs until the next one.
+// `else`, `except` and `finally` lines stay attached to the chunk they
+// continue, lines inside a string spanning lines are part of the chunk the
+// string is in, and an unclosed bracket is cut off at the next line that can
+// only start a statement. Chunks are parsed independently, so an error in one
+// statement doesn't hide the rest of the file, and an `IncrementalParser` only
+// reparses chunks whose text changed since the previous call.
+
+use crate::ast::{Program, Stmt};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    // 1-based line of the start of the chunk that failed to parse.
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    start_line: usize,
+    text: String,
+}
+
+fn first_word(line: &str) -> &str {
+    let end = line.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(line.len());
+    &line[..end]
+}
+
+fn is_continuation(line: &str) -> bool {
+    matches!(first_word(line), "else" | "except" | "finally")
+}
+
+// Lines starting with these can't continue an expression, so they begin a new
+// chunk even when an earlier line left a bracket open.
+fn starts_statement(line: &str) -> bool {
+    matches!(
+        first_word(line),
+        "let" | "mut" | "def" | "class" | "record" | "enum" | "interface" | "type" | "import" | "if" | "while"
+            | "for" | "match" | "try" | "with" | "raise" | "go" | "extern" | "return"
+    )
+}
+
+// The kind of string a line ends inside of, since strings may span lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Open {
+    Nothing,
+    Quoted,
+    TripleQuoted,
+}
+
+// Net change in bracket depth for one line, ignoring strings and comments.
+// `open` is the string the line starts inside of, and becomes the one it ends
+// inside of.
+fn bracket_delta(line: &str, open: &mut Open) -> i32 {
+    let chars: Vec<char> = line.chars().collect();
+    let mut delta = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        // A run of quotes: three or more open or close a """ string, whose
+        // closing three are the last of the run, as in the lexer
+        let quotes = chars[i..].iter().take_while(|&&q| q == '"').count();
+        i += quotes.max(1);
+        match *open {
+            Open::Quoted if c == '"' => *open = if quotes % 2 == 1 { Open::Nothing } else { Open::Quoted },
+            Open::TripleQuoted if quotes >= 3 => *open = Open::Nothing,
+            Open::Quoted | Open::TripleQuoted => {}
+            Open::Nothing => match c {
+                '"' if quotes >= 3 => {
+                    // Quotes after the opening three are text
+                    i -= quotes - 3;
+                    *open = Open::TripleQuoted;
+                }
+                '"' if quotes % 2 == 1 => *open = Open::Quoted,
+                '"' => {}
+                '\'' => i += chars[i..].iter().position(|&q| q == '\'').map_or(chars.len(), |end| end + 1),
+                '#' => break,
+                '/' if chars.get(i) == Some(&'/') => break,
+                '(' | '[' | '{' => delta += 1,
+                ')' | ']' | '}' => delta -= 1,
+                _ => {}
+            },
+        }
+    }
+    delta
+}
+
+fn split_chunks(source: &str) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut depth = 0;
+    let mut open = Open::Nothing;
+
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let blank = trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//");
+        // A line inside a string is text, wherever it starts
+        let at_column_zero = !blank && line.len() == trimmed.len() && open == Open::Nothing;
+        let top_level = at_column_zero && !is_continuation(line) && (depth <= 0 || starts_statement(line));
+
+        if top_level || chunks.is_empty() {
+            depth = 0;
+            chunks.push(Chunk { start_line: i + 1, text: String::new() });
+        }
+        let chunk = chunks.last_mut().unwrap();
+        chunk.text.push_str(line);
+        chunk.text.push('\n');
+        depth += bracket_delta(line, &mut open);
+    }
+    chunks
+}
+
+fn parse_chunk(text: &str) -> Result<Vec<Stmt>, String> {
+    let tokens = Lexer::new(text).tokenize();
+    Parser::new(&tokens).parse().map(|p| p.statements)
+}
+
+// Parses a single statement (e.g. the line or block under the cursor).
+pub fn parse_statement(source: &str) -> Result<Stmt, String> {
+    let mut stmts = parse_chunk(source)?;
+    match stmts.len() {
+        1 => Ok(stmts.remove(0)),
+        0 => Err("Expected a statement".to_string()),
+        n => Err(format!("Expected a single statement, found {}", n)),
+    }
+}
+
+// Parses as much of the source as possible, skipping top-level statements
+// that fail to parse and reporting them as errors.
+pub fn parse_recovering(source: &str) -> (Program, Vec<ParseError>) {
+    IncrementalParser::new().parse(source)
+}
+
+#[derive(Debug, Default)]
+pub struct IncrementalParser {
+    cache: HashMap<String, Result<Vec<Stmt>, String>>,
+}
+
+impl IncrementalParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, source: &str) -> (Program, Vec<ParseError>) {
+        let chunks = split_chunks(source);
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+        let mut cache = HashMap::with_capacity(chunks.len());
+
+        for chunk in chunks {
+            let result = match self.cache.remove(&chunk.text) {
+                Some(cached) => cached,
+                None => parse_chunk(&chunk.text),
+            };
+            match &result {
+                Ok(stmts) => statements.extend(stmts.iter().cloned()),
+                Err(message) => errors.push(ParseError { line: chunk.start_line, message: message.clone() }),
+            }
+            cache.insert(chunk.text, result);
+        }
+
+        // Only keep chunks present in the latest source, so the cache tracks
+        // the current document rather than growing with every edit.
+        self.cache = cache;
+        (Program { statements }, errors)
+    }
+}
@@ -0,0 +1,233 @@
+// Access control for the stdlib's filesystem, network and subprocess calls.
+//
+// Nothing is checked by default. Once an interpreter is given a set of
+// permissions (the CLI does this for `pyro run --sandbox` and the `--allow-*`
+// flags), anything they don't grant fails with a catchable error of kind
+// "PermissionDenied". The goroutines and jobs it starts share them; other
+// interpreters in the process are unaffected.
+//
+//     interpreter.set_permissions(Some(Permissions::none().allow_fs("./data").allow_net("api.example.com")));
+
+use crate::interpreter::{error_with_kind, Interpreter, RuntimeError};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Grant<T> {
+    Nothing,
+    Everything,
+    Only(Vec<T>),
+}
+
+impl<T> Grant<T> {
+    fn add(&mut self, item: T) {
+        match self {
+            Grant::Nothing => *self = Grant::Only(vec![item]),
+            Grant::Only(items) => items.push(item),
+            Grant::Everything => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Permissions {
+    fs: Grant<PathBuf>,
+    net: Grant<String>,
+    // The executables granted commands resolved to, with symlinks resolved
+    run: Grant<PathBuf>,
+}
+
+impl Permissions {
+    // Grants nothing
+    pub fn none() -> Self {
+        Permissions { fs: Grant::Nothing, net: Grant::Nothing, run: Grant::Nothing }
+    }
+
+    // Reading and writing anywhere under `path`
+    pub fn allow_fs(mut self, path: impl AsRef<Path>) -> Self {
+        self.fs.add(resolve(path.as_ref()));
+        self
+    }
+
+    pub fn allow_all_fs(mut self) -> Self {
+        self.fs = Grant::Everything;
+        self
+    }
+
+    // Connections to `host`, or only to one port of it with "host:port"
+    pub fn allow_net(mut self, host: &str) -> Self {
+        self.net.add(host.to_string());
+        self
+    }
+
+    pub fn allow_all_net(mut self) -> Self {
+        self.net = Grant::Everything;
+        self
+    }
+
+    // Running `command` with process.exec. A bare name is looked up on
+    // PATH now, so only the executable it finds is granted, wherever PATH
+    // or the working directory point later. A command that isn't found
+    // grants nothing.
+    pub fn allow_run(mut self, command: &str) -> Self {
+        if let Some(executable) = find_executable(command).and_then(|path| std::fs::canonicalize(path).ok()) {
+            self.run.add(executable);
+        }
+        self
+    }
+
+    pub fn allow_all_run(mut self) -> Self {
+        self.run = Grant::Everything;
+        self
+    }
+
+    fn allows_fs(&self, path: &Path) -> bool {
+        match &self.fs {
+            Grant::Nothing => false,
+            Grant::Everything => true,
+            Grant::Only(roots) => {
+                let path = resolve(path);
+                roots.iter().any(|root| path.starts_with(root))
+            }
+        }
+    }
+
+    fn allows_net(&self, host: &str, port: u16) -> bool {
+        match &self.net {
+            Grant::Nothing => false,
+            Grant::Everything => true,
+            Grant::Only(hosts) => hosts.iter().any(|h| h == host || *h == format!("{}:{}", host, port)),
+        }
+    }
+
+    fn allows_run(&self, executable: &Path) -> bool {
+        match &self.run {
+            Grant::Nothing => false,
+            Grant::Everything => true,
+            Grant::Only(executables) => {
+                std::fs::canonicalize(executable).is_ok_and(|path| executables.contains(&path))
+            }
+        }
+    }
+}
+
+fn denied(message: String) -> RuntimeError {
+    error_with_kind("PermissionDenied", message)
+}
+
+pub fn check_fs(interpreter: &Interpreter, path: &str) -> Result<(), RuntimeError> {
+    match interpreter.permissions() {
+        Some(p) if !p.allows_fs(Path::new(path)) => {
+            Err(denied(format!("Access to '{}' was not granted (use --allow-fs)", path)))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn check_net(interpreter: &Interpreter, host: &str, port: u16) -> Result<(), RuntimeError> {
+    match interpreter.permissions() {
+        Some(p) if !p.allows_net(host, port) => {
+            Err(denied(format!("Network access to '{}:{}' was not granted (use --allow-net)", host, port)))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The program to start for `command`: the command itself when nothing is
+// restricted, or else the executable it was checked as, so what runs is what
+// was granted
+pub fn check_run(interpreter: &Interpreter, command: &str) -> Result<PathBuf, RuntimeError> {
+    let Some(p) = interpreter.permissions() else {
+        return Ok(PathBuf::from(command));
+    };
+    match find_executable(command) {
+        Some(executable) if p.allows_run(&executable) => Ok(executable),
+        _ => Err(denied(format!("Running '{}' was not granted (use --allow-run)", command))),
+    }
+}
+
+// Environment variables such as PATH decide what commands run, and the
+// environment is shared by every interpreter in the process, so it can't be
+// changed from an interpreter with permissions
+pub fn check_env(interpreter: &Interpreter, key: &str) -> Result<(), RuntimeError> {
+    match interpreter.permissions() {
+        Some(_) => Err(denied(format!("Changing environment variable '{}' is not allowed in a sandbox", key))),
+        None => Ok(()),
+    }
+}
+
+// Plugins are native code no permission can restrict, so none load into
+// an interpreter with permissions
+pub fn check_plugin(interpreter: &Interpreter, path: &str) -> Result<(), RuntimeError> {
+    match interpreter.permissions() {
+        Some(_) => Err(denied(format!("Loading plugin '{}' is not allowed in a sandbox", path))),
+        None => Ok(()),
+    }
+}
+
+// Python code is native too, so std.python refuses to call into it from
+// an interpreter with permissions
+pub fn check_python(interpreter: &Interpreter, module: &str, func: &str) -> Result<(), RuntimeError> {
+    match interpreter.permissions() {
+        Some(_) => Err(denied(format!("Calling Python ('{}.{}') is not allowed in a sandbox", module, func))),
+        None => Ok(()),
+    }
+}
+
+// Where starting `command` would find it, as an absolute path: the command
+// itself if it has a directory part, or else the first executable of that
+// name in the directories on PATH
+fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    let found = if path.components().count() > 1 {
+        path.is_file().then(|| path.to_path_buf())?
+    } else {
+        let dirs = std::env::var_os("PATH")?;
+        std::env::split_paths(&dirs).map(|dir| dir.join(command)).find(|path| is_executable(path))?
+    };
+    Some(std::env::current_dir().ok()?.join(found))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+// Absolute, with "." and ".." removed and symlinks in the existing part of the
+// path resolved, so "data/../secrets" can't pass as being under "data"
+fn resolve(path: &Path) -> PathBuf {
+    let absolute = match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    };
+    let mut normal = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    // Canonicalize the longest prefix that exists and keep the rest as is
+    let mut existing = normal.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = std::fs::canonicalize(existing) {
+            return rest.iter().rev().fold(canonical, |p, part| p.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_owned());
+                existing = parent;
+            }
+            _ => return normal,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+// Native extension modules loaded from dynamic libraries at run time, with
+// `import plugin "libgeo.so"` or `Interpreter::load_plugin`, so an extension
+// doesn't need a runner rebuilt around it the way `import rust` does.
+//
+// A plugin is a `cdylib` crate depending on pyro-core that declares its
+// modules with `declare_plugin!`:
+//
+//     fn modules() -> Vec<ModuleDescriptor> {
+//         vec![ModuleDescriptor::new("acme.geo", geo::module())]
+//     }
+//     pyro_core::declare_plugin!(modules);
+//
+// That exports the entry point `pyro_plugin`, an `extern "C"` function
+// returning a PluginDescriptor. Modules are Rust values, so a plugin must be
+// built against the same pyro-core version, with the same compiler, as the
+// program loading it. The version is checked; the compiler can't be.
+// A loaded library stays loaded until the process exits, as functions from
+// it may still be referenced anywhere.
+
+use crate::interpreter::Value;
+#[cfg(feature = "plugins")]
+use libloading::Library;
+use std::ffi::{c_char, CStr};
+#[cfg(feature = "plugins")]
+use std::sync::Mutex;
+
+// Bumped whenever PluginDescriptor changes shape
+pub const ABI_VERSION: u32 = 1;
+
+// The symbol `declare_plugin!` exports
+pub const ENTRY_POINT: &str = "pyro_plugin";
+
+// The import path prefix of `import plugin "<path>"`, which parses to an
+// import of "plugin.<path>", as `import rust "<crate>"` does to "rust.<crate>"
+pub const IMPORT_PREFIX: &str = "plugin.";
+
+// The pyro-core version a plugin is built against, NUL-terminated for C
+#[doc(hidden)]
+pub const CORE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+// A module a plugin provides, by the path it's imported with
+pub struct ModuleDescriptor {
+    pub name: String,
+    pub module: Value,
+}
+
+impl ModuleDescriptor {
+    pub fn new(name: &str, module: Value) -> Self {
+        ModuleDescriptor { name: name.to_string(), module }
+    }
+}
+
+// What a plugin's entry point returns. The ABI version comes first so it can
+// be read whatever the rest of the layout.
+#[repr(C)]
+pub struct PluginDescriptor {
+    pub abi_version: u32,
+    pub core_version: *const c_char,
+    pub modules: fn() -> Vec<ModuleDescriptor>,
+}
+
+impl PluginDescriptor {
+    pub fn new(modules: fn() -> Vec<ModuleDescriptor>) -> Self {
+        PluginDescriptor { abi_version: ABI_VERSION, core_version: CORE_VERSION.as_ptr().cast(), modules }
+    }
+
+    // The plugin's modules, if it was built for this pyro-core
+    pub fn verify(&self) -> Result<Vec<ModuleDescriptor>, String> {
+        if self.abi_version != ABI_VERSION {
+            return Err(format!(
+                "it uses plugin ABI version {}, but this pyro supports version {}",
+                self.abi_version, ABI_VERSION
+            ));
+        }
+        // SAFETY: a plugin of this ABI version sets core_version from
+        // CORE_VERSION, which is NUL-terminated and static
+        let version = unsafe { CStr::from_ptr(self.core_version) }.to_string_lossy();
+        let expected = env!("CARGO_PKG_VERSION");
+        if version != expected {
+            return Err(format!("it was built for pyro-core {}, but this is pyro-core {}", version, expected));
+        }
+        Ok((self.modules)())
+    }
+}
+
+// Exports `$modules`, a `fn() -> Vec<ModuleDescriptor>`, as a plugin's
+// entry point
+#[macro_export]
+macro_rules! declare_plugin {
+    ($modules:path) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn pyro_plugin() -> $crate::plugin::PluginDescriptor {
+            $crate::plugin::PluginDescriptor::new($modules)
+        }
+    };
+}
+
+#[cfg(feature = "plugins")]
+static LOADED: Mutex<Vec<Library>> = Mutex::new(Vec::new());
+
+// Opens the library at `path` and returns the modules it provides. Errors
+// are messages to follow "Could not load plugin '<path>': ".
+#[cfg(feature = "plugins")]
+pub fn load(path: &str) -> Result<Vec<ModuleDescriptor>, String> {
+    // SAFETY: loading a library runs its initialisers; a plugin is trusted
+    // native code, like a Rust crate the runner is built with
+    let library = unsafe { Library::new(path) }.map_err(|e| e.to_string())?;
+    // SAFETY: the signature is the one `declare_plugin!` exports
+    let descriptor = unsafe {
+        let entry = library
+            .get::<extern "C" fn() -> PluginDescriptor>(ENTRY_POINT.as_bytes())
+            .map_err(|_| format!("it has no '{}' entry point; is it a pyro plugin?", ENTRY_POINT))?;
+        entry()
+    };
+    let modules = descriptor.verify()?;
+    LOADED.lock().unwrap_or_else(|e| e.into_inner()).push(library);
+    Ok(modules)
+}
+
+#[cfg(not(feature = "plugins"))]
+pub fn load(_path: &str) -> Result<Vec<ModuleDescriptor>, String> {
+    Err("loading plugins needs pyro built with the 'plugins' feature".to_string())
+}
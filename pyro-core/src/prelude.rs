@@ -0,0 +1,63 @@
+use crate::interpreter::Interpreter;
+use crate::stdlib::{EMBEDDED_MODULES, NATIVE_MODULES};
+
+// The std modules an interpreter starts with. The CLI uses the full prelude;
+// embedders can start from a minimal one and opt modules in, e.g.
+//
+//     let prelude = Prelude::minimal().with_module("std.math")?.with_module("std.json")?;
+//     let mut interpreter = Interpreter::with_prelude(&prelude);
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prelude {
+    modules: Vec<&'static str>,
+}
+
+fn known_modules() -> impl Iterator<Item = &'static str> {
+    let native = NATIVE_MODULES.iter().map(|(name, _)| *name);
+    native.chain(EMBEDDED_MODULES.iter().map(|(name, _)| *name))
+}
+
+impl Prelude {
+    // Every std module, native and embedded
+    pub fn full() -> Self {
+        Prelude { modules: known_modules().collect() }
+    }
+
+    // No std modules. Builtins and the Error class are still available.
+    pub fn minimal() -> Self {
+        Prelude { modules: Vec::new() }
+    }
+
+    // Adds a std module by its import path, e.g. "std.math"
+    pub fn with_module(mut self, name: &str) -> Result<Self, String> {
+        let name = known_modules()
+            .find(|n| *n == name)
+            .ok_or_else(|| format!("Unknown std module '{}'", name))?;
+        if !self.modules.contains(&name) {
+            self.modules.push(name);
+        }
+        Ok(self)
+    }
+
+    pub fn modules(&self) -> &[&'static str] {
+        &self.modules
+    }
+
+    pub fn install(&self, interpreter: &mut Interpreter) {
+        for (name, factory) in NATIVE_MODULES {
+            if self.modules.contains(name) {
+                interpreter.register_native_module(name, factory());
+            }
+        }
+        for (name, source) in EMBEDDED_MODULES {
+            if self.modules.contains(name) {
+                interpreter.register_embedded_module(name, source);
+            }
+        }
+    }
+}
+
+impl Default for Prelude {
+    fn default() -> Self {
+        Prelude::full()
+    }
+}
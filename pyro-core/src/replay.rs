@@ -0,0 +1,122 @@
+// Deterministic record and replay of a script's nondeterministic inputs.
+//
+// Stdlib functions whose results depend on the outside world (the clock,
+// random numbers, the environment, subprocesses) are registered through
+// `call`. While recording, each result is logged in call order; while
+// replaying, the logged results are returned instead of calling out, so a
+// flaky run can be reproduced exactly. Calls made from concurrent `go`
+// routines are logged in whatever order they happen to run.
+//
+// The log is JSON lines, one call per line:
+//
+//     {"call":"time.now","value":1718000000.5}
+//     {"call":"process.exec","error":"No such file or directory","kind":"NotFound"}
+
+use crate::interpreter::{error_with_kind, RuntimeError, Value};
+use crate::stdlib::json::{json_to_value, value_to_json};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    call: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+}
+
+enum Mode {
+    Off,
+    Record(Vec<Entry>),
+    Replay(VecDeque<Entry>),
+}
+
+static MODE: Mutex<Mode> = Mutex::new(Mode::Off);
+
+pub fn start_recording() {
+    *MODE.lock().unwrap() = Mode::Record(Vec::new());
+}
+
+// Stops recording and returns the log
+pub fn finish_recording() -> String {
+    let mode = std::mem::replace(&mut *MODE.lock().unwrap(), Mode::Off);
+    let Mode::Record(entries) = mode else {
+        return String::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| serde_json::to_string(entry).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+pub fn start_replay(log: &str) -> Result<(), String> {
+    let entries = log
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("Invalid replay log at line {}: {}", i + 1, e)))
+        .collect::<Result<VecDeque<Entry>, String>>()?;
+    *MODE.lock().unwrap() = Mode::Replay(entries);
+    Ok(())
+}
+
+pub fn stop() {
+    *MODE.lock().unwrap() = Mode::Off;
+}
+
+// Runs a nondeterministic native function, or answers from the replay log
+pub fn call(name: &str, args: Vec<Value>, f: impl FnOnce(Vec<Value>) -> Result<Value, RuntimeError>) -> Result<Value, RuntimeError> {
+    let replaying = match &mut *MODE.lock().unwrap() {
+        Mode::Off => None,
+        Mode::Record(_) => Some(false),
+        Mode::Replay(entries) => return replay(name, entries.pop_front()),
+    };
+
+    // The lock is not held here, as process.exec can take a while
+    let result = f(args);
+    if replaying == Some(false) && let Mode::Record(entries) = &mut *MODE.lock().unwrap() {
+        entries.push(entry(name, &result));
+    }
+    result
+}
+
+fn entry(name: &str, result: &Result<Value, RuntimeError>) -> Entry {
+    let mut entry = Entry { call: name.to_string(), value: None, error: None, kind: None };
+    match result {
+        Ok(value) => entry.value = Some(value_to_json(value)),
+        Err(RuntimeError { value: Value::String(message), .. }) => entry.error = Some(message.to_string()),
+        Err(RuntimeError { value: Value::Instance { fields, .. }, .. }) => {
+            let fields = fields.read().unwrap();
+            let text = |key: &str| match fields.get(key) {
+                Some(Value::String(s)) => Some(s.to_string()),
+                _ => None,
+            };
+            entry.error = Some(text("message").unwrap_or_default());
+            entry.kind = text("kind");
+        }
+        Err(other) => entry.error = Some(format!("{:?}", other)),
+    }
+    entry
+}
+
+fn replay(name: &str, entry: Option<Entry>) -> Result<Value, RuntimeError> {
+    let Some(entry) = entry else {
+        return Err(error_with_kind("Replay", format!("Replay log ran out at a call to {}", name)));
+    };
+    if entry.call != name {
+        return Err(error_with_kind(
+            "Replay",
+            format!("Replay diverged: the script called {} where the log has {}", name, entry.call),
+        ));
+    }
+    match (entry.value, entry.error) {
+        (_, Some(message)) => Err(error_with_kind(entry.kind.as_deref().unwrap_or("Error"), message)),
+        (Some(value), None) => Ok(json_to_value(&value)),
+        (None, None) => Ok(Value::Void),
+    }
+}
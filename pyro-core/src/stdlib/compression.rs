@@ -0,0 +1,330 @@
+use crate::interpreter::{Value, Interpreter, InterpreterClosure, NativeClosure, RuntimeError};
+use crate::permissions;
+use crate::convert::FromPyroValue;
+use flate2::write::{GzDecoder, GzEncoder};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+
+// Byte chunks are passed around as lists of ints (0-255). Strings are accepted
+// as input and compressed as UTF-8.
+
+const DEFAULT_GZIP_LEVEL: u32 = 6;
+#[cfg(feature = "zstd")]
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+fn parse_format(value: &Value) -> Result<Format, RuntimeError> {
+    let name: String = FromPyroValue::from_value(value).map_err(err)?;
+    match name.as_str() {
+        "gzip" | "gz" => Ok(Format::Gzip),
+        #[cfg(feature = "zstd")]
+        "zstd" | "zst" => Ok(Format::Zstd),
+        #[cfg(not(feature = "zstd"))]
+        "zstd" | "zst" => Err(err("zstd needs pyro built with the 'zstd' feature".to_string())),
+        other => Err(err(format!("Unknown compression format '{}' (expected \"gzip\" or \"zstd\")", other))),
+    }
+}
+
+fn parse_level(args: &[Value], index: usize) -> Result<Option<i64>, RuntimeError> {
+    match args.get(index) {
+        None | Some(Value::Void) => Ok(None),
+        Some(v) => FromPyroValue::from_value(v).map(Some).map_err(err),
+    }
+}
+
+fn to_bytes(value: &Value) -> Result<Vec<u8>, RuntimeError> {
+    let items = match value {
+        Value::String(s) => return Ok(s.as_bytes().to_vec()),
+        Value::List(l) => l.to_vec(),
+        Value::ListMutable(l) => l.read().unwrap().clone(),
+        _ => return Err(err("Expected a string or a list of bytes".to_string())),
+    };
+    items
+        .iter()
+        .map(|v| match v {
+            Value::Int(b) if (0..=255).contains(b) => Ok(*b as u8),
+            _ => Err(err("Byte values must be ints between 0 and 255".to_string())),
+        })
+        .collect()
+}
+
+fn from_bytes(bytes: Vec<u8>) -> Value {
+    Value::List(Arc::new(bytes.into_iter().map(|b| Value::Int(b as i64)).collect()))
+}
+
+// A compressor or decompressor that writes its output into an in-memory
+// buffer, which is drained after every chunk.
+enum Stream {
+    GzipEncoder(GzEncoder<Vec<u8>>),
+    GzipDecoder(GzDecoder<Vec<u8>>),
+    #[cfg(feature = "zstd")]
+    ZstdEncoder(zstd::stream::write::Encoder<'static, Vec<u8>>),
+    #[cfg(feature = "zstd")]
+    ZstdDecoder(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Stream {
+    fn new(format: Format, compress: bool, level: Option<i64>) -> io::Result<Stream> {
+        Ok(match (format, compress) {
+            (Format::Gzip, true) => {
+                let level = level.map(|l| l.clamp(0, 9) as u32).unwrap_or(DEFAULT_GZIP_LEVEL);
+                Stream::GzipEncoder(GzEncoder::new(Vec::new(), flate2::Compression::new(level)))
+            }
+            (Format::Gzip, false) => Stream::GzipDecoder(GzDecoder::new(Vec::new())),
+            #[cfg(feature = "zstd")]
+            (Format::Zstd, true) => {
+                let level = level.map(|l| l as i32).unwrap_or(DEFAULT_ZSTD_LEVEL);
+                Stream::ZstdEncoder(zstd::stream::write::Encoder::new(Vec::new(), level)?)
+            }
+            #[cfg(feature = "zstd")]
+            (Format::Zstd, false) => Stream::ZstdDecoder(zstd::stream::write::Decoder::new(Vec::new())?),
+        })
+    }
+
+    fn write(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        let buffer = match self {
+            Stream::GzipEncoder(s) => {
+                s.write_all(chunk)?;
+                s.get_mut()
+            }
+            Stream::GzipDecoder(s) => {
+                s.write_all(chunk)?;
+                s.get_mut()
+            }
+            #[cfg(feature = "zstd")]
+            Stream::ZstdEncoder(s) => {
+                s.write_all(chunk)?;
+                s.get_mut()
+            }
+            #[cfg(feature = "zstd")]
+            Stream::ZstdDecoder(s) => {
+                s.write_all(chunk)?;
+                s.get_mut()
+            }
+        };
+        Ok(std::mem::take(buffer))
+    }
+
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Stream::GzipEncoder(s) => s.finish(),
+            Stream::GzipDecoder(s) => s.finish(),
+            #[cfg(feature = "zstd")]
+            Stream::ZstdEncoder(s) => s.finish(),
+            #[cfg(feature = "zstd")]
+            Stream::ZstdDecoder(mut s) => {
+                s.flush()?;
+                Ok(s.into_inner())
+            }
+        }
+    }
+}
+
+fn stream_object(stream: Stream) -> Value {
+    let stream = Arc::new(Mutex::new(Some(stream)));
+    let mut methods = HashMap::new();
+
+    let s = stream.clone();
+    methods.insert("write".to_string(), Value::NativeFunction {
+        name: "write".to_string(),
+        func: NativeClosure(Arc::new(move |args| {
+            if args.len() != 1 {
+                return Err(err("Expected 1 argument".to_string()));
+            }
+            let chunk = to_bytes(&args[0])?;
+            let mut guard = s.lock().unwrap();
+            let stream = guard.as_mut().ok_or_else(|| err("Stream is already finished".to_string()))?;
+            stream.write(&chunk).map(from_bytes).map_err(|e| err(e.to_string()))
+        })),
+    });
+
+    methods.insert("finish".to_string(), Value::NativeFunction {
+        name: "finish".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            let stream = stream
+                .lock()
+                .unwrap()
+                .take()
+                .ok_or_else(|| err("Stream is already finished".to_string()))?;
+            stream.finish().map(from_bytes).map_err(|e| err(e.to_string()))
+        })),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
+
+// compressor(format, level?) / decompressor(format)
+fn compressor(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(err("Expected 1 or 2 arguments (format, level)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let stream = Stream::new(format, true, parse_level(&args, 1)?).map_err(|e| err(e.to_string()))?;
+    Ok(stream_object(stream))
+}
+
+fn decompressor(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument (format)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let stream = Stream::new(format, false, None).map_err(|e| err(e.to_string()))?;
+    Ok(stream_object(stream))
+}
+
+fn transform(format: Format, compress: bool, level: Option<i64>, data: &[u8]) -> Result<Vec<u8>, RuntimeError> {
+    let run = || {
+        let mut stream = Stream::new(format, compress, level)?;
+        let mut out = stream.write(data)?;
+        out.extend(stream.finish()?);
+        Ok::<_, io::Error>(out)
+    };
+    run().map_err(|e| err(e.to_string()))
+}
+
+// compress(format, data, level?) -> bytes
+fn compress(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(err("Expected 2 or 3 arguments (format, data, level)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let data = to_bytes(&args[1])?;
+    transform(format, true, parse_level(&args, 2)?, &data).map(from_bytes)
+}
+
+// decompress(format, data) -> bytes
+fn decompress(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (format, data)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let data = to_bytes(&args[1])?;
+    transform(format, false, None, &data).map(from_bytes)
+}
+
+// decompress_text(format, data) -> str
+fn decompress_text(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (format, data)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let data = to_bytes(&args[1])?;
+    let bytes = transform(format, false, None, &data)?;
+    let text = String::from_utf8(bytes).map_err(|_| err("Decompressed data is not valid UTF-8".to_string()))?;
+    Ok(Value::String(Arc::new(text)))
+}
+
+fn copy_file(format: Format, compress: bool, level: Option<i64>, src: &str, dst: &str) -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let writer = BufWriter::new(File::create(dst)?);
+    let copied = match (format, compress) {
+        (Format::Gzip, true) => {
+            let level = level.map(|l| l.clamp(0, 9) as u32).unwrap_or(DEFAULT_GZIP_LEVEL);
+            let mut encoder = GzEncoder::new(writer, flate2::Compression::new(level));
+            let copied = io::copy(&mut reader, &mut encoder)?;
+            encoder.finish()?.flush()?;
+            copied
+        }
+        (Format::Gzip, false) => {
+            let mut decoder = flate2::read::MultiGzDecoder::new(reader);
+            let mut writer = writer;
+            let copied = io::copy(&mut decoder, &mut writer)?;
+            writer.flush()?;
+            copied
+        }
+        #[cfg(feature = "zstd")]
+        (Format::Zstd, true) => {
+            let level = level.map(|l| l as i32).unwrap_or(DEFAULT_ZSTD_LEVEL);
+            let mut encoder = zstd::stream::write::Encoder::new(writer, level)?;
+            let copied = io::copy(&mut reader, &mut encoder)?;
+            encoder.finish()?.flush()?;
+            copied
+        }
+        #[cfg(feature = "zstd")]
+        (Format::Zstd, false) => {
+            let mut decoder = zstd::stream::read::Decoder::new(reader)?;
+            let mut writer = writer;
+            let copied = io::copy(&mut decoder, &mut writer)?;
+            writer.flush()?;
+            copied
+        }
+    };
+    Ok(copied)
+}
+
+// compress_file(format, src, dst, level?) -> bytes read
+fn compress_file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() < 3 || args.len() > 4 {
+        return Err(err("Expected 3 or 4 arguments (format, src, dst, level)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let src: String = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    let dst: String = FromPyroValue::from_value(&args[2]).map_err(err)?;
+    permissions::check_fs(interpreter, &src)?;
+    permissions::check_fs(interpreter, &dst)?;
+    let copied = copy_file(format, true, parse_level(&args, 3)?, &src, &dst)
+        .map_err(|e| err(format!("Could not compress '{}': {}", src, e)))?;
+    Ok(Value::Int(copied as i64))
+}
+
+// decompress_file(format, src, dst) -> bytes written
+fn decompress_file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 3 {
+        return Err(err("Expected 3 arguments (format, src, dst)".to_string()));
+    }
+    let format = parse_format(&args[0])?;
+    let src: String = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    let dst: String = FromPyroValue::from_value(&args[2]).map_err(err)?;
+    permissions::check_fs(interpreter, &src)?;
+    permissions::check_fs(interpreter, &dst)?;
+    let copied = copy_file(format, false, None, &src, &dst)
+        .map_err(|e| err(format!("Could not decompress '{}': {}", src, e)))?;
+    Ok(Value::Int(copied as i64))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("compress".to_string(), Value::NativeFunction {
+        name: "compress".to_string(),
+        func: NativeClosure(Arc::new(compress)),
+    });
+    methods.insert("decompress".to_string(), Value::NativeFunction {
+        name: "decompress".to_string(),
+        func: NativeClosure(Arc::new(decompress)),
+    });
+    methods.insert("decompress_text".to_string(), Value::NativeFunction {
+        name: "decompress_text".to_string(),
+        func: NativeClosure(Arc::new(decompress_text)),
+    });
+    methods.insert("compressor".to_string(), Value::NativeFunction {
+        name: "compressor".to_string(),
+        func: NativeClosure(Arc::new(compressor)),
+    });
+    methods.insert("decompressor".to_string(), Value::NativeFunction {
+        name: "decompressor".to_string(),
+        func: NativeClosure(Arc::new(decompressor)),
+    });
+    methods.insert("compress_file".to_string(), Value::InterpreterFunction {
+        name: "compress_file".to_string(),
+        func: InterpreterClosure(Arc::new(compress_file)),
+    });
+    methods.insert("decompress_file".to_string(), Value::InterpreterFunction {
+        name: "decompress_file".to_string(),
+        func: InterpreterClosure(Arc::new(decompress_file)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,218 @@
+use crate::interpreter::{Value, Interpreter, InterpreterClosure, NativeClosure, RuntimeError};
+use crate::permissions;
+use crate::convert::FromPyroValue;
+use crate::stdlib::json::{json_to_value, value_to_json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Environment variables with this prefix override config keys. Nested keys
+// are separated by a double underscore: PYRO_APP_SERVER__PORT -> server.port
+const ENV_PREFIX: &str = "PYRO_APP_";
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// Recursively merges `overlay` into `base`; objects are merged key by key,
+// anything else replaces the existing value.
+fn merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base_map), serde_json::Value::Object(overlay_map)) => {
+            for (k, v) in overlay_map {
+                match base_map.get_mut(&k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        base_map.insert(k, v);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn read_file(interpreter: &Interpreter, path: &str) -> Result<serde_json::Value, RuntimeError> {
+    permissions::check_fs(interpreter, path)?;
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| err(format!("Could not read config file '{}': {}", path, e)))?;
+
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "toml" => toml::from_str(&content).map_err(|e| err(format!("Invalid TOML in '{}': {}", path, e))),
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| err(format!("Invalid YAML in '{}': {}", path, e))),
+        "json" => serde_json::from_str(&content).map_err(|e| err(format!("Invalid JSON in '{}': {}", path, e))),
+        _ => Err(err(format!("Unsupported config format '{}' (expected .toml, .yaml, .yml or .json)", path))),
+    }
+}
+
+fn env_overrides() -> serde_json::Value {
+    let mut root = serde_json::Value::Object(serde_json::Map::new());
+    for (key, value) in std::env::vars() {
+        let Some(rest) = key.strip_prefix(ENV_PREFIX) else { continue };
+        if rest.is_empty() {
+            continue;
+        }
+
+        // Build {"server": {"port": "8080"}} and merge it in
+        let mut nested = serde_json::Value::String(value);
+        for segment in rest.to_lowercase().split("__").collect::<Vec<_>>().into_iter().rev() {
+            let mut map = serde_json::Map::new();
+            map.insert(segment.to_string(), nested);
+            nested = serde_json::Value::Object(map);
+        }
+        merge(&mut root, nested);
+    }
+    root
+}
+
+fn lookup<'a>(data: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    let mut current = data;
+    for segment in key.split('.') {
+        current = current.as_object()?.get(segment)?;
+    }
+    Some(current)
+}
+
+fn key_and_default(args: &[Value], name: &str) -> Result<(String, Option<Value>), RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(err(format!("{} expects 1 or 2 arguments (key, default)", name)));
+    }
+    let key: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    Ok((key, args.get(1).cloned()))
+}
+
+type Converter = fn(&serde_json::Value) -> Option<Value>;
+
+// Shared implementation of the typed getters: returns the default when the key
+// is missing, otherwise converts the stored value (strings from environment
+// overrides are parsed).
+fn typed_get(
+    data: &serde_json::Value,
+    args: Vec<Value>,
+    name: &str,
+    convert: Converter,
+) -> Result<Value, RuntimeError> {
+    let (key, default) = key_and_default(&args, name)?;
+    match lookup(data, &key) {
+        Some(v) if !v.is_null() => {
+            convert(v).ok_or_else(|| err(format!("Config key '{}' has an invalid value for {}: {}", key, name, v)))
+        }
+        _ => default.ok_or_else(|| err(format!("Config key '{}' not found", key))),
+    }
+}
+
+fn to_int(v: &serde_json::Value) -> Option<Value> {
+    match v {
+        serde_json::Value::Number(n) => n.as_i64().map(Value::Int),
+        serde_json::Value::String(s) => s.trim().parse().ok().map(Value::Int),
+        _ => None,
+    }
+}
+
+fn to_float(v: &serde_json::Value) -> Option<Value> {
+    match v {
+        serde_json::Value::Number(n) => n.as_f64().map(Value::Float),
+        serde_json::Value::String(s) => s.trim().parse().ok().map(Value::Float),
+        _ => None,
+    }
+}
+
+fn to_bool(v: &serde_json::Value) -> Option<Value> {
+    match v {
+        serde_json::Value::Bool(b) => Some(Value::Bool(*b)),
+        serde_json::Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Some(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_string(v: &serde_json::Value) -> Option<Value> {
+    match v {
+        serde_json::Value::String(s) => Some(Value::String(Arc::new(s.clone()))),
+        serde_json::Value::Number(n) => Some(Value::String(Arc::new(n.to_string()))),
+        serde_json::Value::Bool(b) => Some(Value::String(Arc::new(b.to_string()))),
+        _ => None,
+    }
+}
+
+fn config_object(data: serde_json::Value) -> Value {
+    let data = Arc::new(data);
+    let mut methods = HashMap::new();
+
+    methods.insert("data".to_string(), json_to_value(&data));
+
+    let d = data.clone();
+    methods.insert("get".to_string(), Value::NativeFunction {
+        name: "get".to_string(),
+        func: NativeClosure(Arc::new(move |args| typed_get(&d, args, "get", |v| Some(json_to_value(v))))),
+    });
+    let d = data.clone();
+    methods.insert("has".to_string(), Value::NativeFunction {
+        name: "has".to_string(),
+        func: NativeClosure(Arc::new(move |args| {
+            if args.len() != 1 {
+                return Err(err("Expected 1 argument".to_string()));
+            }
+            let key: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+            Ok(Value::Bool(lookup(&d, &key).is_some()))
+        })),
+    });
+
+    let getters: [(&str, Converter); 4] = [
+        ("get_int", to_int),
+        ("get_float", to_float),
+        ("get_bool", to_bool),
+        ("get_string", to_string),
+    ];
+    for (name, convert) in getters {
+        let d = data.clone();
+        methods.insert(name.to_string(), Value::NativeFunction {
+            name: name.to_string(),
+            func: NativeClosure(Arc::new(move |args| typed_get(&d, args, name, convert))),
+        });
+    }
+
+    Value::NativeModule(Arc::new(methods))
+}
+
+// load(path?, defaults?): defaults < file < PYRO_APP_* environment variables
+fn load(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() > 2 {
+        return Err(err("Expected at most 2 arguments (path, defaults)".to_string()));
+    }
+
+    let mut data = match args.get(1) {
+        Some(defaults @ (Value::Dict(_) | Value::DictMutable(_))) => value_to_json(defaults),
+        Some(_) => return Err(err("defaults must be a dict".to_string())),
+        None => serde_json::Value::Object(serde_json::Map::new()),
+    };
+
+    if let Some(path_val) = args.first() {
+        let path: String = FromPyroValue::from_value(path_val).map_err(err)?;
+        if !path.is_empty() {
+            merge(&mut data, read_file(interpreter, &path)?);
+        }
+    }
+
+    merge(&mut data, env_overrides());
+    Ok(config_object(data))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("load".to_string(), Value::InterpreterFunction {
+        name: "load".to_string(),
+        func: InterpreterClosure(Arc::new(load)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
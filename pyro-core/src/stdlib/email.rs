@@ -0,0 +1,163 @@
+use crate::interpreter::{Value, Interpreter, InterpreterClosure, RuntimeError};
+use crate::permissions;
+use lettre::message::{header::ContentType, Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+fn to_map(value: &Value, what: &str) -> Result<HashMap<String, Value>, RuntimeError> {
+    let entries = match value {
+        Value::Dict(d) => d.to_vec(),
+        Value::DictMutable(d) => d.read().unwrap().clone(),
+        _ => return Err(err(format!("{} must be a dict", what))),
+    };
+    let mut map = HashMap::new();
+    for (k, v) in entries {
+        if let Value::String(key) = k {
+            map.insert(key.to_string(), v);
+        }
+    }
+    Ok(map)
+}
+
+fn get_string(map: &HashMap<String, Value>, key: &str) -> Result<Option<String>, RuntimeError> {
+    match map.get(key) {
+        None | Some(Value::Void) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.to_string())),
+        Some(_) => Err(err(format!("'{}' must be a string", key))),
+    }
+}
+
+fn require_string(map: &HashMap<String, Value>, key: &str) -> Result<String, RuntimeError> {
+    get_string(map, key)?.ok_or_else(|| err(format!("Missing required field '{}'", key)))
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, RuntimeError> {
+    address
+        .parse()
+        .map_err(|e| err(format!("Invalid email address '{}': {}", address, e)))
+}
+
+// Recipient fields accept either a single address or a list of addresses.
+fn mailboxes(map: &HashMap<String, Value>, key: &str) -> Result<Vec<Mailbox>, RuntimeError> {
+    match map.get(key) {
+        None | Some(Value::Void) => Ok(Vec::new()),
+        Some(Value::String(s)) => Ok(vec![parse_mailbox(s)?]),
+        Some(Value::List(l)) => l.iter().map(|v| mailbox_value(v, key)).collect(),
+        Some(Value::ListMutable(l)) => l.read().unwrap().iter().map(|v| mailbox_value(v, key)).collect(),
+        Some(_) => Err(err(format!("'{}' must be a string or a list of strings", key))),
+    }
+}
+
+fn mailbox_value(value: &Value, key: &str) -> Result<Mailbox, RuntimeError> {
+    match value {
+        Value::String(s) => parse_mailbox(s),
+        _ => Err(err(format!("'{}' must be a string or a list of strings", key))),
+    }
+}
+
+fn build_message(fields: &HashMap<String, Value>) -> Result<Message, RuntimeError> {
+    let mut builder = Message::builder()
+        .from(parse_mailbox(&require_string(fields, "from")?)?)
+        .subject(require_string(fields, "subject")?);
+
+    let to = mailboxes(fields, "to")?;
+    if to.is_empty() {
+        return Err(err("Missing required field 'to'".to_string()));
+    }
+    for mailbox in to {
+        builder = builder.to(mailbox);
+    }
+    for mailbox in mailboxes(fields, "cc")? {
+        builder = builder.cc(mailbox);
+    }
+    for mailbox in mailboxes(fields, "bcc")? {
+        builder = builder.bcc(mailbox);
+    }
+    if let Some(reply_to) = get_string(fields, "reply_to")? {
+        builder = builder.reply_to(parse_mailbox(&reply_to)?);
+    }
+
+    let body = get_string(fields, "body")?;
+    let html = get_string(fields, "html")?;
+    let message = match (body, html) {
+        (Some(text), Some(html)) => builder.multipart(MultiPart::alternative_plain_html(text, html)),
+        (None, Some(html)) => builder.header(ContentType::TEXT_HTML).body(html),
+        (text, None) => builder.header(ContentType::TEXT_PLAIN).body(text.unwrap_or_default()),
+    };
+    message.map_err(|e| err(format!("Could not build email: {}", e)))
+}
+
+fn build_transport(interpreter: &Interpreter, smtp: &HashMap<String, Value>) -> Result<SmtpTransport, RuntimeError> {
+    let host = require_string(smtp, "host")?;
+    let tls = get_string(smtp, "tls")?.unwrap_or_else(|| "starttls".to_string());
+
+    let mut builder = match tls.as_str() {
+        "starttls" => SmtpTransport::starttls_relay(&host),
+        "tls" => SmtpTransport::relay(&host),
+        "none" => Ok(SmtpTransport::builder_dangerous(&host)),
+        other => return Err(err(format!("Unknown tls mode '{}' (expected \"starttls\", \"tls\" or \"none\")", other))),
+    }
+    .map_err(|e| err(format!("Invalid SMTP host '{}': {}", host, e)))?;
+
+    // lettre's defaults for each mode
+    let mut port = match tls.as_str() {
+        "tls" => 465,
+        "starttls" => 587,
+        _ => 25,
+    };
+    match smtp.get("port") {
+        None | Some(Value::Void) => {}
+        Some(Value::Int(p)) => {
+            port = *p as u16;
+            builder = builder.port(port);
+        }
+        Some(_) => return Err(err("'port' must be an int".to_string())),
+    }
+    permissions::check_net(interpreter, &host, port)?;
+
+    let username = get_string(smtp, "username")?;
+    let password = get_string(smtp, "password")?;
+    if let Some(username) = username {
+        builder = builder.credentials(Credentials::new(username, password.unwrap_or_default()));
+    }
+
+    Ok(builder.build())
+}
+
+// send({to, from, subject, body, html?, cc?, bcc?, reply_to?, smtp: {host, port?, username?, password?, tls?}})
+fn send(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+
+    let fields = to_map(&args[0], "send argument")?;
+    let smtp = to_map(
+        fields.get("smtp").ok_or_else(|| err("Missing required field 'smtp'".to_string()))?,
+        "smtp",
+    )?;
+
+    let message = build_message(&fields)?;
+    let transport = build_transport(interpreter, &smtp)?;
+
+    transport
+        .send(&message)
+        .map_err(|e| err(format!("Failed to send email: {}", e)))?;
+    Ok(Value::Void)
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("send".to_string(), Value::InterpreterFunction {
+        name: "send".to_string(),
+        func: InterpreterClosure(Arc::new(send)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -1,12 +1,14 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{type_name, Interpreter, InterpreterClosure, NativeClosure, RuntimeError, Value};
+use crate::permissions;
+use crate::replay;
 use crate::convert::{FromPyroValue};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::env;
 
-fn var(args: Vec<Value>) -> Result<Value, Value> {
+fn var(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let key: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -17,7 +19,7 @@ fn var(args: Vec<Value>) -> Result<Value, Value> {
     }
 }
 
-fn vars(_args: Vec<Value>) -> Result<Value, Value> {
+fn vars(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     let mut map = Vec::new();
     for (k, v) in env::vars() {
         map.push((Value::String(Arc::new(k)), Value::String(Arc::new(v))));
@@ -25,7 +27,7 @@ fn vars(_args: Vec<Value>) -> Result<Value, Value> {
     Ok(Value::Dict(Arc::new(map)))
 }
 
-fn args(_args: Vec<Value>) -> Result<Value, Value> {
+fn args(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     let mut list = Vec::new();
     for arg in env::args() {
         list.push(Value::String(Arc::new(arg)));
@@ -33,48 +35,163 @@ fn args(_args: Vec<Value>) -> Result<Value, Value> {
     Ok(Value::List(Arc::new(list)))
 }
 
-fn cwd(_args: Vec<Value>) -> Result<Value, Value> {
+fn cwd(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     match env::current_dir() {
         Ok(path) => Ok(Value::String(Arc::new(path.display().to_string()))),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(Value::String(Arc::new(e.to_string())).into()),
     }
 }
 
-fn set_cwd(args: Vec<Value>) -> Result<Value, Value> {
+fn set_cwd(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
-    
+    permissions::check_fs(interpreter, &path)?;
+
     match env::set_current_dir(path) {
         Ok(_) => Ok(Value::Void),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(Value::String(Arc::new(e.to_string())).into()),
+    }
+}
+
+fn invalid(message: String) -> RuntimeError {
+    Value::String(Arc::new(message)).into()
+}
+
+// Names and values the OS can store; std::env panics on anything else
+fn check_var(key: &str, value: Option<&str>) -> Result<(), RuntimeError> {
+    if key.is_empty() || key.contains(['=', '\0']) {
+        return Err(invalid(format!("Invalid environment variable name '{}'", key)));
+    }
+    if value.is_some_and(|value| value.contains('\0')) {
+        return Err(invalid(format!("The value of environment variable '{}' contains a NUL character", key)));
+    }
+    Ok(())
+}
+
+// Changes the environment of the whole process: goroutines see the change,
+// and child processes started afterwards inherit it
+fn put(key: &str, value: Option<&str>) {
+    // SAFETY: std serializes its own environment access. Native code reading
+    // the environment on another thread at the same time could race; nothing
+    // in the runtime does while a script runs.
+    unsafe {
+        match value {
+            Some(value) => env::set_var(key, value),
+            None => env::remove_var(key),
+        }
+    }
+}
+
+fn set(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(invalid("Expected 2 arguments (key, value)".to_string()));
+    }
+    let key: String = FromPyroValue::from_value(&args[0]).map_err(|e| Value::String(Arc::new(e)))?;
+    let value: String = FromPyroValue::from_value(&args[1]).map_err(|e| Value::String(Arc::new(e)))?;
+    check_var(&key, Some(&value))?;
+    permissions::check_env(interpreter, &key)?;
+    put(&key, Some(&value));
+    Ok(Value::Void)
+}
+
+fn unset(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(invalid("Expected 1 argument".to_string()));
+    }
+    let key: String = FromPyroValue::from_value(&args[0]).map_err(|e| Value::String(Arc::new(e)))?;
+    check_var(&key, None)?;
+    permissions::check_env(interpreter, &key)?;
+    put(&key, None);
+    Ok(Value::Void)
+}
+
+fn restore(previous: &[(String, Option<String>)]) {
+    // In reverse, so a key given twice ends up as it was before either
+    for (key, value) in previous.iter().rev() {
+        put(key, value.as_deref());
     }
 }
 
+// scoped(vars, fn?): sets each variable in `vars`. With fn, calls it with no
+// arguments and returns its result, and the previous values come back
+// afterwards, even if fn raises. Without, returns a context manager that
+// puts them back at the end of a `with env.scoped(vars):` block.
+fn scoped(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(invalid("Expected 1 or 2 arguments (vars, function)".to_string()));
+    }
+    let pairs = match &args[0] {
+        Value::Dict(d) => d.to_vec(),
+        Value::DictMutable(d) => d.read().unwrap().clone(),
+        other => return Err(invalid(format!("Expected a dict of variables, found {}", type_name(other)))),
+    };
+    let mut changes = Vec::new();
+    for (key, value) in pairs {
+        let key: String = FromPyroValue::from_value(&key).map_err(|e| Value::String(Arc::new(e)))?;
+        let value: String = FromPyroValue::from_value(&value).map_err(|e| Value::String(Arc::new(e)))?;
+        check_var(&key, Some(&value))?;
+        permissions::check_env(interpreter, &key)?;
+        changes.push((key, value));
+    }
+
+    let previous: Vec<(String, Option<String>)> =
+        changes.iter().map(|(key, _)| (key.clone(), env::var(key).ok())).collect();
+    for (key, value) in &changes {
+        put(key, Some(value));
+    }
+    let Some(func) = args.get(1).filter(|func| !matches!(func, Value::Void)) else {
+        let mut methods = HashMap::new();
+        methods.insert("__exit__".to_string(), Value::NativeFunction {
+            name: "__exit__".to_string(),
+            func: NativeClosure(Arc::new(move |_args| {
+                restore(&previous);
+                Ok(Value::Void)
+            })),
+        });
+        return Ok(Value::NativeModule(Arc::new(methods)));
+    };
+    let result = interpreter.call(func.clone(), Vec::new());
+    restore(&previous);
+    result
+}
+
 pub fn module() -> Value {
     let mut methods = HashMap::new();
     
     methods.insert("var".to_string(), Value::NativeFunction {
         name: "var".to_string(),
-        func: NativeClosure(Arc::new(var)),
+        func: NativeClosure(Arc::new(|args| replay::call("env.var", args, var))),
     });
     methods.insert("vars".to_string(), Value::NativeFunction {
         name: "vars".to_string(),
-        func: NativeClosure(Arc::new(vars)),
+        func: NativeClosure(Arc::new(|args| replay::call("env.vars", args, vars))),
     });
     methods.insert("args".to_string(), Value::NativeFunction {
         name: "args".to_string(),
-        func: NativeClosure(Arc::new(args)),
+        func: NativeClosure(Arc::new(|call_args| replay::call("env.args", call_args, args))),
     });
     methods.insert("cwd".to_string(), Value::NativeFunction {
         name: "cwd".to_string(),
-        func: NativeClosure(Arc::new(cwd)),
+        func: NativeClosure(Arc::new(|args| replay::call("env.cwd", args, cwd))),
     });
-    methods.insert("set_cwd".to_string(), Value::NativeFunction {
+    methods.insert("set_cwd".to_string(), Value::InterpreterFunction {
         name: "set_cwd".to_string(),
-        func: NativeClosure(Arc::new(set_cwd)),
+        func: InterpreterClosure(Arc::new(set_cwd)),
+    });
+    methods.insert("set".to_string(), Value::InterpreterFunction {
+        name: "set".to_string(),
+        func: InterpreterClosure(Arc::new(set)),
+    });
+    methods.insert("unset".to_string(), Value::InterpreterFunction {
+        name: "unset".to_string(),
+        func: InterpreterClosure(Arc::new(unset)),
+    });
+    methods.insert("scoped".to_string(), Value::InterpreterFunction {
+        name: "scoped".to_string(),
+        func: InterpreterClosure(Arc::new(scoped)),
     });
 
     Value::NativeModule(Arc::new(methods))
@@ -0,0 +1,113 @@
+use crate::interpreter::{Interpreter, InterpreterClosure, NativeClosure, RuntimeError, Value};
+use crate::convert::FromPyroValue;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+type Handlers = Arc<RwLock<HashMap<String, Vec<Value>>>>;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+fn event_name(args: &[Value]) -> Result<String, RuntimeError> {
+    match args.first() {
+        Some(v) => FromPyroValue::from_value(v).map_err(err),
+        None => Err(err("Expected an event name".to_string())),
+    }
+}
+
+// Pyro functions never compare equal as values, so handlers are matched by
+// the identity of their body instead.
+fn same_handler(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Function { body: b1, partial_args: p1, .. }, Value::Function { body: b2, partial_args: p2, .. }) => {
+            Arc::ptr_eq(b1, b2) && p1 == p2
+        }
+        _ => a == b,
+    }
+}
+
+fn method(name: &str, f: impl Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static) -> Value {
+    Value::InterpreterFunction {
+        name: name.to_string(),
+        func: InterpreterClosure(Arc::new(f)),
+    }
+}
+
+// new() -> emitter with on, off, emit and count
+fn new(_args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let handlers: Handlers = Arc::new(RwLock::new(HashMap::new()));
+    let mut methods = HashMap::new();
+
+    // on(name, handler)
+    let on_handlers = handlers.clone();
+    methods.insert("on".to_string(), method("on", move |_interpreter, args| {
+        if args.len() != 2 {
+            return Err(err("Expected 2 arguments (name, handler)".to_string()));
+        }
+        let name = event_name(&args)?;
+        on_handlers.write().unwrap().entry(name).or_default().push(args[1].clone());
+        Ok(Value::Void)
+    }));
+
+    // off(name) removes every handler for the event; off(name, handler) just that one
+    let off_handlers = handlers.clone();
+    methods.insert("off".to_string(), method("off", move |_interpreter, args| {
+        if args.is_empty() || args.len() > 2 {
+            return Err(err("Expected 1 or 2 arguments (name, handler)".to_string()));
+        }
+        let name = event_name(&args)?;
+        let mut handlers = off_handlers.write().unwrap();
+        match args.get(1) {
+            Some(handler) => {
+                if let Some(list) = handlers.get_mut(&name) {
+                    list.retain(|h| !same_handler(h, handler));
+                }
+            }
+            None => {
+                handlers.remove(&name);
+            }
+        }
+        Ok(Value::Void)
+    }));
+
+    // emit(name, payload?) -> number of handlers called
+    // Handlers run in the order they were added. The list is copied first, so
+    // handlers can call on/off without affecting the current emit.
+    let emit_handlers = handlers.clone();
+    methods.insert("emit".to_string(), method("emit", move |interpreter, args| {
+        if args.is_empty() || args.len() > 2 {
+            return Err(err("Expected 1 or 2 arguments (name, payload)".to_string()));
+        }
+        let name = event_name(&args)?;
+        let listeners = emit_handlers.read().unwrap().get(&name).cloned().unwrap_or_default();
+        for handler in &listeners {
+            interpreter.call(handler.clone(), args[1..].to_vec())?;
+        }
+        Ok(Value::Int(listeners.len() as i64))
+    }));
+
+    // count(name) -> number of handlers registered for the event
+    let count_handlers = handlers;
+    methods.insert("count".to_string(), Value::NativeFunction {
+        name: "count".to_string(),
+        func: NativeClosure(Arc::new(move |args| {
+            let name = event_name(&args)?;
+            let count = count_handlers.read().unwrap().get(&name).map_or(0, Vec::len);
+            Ok(Value::Int(count as i64))
+        })),
+    });
+
+    Ok(Value::NativeModule(Arc::new(methods)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("new".to_string(), Value::NativeFunction {
+        name: "new".to_string(),
+        func: NativeClosure(Arc::new(new)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,90 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::convert::FromPyroValue;
+use num_format::{Locale, ToFormattedString};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const DEFAULT_LOCALE: &str = "en";
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+pub(crate) fn locale_arg(args: &[Value], index: usize) -> Result<String, RuntimeError> {
+    match args.get(index) {
+        None | Some(Value::Void) => Ok(DEFAULT_LOCALE.to_string()),
+        Some(v) => FromPyroValue::from_value(v).map_err(err),
+    }
+}
+
+// Locale names are accepted as "de", "de-CH" or "de_CH". A bare language
+// falls back to its main region, e.g. "de" -> "de_DE", for date formatting.
+pub(crate) fn date_locale(name: &str) -> Result<chrono::Locale, RuntimeError> {
+    let name = name.replace('-', "_");
+    let mut candidates = vec![name.clone()];
+    if !name.contains('_') {
+        candidates.push(format!("{}_{}", name, name.to_uppercase()));
+        if name == "en" {
+            candidates.push("en_US".to_string());
+        }
+    }
+    candidates
+        .iter()
+        .find_map(|c| chrono::Locale::try_from(c.as_str()).ok())
+        .ok_or_else(|| err(format!("Unknown locale '{}'", name)))
+}
+
+fn number_locale(name: &str) -> Result<Locale, RuntimeError> {
+    let name = name.replace('_', "-");
+    Locale::from_name(&name)
+        .or_else(|_| Locale::from_name(name.split('-').next().unwrap_or("")))
+        .map_err(|_| err(format!("Unknown locale '{}'", name)))
+}
+
+// number(value, locale = "en", decimals?) -> str
+fn number(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(err("Expected 1 to 3 arguments (value, locale, decimals)".to_string()));
+    }
+    let locale = number_locale(&locale_arg(&args, 1)?)?;
+    let decimals: Option<i64> = match args.get(2) {
+        None | Some(Value::Void) => None,
+        Some(v) => Some(FromPyroValue::from_value(v).map_err(err)?),
+    };
+
+    // Render the digits without grouping first, then group the integer part
+    let (negative, digits) = match (&args[0], decimals) {
+        (Value::Int(i), None) => (*i < 0, i.unsigned_abs().to_string()),
+        (Value::Int(i), Some(d)) => (*i < 0, format!("{:.*}", d.max(0) as usize, (*i as f64).abs())),
+        (Value::Float(f), None) => (*f < 0.0, f.abs().to_string()),
+        (Value::Float(f), Some(d)) => (*f < 0.0, format!("{:.*}", d.max(0) as usize, f.abs())),
+        _ => return Err(err("number expects an int or a float".to_string())),
+    };
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (digits.as_str(), None),
+    };
+    let integer: u64 = integer.parse().map_err(|_| err(format!("Number too large to format: {}", digits)))?;
+
+    let mut out = String::new();
+    if negative && digits.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push_str(locale.minus_sign());
+    }
+    out.push_str(&integer.to_formatted_string(&locale));
+    if let Some(fraction) = fraction {
+        out.push_str(locale.decimal());
+        out.push_str(fraction);
+    }
+    Ok(Value::String(Arc::new(out)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("number".to_string(), Value::NativeFunction {
+        name: "number".to_string(),
+        func: NativeClosure(Arc::new(number)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -1,105 +1,125 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{type_name, Interpreter, InterpreterClosure, Value, NativeClosure, io_error, RuntimeError};
+use crate::permissions;
 use crate::convert::{FromPyroValue, ToPyroValue};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
 use std::fs;
-use std::path::Path;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "fs-watch")]
+use std::sync::{mpsc, Condvar};
+#[cfg(feature = "fs-watch")]
+use std::thread;
+#[cfg(feature = "fs-watch")]
+use notify::{EventKind, RecursiveMode, Watcher};
+#[cfg(feature = "fs-watch")]
+use notify::event::ModifyKind;
 
-fn read_to_string(args: Vec<Value>) -> Result<Value, Value> {
+fn read_to_string(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     match fs::read_to_string(path) {
         Ok(content) => Ok(content.to_value()),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
-fn write(args: Vec<Value>) -> Result<Value, Value> {
+fn write(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 2 {
-        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())));
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     let content: String = FromPyroValue::from_value(&args[1])
         .map_err(|e| Value::String(Arc::new(e)))?;
     match fs::write(path, content) {
         Ok(_) => Ok(Value::Void),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
-fn exists(args: Vec<Value>) -> Result<Value, Value> {
+fn exists(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     Ok(Value::Bool(Path::new(&path).exists()))
 }
 
-fn is_file(args: Vec<Value>) -> Result<Value, Value> {
+fn is_file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     Ok(Value::Bool(Path::new(&path).is_file()))
 }
 
-fn is_dir(args: Vec<Value>) -> Result<Value, Value> {
+fn is_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     Ok(Value::Bool(Path::new(&path).is_dir()))
 }
 
-fn create_dir(args: Vec<Value>) -> Result<Value, Value> {
+fn create_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     match fs::create_dir_all(path) {
         Ok(_) => Ok(Value::Void),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
-fn remove_file(args: Vec<Value>) -> Result<Value, Value> {
+fn remove_file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     match fs::remove_file(path) {
         Ok(_) => Ok(Value::Void),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
-fn remove_dir(args: Vec<Value>) -> Result<Value, Value> {
+fn remove_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     match fs::remove_dir(path) {
         Ok(_) => Ok(Value::Void),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
-fn list_dir(args: Vec<Value>) -> Result<Value, Value> {
+fn list_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
     
     match fs::read_dir(path) {
         Ok(entries) => {
@@ -116,48 +136,425 @@ fn list_dir(args: Vec<Value>) -> Result<Value, Value> {
             }
             Ok(Value::List(Arc::new(result)))
         },
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(io_error(&e)),
     }
 }
 
+// Temp files and directories not removed yet. remove_temp_paths deletes
+// them when the program ends; the CLI calls it on exit.
+static TEMP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+const TEMP_PREFIX: &str = "pyro-";
+
+// A new, empty file or directory in the system temp directory, named
+// `prefix` followed by random characters
+fn create_temp(interpreter: &Interpreter, prefix: &str, dir: bool) -> Result<PathBuf, RuntimeError> {
+    if prefix.contains(['/', '\\']) {
+        return Err(Value::String(Arc::new(format!("Invalid temp prefix '{}'", prefix))).into());
+    }
+    let base = std::env::temp_dir();
+    permissions::check_fs(interpreter, &base.to_string_lossy())?;
+    loop {
+        let path = base.join(format!("{}{:016x}", prefix, rand::random::<u64>()));
+        let created = if dir {
+            fs::create_dir(&path)
+        } else {
+            fs::OpenOptions::new().write(true).create_new(true).open(&path).map(|_| ())
+        };
+        match created {
+            Ok(()) => {
+                TEMP_PATHS.lock().unwrap().push(path.clone());
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(io_error(&e)),
+        }
+    }
+}
+
+fn remove_temp(path: &Path) {
+    TEMP_PATHS.lock().unwrap().retain(|p| p != path);
+    let _ = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+}
+
+// Removes every temp file and directory the program created and still has
+pub fn remove_temp_paths() {
+    let paths = std::mem::take(&mut *TEMP_PATHS.lock().unwrap());
+    for path in paths {
+        remove_temp(&path);
+    }
+}
+
+// With a function, it's called with the path, and the path is removed as it
+// returns or raises; what it returns is the result. Without one, the path
+// itself is the result and lasts until the program ends.
+fn with_temp(interpreter: &mut Interpreter, path: PathBuf, func: Option<Value>) -> Result<Value, RuntimeError> {
+    let value = Value::Path(Arc::new(path.clone()));
+    match func {
+        Some(func) => {
+            let result = interpreter.call(func, vec![value]);
+            remove_temp(&path);
+            result
+        }
+        None => Ok(value),
+    }
+}
+
+fn callback(value: Option<&Value>) -> Result<Option<Value>, RuntimeError> {
+    match value {
+        None | Some(Value::Void) => Ok(None),
+        Some(f) if type_name(f) == "function" => Ok(Some(f.clone())),
+        Some(other) => Err(Value::String(Arc::new(format!("Expected a function, found {}", type_name(other)))).into()),
+    }
+}
+
+// temp_dir(f?)
+fn temp_dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() > 1 {
+        return Err(Value::String(Arc::new("Expected at most 1 argument".to_string())).into());
+    }
+    let func = callback(args.first())?;
+    let path = create_temp(interpreter, TEMP_PREFIX, true)?;
+    with_temp(interpreter, path, func)
+}
+
+// temp_file(prefix = "pyro-", f?)
+fn temp_file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() > 2 {
+        return Err(Value::String(Arc::new("Expected at most 2 arguments".to_string())).into());
+    }
+    let prefix = match args.first() {
+        None | Some(Value::Void) => TEMP_PREFIX.to_string(),
+        Some(prefix) => FromPyroValue::from_value(prefix).map_err(|e| Value::String(Arc::new(e)))?,
+    };
+    let func = callback(args.get(1))?;
+    let path = create_temp(interpreter, &prefix, false)?;
+    with_temp(interpreter, path, func)
+}
+
+// write_atomic(path, content): readers see the old content or the new, never
+// part of it, even if the program dies halfway
+fn write_atomic(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
+    }
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
+    let content: String = FromPyroValue::from_value(&args[1])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+
+    // The temp file goes next to the target, as rename can't cross file systems
+    let target = Path::new(&path);
+    let name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = target.with_file_name(format!(".{}.{:016x}.tmp", name, rand::random::<u64>()));
+    let written = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, target));
+    match written {
+        Ok(()) => Ok(Value::Void),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(io_error(&e))
+        }
+    }
+}
+
+fn closed() -> RuntimeError {
+    Value::String(Arc::new("File is closed".to_string())).into()
+}
+
+// open(path, mode = "r") -> file
+// Mode "r" reads, "w" truncates and writes, "a" appends. The file has read(),
+// write(text) and close(), and closes itself at the end of a `with` block.
+fn open(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(Value::String(Arc::new("Expected 1 or 2 arguments (path, mode)".to_string())).into());
+    }
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    let mode = match args.get(1) {
+        None | Some(Value::Void) => "r".to_string(),
+        Some(mode) => FromPyroValue::from_value(mode).map_err(|e: String| Value::String(Arc::new(e)))?,
+    };
+    permissions::check_fs(interpreter, &path)?;
+    let mut options = fs::OpenOptions::new();
+    match mode.as_str() {
+        "r" => options.read(true),
+        "w" => options.write(true).create(true).truncate(true),
+        "a" => options.append(true).create(true),
+        other => return Err(Value::String(Arc::new(format!("Unknown file mode '{}'; expected r, w or a", other))).into()),
+    };
+    let file = options.open(&path).map_err(|e| io_error(&e))?;
+    let file = Arc::new(Mutex::new(Some(file)));
+
+    let mut methods = HashMap::new();
+    let reader = file.clone();
+    methods.insert("read".to_string(), Value::NativeFunction {
+        name: "read".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            let mut file = reader.lock().unwrap();
+            let file = file.as_mut().ok_or_else(closed)?;
+            let mut content = String::new();
+            file.read_to_string(&mut content).map_err(|e| io_error(&e))?;
+            Ok(content.to_value())
+        })),
+    });
+    let writer = file.clone();
+    methods.insert("write".to_string(), Value::NativeFunction {
+        name: "write".to_string(),
+        func: NativeClosure(Arc::new(move |args| {
+            if args.len() != 1 {
+                return Err(Value::String(Arc::new("Expected 1 argument (text)".to_string())).into());
+            }
+            let text: String = FromPyroValue::from_value(&args[0])
+                .map_err(|e| Value::String(Arc::new(e)))?;
+            let mut file = writer.lock().unwrap();
+            let file = file.as_mut().ok_or_else(closed)?;
+            file.write_all(text.as_bytes()).map_err(|e| io_error(&e))?;
+            Ok(Value::Void)
+        })),
+    });
+    let closer = file.clone();
+    methods.insert("close".to_string(), Value::NativeFunction {
+        name: "close".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            closer.lock().unwrap().take();
+            Ok(Value::Void)
+        })),
+    });
+    methods.insert("__exit__".to_string(), Value::NativeFunction {
+        name: "__exit__".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            file.lock().unwrap().take();
+            Ok(Value::Void)
+        })),
+    });
+    Ok(Value::NativeModule(Arc::new(methods)))
+}
+
+// Open lock files, by the path given to lock. Closing one releases the lock.
+static LOCKS: Mutex<Vec<(PathBuf, fs::File)>> = Mutex::new(Vec::new());
+
+// Locks are taken on `<path>.lock` rather than the file itself, which
+// write_atomic replaces
+fn lock_file(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.lock", path))
+}
+
+fn release(path: &Path) -> bool {
+    let mut locks = LOCKS.lock().unwrap();
+    let before = locks.len();
+    locks.retain(|(locked, _)| locked != path);
+    locks.len() != before
+}
+
+// lock(path, f?): waits until no other program or goroutine holds the lock.
+// With a function, calls it and unlocks as it returns or raises.
+fn lock(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(Value::String(Arc::new("Expected 1 or 2 arguments".to_string())).into());
+    }
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
+    let func = callback(args.get(1))?;
+
+    let lock_path = lock_file(&path);
+    let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+        .map_err(|e| io_error(&e))?;
+    // Waiting blocks the thread, so let tokio move other goroutines off it
+    tokio::task::block_in_place(|| file.lock()).map_err(|e| io_error(&e))?;
+    LOCKS.lock().unwrap().push((lock_path.clone(), file));
+
+    match func {
+        Some(func) => {
+            let result = interpreter.call(func, Vec::new());
+            release(&lock_path);
+            result
+        }
+        None => Ok(Value::Void),
+    }
+}
+
+fn unlock(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    if !release(&lock_file(&path)) {
+        return Err(Value::String(Arc::new(format!("'{}' is not locked", path))).into());
+    }
+    Ok(Value::Void)
+}
+
+#[cfg(feature = "fs-watch")]
+fn watch_error(e: notify::Error) -> RuntimeError {
+    Value::String(Arc::new(format!("Cannot watch: {}", e))).into()
+}
+
+// The kind passed to watch handlers; reads aren't reported
+#[cfg(feature = "fs-watch")]
+fn event_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("create"),
+        EventKind::Modify(ModifyKind::Name(_)) => Some("rename"),
+        EventKind::Modify(_) => Some("modify"),
+        EventKind::Remove(_) => Some("remove"),
+        EventKind::Access(_) => None,
+        EventKind::Any | EventKind::Other => Some("other"),
+    }
+}
+
+// watch(path, handler) -> watcher
+// The handler is called with {"kind": ..., "path": ...} for each change under
+// `path`, on its own thread against a clone of the interpreter, like
+// scheduled jobs.
+#[cfg(feature = "fs-watch")]
+fn watch(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(Value::String(Arc::new("Expected 2 arguments (path, handler)".to_string())).into());
+    }
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_fs(interpreter, &path)?;
+    let Some(handler) = callback(Some(&args[1]))? else {
+        return Err(Value::String(Arc::new("Expected a function as the second argument".to_string())).into());
+    };
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+    watcher.watch(Path::new(&path), RecursiveMode::Recursive).map_err(watch_error)?;
+    // Dropping the watcher closes the channel, which ends the thread
+    let watcher = Arc::new(Mutex::new(Some(watcher)));
+    let done = Arc::new((Mutex::new(false), Condvar::new()));
+
+    let mut handler_interpreter = interpreter.fork();
+    let runtime = tokio::runtime::Handle::try_current().ok();
+    let thread_done = done.clone();
+    thread::spawn(move || {
+        let _guard = runtime.as_ref().map(|handle| handle.enter());
+        for event in events {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    let _ = handler_interpreter.write_error(&format!("Error watching files: {}", e));
+                    continue;
+                }
+            };
+            let Some(kind) = event_kind(&event.kind) else { continue };
+            for changed in event.paths {
+                let value = Value::Dict(Arc::new(vec![
+                    (Value::String(Arc::new("kind".to_string())), Value::String(Arc::new(kind.to_string()))),
+                    (Value::String(Arc::new("path".to_string())), Value::Path(Arc::new(changed))),
+                ]));
+                // A failing handler is reported but doesn't stop the watch
+                if let Err(e) = handler_interpreter.call(handler.clone(), vec![value]) {
+                    let _ = handler_interpreter.write_error(&format!("Error in watch handler: {:?}", e));
+                }
+            }
+        }
+        *thread_done.0.lock().unwrap() = true;
+        thread_done.1.notify_all();
+    });
+
+    let mut methods = HashMap::new();
+    methods.insert("stop".to_string(), Value::NativeFunction {
+        name: "stop".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            watcher.lock().unwrap().take();
+            Ok(Value::Void)
+        })),
+    });
+    // wait() blocks until the watch is stopped, keeping daemon scripts alive
+    methods.insert("wait".to_string(), Value::NativeFunction {
+        name: "wait".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            let mut finished = done.0.lock().unwrap();
+            while !*finished {
+                finished = done.1.wait(finished).unwrap();
+            }
+            Ok(Value::Void)
+        })),
+    });
+    Ok(Value::NativeModule(Arc::new(methods)))
+}
+
+#[cfg(not(feature = "fs-watch"))]
+fn watch(_interpreter: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    Err(Value::String(Arc::new("fs.watch needs pyro built with the 'fs-watch' feature".to_string())).into())
+}
+
 pub fn module() -> Value {
     let mut methods = HashMap::new();
     
-    methods.insert("read_to_string".to_string(), Value::NativeFunction {
+    methods.insert("read_to_string".to_string(), Value::InterpreterFunction {
         name: "read_to_string".to_string(),
-        func: NativeClosure(Arc::new(read_to_string)),
+        func: InterpreterClosure(Arc::new(read_to_string)),
     });
-    methods.insert("write".to_string(), Value::NativeFunction {
+    methods.insert("write".to_string(), Value::InterpreterFunction {
         name: "write".to_string(),
-        func: NativeClosure(Arc::new(write)),
+        func: InterpreterClosure(Arc::new(write)),
     });
-    methods.insert("exists".to_string(), Value::NativeFunction {
+    methods.insert("exists".to_string(), Value::InterpreterFunction {
         name: "exists".to_string(),
-        func: NativeClosure(Arc::new(exists)),
+        func: InterpreterClosure(Arc::new(exists)),
     });
-    methods.insert("is_file".to_string(), Value::NativeFunction {
+    methods.insert("is_file".to_string(), Value::InterpreterFunction {
         name: "is_file".to_string(),
-        func: NativeClosure(Arc::new(is_file)),
+        func: InterpreterClosure(Arc::new(is_file)),
     });
-    methods.insert("is_dir".to_string(), Value::NativeFunction {
+    methods.insert("is_dir".to_string(), Value::InterpreterFunction {
         name: "is_dir".to_string(),
-        func: NativeClosure(Arc::new(is_dir)),
+        func: InterpreterClosure(Arc::new(is_dir)),
     });
-    methods.insert("create_dir".to_string(), Value::NativeFunction {
+    methods.insert("create_dir".to_string(), Value::InterpreterFunction {
         name: "create_dir".to_string(),
-        func: NativeClosure(Arc::new(create_dir)),
+        func: InterpreterClosure(Arc::new(create_dir)),
     });
-    methods.insert("remove_file".to_string(), Value::NativeFunction {
+    methods.insert("remove_file".to_string(), Value::InterpreterFunction {
         name: "remove_file".to_string(),
-        func: NativeClosure(Arc::new(remove_file)),
+        func: InterpreterClosure(Arc::new(remove_file)),
     });
-    methods.insert("remove_dir".to_string(), Value::NativeFunction {
+    methods.insert("remove_dir".to_string(), Value::InterpreterFunction {
         name: "remove_dir".to_string(),
-        func: NativeClosure(Arc::new(remove_dir)),
+        func: InterpreterClosure(Arc::new(remove_dir)),
     });
-    methods.insert("list_dir".to_string(), Value::NativeFunction {
+    methods.insert("list_dir".to_string(), Value::InterpreterFunction {
         name: "list_dir".to_string(),
-        func: NativeClosure(Arc::new(list_dir)),
+        func: InterpreterClosure(Arc::new(list_dir)),
+    });
+    methods.insert("open".to_string(), Value::InterpreterFunction {
+        name: "open".to_string(),
+        func: InterpreterClosure(Arc::new(open)),
+    });
+    methods.insert("write_atomic".to_string(), Value::InterpreterFunction {
+        name: "write_atomic".to_string(),
+        func: InterpreterClosure(Arc::new(write_atomic)),
+    });
+    methods.insert("lock".to_string(), Value::InterpreterFunction {
+        name: "lock".to_string(),
+        func: InterpreterClosure(Arc::new(lock)),
+    });
+    methods.insert("unlock".to_string(), Value::NativeFunction {
+        name: "unlock".to_string(),
+        func: NativeClosure(Arc::new(unlock)),
+    });
+    methods.insert("temp_dir".to_string(), Value::InterpreterFunction {
+        name: "temp_dir".to_string(),
+        func: InterpreterClosure(Arc::new(temp_dir)),
+    });
+    methods.insert("temp_file".to_string(), Value::InterpreterFunction {
+        name: "temp_file".to_string(),
+        func: InterpreterClosure(Arc::new(temp_file)),
+    });
+    methods.insert("watch".to_string(), Value::InterpreterFunction {
+        name: "watch".to_string(),
+        func: InterpreterClosure(Arc::new(watch)),
     });
 
     Value::NativeModule(Arc::new(methods))
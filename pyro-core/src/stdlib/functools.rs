@@ -0,0 +1,167 @@
+use crate::interpreter::{Interpreter, InterpreterClosure, RuntimeError, Value};
+use crate::convert::FromPyroValue;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+fn is_callable(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Function { .. }
+            | Value::NativeFunction { .. }
+            | Value::InterpreterFunction { .. }
+            | Value::BoundMethod { .. }
+            | Value::BuiltinMethod { .. }
+            | Value::RecordConstructor { .. }
+            | Value::Class { .. }
+    )
+}
+
+fn callable_arg(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args.first() {
+        Some(f) if is_callable(f) => Ok(f.clone()),
+        _ => Err(err("Expected a function as the first argument".to_string())),
+    }
+}
+
+fn wrap(name: &str, f: impl Fn(&mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static) -> Value {
+    Value::InterpreterFunction {
+        name: name.to_string(),
+        func: InterpreterClosure(Arc::new(f)),
+    }
+}
+
+// Values have no Hash impl, so cache entries are keyed by the debug form of
+// the arguments. Mutable collections are keyed by their contents at call time.
+fn cache_key(args: &[Value]) -> String {
+    format!("{:?}", args)
+}
+
+// Results are cached only when the call succeeds. The lock is never held while
+// the wrapped function runs, so recursive memoized functions work.
+struct Lru {
+    capacity: Option<usize>,
+    entries: HashMap<String, Value>,
+    order: VecDeque<String>,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<Value> {
+        let value = self.entries.get(key)?.clone();
+        if self.capacity.is_some() {
+            self.order.retain(|k| k != key);
+            self.order.push_back(key.to_string());
+        }
+        Some(value)
+    }
+
+    fn insert(&mut self, key: String, value: Value) {
+        if let Some(capacity) = self.capacity {
+            if self.entries.contains_key(&key) {
+                self.order.retain(|k| k != &key);
+            } else if self.entries.len() >= capacity && let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+            self.order.push_back(key.clone());
+        }
+        self.entries.insert(key, value);
+    }
+}
+
+fn cached(name: &str, func: Value, capacity: Option<usize>) -> Value {
+    let cache = Mutex::new(Lru { capacity, entries: HashMap::new(), order: VecDeque::new() });
+    wrap(name, move |interpreter, args| {
+        let key = cache_key(&args);
+        if let Some(hit) = cache.lock().unwrap().get(&key) {
+            return Ok(hit);
+        }
+        let result = interpreter.call(func.clone(), args)?;
+        if capacity != Some(0) {
+            cache.lock().unwrap().insert(key, result.clone());
+        }
+        Ok(result)
+    })
+}
+
+// memoize(fn) caches every result by argument
+fn memoize(_interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument (fn)".to_string()));
+    }
+    Ok(cached("memoize", callable_arg(&args)?, None))
+}
+
+// lru_cache(fn, size) keeps the `size` most recently used results
+fn lru_cache(_interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (fn, size)".to_string()));
+    }
+    let func = callable_arg(&args)?;
+    let size: i64 = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    if size < 0 {
+        return Err(err("Cache size must not be negative".to_string()));
+    }
+    Ok(cached("lru_cache", func, Some(size as usize)))
+}
+
+// once(fn) runs fn on the first successful call and returns that result
+// afterwards, whatever the arguments
+fn once(_interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument (fn)".to_string()));
+    }
+    let func = callable_arg(&args)?;
+    let result: Mutex<Option<Value>> = Mutex::new(None);
+    Ok(wrap("once", move |interpreter, args| {
+        if let Some(done) = result.lock().unwrap().clone() {
+            return Ok(done);
+        }
+        let value = interpreter.call(func.clone(), args)?;
+        Ok(result.lock().unwrap().get_or_insert(value).clone())
+    }))
+}
+
+// partial(fn, args...) fixes leading arguments. Pyro functions reuse their
+// own partial application, so the result curries like any other function.
+fn partial(_interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let func = callable_arg(&args)?;
+    let bound = args[1..].to_vec();
+    if let Value::Function { generics, params, body, mut partial_args, env } = func {
+        if partial_args.len() + bound.len() > params.len() {
+            return Err(err(format!("Function takes {} arguments but {} were given", params.len(), partial_args.len() + bound.len())));
+        }
+        partial_args.extend(bound);
+        return Ok(Value::Function { generics, params, body, partial_args, env });
+    }
+    Ok(wrap("partial", move |interpreter, args| {
+        let mut all = bound.clone();
+        all.extend(args);
+        interpreter.call(func.clone(), all)
+    }))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("memoize".to_string(), Value::InterpreterFunction {
+        name: "memoize".to_string(),
+        func: InterpreterClosure(Arc::new(memoize)),
+    });
+    methods.insert("lru_cache".to_string(), Value::InterpreterFunction {
+        name: "lru_cache".to_string(),
+        func: InterpreterClosure(Arc::new(lru_cache)),
+    });
+    methods.insert("once".to_string(), Value::InterpreterFunction {
+        name: "once".to_string(),
+        func: InterpreterClosure(Arc::new(once)),
+    });
+    methods.insert("partial".to_string(), Value::InterpreterFunction {
+        name: "partial".to_string(),
+        func: InterpreterClosure(Arc::new(partial)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
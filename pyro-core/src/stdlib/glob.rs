@@ -0,0 +1,112 @@
+use crate::interpreter::{Value, Interpreter, InterpreterClosure, NativeClosure, RuntimeError};
+use crate::permissions;
+use crate::convert::FromPyroValue;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// `*` and `?` stop at path separators and `**` matches any number of
+// directories, as in .gitignore and most build tools.
+fn compile(pattern: &str) -> Result<Glob, RuntimeError> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| err(format!("Invalid glob pattern '{}': {}", pattern, e)))
+}
+
+fn build_set(patterns: &[String]) -> Result<GlobSet, RuntimeError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(compile(pattern)?);
+    }
+    builder.build().map_err(|e| err(e.to_string()))
+}
+
+// Strips "./" so paths compare the same way whether or not they were written
+// with it.
+fn normalize(path: &str) -> &str {
+    path.strip_prefix("./").unwrap_or(path)
+}
+
+// match(pattern, path) -> bool
+fn glob_match(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (pattern, path)".to_string()));
+    }
+    let pattern: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let path: String = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    let matcher = compile(normalize(&pattern))?.compile_matcher();
+    Ok(Value::Bool(matcher.is_match(normalize(&path))))
+}
+
+// walk(root, patterns, respect_gitignore = true) -> [str]
+// Patterns starting with "!" exclude files matched by the others.
+fn walk(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(err("Expected 2 or 3 arguments (root, patterns, respect_gitignore)".to_string()));
+    }
+    let root: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let patterns: Vec<String> = match &args[1] {
+        Value::String(s) => vec![s.to_string()],
+        other => FromPyroValue::from_value(other).map_err(err)?,
+    };
+    let respect_gitignore = match args.get(2) {
+        Some(v) => FromPyroValue::from_value(v).map_err(err)?,
+        None => true,
+    };
+
+    let (excludes, includes): (Vec<String>, Vec<String>) = patterns
+        .iter()
+        .map(|p| normalize(p).to_string())
+        .partition(|p| p.starts_with('!'));
+    let excludes: Vec<String> = excludes.iter().map(|p| p[1..].to_string()).collect();
+    let includes = build_set(&includes)?;
+    let excludes = build_set(&excludes)?;
+
+    permissions::check_fs(interpreter, &root)?;
+    let root_path = Path::new(&root);
+    let walker = WalkBuilder::new(root_path)
+        .standard_filters(respect_gitignore)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|e| err(format!("Could not walk '{}': {}", root, e)))?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        // Patterns are matched against the path relative to the root
+        let relative = entry.path().strip_prefix(root_path).unwrap_or(entry.path());
+        if includes.is_match(relative) && !excludes.is_match(relative) {
+            let path = entry.path().to_string_lossy();
+            files.push(normalize(&path).to_string());
+        }
+    }
+    files.sort();
+
+    Ok(Value::List(Arc::new(files.into_iter().map(|f| Value::String(Arc::new(f))).collect())))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("match".to_string(), Value::NativeFunction {
+        name: "match".to_string(),
+        func: NativeClosure(Arc::new(glob_match)),
+    });
+    methods.insert("walk".to_string(), Value::InterpreterFunction {
+        name: "walk".to_string(),
+        func: InterpreterClosure(Arc::new(walk)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,93 @@
+use crate::interpreter::{io_error, Interpreter, InterpreterClosure, RuntimeError, Value};
+use crate::permissions;
+use crate::convert::FromPyroValue;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// Files are read in chunks, so large ones aren't held in memory
+fn update_from_file(hasher: &mut Sha256, path: &Path) -> io::Result<()> {
+    let mut file = fs::File::open(path)?;
+    io::copy(&mut file, hasher)?;
+    Ok(())
+}
+
+// SHA-256 of a file's content, in hex
+pub fn file_checksum(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    update_from_file(&mut hasher, path)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// SHA-256 over every file under `path` in name order: each file's content
+// followed by its path as walked, so renaming a file changes the checksum.
+// Files with a path component named in `exclude` are left out. `pyro install`
+// records this for each package in pyro.lock.
+pub fn dir_checksum(path: &Path, exclude: &[String]) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    for entry in WalkDir::new(path).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            continue;
+        }
+        if path.components().any(|c| exclude.iter().any(|name| c.as_os_str() == name.as_str())) {
+            continue;
+        }
+        update_from_file(&mut hasher, path)?;
+        hasher.update(path.to_string_lossy().as_bytes());
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// file(path) -> str
+fn file(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument (path)".to_string()));
+    }
+    let path: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    permissions::check_fs(interpreter, &path)?;
+    let checksum = file_checksum(Path::new(&path)).map_err(|e| io_error(&e))?;
+    Ok(Value::String(Arc::new(checksum)))
+}
+
+// dir(path, exclude = [".git"]) -> str
+fn dir(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(err("Expected 1 or 2 arguments (path, exclude)".to_string()));
+    }
+    let path: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let exclude: Vec<String> = match args.get(1) {
+        None | Some(Value::Void) => vec![".git".to_string()],
+        Some(exclude) => FromPyroValue::from_value(exclude).map_err(err)?,
+    };
+    permissions::check_fs(interpreter, &path)?;
+    if !Path::new(&path).is_dir() {
+        return Err(err(format!("'{}' is not a directory", path)));
+    }
+    let checksum = dir_checksum(Path::new(&path), &exclude).map_err(|e| io_error(&e))?;
+    Ok(Value::String(Arc::new(checksum)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("file".to_string(), Value::InterpreterFunction {
+        name: "file".to_string(),
+        func: InterpreterClosure(Arc::new(file)),
+    });
+    methods.insert("dir".to_string(), Value::InterpreterFunction {
+        name: "dir".to_string(),
+        func: InterpreterClosure(Arc::new(dir)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,244 @@
+use crate::interpreter::{Value, NativeClosure, io_error, RuntimeError};
+use crate::stdlib::json::{json_to_value, value_to_json};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+struct RecordedRequest {
+    method: String,
+    path: String,
+    body: String,
+}
+
+// Converts a route's canned response. A plain string is served as text, a
+// dict with any of status/body/headers is used as is, and anything else is
+// served as JSON.
+fn to_mock_response(value: &Value) -> Result<MockResponse, RuntimeError> {
+    let entries = match value {
+        Value::String(s) => {
+            return Ok(MockResponse {
+                status: 200,
+                headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+                body: s.to_string(),
+            });
+        }
+        Value::Dict(d) => d.to_vec(),
+        Value::DictMutable(d) => d.read().unwrap().clone(),
+        other => {
+            return Ok(MockResponse {
+                status: 200,
+                headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+                body: value_to_json(other).to_string(),
+            });
+        }
+    };
+
+    let is_spec = entries.iter().all(|(k, _)| {
+        matches!(k, Value::String(s) if matches!(s.as_str(), "status" | "body" | "headers"))
+    });
+    if !is_spec {
+        return Ok(MockResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: value_to_json(value).to_string(),
+        });
+    }
+
+    let mut response = MockResponse { status: 200, headers: Vec::new(), body: String::new() };
+    for (k, v) in entries {
+        let Value::String(key) = k else { continue };
+        match (key.as_str(), v) {
+            ("status", Value::Int(code)) => response.status = code as u16,
+            ("status", _) => return Err(err("Mock response status must be an int".to_string())),
+            ("body", Value::String(s)) => response.body = s.to_string(),
+            ("body", other) => {
+                response.body = value_to_json(&other).to_string();
+                response.headers.push(("Content-Type".to_string(), "application/json".to_string()));
+            }
+            ("headers", Value::Dict(h)) => {
+                for (name, val) in h.iter() {
+                    if let (Value::String(n), Value::String(v)) = (name, val) {
+                        response.headers.push((n.to_string(), v.to_string()));
+                    }
+                }
+            }
+            ("headers", _) => return Err(err("Mock response headers must be a dict".to_string())),
+            _ => {}
+        }
+    }
+    Ok(response)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    routes: &HashMap<String, MockResponse>,
+    requests: &Mutex<Vec<RecordedRequest>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+    if method.is_empty() {
+        return Ok(());
+    }
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    requests.lock().unwrap().push(RecordedRequest {
+        method: method.clone(),
+        path: path.clone(),
+        body: String::from_utf8_lossy(&body).to_string(),
+    });
+
+    // "GET /users" takes precedence over "/users"
+    let route_path = path.split('?').next().unwrap_or("");
+    let not_found = MockResponse { status: 404, headers: Vec::new(), body: "Not Found".to_string() };
+    let response = routes
+        .get(&format!("{} {}", method, route_path))
+        .or_else(|| routes.get(route_path))
+        .unwrap_or(&not_found);
+
+    let mut out = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+    for (name, value) in &response.headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+    out.push_str(&response.body);
+
+    let mut stream = stream;
+    stream.write_all(out.as_bytes())?;
+    stream.flush()
+}
+
+// mock(routes: dict) starts a local HTTP server on a free port. Route keys are
+// either a path ("/users") or a method and path ("POST /users").
+fn mock(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+
+    let entries = match &args[0] {
+        Value::Dict(d) => d.to_vec(),
+        Value::DictMutable(d) => d.read().unwrap().clone(),
+        _ => return Err(err("mock expects a dict of routes".to_string())),
+    };
+
+    let mut routes = HashMap::new();
+    for (k, v) in entries {
+        let Value::String(route) = k else {
+            return Err(err("Route keys must be strings".to_string()));
+        };
+        routes.insert(route.to_string(), to_mock_response(&v)?);
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| io_error(&e))?;
+    let addr = listener.local_addr().map_err(|e| io_error(&e))?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    {
+        let stopped = stopped.clone();
+        let requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let _ = handle_connection(stream, &routes, &requests);
+                }
+            }
+        });
+    }
+
+    let mut server = HashMap::new();
+    server.insert("url".to_string(), Value::String(Arc::new(format!("http://{}", addr))));
+    server.insert("port".to_string(), Value::Int(addr.port() as i64));
+
+    server.insert("stop".to_string(), Value::NativeFunction {
+        name: "stop".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            if !stopped.swap(true, Ordering::AcqRel) {
+                // Wake the accept loop so it sees the flag
+                let _ = TcpStream::connect(addr);
+            }
+            Ok(Value::Void)
+        })),
+    });
+
+    server.insert("requests".to_string(), Value::NativeFunction {
+        name: "requests".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            let recorded = requests.lock().unwrap();
+            let list = recorded
+                .iter()
+                .map(|r| {
+                    let json = serde_json::json!({ "method": r.method, "path": r.path, "body": r.body });
+                    json_to_value(&json)
+                })
+                .collect();
+            Ok(Value::List(Arc::new(list)))
+        })),
+    });
+
+    Ok(Value::NativeModule(Arc::new(server)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("mock".to_string(), Value::NativeFunction {
+        name: "mock".to_string(),
+        func: NativeClosure(Arc::new(mock)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -1,11 +1,11 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{Value, NativeClosure, io_error, RuntimeError};
 use crate::convert::{FromPyroValue};
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use serde_json;
 
-fn value_to_json(val: &Value) -> serde_json::Value {
+pub(crate) fn value_to_json(val: &Value) -> serde_json::Value {
     match val {
         Value::Int(i) => serde_json::Value::Number((*i).into()),
         Value::Float(f) => {
@@ -15,8 +15,18 @@ fn value_to_json(val: &Value) -> serde_json::Value {
                 serde_json::Value::Null
             }
         },
+        // Numbers keep their digits (serde_json's arbitrary_precision), so a
+        // decimal is written exactly, trailing zeros included
+        Value::Decimal(d) => {
+            match d.to_string().parse::<serde_json::Number>() {
+                Ok(n) => serde_json::Value::Number(n),
+                Err(_) => serde_json::Value::Null,
+            }
+        },
         Value::Bool(b) => serde_json::Value::Bool(*b),
         Value::String(s) => serde_json::Value::String(s.to_string()),
+        Value::Char(c) => serde_json::Value::String(c.to_string()),
+        Value::Path(p) => serde_json::Value::String(p.to_string_lossy().into_owned()),
         Value::List(l) => {
             let vec: Vec<serde_json::Value> = l.iter().map(value_to_json).collect();
             serde_json::Value::Array(vec)
@@ -68,55 +78,68 @@ fn value_to_json(val: &Value) -> serde_json::Value {
     }
 }
 
-fn json_to_value(val: &serde_json::Value) -> Value {
+pub(crate) fn json_to_value(val: &serde_json::Value) -> Value {
+    json_to_value_with(val, false)
+}
+
+// With `decimals`, non-integer numbers become Decimal values instead of floats
+fn json_to_value_with(val: &serde_json::Value, decimals: bool) -> Value {
     match val {
         serde_json::Value::Null => Value::Void,
         serde_json::Value::Bool(b) => Value::Bool(*b),
         serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                Value::Int(n.as_i64().unwrap())
-            } else if n.is_f64() {
-                Value::Float(n.as_f64().unwrap())
+            if let Some(i) = n.as_i64() {
+                Value::Int(i)
+            } else if decimals && let Ok(d) = rust_decimal::Decimal::from_str_exact(&n.to_string())
+                .or_else(|_| rust_decimal::Decimal::from_scientific(&n.to_string())) {
+                Value::Decimal(d)
             } else {
-                Value::Int(n.as_i64().unwrap_or(0)) // fallback
+                // Including integers too big for an int
+                Value::Float(n.as_f64().unwrap_or(f64::NAN))
             }
         },
         serde_json::Value::String(s) => Value::String(Arc::new(s.clone())),
         serde_json::Value::Array(arr) => {
-            let vec: Vec<Value> = arr.iter().map(json_to_value).collect();
+            let vec: Vec<Value> = arr.iter().map(|v| json_to_value_with(v, decimals)).collect();
             Value::List(Arc::new(vec))
         },
         serde_json::Value::Object(obj) => {
             let mut vec = Vec::new();
             for (k, v) in obj {
-                vec.push((Value::String(Arc::new(k.clone())), json_to_value(v)));
+                vec.push((Value::String(Arc::new(k.clone())), json_to_value_with(v, decimals)));
             }
             Value::Dict(Arc::new(vec))
         },
     }
 }
 
-fn stringify(args: Vec<Value>) -> Result<Value, Value> {
+fn stringify(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let json_val = value_to_json(&args[0]);
     match serde_json::to_string(&json_val) {
         Ok(s) => Ok(Value::String(Arc::new(s))),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(Value::String(Arc::new(e.to_string())).into()),
     }
 }
 
-fn parse(args: Vec<Value>) -> Result<Value, Value> {
-    if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+// parse(text, decimals = false)
+fn parse(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(Value::String(Arc::new("Expected 1 or 2 arguments (text, decimals)".to_string())).into());
     }
     let s: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    let decimals: bool = match args.get(1) {
+        Some(v) => FromPyroValue::from_value(v).map_err(|e| Value::String(Arc::new(e)))?,
+        None => false,
+    };
     
     match serde_json::from_str(&s) {
-        Ok(v) => Ok(json_to_value(&v)),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Ok(v) => Ok(json_to_value_with(&v, decimals)),
+        // serde_json maps syntax errors to InvalidData and truncated input to UnexpectedEof
+        Err(e) => Err(io_error(&e.into())),
     }
 }
 
@@ -0,0 +1,322 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::convert::FromPyroValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// A dense row-major matrix of f64 in a single contiguous buffer. Vectors are
+// column matrices (n x 1), so the same operations apply to both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f64>,
+}
+
+impl Matrix {
+    pub fn zeros(rows: usize, cols: usize) -> Matrix {
+        Matrix { rows, cols, data: vec![0.0; rows * cols] }
+    }
+
+    pub fn identity(n: usize) -> Matrix {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            m.data[i * n + i] = 1.0;
+        }
+        m
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.cols == 1
+    }
+
+    fn zip_with(&self, other: &Matrix, op: &str, f: impl Fn(f64, f64) -> f64) -> Result<Matrix, String> {
+        if self.rows != other.rows || self.cols != other.cols {
+            return Err(format!(
+                "Cannot {} a {}x{} matrix and a {}x{} matrix",
+                op, self.rows, self.cols, other.rows, other.cols
+            ));
+        }
+        let data = self.data.iter().zip(&other.data).map(|(a, b)| f(*a, *b)).collect();
+        Ok(Matrix { rows: self.rows, cols: self.cols, data })
+    }
+
+    pub fn add(&self, other: &Matrix) -> Result<Matrix, String> {
+        self.zip_with(other, "add", |a, b| a + b)
+    }
+
+    pub fn sub(&self, other: &Matrix) -> Result<Matrix, String> {
+        self.zip_with(other, "subtract", |a, b| a - b)
+    }
+
+    pub fn scale(&self, factor: f64) -> Matrix {
+        Matrix { rows: self.rows, cols: self.cols, data: self.data.iter().map(|x| x * factor).collect() }
+    }
+
+    pub fn mul(&self, other: &Matrix) -> Result<Matrix, String> {
+        if self.cols != other.rows {
+            return Err(format!(
+                "Cannot multiply a {}x{} matrix by a {}x{} matrix",
+                self.rows, self.cols, other.rows, other.cols
+            ));
+        }
+        let mut out = Matrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self.data[i * self.cols + k];
+                let row = &other.data[k * other.cols..(k + 1) * other.cols];
+                let target = &mut out.data[i * other.cols..(i + 1) * other.cols];
+                for (t, b) in target.iter_mut().zip(row) {
+                    *t += a * b;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut out = Matrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                out.data[j * self.rows + i] = self.data[i * self.cols + j];
+            }
+        }
+        out
+    }
+
+    pub fn dot(&self, other: &Matrix) -> Result<f64, String> {
+        if self.data.len() != other.data.len() {
+            return Err(format!(
+                "Cannot take the dot product of {} and {} elements",
+                self.data.len(),
+                other.data.len()
+            ));
+        }
+        Ok(self.data.iter().zip(&other.data).map(|(a, b)| a * b).sum())
+    }
+}
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+fn to_f64(value: &Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        _ => Err(err("Expected a number".to_string())),
+    }
+}
+
+fn to_numbers(value: &Value) -> Result<Vec<f64>, RuntimeError> {
+    let items = match value {
+        Value::List(l) => l.to_vec(),
+        Value::ListMutable(l) => l.read().unwrap().clone(),
+        _ => return Err(err("Expected a list of numbers".to_string())),
+    };
+    items.iter().map(to_f64).collect()
+}
+
+fn matrix_arg(args: &[Value], index: usize) -> Result<Arc<Matrix>, RuntimeError> {
+    match args.get(index) {
+        Some(Value::Matrix(m)) => Ok(m.clone()),
+        Some(_) => Err(err("Expected a vector or matrix".to_string())),
+        None => Err(err(format!("Expected {} arguments", index + 1))),
+    }
+}
+
+fn expect_args(args: &[Value], n: usize) -> Result<(), RuntimeError> {
+    if args.len() != n {
+        let noun = if n == 1 { "argument" } else { "arguments" };
+        return Err(err(format!("Expected {} {}", n, noun)));
+    }
+    Ok(())
+}
+
+fn wrap(m: Matrix) -> Value {
+    Value::Matrix(Arc::new(m))
+}
+
+// vector([numbers]) -> n x 1 matrix
+fn vector(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    let data = to_numbers(&args[0])?;
+    Ok(wrap(Matrix { rows: data.len(), cols: 1, data }))
+}
+
+// matrix([[numbers]]) from a list of rows
+fn matrix(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    let rows = match &args[0] {
+        Value::List(l) => l.to_vec(),
+        Value::ListMutable(l) => l.read().unwrap().clone(),
+        _ => return Err(err("matrix expects a list of rows".to_string())),
+    };
+    let mut data = Vec::new();
+    let mut cols = None;
+    for row in &rows {
+        let values = to_numbers(row)?;
+        if *cols.get_or_insert(values.len()) != values.len() {
+            return Err(err("All matrix rows must have the same length".to_string()));
+        }
+        data.extend(values);
+    }
+    Ok(wrap(Matrix { rows: rows.len(), cols: cols.unwrap_or(0), data }))
+}
+
+// Checks the dimensions of a matrix to be allocated, which must be
+// non-negative and small enough for its buffer to be addressable
+fn dimensions(rows: i64, cols: i64) -> Result<(usize, usize), RuntimeError> {
+    if rows < 0 || cols < 0 {
+        return Err(err("Matrix dimensions must not be negative".to_string()));
+    }
+    let (rows, cols) = (rows as usize, cols as usize);
+    match rows.checked_mul(cols) {
+        Some(len) if len <= isize::MAX as usize / std::mem::size_of::<f64>() => Ok((rows, cols)),
+        _ => Err(err("Matrix too large".to_string())),
+    }
+}
+
+fn zeros(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 2)?;
+    let rows: i64 = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let cols: i64 = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    let (rows, cols) = dimensions(rows, cols)?;
+    Ok(wrap(Matrix::zeros(rows, cols)))
+}
+
+fn identity(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    let n: i64 = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let (n, _) = dimensions(n, n)?;
+    Ok(wrap(Matrix::identity(n)))
+}
+
+fn add(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 2)?;
+    let (a, b) = (matrix_arg(&args, 0)?, matrix_arg(&args, 1)?);
+    a.add(&b).map(wrap).map_err(err)
+}
+
+fn sub(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 2)?;
+    let (a, b) = (matrix_arg(&args, 0)?, matrix_arg(&args, 1)?);
+    a.sub(&b).map(wrap).map_err(err)
+}
+
+// mul(a, b): matrix product, or scaling when either side is a number
+fn mul(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 2)?;
+    match (&args[0], &args[1]) {
+        (Value::Matrix(a), Value::Matrix(b)) => a.mul(b).map(wrap).map_err(err),
+        (Value::Matrix(m), n) | (n, Value::Matrix(m)) => Ok(wrap(m.scale(to_f64(n)?))),
+        _ => Err(err("mul expects a vector or matrix".to_string())),
+    }
+}
+
+fn transpose(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    Ok(wrap(matrix_arg(&args, 0)?.transpose()))
+}
+
+fn dot(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 2)?;
+    let (a, b) = (matrix_arg(&args, 0)?, matrix_arg(&args, 1)?);
+    a.dot(&b).map(Value::Float).map_err(err)
+}
+
+fn get(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(err("Expected 2 or 3 arguments (m, row, col)".to_string()));
+    }
+    let m = matrix_arg(&args, 0)?;
+    let row: i64 = FromPyroValue::from_value(&args[1]).map_err(err)?;
+    let col: i64 = match args.get(2) {
+        Some(v) => FromPyroValue::from_value(v).map_err(err)?,
+        None => 0,
+    };
+    if row < 0 || col < 0 || row as usize >= m.rows || col as usize >= m.cols {
+        return Err(err("Index out of bounds".to_string()));
+    }
+    Ok(Value::Float(m.get(row as usize, col as usize)))
+}
+
+fn shape(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    let m = matrix_arg(&args, 0)?;
+    Ok(Value::Tuple(Arc::new(vec![Value::Int(m.rows as i64), Value::Int(m.cols as i64)])))
+}
+
+// to_list(m): a flat list for vectors, a list of rows for matrices
+fn to_list(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    expect_args(&args, 1)?;
+    let m = matrix_arg(&args, 0)?;
+    let floats = |xs: &[f64]| Value::List(Arc::new(xs.iter().map(|x| Value::Float(*x)).collect()));
+    if m.is_vector() {
+        return Ok(floats(&m.data));
+    }
+    let rows = if m.cols == 0 {
+        vec![Value::List(Arc::new(Vec::new())); m.rows]
+    } else {
+        m.data.chunks(m.cols).map(floats).collect()
+    };
+    Ok(Value::List(Arc::new(rows)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("vector".to_string(), Value::NativeFunction {
+        name: "vector".to_string(),
+        func: NativeClosure(Arc::new(vector)),
+    });
+    methods.insert("matrix".to_string(), Value::NativeFunction {
+        name: "matrix".to_string(),
+        func: NativeClosure(Arc::new(matrix)),
+    });
+    methods.insert("zeros".to_string(), Value::NativeFunction {
+        name: "zeros".to_string(),
+        func: NativeClosure(Arc::new(zeros)),
+    });
+    methods.insert("identity".to_string(), Value::NativeFunction {
+        name: "identity".to_string(),
+        func: NativeClosure(Arc::new(identity)),
+    });
+    methods.insert("add".to_string(), Value::NativeFunction {
+        name: "add".to_string(),
+        func: NativeClosure(Arc::new(add)),
+    });
+    methods.insert("sub".to_string(), Value::NativeFunction {
+        name: "sub".to_string(),
+        func: NativeClosure(Arc::new(sub)),
+    });
+    methods.insert("mul".to_string(), Value::NativeFunction {
+        name: "mul".to_string(),
+        func: NativeClosure(Arc::new(mul)),
+    });
+    methods.insert("transpose".to_string(), Value::NativeFunction {
+        name: "transpose".to_string(),
+        func: NativeClosure(Arc::new(transpose)),
+    });
+    methods.insert("dot".to_string(), Value::NativeFunction {
+        name: "dot".to_string(),
+        func: NativeClosure(Arc::new(dot)),
+    });
+    methods.insert("get".to_string(), Value::NativeFunction {
+        name: "get".to_string(),
+        func: NativeClosure(Arc::new(get)),
+    });
+    methods.insert("shape".to_string(), Value::NativeFunction {
+        name: "shape".to_string(),
+        func: NativeClosure(Arc::new(shape)),
+    });
+    methods.insert("to_list".to_string(), Value::NativeFunction {
+        name: "to_list".to_string(),
+        func: NativeClosure(Arc::new(to_list)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
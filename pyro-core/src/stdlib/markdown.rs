@@ -0,0 +1,36 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::convert::FromPyroValue;
+use pulldown_cmark::{html, Options, Parser};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// CommonMark plus the GitHub extensions people expect in reports: tables,
+// strikethrough, task lists and footnotes.
+fn to_html(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+
+    let text: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+
+    let mut output = String::new();
+    html::push_html(&mut output, Parser::new_ext(&text, options));
+    Ok(Value::String(Arc::new(output)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("to_html".to_string(), Value::NativeFunction {
+        name: "to_html".to_string(),
+        func: NativeClosure(Arc::new(to_html)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -1,11 +1,34 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
 use crate::convert::{FromPyroValue, ToPyroValue};
+use num_complex::Complex64;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-fn sqrt(args: Vec<Value>) -> Result<Value, Value> {
+fn complex_arg(value: &Value) -> Option<Complex64> {
+    match value {
+        Value::Complex(z) => Some(*z),
+        _ => None,
+    }
+}
+
+// Like complex_arg, but reals are accepted as complex numbers with no imaginary part
+fn to_complex(value: &Value) -> Result<Complex64, RuntimeError> {
+    match value {
+        Value::Complex(z) => Ok(*z),
+        other => {
+            let re: f64 = FromPyroValue::from_value(other)
+                .map_err(|_| Value::String(Arc::new("Expected a number or complex".to_string())))?;
+            Ok(Complex64::new(re, 0.0))
+        }
+    }
+}
+
+fn sqrt(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.sqrt()));
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -14,9 +37,12 @@ fn sqrt(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.sqrt().to_value())
 }
 
-fn abs(args: Vec<Value>) -> Result<Value, Value> {
+fn abs(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Float(z.norm()));
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -25,9 +51,9 @@ fn abs(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.abs().to_value())
 }
 
-fn ceil(args: Vec<Value>) -> Result<Value, Value> {
+fn ceil(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -36,9 +62,9 @@ fn ceil(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.ceil().to_value())
 }
 
-fn floor(args: Vec<Value>) -> Result<Value, Value> {
+fn floor(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -47,9 +73,9 @@ fn floor(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.floor().to_value())
 }
 
-fn round(args: Vec<Value>) -> Result<Value, Value> {
+fn round(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -58,9 +84,12 @@ fn round(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.round().to_value())
 }
 
-fn sin(args: Vec<Value>) -> Result<Value, Value> {
+fn sin(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.sin()));
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -69,9 +98,12 @@ fn sin(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.sin().to_value())
 }
 
-fn cos(args: Vec<Value>) -> Result<Value, Value> {
+fn cos(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.cos()));
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -80,9 +112,12 @@ fn cos(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.cos().to_value())
 }
 
-fn tan(args: Vec<Value>) -> Result<Value, Value> {
+fn tan(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.tan()));
     }
 
     let val: f64 = FromPyroValue::from_value(&args[0])
@@ -91,9 +126,13 @@ fn tan(args: Vec<Value>) -> Result<Value, Value> {
     Ok(val.tan().to_value())
 }
 
-fn pow(args: Vec<Value>) -> Result<Value, Value> {
+fn pow(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 2 {
-        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())));
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
+    }
+
+    if complex_arg(&args[0]).is_some() || complex_arg(&args[1]).is_some() {
+        return Ok(Value::Complex(to_complex(&args[0])?.powc(to_complex(&args[1])?)));
     }
 
     let base: f64 = FromPyroValue::from_value(&args[0])
@@ -104,36 +143,36 @@ fn pow(args: Vec<Value>) -> Result<Value, Value> {
     Ok(base.powf(exp).to_value())
 }
 
-fn asin(args: Vec<Value>) -> Result<Value, Value> {
+fn asin(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let val: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.asin().to_value())
 }
 
-fn acos(args: Vec<Value>) -> Result<Value, Value> {
+fn acos(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let val: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.acos().to_value())
 }
 
-fn atan(args: Vec<Value>) -> Result<Value, Value> {
+fn atan(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let val: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.atan().to_value())
 }
 
-fn atan2(args: Vec<Value>) -> Result<Value, Value> {
+fn atan2(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 2 {
-        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())));
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
     }
     let y: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -142,40 +181,109 @@ fn atan2(args: Vec<Value>) -> Result<Value, Value> {
     Ok(y.atan2(x).to_value())
 }
 
-fn log(args: Vec<Value>) -> Result<Value, Value> {
+fn log(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 2 {
-        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())));
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
     }
-    let val: f64 = FromPyroValue::from_value(&args[0])
-        .map_err(|e| Value::String(Arc::new(e)))?;
     let base: f64 = FromPyroValue::from_value(&args[1])
         .map_err(|e| Value::String(Arc::new(e)))?;
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.log(base)));
+    }
+    let val: f64 = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.log(base).to_value())
 }
 
-fn log2(args: Vec<Value>) -> Result<Value, Value> {
+fn log2(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let val: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.log2().to_value())
 }
 
-fn log10(args: Vec<Value>) -> Result<Value, Value> {
+fn log10(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let val: f64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     Ok(val.log10().to_value())
 }
 
-fn pi(_args: Vec<Value>) -> Result<Value, Value> {
+fn exp(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    if let Some(z) = complex_arg(&args[0]) {
+        return Ok(Value::Complex(z.exp()));
+    }
+    let val: f64 = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    Ok(val.exp().to_value())
+}
+
+// complex(re, im = 0)
+fn complex(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(Value::String(Arc::new("Expected 1 or 2 arguments (re, im)".to_string())).into());
+    }
+    let re: f64 = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    let im: f64 = match args.get(1) {
+        Some(v) => FromPyroValue::from_value(v).map_err(|e| Value::String(Arc::new(e)))?,
+        None => 0.0,
+    };
+    Ok(Value::Complex(Complex64::new(re, im)))
+}
+
+// polar(r, theta) builds a complex number from its modulus and argument
+fn polar(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(Value::String(Arc::new("Expected 2 arguments".to_string())).into());
+    }
+    let r: f64 = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    let theta: f64 = FromPyroValue::from_value(&args[1])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    Ok(Value::Complex(Complex64::from_polar(r, theta)))
+}
+
+fn re(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    Ok(to_complex(&args[0])?.re.to_value())
+}
+
+fn im(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    Ok(to_complex(&args[0])?.im.to_value())
+}
+
+fn arg(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    Ok(to_complex(&args[0])?.arg().to_value())
+}
+
+fn conjugate(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+    Ok(Value::Complex(to_complex(&args[0])?.conj()))
+}
+
+fn pi(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     Ok(std::f64::consts::PI.to_value())
 }
 
-fn e(_args: Vec<Value>) -> Result<Value, Value> {
+fn e(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     Ok(std::f64::consts::E.to_value())
 }
 
@@ -254,6 +362,34 @@ pub fn module() -> Value {
         name: "e".to_string(),
         func: NativeClosure(Arc::new(e)),
     });
+    methods.insert("exp".to_string(), Value::NativeFunction {
+        name: "exp".to_string(),
+        func: NativeClosure(Arc::new(exp)),
+    });
+    methods.insert("complex".to_string(), Value::NativeFunction {
+        name: "complex".to_string(),
+        func: NativeClosure(Arc::new(complex)),
+    });
+    methods.insert("polar".to_string(), Value::NativeFunction {
+        name: "polar".to_string(),
+        func: NativeClosure(Arc::new(polar)),
+    });
+    methods.insert("re".to_string(), Value::NativeFunction {
+        name: "re".to_string(),
+        func: NativeClosure(Arc::new(re)),
+    });
+    methods.insert("im".to_string(), Value::NativeFunction {
+        name: "im".to_string(),
+        func: NativeClosure(Arc::new(im)),
+    });
+    methods.insert("arg".to_string(), Value::NativeFunction {
+        name: "arg".to_string(),
+        func: NativeClosure(Arc::new(arg)),
+    });
+    methods.insert("conjugate".to_string(), Value::NativeFunction {
+        name: "conjugate".to_string(),
+        func: NativeClosure(Arc::new(conjugate)),
+    });
 
     Value::NativeModule(Arc::new(methods))
 }
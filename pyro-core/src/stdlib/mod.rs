@@ -6,16 +6,110 @@ pub mod path;
 pub mod process;
 pub mod json;
 pub mod random;
+pub mod runtime;
+pub mod config;
+pub mod http;
+#[cfg(feature = "email")]
+pub mod email;
+#[cfg(feature = "sftp")]
+pub mod sftp;
+pub mod compression;
+pub mod semver;
+pub mod glob;
+pub mod markdown;
+pub mod unicode;
+pub mod format;
+pub mod linalg;
+pub mod schedule;
+pub mod functools;
+pub mod events;
+pub mod validation;
+pub mod testing;
+pub mod hash;
+#[cfg(feature = "python-interop")]
+pub mod python;
 
-use crate::interpreter::Interpreter;
+use crate::interpreter::{Interpreter, Value};
+use crate::prelude::Prelude;
+
+pub type ModuleFactory = fn() -> Value;
+
+// Every native std module, in registration order
+pub const NATIVE_MODULES: &[(&str, ModuleFactory)] = &[
+    ("std.math", math::module),
+    ("std.fs", fs::module),
+    ("std.time", time::module),
+    ("std.env", env::module),
+    ("std.path", path::module),
+    ("std.process", process::module),
+    ("std.json", json::module),
+    ("std.random", random::module),
+    ("std.runtime", runtime::module),
+    ("std.config", config::module),
+    ("std.http", http::module),
+    #[cfg(feature = "email")]
+    ("std.email", email::module),
+    #[cfg(feature = "sftp")]
+    ("std.sftp", sftp::module),
+    ("std.compression", compression::module),
+    ("std.semver", semver::module),
+    ("std.glob", glob::module),
+    ("std.markdown", markdown::module),
+    ("std.unicode", unicode::module),
+    ("std.format", format::module),
+    ("std.linalg", linalg::module),
+    ("std.schedule", schedule::module),
+    ("std.functools", functools::module),
+    ("std.events", events::module),
+    ("std.validation", validation::module),
+    ("std.testing", testing::module),
+    ("std.hash", hash::module),
+    #[cfg(feature = "python-interop")]
+    ("std.python", python::module),
+];
+
+// Parts of the standard library written in Pyro. They load on `import std.x`,
+// merged with the native module of the same name if there is one.
+pub const EMBEDDED_MODULES: &[(&str, &str)] = &[
+    ("std.itertools", include_str!("pyro/itertools.pyro")),
+];
+
+// Signatures of the native modules' functions as `extern def` declarations,
+// which `crate::stubs` checks calls against before a program runs. Every
+// module in NATIVE_MODULES has one, declaring exactly its functions.
+pub const STUBS: &[(&str, &str)] = &[
+    ("std.math", include_str!("stubs/math.pyro")),
+    ("std.fs", include_str!("stubs/fs.pyro")),
+    ("std.time", include_str!("stubs/time.pyro")),
+    ("std.env", include_str!("stubs/env.pyro")),
+    ("std.path", include_str!("stubs/path.pyro")),
+    ("std.process", include_str!("stubs/process.pyro")),
+    ("std.json", include_str!("stubs/json.pyro")),
+    ("std.random", include_str!("stubs/random.pyro")),
+    ("std.runtime", include_str!("stubs/runtime.pyro")),
+    ("std.config", include_str!("stubs/config.pyro")),
+    ("std.http", include_str!("stubs/http.pyro")),
+    #[cfg(feature = "email")]
+    ("std.email", include_str!("stubs/email.pyro")),
+    #[cfg(feature = "sftp")]
+    ("std.sftp", include_str!("stubs/sftp.pyro")),
+    ("std.compression", include_str!("stubs/compression.pyro")),
+    ("std.semver", include_str!("stubs/semver.pyro")),
+    ("std.glob", include_str!("stubs/glob.pyro")),
+    ("std.markdown", include_str!("stubs/markdown.pyro")),
+    ("std.unicode", include_str!("stubs/unicode.pyro")),
+    ("std.format", include_str!("stubs/format.pyro")),
+    ("std.linalg", include_str!("stubs/linalg.pyro")),
+    ("std.schedule", include_str!("stubs/schedule.pyro")),
+    ("std.functools", include_str!("stubs/functools.pyro")),
+    ("std.events", include_str!("stubs/events.pyro")),
+    ("std.validation", include_str!("stubs/validation.pyro")),
+    ("std.testing", include_str!("stubs/testing.pyro")),
+    ("std.hash", include_str!("stubs/hash.pyro")),
+    #[cfg(feature = "python-interop")]
+    ("std.python", include_str!("stubs/python.pyro")),
+];
 
 pub fn register_std_libs(interpreter: &mut Interpreter) {
-    interpreter.register_native_module("std.math", math::module());
-    interpreter.register_native_module("std.fs", fs::module());
-    interpreter.register_native_module("std.time", time::module());
-    interpreter.register_native_module("std.env", env::module());
-    interpreter.register_native_module("std.path", path::module());
-    interpreter.register_native_module("std.process", process::module());
-    interpreter.register_native_module("std.json", json::module());
-    interpreter.register_native_module("std.random", random::module());
+    Prelude::full().install(interpreter);
 }
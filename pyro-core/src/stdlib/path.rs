@@ -1,12 +1,111 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{type_name, Interpreter, InterpreterClosure, Value, NativeClosure, RuntimeError};
+use crate::permissions;
 use crate::convert::{FromPyroValue};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-fn join(args: Vec<Value>) -> Result<Value, Value> {
+fn err(message: String) -> RuntimeError {
+    Value::String(Arc::new(message)).into()
+}
+
+fn wrap(path: PathBuf) -> Value {
+    Value::Path(Arc::new(path))
+}
+
+// A path given as a Path or a string
+fn to_path(value: &Value) -> Result<PathBuf, RuntimeError> {
+    match value {
+        Value::Path(p) => Ok(p.to_path_buf()),
+        Value::String(s) => Ok(PathBuf::from(s.as_str())),
+        other => Err(err(format!("Expected a path or string, found {}", type_name(other)))),
+    }
+}
+
+// `a / b` and a.join(b): b replaces a if it's absolute, as with PathBuf::push
+pub fn join_value(base: &Path, part: &Value) -> Result<Value, RuntimeError> {
+    Ok(wrap(base.join(to_path(part)?)))
+}
+
+fn text(s: &std::ffi::OsStr) -> Value {
+    Value::String(Arc::new(s.to_string_lossy().into_owned()))
+}
+
+// The methods of a Path value
+pub fn method(interpreter: &Interpreter, path: &Path, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+    let expect = |count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            let noun = if count == 1 { "argument" } else { "arguments" };
+            Err(err(format!("{} expects {} {}", name, count, noun)))
+        }
+    };
+    match name {
+        "join" => {
+            let mut joined = path.to_path_buf();
+            for part in args {
+                joined.push(to_path(part)?);
+            }
+            Ok(wrap(joined))
+        }
+        // Like Python's: "." for a relative path with one part, and the
+        // path itself for a root
+        "parent" => {
+            expect(0)?;
+            Ok(wrap(match path.parent() {
+                Some(parent) if parent.as_os_str().is_empty() => PathBuf::from("."),
+                Some(parent) => parent.to_path_buf(),
+                None => path.to_path_buf(),
+            }))
+        }
+        "name" => {
+            expect(0)?;
+            Ok(path.file_name().map(text).unwrap_or_else(|| Value::String(Arc::new(String::new()))))
+        }
+        "stem" => {
+            expect(0)?;
+            Ok(path.file_stem().map(text).unwrap_or_else(|| Value::String(Arc::new(String::new()))))
+        }
+        // Without the dot, as extname gives it
+        "ext" => {
+            expect(0)?;
+            Ok(path.extension().map(text).unwrap_or_else(|| Value::String(Arc::new(String::new()))))
+        }
+        // "" removes the extension; a leading dot is optional
+        "with_ext" => {
+            expect(1)?;
+            let ext: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+            Ok(wrap(path.with_extension(ext.strip_prefix('.').unwrap_or(&ext))))
+        }
+        "exists" => {
+            expect(0)?;
+            permissions::check_fs(interpreter, &path.to_string_lossy())?;
+            Ok(Value::Bool(path.exists()))
+        }
+        "is_absolute" => {
+            expect(0)?;
+            Ok(Value::Bool(path.is_absolute()))
+        }
+        "parts" => {
+            expect(0)?;
+            Ok(Value::List(Arc::new(path.components().map(|c| text(c.as_os_str())).collect())))
+        }
+        _ => Err(err(format!("Method '{}' not found on Path", name))),
+    }
+}
+
+// path(s): a Path from a string, or a copy of a Path
+fn new(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument (list of paths)".to_string())));
+        return Err(err("Expected 1 argument".to_string()));
+    }
+    Ok(wrap(to_path(&args[0])?))
+}
+
+fn join(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument (list of paths)".to_string())).into());
     }
     
     // Expect list of strings
@@ -21,9 +120,9 @@ fn join(args: Vec<Value>) -> Result<Value, Value> {
     Ok(Value::String(Arc::new(path.display().to_string())))
 }
 
-fn basename(args: Vec<Value>) -> Result<Value, Value> {
+fn basename(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path_str: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -35,9 +134,9 @@ fn basename(args: Vec<Value>) -> Result<Value, Value> {
     }
 }
 
-fn dirname(args: Vec<Value>) -> Result<Value, Value> {
+fn dirname(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path_str: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -49,9 +148,9 @@ fn dirname(args: Vec<Value>) -> Result<Value, Value> {
     }
 }
 
-fn extname(args: Vec<Value>) -> Result<Value, Value> {
+fn extname(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path_str: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -63,22 +162,27 @@ fn extname(args: Vec<Value>) -> Result<Value, Value> {
     }
 }
 
-fn abs_path(args: Vec<Value>) -> Result<Value, Value> {
+fn abs_path(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
     let path_str: String = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
     
+    permissions::check_fs(interpreter, &path_str)?;
     match std::fs::canonicalize(path_str) {
         Ok(path) => Ok(Value::String(Arc::new(path.display().to_string()))),
-        Err(e) => Err(Value::String(Arc::new(e.to_string()))),
+        Err(e) => Err(Value::String(Arc::new(e.to_string())).into()),
     }
 }
 
 pub fn module() -> Value {
     let mut methods = HashMap::new();
     
+    methods.insert("path".to_string(), Value::NativeFunction {
+        name: "path".to_string(),
+        func: NativeClosure(Arc::new(new)),
+    });
     methods.insert("join".to_string(), Value::NativeFunction {
         name: "join".to_string(),
         func: NativeClosure(Arc::new(join)),
@@ -95,9 +199,9 @@ pub fn module() -> Value {
         name: "extname".to_string(),
         func: NativeClosure(Arc::new(extname)),
     });
-    methods.insert("abs_path".to_string(), Value::NativeFunction {
+    methods.insert("abs_path".to_string(), Value::InterpreterFunction {
         name: "abs_path".to_string(),
-        func: NativeClosure(Arc::new(abs_path)),
+        func: InterpreterClosure(Arc::new(abs_path)),
     });
 
     Value::NativeModule(Arc::new(methods))
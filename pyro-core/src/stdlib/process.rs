@@ -1,10 +1,16 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{Value, NativeClosure, InterpreterClosure, Interpreter, error_with_kind, io_error, RuntimeError};
+use crate::permissions;
+use crate::replay;
 use crate::convert::{FromPyroValue};
+use crate::stdlib::time::format_duration;
 use std::collections::HashMap;
+use std::io::Read;
 use std::sync::Arc;
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
-fn exit(args: Vec<Value>) -> Result<Value, Value> {
+fn exit(args: Vec<Value>) -> Result<Value, RuntimeError> {
     let code = if args.len() > 0 {
         match &args[0] {
             Value::Int(i) => *i as i32,
@@ -13,12 +19,26 @@ fn exit(args: Vec<Value>) -> Result<Value, Value> {
     } else {
         0
     };
+    crate::stdlib::fs::remove_temp_paths();
     std::process::exit(code);
 }
 
-fn exec(args: Vec<Value>) -> Result<Value, Value> {
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+// exec(command, args = [], timeout?) -> {stdout, stderr, code}
+fn exec(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() < 1 {
-        return Err(Value::String(Arc::new("Expected at least 1 argument (command)".to_string())));
+        return Err(Value::String(Arc::new("Expected at least 1 argument (command)".to_string())).into());
     }
     
     let cmd_str: String = FromPyroValue::from_value(&args[0])
@@ -30,25 +50,63 @@ fn exec(args: Vec<Value>) -> Result<Value, Value> {
         Vec::new()
     };
     
-    let output = Command::new(cmd_str)
+    let timeout = match args.get(2) {
+        None | Some(Value::Void) => None,
+        Some(Value::Duration(d)) => Some(*d),
+        Some(v) => {
+            let seconds: f64 = FromPyroValue::from_value(v).map_err(|e| Value::String(Arc::new(e)))?;
+            Some(Duration::try_from_secs_f64(seconds)
+                .map_err(|_| Value::String(Arc::new(format!("Invalid timeout {}", seconds))))?)
+        }
+    };
+
+    let program = permissions::check_run(interpreter, &cmd_str)?;
+    let mut child = Command::new(program)
         .args(cmd_args)
+        .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| Value::String(Arc::new(e.to_string())))?;
-        
+        .spawn()
+        .map_err(|e| io_error(&e))?;
+
+    // Drain the pipes on their own threads so a chatty child can't block on a
+    // full pipe while we poll it
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    // Poll instead of wait() so a timeout or Ctrl-C in the REPL can stop it
+    let interrupt = interpreter.interrupt_token();
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| io_error(&e))? {
+            break status;
+        }
+        let timed_out = deadline.is_some_and(|d| Instant::now() >= d);
+        if timed_out || interrupt.interrupted() {
+            let _ = child.kill();
+            let _ = child.wait();
+            if timed_out {
+                return Err(error_with_kind("TimedOut", format!("Command timed out after {}", format_duration(timeout.unwrap_or_default()))));
+            }
+            interrupt.check()?;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
     let mut result_map = Vec::new();
     result_map.push((
         Value::String(Arc::new("stdout".to_string())),
-        Value::String(Arc::new(String::from_utf8_lossy(&output.stdout).to_string()))
+        Value::String(Arc::new(String::from_utf8_lossy(&stdout).to_string()))
     ));
     result_map.push((
         Value::String(Arc::new("stderr".to_string())),
-        Value::String(Arc::new(String::from_utf8_lossy(&output.stderr).to_string()))
+        Value::String(Arc::new(String::from_utf8_lossy(&stderr).to_string()))
     ));
     result_map.push((
         Value::String(Arc::new("code".to_string())),
-        Value::Int(output.status.code().unwrap_or(-1) as i64)
+        Value::Int(status.code().unwrap_or(-1) as i64)
     ));
     
     Ok(Value::Dict(Arc::new(result_map)))
@@ -61,9 +119,11 @@ pub fn module() -> Value {
         name: "exit".to_string(),
         func: NativeClosure(Arc::new(exit)),
     });
-    methods.insert("exec".to_string(), Value::NativeFunction {
+    methods.insert("exec".to_string(), Value::InterpreterFunction {
         name: "exec".to_string(),
-        func: NativeClosure(Arc::new(exec)),
+        func: InterpreterClosure(Arc::new(|interpreter, args| {
+            replay::call("process.exec", args, |args| exec(interpreter, args))
+        })),
     });
 
     Value::NativeModule(Arc::new(methods))
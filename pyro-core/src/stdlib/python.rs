@@ -0,0 +1,148 @@
+// Calls into Python, for leaning on existing Python libraries while code
+// moves to Pyro. Only built with the `python-interop` feature; the
+// interpreter embeds the Python that pyo3 finds at build time.
+//
+//     import std.python
+//     let root = python.call("math", "sqrt", [16])
+//
+// Values cross by copying. None, bools, ints, floats, decimals, complex
+// numbers, strings, lists, tuples, sets and dicts map to their counterparts,
+// mutable Pyro collections included; chars become one-character strings and
+// paths pathlib paths.
+// Anything else can't cross and is an error.
+
+use crate::convert::FromPyroValue;
+use crate::interpreter::{error_with_kind, type_name, Interpreter, InterpreterClosure, RuntimeError, Value};
+use crate::permissions;
+use num_complex::Complex64;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyComplex, PyDict, PyFloat, PyFrozenSet, PyInt, PyList, PySet, PyString, PyTuple};
+use pyo3::IntoPyObjectExt;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn python_error(e: PyErr) -> RuntimeError {
+    error_with_kind("PythonError", e.to_string())
+}
+
+fn items<'py>(py: Python<'py>, values: &[Value]) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    values.iter().map(|value| to_python(py, value)).collect()
+}
+
+fn dict<'py>(py: Python<'py>, pairs: &[(Value, Value)]) -> PyResult<Bound<'py, PyAny>> {
+    let dict = PyDict::new(py);
+    for (key, value) in pairs {
+        dict.set_item(to_python(py, key)?, to_python(py, value)?)?;
+    }
+    Ok(dict.into_any())
+}
+
+// The Python object for a Pyro value
+pub fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Void => Ok(py.None().into_bound(py)),
+        Value::Bool(b) => b.into_bound_py_any(py),
+        Value::Int(i) => i.into_bound_py_any(py),
+        Value::Float(f) => f.into_bound_py_any(py),
+        Value::Decimal(d) => py.import("decimal")?.getattr("Decimal")?.call1((d.to_string(),)),
+        Value::Complex(c) => Ok(PyComplex::from_doubles(py, c.re, c.im).into_any()),
+        Value::String(s) => s.as_str().into_bound_py_any(py),
+        Value::Char(c) => c.to_string().into_bound_py_any(py),
+        Value::Path(p) => p.as_path().into_bound_py_any(py),
+        Value::List(l) => Ok(PyList::new(py, items(py, l)?)?.into_any()),
+        Value::ListMutable(l) => Ok(PyList::new(py, items(py, &l.read().unwrap())?)?.into_any()),
+        Value::Tuple(t) => Ok(PyTuple::new(py, items(py, t)?)?.into_any()),
+        Value::TupleMutable(t) => Ok(PyTuple::new(py, items(py, &t.read().unwrap())?)?.into_any()),
+        Value::Set(s) => Ok(PySet::new(py, items(py, s)?)?.into_any()),
+        Value::SetMutable(s) => Ok(PySet::new(py, items(py, &s.read().unwrap())?)?.into_any()),
+        Value::Dict(d) => dict(py, d),
+        Value::DictMutable(d) => dict(py, &d.read().unwrap()),
+        other => Err(pyo3::exceptions::PyTypeError::new_err(format!(
+            "A Pyro {} has no Python equivalent",
+            type_name(other)
+        ))),
+    }
+}
+
+fn values(object: &Bound<'_, PyAny>) -> Result<Vec<Value>, String> {
+    object.try_iter().map_err(|e| e.to_string())?.map(|item| from_python(&item.map_err(|e| e.to_string())?)).collect()
+}
+
+// The Pyro value for a Python object
+pub fn from_python(object: &Bound<'_, PyAny>) -> Result<Value, String> {
+    let failed = |e: PyErr| e.to_string();
+    // bool before int, as Python's bool is a subclass of int
+    if object.is_none() {
+        Ok(Value::Void)
+    } else if object.is_instance_of::<PyBool>() {
+        Ok(Value::Bool(object.extract().map_err(failed)?))
+    } else if object.is_instance_of::<PyInt>() {
+        let int: i64 = object.extract().map_err(|_| format!("Python int {} doesn't fit in a Pyro int", object))?;
+        Ok(Value::Int(int))
+    } else if object.is_instance_of::<PyFloat>() {
+        Ok(Value::Float(object.extract().map_err(failed)?))
+    } else if let Ok(complex) = object.cast::<PyComplex>() {
+        Ok(Value::Complex(Complex64::new(complex.real(), complex.imag())))
+    } else if object.is_instance_of::<PyString>() {
+        Ok(Value::String(Arc::new(object.extract().map_err(failed)?)))
+    } else if object.is_instance_of::<PyList>() {
+        Ok(Value::List(Arc::new(values(object)?)))
+    } else if object.is_instance_of::<PyTuple>() {
+        Ok(Value::Tuple(Arc::new(values(object)?)))
+    } else if object.is_instance_of::<PySet>() || object.is_instance_of::<PyFrozenSet>() {
+        Ok(Value::Set(Arc::new(values(object)?)))
+    } else if let Ok(dict) = object.cast::<PyDict>() {
+        let pairs = dict
+            .iter()
+            .map(|(key, value)| Ok((from_python(&key)?, from_python(&value)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Value::Dict(Arc::new(pairs)))
+    } else {
+        let class = object.get_type();
+        let name = class.name().map_err(failed)?;
+        let module = class.module().map_err(failed)?;
+        if module == "decimal" && name == "Decimal" {
+            let text = object.str().map_err(failed)?.to_string();
+            return text.parse::<Decimal>().map(Value::Decimal).map_err(|e| format!("Python Decimal {}: {}", text, e));
+        }
+        if module == "pathlib" {
+            return Ok(Value::Path(Arc::new(object.extract().map_err(failed)?)));
+        }
+        Err(format!("Python value of type '{}' has no Pyro equivalent", name))
+    }
+}
+
+// call(module, func, args = []): module.func(*args) in Python
+fn call(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(Value::String(Arc::new("Expected 2 or 3 arguments (module, func, args)".to_string())).into());
+    }
+    let module: String = FromPyroValue::from_value(&args[0]).map_err(|e| Value::String(Arc::new(e)))?;
+    let func: String = FromPyroValue::from_value(&args[1]).map_err(|e| Value::String(Arc::new(e)))?;
+    permissions::check_python(interpreter, &module, &func)?;
+    let call_args = match args.get(2) {
+        None | Some(Value::Void) => Vec::new(),
+        Some(Value::List(l) | Value::Tuple(l)) => l.to_vec(),
+        Some(Value::ListMutable(l) | Value::TupleMutable(l)) => l.read().unwrap().clone(),
+        Some(other) => {
+            return Err(Value::String(Arc::new(format!("Expected a list of arguments, found {}", type_name(other)))).into())
+        }
+    };
+
+    Python::attach(|py| {
+        let function = py.import(module.as_str()).and_then(|m| m.getattr(func.as_str())).map_err(python_error)?;
+        let call_args = PyTuple::new(py, items(py, &call_args).map_err(python_error)?).map_err(python_error)?;
+        let result = function.call1(call_args).map_err(python_error)?;
+        from_python(&result).map_err(|e| error_with_kind("PythonError", e))
+    })
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+    methods.insert("call".to_string(), Value::InterpreterFunction {
+        name: "call".to_string(),
+        func: InterpreterClosure(Arc::new(call)),
+    });
+    Value::NativeModule(Arc::new(methods))
+}
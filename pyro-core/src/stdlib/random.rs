@@ -1,16 +1,17 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::replay;
 use crate::convert::{FromPyroValue};
 use std::collections::HashMap;
 use std::sync::Arc;
 use rand::Rng;
 
-fn random(_args: Vec<Value>) -> Result<Value, Value> {
+fn random(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     Ok(Value::Float(rand::random()))
 }
 
-fn randint(args: Vec<Value>) -> Result<Value, Value> {
+fn randint(args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 2 {
-        return Err(Value::String(Arc::new("Expected 2 arguments (min, max)".to_string())));
+        return Err(Value::String(Arc::new("Expected 2 arguments (min, max)".to_string())).into());
     }
     let min: i64 = FromPyroValue::from_value(&args[0])
         .map_err(|e| Value::String(Arc::new(e)))?;
@@ -26,11 +27,11 @@ pub fn module() -> Value {
     
     methods.insert("random".to_string(), Value::NativeFunction {
         name: "random".to_string(),
-        func: NativeClosure(Arc::new(random)),
+        func: NativeClosure(Arc::new(|args| replay::call("random.random", args, random))),
     });
     methods.insert("randint".to_string(), Value::NativeFunction {
         name: "randint".to_string(),
-        func: NativeClosure(Arc::new(randint)),
+        func: NativeClosure(Arc::new(|args| replay::call("random.randint", args, randint))),
     });
 
     Value::NativeModule(Arc::new(methods))
@@ -0,0 +1,216 @@
+use crate::ast::Stmt;
+use crate::interpreter::{error_with_kind, Interpreter, InterpreterClosure, RuntimeError, Value};
+use crate::permissions;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::convert::FromPyroValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Work other threads hand to a running program: files a file watcher
+// (`pyro run --watch`) queues for reload, and source an inspector (`pyro run
+// --inspect`) sends. Get it with `Interpreter::requests`; only that
+// interpreter does the work, between statements and while it waits in
+// time.sleep or on a channel, never the goroutines or jobs it starts.
+#[derive(Clone, Default)]
+pub struct Requests(Arc<RequestQueue>);
+
+#[derive(Default)]
+struct RequestQueue {
+    // Lets the interpreter check for work without taking a lock every statement
+    pending: AtomicBool,
+    reloads: Mutex<Vec<String>>,
+    evals: Mutex<Vec<(String, mpsc::Sender<EvalReply>)>>,
+}
+
+impl Requests {
+    pub fn reload(&self, path: &str) {
+        self.0.reloads.lock().unwrap().push(path.to_string());
+        self.0.pending.store(true, Ordering::Release);
+    }
+
+    // Each eval sees the program's current variables, and gets back the
+    // value of its last expression or the error it raised
+    pub fn eval(&self, source: &str) -> mpsc::Receiver<EvalReply> {
+        let (reply, receiver) = mpsc::channel();
+        self.0.evals.lock().unwrap().push((source.to_string(), reply));
+        self.0.pending.store(true, Ordering::Release);
+        receiver
+    }
+
+    pub(crate) fn pending(&self) -> bool {
+        self.0.pending.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn apply(&self, interpreter: &mut Interpreter) {
+        let (paths, evals): (Vec<String>, Vec<_>) = {
+            let mut reloads = self.0.reloads.lock().unwrap();
+            let mut evals = self.0.evals.lock().unwrap();
+            self.0.pending.store(false, Ordering::Release);
+            (reloads.drain(..).collect(), evals.drain(..).collect())
+        };
+        for path in paths {
+            // A broken edit shouldn't take down the running program
+            if let Err(e) = reload_file(interpreter, &path) {
+                let _ = interpreter.write_error(&format!("Reload of '{}' failed: {:?}", path, e));
+            }
+        }
+        for (source, reply) in evals {
+            // The inspector may have disconnected; the program carries on either way
+            let _ = reply.send(eval_source(interpreter, &source));
+        }
+    }
+}
+
+// What an inspector gets back for the source it sent
+pub type EvalReply = Result<String, String>;
+
+fn eval_source(interpreter: &mut Interpreter, source: &str) -> EvalReply {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().map_err(|e| format!("Parse error: {}", e))?;
+    let mut last = Value::Void;
+    for stmt in program.statements {
+        let result = match stmt {
+            Stmt::Expr(expr) => interpreter.evaluate(expr),
+            other => interpreter.run(vec![other]).map(|_| Value::Void),
+        };
+        last = result.map_err(|e| describe_error(&interpreter.native_error(e).value))?;
+    }
+    Ok(match last {
+        Value::Void => String::new(),
+        value => format!("{:?}", value),
+    })
+}
+
+fn describe_error(err: &Value) -> String {
+    match err {
+        Value::Instance { class_name, fields, .. } => match fields.read().unwrap().get("message") {
+            Some(Value::String(message)) => format!("{}: {}", class_name, message),
+            _ => format!("{:?}", err),
+        },
+        other => format!("{:?}", other),
+    }
+}
+
+// Cancels what an interpreter is running. Each interpreter has its own,
+// shared with the goroutines and jobs it starts. A host's SIGINT handler (the
+// REPL) sets it to cancel the current evaluation; loops, calls and
+// long-running native calls like time.sleep and process.exec check it and
+// raise an error with kind "Interrupted". Embedders running untrusted code
+// can also give it a wall-clock deadline, past which the same checks raise
+// an error with kind "TimedOut".
+#[derive(Clone, Default)]
+pub struct InterruptToken(Arc<InterruptState>);
+
+#[derive(Default)]
+struct InterruptState {
+    interrupted: AtomicBool,
+    // The flag lets checks skip the lock when there is no deadline
+    has_deadline: AtomicBool,
+    deadline: Mutex<Option<Instant>>,
+}
+
+// How often blocking calls wake up to check for an interrupt
+pub(crate) const INTERRUPT_POLL: Duration = Duration::from_millis(20);
+
+impl InterruptToken {
+    pub fn interrupt(&self) {
+        self.0.interrupted.store(true, Ordering::Release);
+    }
+
+    pub fn clear(&self) {
+        self.0.interrupted.store(false, Ordering::Release);
+    }
+
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        *self.0.deadline.lock().unwrap() = deadline;
+        self.0.has_deadline.store(deadline.is_some(), Ordering::Release);
+    }
+
+    fn past_deadline(&self) -> bool {
+        self.0.has_deadline.load(Ordering::Acquire)
+            && self.0.deadline.lock().unwrap().is_some_and(|d| Instant::now() >= d)
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.0.interrupted.load(Ordering::Acquire) || self.past_deadline()
+    }
+
+    pub fn check(&self) -> Result<(), RuntimeError> {
+        if self.0.interrupted.load(Ordering::Acquire) {
+            return Err(error_with_kind("Interrupted", "Interrupted"));
+        }
+        if self.past_deadline() {
+            return Err(error_with_kind("TimedOut", "Time limit exceeded"));
+        }
+        Ok(())
+    }
+}
+
+// Re-parses a module and re-runs only its definitions, so functions, classes
+// and records are swapped in place while variables keep their current values.
+pub fn reload_file(interpreter: &mut Interpreter, path: &str) -> Result<(), RuntimeError> {
+    permissions::check_fs(interpreter, path)?;
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Value::String(Arc::new(format!("Could not read '{}': {}", path, e))))?;
+
+    let tokens = Lexer::new(&content).tokenize();
+    let program = Parser::new(&tokens)
+        .parse()
+        .map_err(|e| Value::String(Arc::new(format!("Parse error in '{}': {}", path, e))))?;
+
+    let definitions: Vec<Stmt> = program
+        .statements
+        .into_iter()
+        .filter(|stmt| match stmt {
+            Stmt::FnDecl { .. } | Stmt::ClassDecl { .. } | Stmt::RecordDef { .. } | Stmt::EnumDef { .. } => true,
+            Stmt::Import(module) => interpreter.has_native_module(module),
+            _ => false,
+        })
+        .collect();
+
+    interpreter.run_global(definitions)?;
+    Ok(())
+}
+
+fn reload(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
+    }
+
+    let path: String = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+
+    reload_file(interpreter, &path)?;
+    Ok(Value::Void)
+}
+
+// set_max_call_depth(n): calls nested deeper than n raise a RecursionError
+fn set_max_call_depth(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let [Value::Int(depth)] = args.as_slice() else {
+        return Err(Value::String(Arc::new("Expected 1 argument (depth: int)".to_string())).into());
+    };
+    if *depth < 1 {
+        return Err(Value::String(Arc::new(format!("Call depth must be at least 1, got {}", depth))).into());
+    }
+    interpreter.set_max_call_depth(*depth as usize);
+    Ok(Value::Void)
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("reload".to_string(), Value::InterpreterFunction {
+        name: "reload".to_string(),
+        func: InterpreterClosure(Arc::new(reload)),
+    });
+
+    methods.insert("set_max_call_depth".to_string(), Value::InterpreterFunction {
+        name: "set_max_call_depth".to_string(),
+        func: InterpreterClosure(Arc::new(set_max_call_depth)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,188 @@
+use crate::interpreter::{Interpreter, InterpreterClosure, NativeClosure, RuntimeError, Value};
+use crate::convert::FromPyroValue;
+use crate::stdlib::time::parse_duration;
+#[cfg(feature = "cron")]
+use chrono::Local;
+#[cfg(feature = "cron")]
+use cron::Schedule;
+use std::collections::HashMap;
+#[cfg(feature = "cron")]
+use std::str::FromStr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Number of jobs still scheduled, so wait() can block until they are all
+// cancelled.
+static ACTIVE_JOBS: Mutex<usize> = Mutex::new(0);
+static JOBS_CHANGED: Condvar = Condvar::new();
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+enum Timing {
+    Every(Duration),
+    #[cfg(feature = "cron")]
+    Cron(Box<Schedule>),
+}
+
+impl Timing {
+    // Time to wait before the next run, or None when a cron schedule has no
+    // future runs left.
+    fn next_delay(&self) -> Option<Duration> {
+        match self {
+            Timing::Every(interval) => Some(*interval),
+            #[cfg(feature = "cron")]
+            Timing::Cron(schedule) => {
+                let next = schedule.upcoming(Local).next()?;
+                Some((next - Local::now()).to_std().unwrap_or(Duration::ZERO))
+            }
+        }
+    }
+}
+
+// A cancel flag the job thread sleeps on, so cancel() takes effect at once
+// rather than after the current interval.
+struct Cancel {
+    cancelled: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl Cancel {
+    // Sleeps for `delay`, returning false if the job was cancelled meanwhile
+    fn sleep(&self, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        let mut cancelled = self.cancelled.lock().unwrap();
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            cancelled = self.wake.wait_timeout(cancelled, deadline - now).unwrap().0;
+        }
+        false
+    }
+
+    fn cancel(&self) {
+        *self.cancelled.lock().unwrap() = true;
+        self.wake.notify_all();
+    }
+}
+
+// Each job runs its callback on its own thread against a fork of the
+// interpreter, the same way `go` statements do.
+fn start(interpreter: &Interpreter, timing: Timing, callback: Value) -> Value {
+    let cancel = Arc::new(Cancel { cancelled: Mutex::new(false), wake: Condvar::new() });
+    let mut job_interpreter = interpreter.fork();
+    let runtime = tokio::runtime::Handle::try_current().ok();
+    *ACTIVE_JOBS.lock().unwrap() += 1;
+
+    let job_cancel = cancel.clone();
+    thread::spawn(move || {
+        // Let callbacks use `go` and async stdlib functions
+        let _guard = runtime.as_ref().map(|handle| handle.enter());
+        while let Some(delay) = timing.next_delay() {
+            if !job_cancel.sleep(delay) {
+                break;
+            }
+            // A failing run is reported but doesn't stop the schedule
+            if let Err(e) = job_interpreter.call(callback.clone(), Vec::new()) {
+                let _ = job_interpreter.write_error(&format!("Error in scheduled job: {:?}", e));
+            }
+        }
+        *ACTIVE_JOBS.lock().unwrap() -= 1;
+        JOBS_CHANGED.notify_all();
+    });
+
+    let mut methods = HashMap::new();
+    methods.insert("cancel".to_string(), Value::NativeFunction {
+        name: "cancel".to_string(),
+        func: NativeClosure(Arc::new(move |_args| {
+            cancel.cancel();
+            Ok(Value::Void)
+        })),
+    });
+    Value::NativeModule(Arc::new(methods))
+}
+
+fn callback_arg(args: &[Value]) -> Result<Value, RuntimeError> {
+    match args.get(1) {
+        Some(f @ (Value::Function { .. } | Value::NativeFunction { .. } | Value::InterpreterFunction { .. } | Value::BoundMethod { .. })) => Ok(f.clone()),
+        _ => Err(err("Expected a function as the second argument".to_string())),
+    }
+}
+
+// every(interval, fn) -> job
+// The interval is a duration, a number of seconds or text such as "5m".
+fn every(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (interval, fn)".to_string()));
+    }
+    let interval = match &args[0] {
+        Value::Duration(d) => *d,
+        Value::String(s) => parse_duration(s).map_err(err)?,
+        other => {
+            let seconds: f64 = FromPyroValue::from_value(other).map_err(err)?;
+            Duration::try_from_secs_f64(seconds).map_err(|_| err(format!("Invalid interval {}", seconds)))?
+        }
+    };
+    if interval.is_zero() {
+        return Err(err("Interval must be greater than zero".to_string()));
+    }
+    let callback = callback_arg(&args)?;
+    Ok(start(interpreter, Timing::Every(interval), callback))
+}
+
+// cron(expression, fn) -> job
+// Standard five-field expressions ("*/5 * * * *") run at second 0; six or
+// seven fields give seconds and years explicitly. Times are local.
+#[cfg(feature = "cron")]
+fn cron(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (expression, fn)".to_string()));
+    }
+    let expression: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let full = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.clone()
+    };
+    let schedule = Schedule::from_str(&full)
+        .map_err(|e| err(format!("Invalid cron expression '{}': {}", expression, e)))?;
+    let callback = callback_arg(&args)?;
+    Ok(start(interpreter, Timing::Cron(Box::new(schedule)), callback))
+}
+
+#[cfg(not(feature = "cron"))]
+fn cron(_interpreter: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    Err(err("schedule.cron needs pyro built with the 'cron' feature".to_string()))
+}
+
+// wait() blocks until every job has been cancelled, keeping daemon scripts alive
+fn wait(_args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut active = ACTIVE_JOBS.lock().unwrap();
+    while *active > 0 {
+        active = JOBS_CHANGED.wait(active).unwrap();
+    }
+    Ok(Value::Void)
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("every".to_string(), Value::InterpreterFunction {
+        name: "every".to_string(),
+        func: InterpreterClosure(Arc::new(every)),
+    });
+    methods.insert("cron".to_string(), Value::InterpreterFunction {
+        name: "cron".to_string(),
+        func: InterpreterClosure(Arc::new(cron)),
+    });
+    methods.insert("wait".to_string(), Value::NativeFunction {
+        name: "wait".to_string(),
+        func: NativeClosure(Arc::new(wait)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,126 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::convert::FromPyroValue;
+use semver::{Version, VersionReq};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// Parses a version, accepting the leading "v" used by git tags ("v1.2.3").
+// Public so the package manager can resolve versions the same way scripts do.
+pub fn parse_version(s: &str) -> Result<Version, String> {
+    let trimmed = s.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    Version::parse(trimmed).map_err(|e| format!("Invalid version '{}': {}", s, e))
+}
+
+// Requirements use Cargo's syntax: "^1.2", "~1.2.3", ">=1.0, <2.0", "1.*".
+pub fn parse_requirement(s: &str) -> Result<VersionReq, String> {
+    VersionReq::parse(s.trim()).map_err(|e| format!("Invalid version requirement '{}': {}", s, e))
+}
+
+// Returns the highest of `versions` matching `req`, ignoring entries that
+// aren't valid versions (e.g. non-release git tags).
+pub fn max_satisfying<'a>(versions: &'a [String], req: &VersionReq) -> Option<&'a String> {
+    versions
+        .iter()
+        .filter_map(|s| parse_version(s).ok().map(|v| (v, s)))
+        .filter(|(v, _)| req.matches(v))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, s)| s)
+}
+
+fn string_arg(args: &[Value], index: usize) -> Result<String, RuntimeError> {
+    FromPyroValue::from_value(&args[index]).map_err(err)
+}
+
+// parse(version) -> {major, minor, patch, pre, build}
+fn parse(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+    let version = parse_version(&string_arg(&args, 0)?).map_err(err)?;
+
+    let field = |name: &str, value: Value| (Value::String(Arc::new(name.to_string())), value);
+    Ok(Value::Dict(Arc::new(vec![
+        field("major", Value::Int(version.major as i64)),
+        field("minor", Value::Int(version.minor as i64)),
+        field("patch", Value::Int(version.patch as i64)),
+        field("pre", Value::String(Arc::new(version.pre.to_string()))),
+        field("build", Value::String(Arc::new(version.build.to_string()))),
+    ])))
+}
+
+fn valid(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+    Ok(Value::Bool(parse_version(&string_arg(&args, 0)?).is_ok()))
+}
+
+// compare(a, b) -> -1, 0 or 1
+fn compare(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments".to_string()));
+    }
+    let a = parse_version(&string_arg(&args, 0)?).map_err(err)?;
+    let b = parse_version(&string_arg(&args, 1)?).map_err(err)?;
+    Ok(Value::Int(match a.cmp(&b) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }))
+}
+
+// satisfies(version, requirement) -> bool
+fn satisfies(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments".to_string()));
+    }
+    let version = parse_version(&string_arg(&args, 0)?).map_err(err)?;
+    let req = parse_requirement(&string_arg(&args, 1)?).map_err(err)?;
+    Ok(Value::Bool(req.matches(&version)))
+}
+
+// max_satisfying(versions, requirement) -> str, or void when nothing matches
+fn max_satisfying_fn(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments".to_string()));
+    }
+    let versions: Vec<String> = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let req = parse_requirement(&string_arg(&args, 1)?).map_err(err)?;
+    Ok(match max_satisfying(&versions, &req) {
+        Some(v) => Value::String(Arc::new(v.clone())),
+        None => Value::Void,
+    })
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("parse".to_string(), Value::NativeFunction {
+        name: "parse".to_string(),
+        func: NativeClosure(Arc::new(parse)),
+    });
+    methods.insert("valid".to_string(), Value::NativeFunction {
+        name: "valid".to_string(),
+        func: NativeClosure(Arc::new(valid)),
+    });
+    methods.insert("compare".to_string(), Value::NativeFunction {
+        name: "compare".to_string(),
+        func: NativeClosure(Arc::new(compare)),
+    });
+    methods.insert("satisfies".to_string(), Value::NativeFunction {
+        name: "satisfies".to_string(),
+        func: NativeClosure(Arc::new(satisfies)),
+    });
+    methods.insert("max_satisfying".to_string(), Value::NativeFunction {
+        name: "max_satisfying".to_string(),
+        func: NativeClosure(Arc::new(max_satisfying_fn)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
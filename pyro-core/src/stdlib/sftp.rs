@@ -0,0 +1,272 @@
+use crate::interpreter::{Value, Interpreter, InterpreterClosure, RuntimeError};
+use crate::permissions;
+use crate::convert::FromPyroValue;
+use russh::client::{self, Handle};
+use russh::keys::{load_secret_key, PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use russh_sftp::client::SftpSession;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// Native functions are synchronous, so SFTP calls block the calling thread on
+// the surrounding tokio runtime, the same way channel operations do. Embedders
+// running the interpreter outside a runtime get a shared one.
+fn block_on<F: Future>(future: F) -> F::Output {
+    static FALLBACK: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+        Err(_) => FALLBACK
+            .get_or_init(|| tokio::runtime::Runtime::new().expect("Failed to build tokio runtime"))
+            .block_on(future),
+    }
+}
+
+struct Client {
+    host: String,
+    port: u16,
+    verify_host_key: bool,
+}
+
+impl client::Handler for Client {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, key: &PublicKeyOrCertificate) -> Result<bool, Self::Error> {
+        if !self.verify_host_key {
+            return Ok(true);
+        }
+        match key {
+            PublicKeyOrCertificate::PublicKey { key, .. } => {
+                Ok(russh::keys::check_known_hosts(&self.host, self.port, key)?)
+            }
+            PublicKeyOrCertificate::Certificate(_) => Ok(false),
+        }
+    }
+}
+
+struct Connection {
+    // Keeps the SSH session open for as long as the SFTP session is in use
+    _ssh: Handle<Client>,
+    sftp: SftpSession,
+}
+
+struct Options {
+    host: String,
+    port: u16,
+    username: String,
+    password: Option<String>,
+    key_path: Option<String>,
+    key_passphrase: Option<String>,
+    verify_host_key: bool,
+}
+
+fn parse_options(interpreter: &Interpreter, value: &Value) -> Result<Options, RuntimeError> {
+    let entries = match value {
+        Value::Dict(d) => d.to_vec(),
+        Value::DictMutable(d) => d.read().unwrap().clone(),
+        _ => return Err(err("connect expects a dict of options".to_string())),
+    };
+
+    let mut options = Options {
+        host: String::new(),
+        port: 22,
+        username: String::new(),
+        password: None,
+        key_path: None,
+        key_passphrase: None,
+        verify_host_key: true,
+    };
+    for (k, v) in entries {
+        let Value::String(key) = k else { continue };
+        match (key.as_str(), v) {
+            ("host", Value::String(s)) => options.host = s.to_string(),
+            ("port", Value::Int(p)) => options.port = p as u16,
+            ("username", Value::String(s)) => options.username = s.to_string(),
+            ("password", Value::String(s)) => options.password = Some(s.to_string()),
+            ("key_path", Value::String(s)) => options.key_path = Some(s.to_string()),
+            ("key_passphrase", Value::String(s)) => options.key_passphrase = Some(s.to_string()),
+            ("known_hosts", Value::Bool(b)) => options.verify_host_key = b,
+            (name, _) => return Err(err(format!("Invalid sftp option '{}'", name))),
+        }
+    }
+
+    if options.host.is_empty() {
+        return Err(err("Missing required option 'host'".to_string()));
+    }
+    if options.username.is_empty() {
+        return Err(err("Missing required option 'username'".to_string()));
+    }
+    permissions::check_net(interpreter, &options.host, options.port)?;
+    if let Some(key_path) = &options.key_path {
+        permissions::check_fs(interpreter, key_path)?;
+    }
+    if options.password.is_none() && options.key_path.is_none() {
+        return Err(err("Either 'password' or 'key_path' is required".to_string()));
+    }
+    Ok(options)
+}
+
+async fn open(options: Options) -> Result<Connection, RuntimeError> {
+    let handler = Client {
+        host: options.host.clone(),
+        port: options.port,
+        verify_host_key: options.verify_host_key,
+    };
+    let config = Arc::new(client::Config::default());
+
+    let mut ssh = client::connect(config, (options.host.as_str(), options.port), handler)
+        .await
+        .map_err(|e| match e {
+            russh::Error::UnknownKey => err(format!(
+                "Host key for '{}' is not in known_hosts (set known_hosts: false to skip verification)",
+                options.host
+            )),
+            e => err(format!("Could not connect to '{}:{}': {}", options.host, options.port, e)),
+        })?;
+
+    let auth = match (&options.key_path, &options.password) {
+        (Some(path), _) => {
+            let key = load_secret_key(path, options.key_passphrase.as_deref())
+                .map_err(|e| err(format!("Could not load key '{}': {}", path, e)))?;
+            let hash = ssh.best_supported_rsa_hash().await.map_err(|e| err(e.to_string()))?.flatten();
+            ssh.authenticate_publickey(&options.username, PrivateKeyWithHashAlg::new(Arc::new(key), hash))
+                .await
+        }
+        (None, Some(password)) => ssh.authenticate_password(&options.username, password).await,
+        (None, None) => unreachable!(),
+    }
+    .map_err(|e| err(format!("Authentication failed: {}", e)))?;
+
+    if !auth.success() {
+        return Err(err(format!("Authentication failed for user '{}'", options.username)));
+    }
+
+    let channel = ssh.channel_open_session().await.map_err(|e| err(e.to_string()))?;
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| err(format!("Server does not support SFTP: {}", e)))?;
+    let sftp = SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| err(format!("Could not start SFTP session: {}", e)))?;
+
+    Ok(Connection { _ssh: ssh, sftp })
+}
+
+fn path_arg(args: &[Value], index: usize) -> Result<String, RuntimeError> {
+    let value = args.get(index).ok_or_else(|| err(format!("Expected {} arguments", index + 1)))?;
+    FromPyroValue::from_value(value).map_err(err)
+}
+
+fn list(conn: &Connection, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = path_arg(&args, 0)?;
+    let entries = block_on(conn.sftp.read_dir(path.clone()))
+        .map_err(|e| err(format!("Could not list '{}': {}", path, e)))?;
+    let names = entries
+        .map(|entry| entry.file_name())
+        .filter(|name| name != "." && name != "..")
+        .map(|name| Value::String(Arc::new(name)))
+        .collect();
+    Ok(Value::List(Arc::new(names)))
+}
+
+// get(remote, local) and put(local, remote) stream the file in chunks rather
+// than holding it in memory, so large artifacts are fine.
+fn get(conn: &Connection, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let remote = path_arg(&args, 0)?;
+    let local = path_arg(&args, 1)?;
+    permissions::check_fs(interpreter, &local)?;
+    let copied = block_on(async {
+        let mut source = conn.sftp.open(remote.clone()).await.map_err(|e| e.to_string())?;
+        let mut target = tokio::fs::File::create(&local).await.map_err(|e| e.to_string())?;
+        tokio::io::copy(&mut source, &mut target).await.map_err(|e| e.to_string())
+    })
+    .map_err(|e| err(format!("Could not download '{}': {}", remote, e)))?;
+    Ok(Value::Int(copied as i64))
+}
+
+fn put(conn: &Connection, interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let local = path_arg(&args, 0)?;
+    let remote = path_arg(&args, 1)?;
+    permissions::check_fs(interpreter, &local)?;
+    let copied = block_on(async {
+        let mut source = tokio::fs::File::open(&local).await.map_err(|e| e.to_string())?;
+        let mut target = conn.sftp.create(remote.clone()).await.map_err(|e| e.to_string())?;
+        let copied = tokio::io::copy(&mut source, &mut target).await.map_err(|e| e.to_string())?;
+        target.close().await.map_err(|e| e.to_string())?;
+        Ok::<_, String>(copied)
+    })
+    .map_err(|e| err(format!("Could not upload '{}': {}", local, e)))?;
+    Ok(Value::Int(copied as i64))
+}
+
+fn exists(conn: &Connection, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = path_arg(&args, 0)?;
+    let found = block_on(conn.sftp.try_exists(path)).map_err(|e| err(e.to_string()))?;
+    Ok(Value::Bool(found))
+}
+
+fn mkdir(conn: &Connection, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = path_arg(&args, 0)?;
+    block_on(conn.sftp.create_dir(path.clone()))
+        .map_err(|e| err(format!("Could not create '{}': {}", path, e)))?;
+    Ok(Value::Void)
+}
+
+fn remove(conn: &Connection, _interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let path = path_arg(&args, 0)?;
+    block_on(conn.sftp.remove_file(path.clone()))
+        .map_err(|e| err(format!("Could not remove '{}': {}", path, e)))?;
+    Ok(Value::Void)
+}
+
+fn close(conn: &Connection, _interpreter: &mut Interpreter, _args: Vec<Value>) -> Result<Value, RuntimeError> {
+    block_on(conn.sftp.close()).map_err(|e| err(e.to_string()))?;
+    Ok(Value::Void)
+}
+
+type SessionMethod = fn(&Connection, &mut Interpreter, Vec<Value>) -> Result<Value, RuntimeError>;
+
+fn connect(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+
+    let options = parse_options(interpreter, &args[0])?;
+    let conn = Arc::new(block_on(open(options))?);
+
+    let methods: [(&str, SessionMethod); 7] = [
+        ("list", list),
+        ("get", get),
+        ("put", put),
+        ("exists", exists),
+        ("mkdir", mkdir),
+        ("remove", remove),
+        ("close", close),
+    ];
+
+    let mut session = HashMap::new();
+    for (name, method) in methods {
+        let conn = conn.clone();
+        session.insert(name.to_string(), Value::InterpreterFunction {
+            name: name.to_string(),
+            func: InterpreterClosure(Arc::new(move |interpreter, args| method(&conn, interpreter, args))),
+        });
+    }
+
+    Ok(Value::NativeModule(Arc::new(session)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("connect".to_string(), Value::InterpreterFunction {
+        name: "connect".to_string(),
+        func: InterpreterClosure(Arc::new(connect)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,126 @@
+use crate::interpreter::{error_with_kind, io_error, Interpreter, InterpreterClosure, RuntimeError, Value};
+use crate::convert::FromPyroValue;
+use crate::permissions;
+use crate::stdlib::json::value_to_json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+// `pyro test` points this at the __snapshots__ directory next to the test file
+fn snapshot_dir() -> PathBuf {
+    std::env::var_os("PYRO_SNAPSHOT_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("__snapshots__"))
+}
+
+fn updating() -> bool {
+    std::env::var_os("PYRO_UPDATE_SNAPSHOTS").is_some()
+}
+
+// Values JSON can hold without losing anything
+fn is_data(value: &Value) -> bool {
+    match value {
+        Value::Int(_) | Value::Decimal(_) | Value::Bool(_) | Value::String(_) | Value::Char(_) | Value::Void => true,
+        Value::Float(f) => f.is_finite(),
+        Value::List(items) | Value::Tuple(items) | Value::Set(items) => items.iter().all(is_data),
+        Value::ListMutable(items) | Value::TupleMutable(items) | Value::SetMutable(items) => {
+            items.read().unwrap().iter().all(is_data)
+        }
+        Value::Dict(pairs) => pairs.iter().all(|(k, v)| matches!(k, Value::String(_)) && is_data(v)),
+        Value::DictMutable(pairs) => pairs.read().unwrap().iter().all(|(k, v)| matches!(k, Value::String(_)) && is_data(v)),
+        _ => false,
+    }
+}
+
+// Strings are stored as they are, so multi-line output such as generated code
+// stays readable in the snapshot file. Other data is stored as pretty JSON,
+// and anything JSON can't hold in its debug form.
+pub fn serialize(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.to_string(),
+        v if is_data(v) => serde_json::to_string_pretty(&value_to_json(v)).unwrap_or_default(),
+        v => format!("{:?}", v),
+    };
+    if text.ends_with('\n') { text } else { text + "\n" }
+}
+
+// The first line that differs, for the mismatch message
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return format!("line {}:\n- {}\n+ {}", line, e.unwrap_or("<end of snapshot>"), a.unwrap_or("<end of value>"));
+            }
+        }
+    }
+}
+
+// assert_snapshot(name, value): compares the serialized value with
+// __snapshots__/<name>.snap, creating the file the first time
+fn assert_snapshot(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (name, value)".to_string()));
+    }
+    let name: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(err(format!("Invalid snapshot name '{}'", name)));
+    }
+    let path = snapshot_dir().join(format!("{}.snap", name));
+    permissions::check_fs(interpreter, &path.to_string_lossy())?;
+    let actual = serialize(&args[1]);
+
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => Ok(Value::Void),
+        Ok(expected) if !updating() => Err(error_with_kind(
+            "SnapshotMismatch",
+            format!(
+                "Snapshot '{}' does not match {}, first difference at {}\nRun `pyro test --update-snapshots` to accept the new value",
+                name,
+                path.display(),
+                first_difference(&expected, &actual)
+            ),
+        )),
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(&e)),
+        _ => {
+            fs::create_dir_all(snapshot_dir()).map_err(|e| io_error(&e))?;
+            fs::write(&path, actual).map_err(|e| io_error(&e))?;
+            Ok(Value::Void)
+        }
+    }
+}
+
+// capture(fn): calls fn with no arguments and returns what it printed. The
+// previous output comes back even if fn raises, and the error propagates.
+fn capture(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let [func] = <[Value; 1]>::try_from(args).map_err(|_| err("Expected 1 argument (function)".to_string()))?;
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let previous = interpreter.replace_output(Some(buffer.clone()));
+    let result = interpreter.call(func, Vec::new());
+    interpreter.replace_output(previous);
+    result?;
+    let bytes = buffer.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(Value::String(Arc::new(String::from_utf8_lossy(&bytes).into_owned())))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("assert_snapshot".to_string(), Value::InterpreterFunction {
+        name: "assert_snapshot".to_string(),
+        func: InterpreterClosure(Arc::new(assert_snapshot)),
+    });
+
+    methods.insert("capture".to_string(), Value::InterpreterFunction {
+        name: "capture".to_string(),
+        func: InterpreterClosure(Arc::new(capture)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
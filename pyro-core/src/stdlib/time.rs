@@ -1,11 +1,14 @@
-use crate::interpreter::{Value, NativeClosure};
+use crate::interpreter::{Value, NativeClosure, InterpreterClosure, Interpreter, RuntimeError};
+use crate::replay;
 use crate::convert::{FromPyroValue, ToPyroValue};
+use crate::stdlib::format::{date_locale, locale_arg};
+use chrono::{Local, TimeZone};
 use std::collections::HashMap;
+use std::fmt::Write;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH, Duration};
-use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH, Duration};
 
-fn now(_args: Vec<Value>) -> Result<Value, Value> {
+fn now(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     let start = SystemTime::now();
     let since_the_epoch = start
         .duration_since(UNIX_EPOCH)
@@ -13,23 +16,127 @@ fn now(_args: Vec<Value>) -> Result<Value, Value> {
     Ok(since_the_epoch.as_secs_f64().to_value())
 }
 
-fn sleep(args: Vec<Value>) -> Result<Value, Value> {
+// Parses "1h 20m 5s", "250ms" or "1.5s". Units are d, h, m, s, ms, us (or µs)
+// and ns; the spaces between parts are optional.
+pub(crate) fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration '{}'", text);
+    let mut total = 0.0;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().map_err(|_| invalid())?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace()).unwrap_or(rest.len());
+        let scale = match &rest[..unit_end] {
+            "d" => 86400.0,
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 1e-3,
+            "us" | "µs" => 1e-6,
+            "ns" => 1e-9,
+            _ => return Err(invalid()),
+        };
+        total += number * scale;
+        rest = rest[unit_end..].trim_start();
+    }
+    Duration::try_from_secs_f64(total).map_err(|_| invalid())
+}
+
+// Human readable form: "1h 20m 5s", "2.5s", "250ms". Whole units from days
+// down to seconds; under a minute seconds keep millisecond precision, and
+// below a second the largest fitting unit is used.
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.subsec_nanos();
+    let secs = d.as_secs();
+    if secs == 0 {
+        return match nanos {
+            0 => "0s".to_string(),
+            n if n >= 1_000_000 => format!("{}ms", trim_fraction(n as f64 / 1e6)),
+            n if n >= 1_000 => format!("{}µs", trim_fraction(n as f64 / 1e3)),
+            n => format!("{}ns", n),
+        };
+    }
+    let mut parts = Vec::new();
+    for (value, unit) in [(secs / 86400, "d"), (secs % 86400 / 3600, "h"), (secs % 3600 / 60, "m")] {
+        if value > 0 {
+            parts.push(format!("{}{}", value, unit));
+        }
+    }
+    // Keep sub-second precision only while it is still meaningful
+    let seconds = secs % 60;
+    if secs < 60 && nanos > 0 {
+        parts.push(format!("{}s", trim_fraction(seconds as f64 + (nanos / 1_000_000) as f64 / 1e3)));
+    } else if seconds > 0 || parts.is_empty() {
+        parts.push(format!("{}s", seconds));
+    }
+    parts.join(" ")
+}
+
+// Best and mean of repeated runs, for timeit and the shell's :time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pub best: Duration,
+    pub total: Duration,
+    pub runs: u32,
+}
+
+impl Timings {
+    pub fn record(&mut self, elapsed: Duration) {
+        if self.runs == 0 || elapsed < self.best {
+            self.best = elapsed;
+        }
+        self.total += elapsed;
+        self.runs += 1;
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total.checked_div(self.runs).unwrap_or_default()
+    }
+
+    pub fn to_value(self) -> Value {
+        Value::Dict(Arc::new(vec![
+            ("best".to_string().to_value(), Value::Duration(self.best)),
+            ("mean".to_string().to_value(), Value::Duration(self.mean())),
+            ("runs".to_string().to_value(), Value::Int(self.runs as i64)),
+        ]))
+    }
+}
+
+impl std::fmt::Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "best {}, mean {} over {} runs", format_duration(self.best), format_duration(self.mean()), self.runs)
+    }
+}
+
+fn trim_fraction(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn sleep(interpreter: &mut Interpreter, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(Value::String(Arc::new("Expected 1 argument".to_string())));
+        return Err(Value::String(Arc::new("Expected 1 argument".to_string())).into());
     }
 
-    let seconds: f64 = FromPyroValue::from_value(&args[0])
-        .map_err(|e| Value::String(Arc::new(e)))?;
+    let seconds: f64 = match &args[0] {
+        Value::Duration(d) => d.as_secs_f64(),
+        other => FromPyroValue::from_value(other).map_err(|e| Value::String(Arc::new(e)))?,
+    };
 
-    // Use tokio sleep if in async context ideally, but std::thread::sleep is fine for blocking thread
-    // However, if we want async spawn later, we should probably use tokio::time::sleep within async blocks
-    // For now, let's keep it blocking as standard library functions are synchronous in this interpreter implementation so far
-    thread::sleep(Duration::from_secs_f64(seconds));
+    // Blocking, as standard library functions are synchronous, but woken
+    // periodically so Ctrl-C in the REPL can cancel it
+    let duration = Duration::try_from_secs_f64(seconds)
+        .map_err(|_| Value::String(Arc::new(format!("Invalid sleep duration {}", seconds))))?;
+    interpreter.sleep(duration)?;
 
     Ok(Value::Void)
 }
 
-fn millis(_args: Vec<Value>) -> Result<Value, Value> {
+fn millis(_args: Vec<Value>) -> Result<Value, RuntimeError> {
     let start = SystemTime::now();
     let since_the_epoch = start
         .duration_since(UNIX_EPOCH)
@@ -37,21 +144,84 @@ fn millis(_args: Vec<Value>) -> Result<Value, Value> {
     Ok(Value::Int(since_the_epoch.as_millis() as i64))
 }
 
+// format(timestamp, pattern = "%Y-%m-%d %H:%M:%S", locale = "en") -> str
+// Formats a timestamp from now() in the local time zone. Month and day names
+// in the pattern (%B, %A, %b, %a) follow the locale.
+fn format(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(Value::String(Arc::new("Expected 1 to 3 arguments (timestamp, pattern, locale)".to_string())).into());
+    }
+
+    let seconds: f64 = FromPyroValue::from_value(&args[0])
+        .map_err(|e| Value::String(Arc::new(e)))?;
+    let pattern: String = match args.get(1) {
+        None | Some(Value::Void) => "%Y-%m-%d %H:%M:%S".to_string(),
+        Some(v) => FromPyroValue::from_value(v).map_err(|e| Value::String(Arc::new(e)))?,
+    };
+    let locale = date_locale(&locale_arg(&args, 2)?)?;
+
+    let nanos = (seconds.fract() * 1e9) as u32;
+    let datetime = Local
+        .timestamp_opt(seconds.trunc() as i64, nanos)
+        .single()
+        .ok_or_else(|| Value::String(Arc::new(format!("Invalid timestamp {}", seconds))))?;
+
+    // Write through fmt::Write so a bad pattern becomes an error rather than a panic
+    let mut out = String::new();
+    write!(out, "{}", datetime.format_localized(&pattern, locale))
+        .map_err(|_| Value::String(Arc::new(format!("Invalid format pattern '{}'", pattern))))?;
+    Ok(Value::String(Arc::new(out)))
+}
+
+// instant() -> Instant, a monotonic clock reading for measuring elapsed time
+fn instant(_args: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Instant(Instant::now()))
+}
+
+// duration(seconds | "1h 20m 5s") -> Duration
+fn duration(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(Value::String(Arc::new("Expected 1 argument (seconds or text)".to_string())).into());
+    }
+    let d = match &args[0] {
+        Value::Duration(d) => *d,
+        Value::String(text) => parse_duration(text).map_err(|e| Value::String(Arc::new(e)))?,
+        other => {
+            let seconds: f64 = FromPyroValue::from_value(other).map_err(|e| Value::String(Arc::new(e)))?;
+            Duration::try_from_secs_f64(seconds)
+                .map_err(|_| Value::String(Arc::new(format!("Invalid duration {}", seconds))))?
+        }
+    };
+    Ok(Value::Duration(d))
+}
+
 pub fn module() -> Value {
     let mut methods = HashMap::new();
     
     methods.insert("now".to_string(), Value::NativeFunction {
         name: "now".to_string(),
-        func: NativeClosure(Arc::new(now)),
+        func: NativeClosure(Arc::new(|args| replay::call("time.now", args, now))),
     });
 
-    methods.insert("sleep".to_string(), Value::NativeFunction {
+    methods.insert("sleep".to_string(), Value::InterpreterFunction {
         name: "sleep".to_string(),
-        func: NativeClosure(Arc::new(sleep)),
+        func: InterpreterClosure(Arc::new(sleep)),
     });
     methods.insert("millis".to_string(), Value::NativeFunction {
         name: "millis".to_string(),
-        func: NativeClosure(Arc::new(millis)),
+        func: NativeClosure(Arc::new(|args| replay::call("time.millis", args, millis))),
+    });
+    methods.insert("format".to_string(), Value::NativeFunction {
+        name: "format".to_string(),
+        func: NativeClosure(Arc::new(format)),
+    });
+    methods.insert("instant".to_string(), Value::NativeFunction {
+        name: "instant".to_string(),
+        func: NativeClosure(Arc::new(instant)),
+    });
+    methods.insert("duration".to_string(), Value::NativeFunction {
+        name: "duration".to_string(),
+        func: NativeClosure(Arc::new(duration)),
     });
 
     Value::NativeModule(Arc::new(methods))
@@ -0,0 +1,111 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use crate::convert::FromPyroValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+use unicode_normalization::{is_nfc, is_nfd, is_nfkc, is_nfkd, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+#[derive(Clone, Copy)]
+enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+fn parse_form(value: Option<&Value>) -> Result<Form, RuntimeError> {
+    let Some(value) = value else { return Ok(Form::Nfc) };
+    let name: String = FromPyroValue::from_value(value).map_err(err)?;
+    match name.to_uppercase().as_str() {
+        "NFC" => Ok(Form::Nfc),
+        "NFD" => Ok(Form::Nfd),
+        "NFKC" => Ok(Form::Nfkc),
+        "NFKD" => Ok(Form::Nfkd),
+        _ => Err(err(format!("Unknown normalization form '{}' (expected NFC, NFD, NFKC or NFKD)", name))),
+    }
+}
+
+fn text_and_form(args: &[Value]) -> Result<(String, Form), RuntimeError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(err("Expected 1 or 2 arguments (text, form)".to_string()));
+    }
+    let text: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    Ok((text, parse_form(args.get(1))?))
+}
+
+// normalize(text, form = "NFC") -> str
+fn normalize(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (text, form) = text_and_form(&args)?;
+    let normalized: String = match form {
+        Form::Nfc => text.nfc().collect(),
+        Form::Nfd => text.nfd().collect(),
+        Form::Nfkc => text.nfkc().collect(),
+        Form::Nfkd => text.nfkd().collect(),
+    };
+    Ok(Value::String(Arc::new(normalized)))
+}
+
+// is_normalized(text, form = "NFC") -> bool
+fn is_normalized(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let (text, form) = text_and_form(&args)?;
+    Ok(Value::Bool(match form {
+        Form::Nfc => is_nfc(&text),
+        Form::Nfd => is_nfd(&text),
+        Form::Nfkc => is_nfkc(&text),
+        Form::Nfkd => is_nfkd(&text),
+    }))
+}
+
+// grapheme_len(text) -> int: the number of user-perceived characters
+fn grapheme_len(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+    let text: String = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    Ok(Value::Int(text.graphemes(true).count() as i64))
+}
+
+// from_code_points([int]) -> str
+fn from_code_points(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        return Err(err("Expected 1 argument".to_string()));
+    }
+    let points: Vec<i64> = FromPyroValue::from_value(&args[0]).map_err(err)?;
+    let text = points
+        .iter()
+        .map(|&p| {
+            u32::try_from(p)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| err(format!("Invalid code point {}", p)))
+        })
+        .collect::<Result<String, RuntimeError>>()?;
+    Ok(Value::String(Arc::new(text)))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("normalize".to_string(), Value::NativeFunction {
+        name: "normalize".to_string(),
+        func: NativeClosure(Arc::new(normalize)),
+    });
+    methods.insert("is_normalized".to_string(), Value::NativeFunction {
+        name: "is_normalized".to_string(),
+        func: NativeClosure(Arc::new(is_normalized)),
+    });
+    methods.insert("grapheme_len".to_string(), Value::NativeFunction {
+        name: "grapheme_len".to_string(),
+        func: NativeClosure(Arc::new(grapheme_len)),
+    });
+    methods.insert("from_code_points".to_string(), Value::NativeFunction {
+        name: "from_code_points".to_string(),
+        func: NativeClosure(Arc::new(from_code_points)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
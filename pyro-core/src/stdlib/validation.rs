@@ -0,0 +1,247 @@
+use crate::interpreter::{Value, NativeClosure, RuntimeError};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const RULE_KEYS: [&str; 10] = ["type", "required", "min", "max", "min_len", "max_len", "pattern", "one_of", "fields", "items"];
+
+fn err(msg: String) -> RuntimeError {
+    RuntimeError::from(Value::String(Arc::new(msg)))
+}
+
+fn entries(value: &Value) -> Option<Vec<(Value, Value)>> {
+    match value {
+        Value::Dict(d) => Some(d.to_vec()),
+        Value::DictMutable(d) => Some(d.read().unwrap().clone()),
+        _ => None,
+    }
+}
+
+fn items(value: &Value) -> Option<Vec<Value>> {
+    match value {
+        Value::List(l) => Some(l.to_vec()),
+        Value::ListMutable(l) => Some(l.read().unwrap().clone()),
+        _ => None,
+    }
+}
+
+fn lookup<'a>(pairs: &'a [(Value, Value)], key: &str) -> Option<&'a Value> {
+    pairs.iter().find_map(|(k, v)| match k {
+        Value::String(s) if s.as_str() == key => Some(v),
+        _ => None,
+    })
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) }
+}
+
+fn type_matches(name: &str, value: &Value) -> Result<bool, RuntimeError> {
+    Ok(match name {
+        "string" => matches!(value, Value::String(_)),
+        "int" => matches!(value, Value::Int(_)),
+        "float" => matches!(value, Value::Float(_)),
+        "number" => matches!(value, Value::Int(_) | Value::Float(_) | Value::Decimal(_)),
+        "bool" => matches!(value, Value::Bool(_)),
+        "list" => matches!(value, Value::List(_) | Value::ListMutable(_)),
+        "dict" => matches!(value, Value::Dict(_) | Value::DictMutable(_)),
+        "any" => true,
+        other => return Err(err(format!("Unknown type '{}' in schema", other))),
+    })
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn length(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(s) => Some(s.chars().count()),
+        Value::List(l) => Some(l.len()),
+        Value::ListMutable(l) => Some(l.read().unwrap().len()),
+        Value::Dict(d) => Some(d.len()),
+        Value::DictMutable(d) => Some(d.read().unwrap().len()),
+        _ => None,
+    }
+}
+
+// Reads a numeric rule option, treating a wrong type as a schema error
+fn option_number(rule: &[(Value, Value)], key: &str) -> Result<Option<f64>, RuntimeError> {
+    match lookup(rule, key) {
+        None => Ok(None),
+        Some(v) => number(v).map(Some).ok_or_else(|| err(format!("Schema option '{}' must be a number", key))),
+    }
+}
+
+struct Errors(Vec<(String, String)>);
+
+impl Errors {
+    fn add(&mut self, path: &str, message: String) {
+        self.0.push((path.to_string(), message));
+    }
+}
+
+// Checks one value against one rule. A rule is either a type name ("string")
+// or a dict of options. Mistakes in the schema itself are raised as errors
+// rather than reported as validation failures.
+fn check(value: Option<&Value>, rule: &Value, path: &str, errors: &mut Errors) -> Result<(), RuntimeError> {
+    let rule = match rule {
+        Value::String(name) => vec![(Value::String(Arc::new("type".to_string())), Value::String(name.clone()))],
+        other => entries(other).ok_or_else(|| err(format!("Schema rule for '{}' must be a type name or a dict", path)))?,
+    };
+    for (key, _) in &rule {
+        match key {
+            Value::String(k) if RULE_KEYS.contains(&k.as_str()) => {}
+            other => return Err(err(format!("Unknown schema option {:?} for '{}'", other, path))),
+        }
+    }
+
+    let required = match lookup(&rule, "required") {
+        None => false,
+        Some(Value::Bool(b)) => *b,
+        Some(_) => return Err(err("Schema option 'required' must be a bool".to_string())),
+    };
+    let value = match value {
+        None | Some(Value::Void) => {
+            if required {
+                errors.add(path, "is required".to_string());
+            }
+            return Ok(());
+        }
+        Some(v) => v,
+    };
+
+    if let Some(typ) = lookup(&rule, "type") {
+        let Value::String(name) = typ else {
+            return Err(err("Schema option 'type' must be a string".to_string()));
+        };
+        if !type_matches(name, value)? {
+            errors.add(path, format!("must be of type {}", name));
+            return Ok(());
+        }
+    }
+
+    if let Some(n) = number(value) {
+        if let Some(min) = option_number(&rule, "min")? && n < min {
+            errors.add(path, format!("must be at least {}", min));
+        }
+        if let Some(max) = option_number(&rule, "max")? && n > max {
+            errors.add(path, format!("must be at most {}", max));
+        }
+    }
+
+    if let Some(len) = length(value) {
+        if let Some(min) = option_number(&rule, "min_len")? && (len as f64) < min {
+            errors.add(path, format!("must have a length of at least {}", min));
+        }
+        if let Some(max) = option_number(&rule, "max_len")? && (len as f64) > max {
+            errors.add(path, format!("must have a length of at most {}", max));
+        }
+    }
+
+    if let Some(pattern) = lookup(&rule, "pattern") {
+        let Value::String(pattern) = pattern else {
+            return Err(err("Schema option 'pattern' must be a string".to_string()));
+        };
+        let re = Regex::new(pattern).map_err(|e| err(format!("Invalid pattern '{}': {}", pattern, e)))?;
+        if let Value::String(s) = value && !re.is_match(s) {
+            errors.add(path, format!("must match pattern {}", pattern));
+        }
+    }
+
+    if let Some(allowed) = lookup(&rule, "one_of") {
+        let allowed = items(allowed).ok_or_else(|| err("Schema option 'one_of' must be a list".to_string()))?;
+        if !allowed.contains(value) {
+            let names: Vec<String> = allowed.iter().map(describe).collect();
+            errors.add(path, format!("must be one of {}", names.join(", ")));
+        }
+    }
+
+    if let Some(fields) = lookup(&rule, "fields") {
+        match entries(value) {
+            Some(pairs) => check_fields(&pairs, fields, path, errors)?,
+            None => errors.add(path, "must be of type dict".to_string()),
+        }
+    }
+
+    if let Some(item_rule) = lookup(&rule, "items") {
+        match items(value) {
+            Some(list) => {
+                for (i, item) in list.iter().enumerate() {
+                    check(Some(item), item_rule, &format!("{}[{}]", path, i), errors)?;
+                }
+            }
+            None => errors.add(path, "must be of type list".to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_fields(data: &[(Value, Value)], schema: &Value, path: &str, errors: &mut Errors) -> Result<(), RuntimeError> {
+    let schema = entries(schema).ok_or_else(|| err("Schema must be a dict of field rules".to_string()))?;
+    for (key, rule) in &schema {
+        let Value::String(name) = key else {
+            return Err(err("Schema field names must be strings".to_string()));
+        };
+        check(lookup(data, name), rule, &join(path, name), errors)?;
+    }
+    Ok(())
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("\"{}\"", s),
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn run(args: &[Value]) -> Result<Errors, RuntimeError> {
+    if args.len() != 2 {
+        return Err(err("Expected 2 arguments (data, schema)".to_string()));
+    }
+    let mut errors = Errors(Vec::new());
+    match entries(&args[0]) {
+        Some(data) => check_fields(&data, &args[1], "", &mut errors)?,
+        None => errors.add("", "must be of type dict".to_string()),
+    }
+    Ok(errors)
+}
+
+// validate(data, schema) -> [{path, message}], empty when the data is valid
+fn validate(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let errors = run(&args)?;
+    let list = errors.0.into_iter().map(|(path, message)| {
+        Value::Dict(Arc::new(vec![
+            (Value::String(Arc::new("path".to_string())), Value::String(Arc::new(path))),
+            (Value::String(Arc::new("message".to_string())), Value::String(Arc::new(message))),
+        ]))
+    });
+    Ok(Value::List(Arc::new(list.collect())))
+}
+
+fn is_valid(args: Vec<Value>) -> Result<Value, RuntimeError> {
+    Ok(Value::Bool(run(&args)?.0.is_empty()))
+}
+
+pub fn module() -> Value {
+    let mut methods = HashMap::new();
+
+    methods.insert("validate".to_string(), Value::NativeFunction {
+        name: "validate".to_string(),
+        func: NativeClosure(Arc::new(validate)),
+    });
+    methods.insert("is_valid".to_string(), Value::NativeFunction {
+        name: "is_valid".to_string(),
+        func: NativeClosure(Arc::new(is_valid)),
+    });
+
+    Value::NativeModule(Arc::new(methods))
+}
@@ -0,0 +1,242 @@
+// The checks `pyro check --strict` adds. Pyro is gradually typed: a parameter
+// without an annotation has an implicit `any` type and accepts whatever it is
+// given, which is what scripts want. Strict mode asks more of a program:
+//
+// - public functions and methods, those whose names don't start with `_`
+//   (dunder methods such as `__init__` count as public), annotate every
+//   parameter but `self`, and declare a return type if they return a value.
+//   `any` is an annotation, for parameters that really take anything.
+// - a value whose type is only implicitly `any` isn't passed to an annotated
+//   parameter, returned from a function declaring its return type, or stored
+//   in an annotated variable, where it would let an unchecked type through.
+//
+// Block lambda parameters are left alone: their types come from the call they
+// are passed to.
+
+use crate::ast::visit::{walk_block, walk_expr, walk_stmt, Visitor};
+use crate::ast::{parameter_for, type_to_source, Expr, Stmt, Type};
+use crate::diagnostics::Diagnostic;
+use std::collections::HashMap;
+
+fn is_public(name: &str) -> bool {
+    !name.starts_with('_') || (name.starts_with("__") && name.ends_with("__"))
+}
+
+// A type that says something about the values it holds
+fn is_concrete(typ: &Type) -> bool {
+    !matches!(typ, Type::Any | Type::ImplicitAny | Type::Void)
+}
+
+// A parameter written without a type, `*rest` included
+fn unannotated(typ: &Type) -> bool {
+    match typ {
+        Type::Variadic(each) => **each == Type::ImplicitAny,
+        _ => *typ == Type::ImplicitAny,
+    }
+}
+
+// Whether a function body returns a value, not counting nested functions
+#[derive(Default)]
+struct ReturnsValue(bool);
+
+impl Visitor for ReturnsValue {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Return(Some(_)) => self.0 = true,
+            Stmt::FnDecl { .. } | Stmt::ClassDecl { .. } | Stmt::RecordDef { .. } => {}
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, _expr: &Expr) {}
+}
+
+#[derive(Default)]
+struct Checker {
+    // Parameter types of top-level functions
+    functions: HashMap<String, Vec<(String, Type)>>,
+    // Variables in scope whose type is implicitly `any`, and the parameter
+    // it came from
+    implicit: HashMap<String, String>,
+    // Annotations of variables in scope
+    annotations: HashMap<String, Type>,
+    // The function being checked and its declared return type
+    function: Option<(String, Type)>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn check_signature(&mut self, kind: &str, name: &str, params: &[(String, Type)], return_type: &Type, body: &[Stmt]) {
+        // Functions nested in others aren't part of any interface
+        if !is_public(name) || self.function.is_some() {
+            return;
+        }
+        for (param, typ) in params {
+            if param != "self" && unannotated(typ) {
+                self.errors.push(
+                    Diagnostic::error("annotation", format!("Parameter '{}' of public {} '{}' has no type annotation", param, kind, name))
+                        .with_note("annotate it, with `any` if it takes any value"),
+                );
+            }
+        }
+        if *return_type == Type::Void {
+            let mut returns = ReturnsValue::default();
+            walk_block(&mut returns, body);
+            if returns.0 {
+                self.errors.push(Diagnostic::error(
+                    "annotation",
+                    format!("Public {} '{}' returns a value but declares no return type", kind, name),
+                ));
+            }
+        }
+    }
+
+    // Reports `value` if it is implicitly `any` and flows into `expected`
+    fn check_flow(&mut self, value: &Expr, expected: &Type, destination: String) {
+        if !is_concrete(expected) {
+            return;
+        }
+        let Expr::Identifier(name) = value else { return };
+        if let Some(origin) = self.implicit.get(name) {
+            self.errors.push(
+                Diagnostic::error(
+                    "implicit-any",
+                    format!("'{}' has an implicit any type but is {}, declared {}", name, destination, type_to_source(expected)),
+                )
+                .with_note(format!("'{}' comes from the unannotated {}", name, origin)),
+            );
+        }
+    }
+
+    fn bind(&mut self, name: &str, typ: Option<&Type>, value: Option<&Expr>) {
+        match typ {
+            Some(typ) => self.annotations.insert(name.to_string(), typ.clone()),
+            None => self.annotations.remove(name),
+        };
+        // `let y = x` carries x's implicit type along
+        let origin = match (typ, value) {
+            (None, Some(Expr::Identifier(from))) => self.implicit.get(from).cloned(),
+            _ => None,
+        };
+        match origin {
+            Some(origin) => self.implicit.insert(name.to_string(), origin),
+            None => self.implicit.remove(name),
+        };
+    }
+
+    fn check_function(&mut self, name: &str, params: &[(String, Type)], return_type: &Type, body: &[Stmt]) {
+        let outer_implicit = std::mem::take(&mut self.implicit);
+        let outer_annotations = std::mem::take(&mut self.annotations);
+        let outer_function = self.function.replace((name.to_string(), return_type.clone()));
+        for (param, typ) in params {
+            if param != "self" && unannotated(typ) {
+                self.implicit.insert(param.clone(), format!("parameter '{}' of '{}'", param, name));
+            } else if let Type::Variadic(_) = typ {
+                self.annotations.insert(param.clone(), Type::List);
+            } else {
+                self.annotations.insert(param.clone(), typ.clone());
+            }
+        }
+        walk_block(self, body);
+        self.implicit = outer_implicit;
+        self.annotations = outer_annotations;
+        self.function = outer_function;
+    }
+}
+
+impl Visitor for Checker {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FnDecl { name, params, return_type, body, .. } => {
+                self.check_signature("function", name, params, return_type, body);
+                self.check_function(name, params, return_type, body);
+            }
+            Stmt::RecordDef { methods, .. } | Stmt::ClassDecl { methods, .. } => {
+                for method in methods {
+                    if let Stmt::FnDecl { name, params, return_type, body, .. } = method {
+                        self.check_signature("method", name, params, return_type, body);
+                        self.check_function(name, params, return_type, body);
+                    }
+                }
+            }
+            Stmt::VarDecl { name, typ, value, .. } | Stmt::ConstDecl { name, typ, value } => {
+                if let Some(typ) = typ {
+                    self.check_flow(value, typ, format!("given to '{}'", name));
+                }
+                walk_stmt(self, stmt);
+                self.bind(name, typ.as_ref(), Some(value));
+            }
+            Stmt::Assign { name, value } => {
+                let annotation = self.annotations.get(name).cloned();
+                if let Some(typ) = &annotation {
+                    self.check_flow(value, typ, format!("assigned to '{}'", name));
+                }
+                walk_stmt(self, stmt);
+                self.bind(name, annotation.as_ref(), Some(value));
+            }
+            Stmt::Return(Some(value)) => {
+                if let Some((function, returns)) = self.function.clone() {
+                    self.check_flow(value, &returns, format!("returned from '{}'", function));
+                }
+                walk_stmt(self, stmt);
+            }
+            Stmt::For { names, .. } => {
+                walk_stmt(self, stmt);
+                for name in names {
+                    self.bind(name, None, None);
+                }
+            }
+            Stmt::With { name: Some(name), .. } => {
+                self.bind(name, None, None);
+                walk_stmt(self, stmt);
+            }
+            Stmt::Match { cases, .. } => {
+                for name in cases.iter().flat_map(|case| case.pattern.names()) {
+                    self.bind(name, None, None);
+                }
+                walk_stmt(self, stmt);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Call { function, args, .. } = expr
+            && let Expr::Identifier(name) = &**function
+            && let Some(params) = self.functions.get(name).cloned()
+        {
+            for (i, arg) in args.iter().take_while(|arg| !matches!(arg, Expr::Spread(_))).enumerate() {
+                if let Some((param, typ)) = parameter_for(&params, i) {
+                    self.check_flow(arg, typ, format!("passed as '{}' to '{}'", param, name));
+                }
+            }
+        }
+        match expr {
+            // A lambda's `return` leaves the lambda, not the function
+            Expr::Lambda { .. } => {
+                let outer_function = self.function.take();
+                walk_expr(self, expr);
+                self.function = outer_function;
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+// Returns every problem found, with the code "annotation" for a missing
+// annotation and "implicit-any" for an implicitly typed value flowing into a
+// typed place
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut checker = Checker::default();
+    for stmt in statements {
+        if let Stmt::FnDecl { name, params, .. } = stmt {
+            checker.functions.insert(name.clone(), params.clone());
+        }
+    }
+    walk_block(&mut checker, statements);
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors)
+    }
+}
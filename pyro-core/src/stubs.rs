@@ -0,0 +1,281 @@
+// Checks calls to the native standard library modules against the
+// signatures declared for them in `stdlib/stubs/`, so `fs.read_to_string(42)`
+// is reported before the program runs rather than raised while it runs.
+//
+// A call `module.name(args)` is checked when `module` is bound by
+// `import std.module` and by nothing else in the program. The function must
+// exist, the number of arguments must fit its parameters (those whose type
+// includes `void` may be left out, and a trailing parameter named `rest`
+// takes any number), and an argument whose type is known before running must
+// fit its parameter's type. Types are known for literals, f-strings, lambdas,
+// conditionals whose branches agree and calls to other stubbed functions; an
+// int fits a float, and a char or a path a string. Anything else is checked as the
+// program runs, as before.
+
+use crate::ast::visit::{walk_expr, walk_stmt, Visitor};
+use crate::ast::{type_to_source, Expr, Stmt, Type};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::stdlib::{EMBEDDED_MODULES, STUBS};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Name of the trailing parameter standing for any number of arguments
+const REST: &str = "rest";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<(String, Type)>,
+    pub return_type: Type,
+}
+
+fn parse_stub(module: &str, source: &str) -> HashMap<String, Signature> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens)
+        .parse()
+        .unwrap_or_else(|e| panic!("stub for {} doesn't parse: {}", module, e));
+    program
+        .statements
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Extern { func_name, params, return_type, .. } => {
+                Some((func_name, Signature { params, return_type }))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// The declared functions of a native module, by its import path ("std.fs")
+pub fn signatures(module: &str) -> Option<&'static HashMap<String, Signature>> {
+    static PARSED: OnceLock<HashMap<&'static str, HashMap<String, Signature>>> = OnceLock::new();
+    PARSED
+        .get_or_init(|| STUBS.iter().map(|(name, source)| (*name, parse_stub(name, source))).collect())
+        .get(module)
+}
+
+fn is_optional(typ: &Type) -> bool {
+    match typ {
+        Type::Void => true,
+        Type::Union(types) => types.iter().any(is_optional),
+        _ => false,
+    }
+}
+
+// Whether a value of type `arg` may be passed where `param` is declared
+fn fits(param: &Type, arg: &Type) -> bool {
+    match (param, arg) {
+        (Type::Any | Type::ImplicitAny, _) | (_, Type::Any | Type::ImplicitAny) => true,
+        (Type::Union(params), _) => params.iter().any(|p| fits(p, arg)),
+        // A stubbed call that may give one of several types fits when any of
+        // them does; which one it gives is only known as it runs
+        (_, Type::Union(args)) => args.iter().any(|a| fits(param, a)),
+        (Type::Float, Type::Int) | (Type::String, Type::Char) => true,
+        (Type::String, Type::UserDefined(name, _)) => name == "path",
+        _ => param == arg,
+    }
+}
+
+struct Calls {
+    // Local name of each checked module -> its import path
+    modules: HashMap<String, &'static str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Calls {
+    fn function(&self, object: &Expr, name: &str) -> Option<(&'static str, Option<&'static Signature>)> {
+        let Expr::Identifier(alias) = object else { return None };
+        let module = *self.modules.get(alias)?;
+        Some((module, signatures(module)?.get(name)))
+    }
+
+    // The type of a value known before running, if there is one
+    fn infer(&self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::LiteralInt(_) => Some(Type::Int),
+            Expr::LiteralFloat(_) => Some(Type::Float),
+            Expr::LiteralBool(_) => Some(Type::Bool),
+            Expr::LiteralString(_) | Expr::InterpolatedString(_) => Some(Type::String),
+            Expr::LiteralChar(_) => Some(Type::Char),
+            Expr::List(_) => Some(Type::List),
+            Expr::Tuple(_) => Some(Type::Tuple),
+            Expr::Set(_) => Some(Type::Set),
+            Expr::Dict(_) => Some(Type::Dict),
+            Expr::Range { .. } => Some(Type::UserDefined("range".to_string(), Vec::new())),
+            Expr::Lambda { .. } => Some(Type::UserDefined("function".to_string(), Vec::new())),
+            Expr::Conditional { then_expr, else_expr, .. } => {
+                let then_type = self.infer(then_expr)?;
+                (self.infer(else_expr)? == then_type).then_some(then_type)
+            }
+            Expr::Call { function, .. } => match function.as_ref() {
+                Expr::Get { object, name } => {
+                    let (_, signature) = self.function(object, name)?;
+                    Some(signature?.return_type.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn check_call(&mut self, module: &str, name: &str, signature: &Signature, args: &[Expr]) {
+        let short = module.rsplit('.').next().unwrap_or(module);
+        let qualified = format!("{}.{}", short, name);
+        let variadic = signature.params.last().is_some_and(|(param, _)| param == REST);
+        let fixed = &signature.params[..signature.params.len() - usize::from(variadic)];
+        let required = fixed.iter().take_while(|(_, typ)| !is_optional(typ)).count();
+        // Past a `*items` argument, how many there are is only known as it runs
+        let spread = args.iter().position(|arg| matches!(arg, Expr::Spread(_)));
+        let args = &args[..spread.unwrap_or(args.len())];
+        if spread.is_none() && (args.len() < required || (!variadic && args.len() > fixed.len())) {
+            let expected = if variadic {
+                format!("at least {}", required)
+            } else if required == fixed.len() {
+                required.to_string()
+            } else {
+                format!("{} to {}", required, fixed.len())
+            };
+            let noun = if expected == "1" { "argument" } else { "arguments" };
+            self.diagnostics.push(Diagnostic::error(
+                "stdlib",
+                format!("'{}' takes {} {}, {} given", qualified, expected, noun, args.len()),
+            ));
+            return;
+        }
+        for (i, (arg, (_, param))) in args.iter().zip(fixed).enumerate() {
+            if let Some(found) = self.infer(arg)
+                && !fits(param, &found)
+            {
+                self.diagnostics.push(Diagnostic::error(
+                    "stdlib",
+                    format!(
+                        "Argument {} of '{}' must be {}, found {}",
+                        i + 1,
+                        qualified,
+                        type_to_source(param),
+                        type_to_source(&found)
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_member(&mut self, object: &Expr, name: &str) -> Option<(&'static str, &'static Signature)> {
+        let (module, signature) = self.function(object, name)?;
+        // An embedded module of the same name may add functions of its own
+        if signature.is_none() && !EMBEDDED_MODULES.iter().any(|(embedded, _)| *embedded == module) {
+            let short = module.rsplit('.').next().unwrap_or(module);
+            self.diagnostics.push(Diagnostic::error("stdlib", format!("'{}' has no function '{}'", short, name)));
+        }
+        Some((module, signature?))
+    }
+}
+
+impl Visitor for Calls {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call { function, args, .. } => {
+                if let Expr::Get { object, name } = function.as_ref() {
+                    if let Some((module, signature)) = self.check_member(object, name) {
+                        self.check_call(module, name, signature, args);
+                    }
+                    self.visit_expr(object);
+                } else {
+                    self.visit_expr(function);
+                }
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+            }
+            Expr::Get { object, name } => {
+                self.check_member(object, name);
+                walk_expr(self, expr);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+// Names bound anywhere in a program other than by an import
+#[derive(Default)]
+struct Bound {
+    names: HashSet<String>,
+}
+
+impl Visitor for Bound {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl { name, .. }
+            | Stmt::ConstDecl { name, .. }
+            | Stmt::Assign { name, .. }
+            | Stmt::RecordDef { name, .. }
+            | Stmt::EnumDef { name, .. }
+            | Stmt::ClassDecl { name, .. }
+            | Stmt::Extern { func_name: name, .. } => {
+                self.names.insert(name.clone());
+            }
+            Stmt::FnDecl { name, params, .. } => {
+                self.names.insert(name.clone());
+                self.names.extend(params.iter().map(|(param, _)| param.clone()));
+            }
+            Stmt::Destructure { names, .. } | Stmt::DestructureAssign { names, .. } | Stmt::For { names, .. } => {
+                self.names.extend(names.iter().cloned())
+            }
+            Stmt::Try { catch_var, .. } => self.names.extend(catch_var.clone()),
+            Stmt::With { name, .. } => self.names.extend(name.clone()),
+            Stmt::Match { cases, .. } => {
+                self.names.extend(cases.iter().flat_map(|case| case.pattern.names()).map(str::to_string))
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Lambda { params, .. } = expr {
+            self.names.extend(params.iter().map(|(param, _)| param.clone()));
+        }
+        walk_expr(self, expr);
+    }
+}
+
+// Imports of stubbed modules, anywhere in the program
+#[derive(Default)]
+struct Imports {
+    modules: HashMap<String, &'static str>,
+}
+
+impl Visitor for Imports {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Import(path) = stmt
+            && let Some((module, _)) = STUBS.iter().find(|(module, _)| module == path)
+        {
+            let alias = module.rsplit('.').next().unwrap_or(module);
+            self.modules.insert(alias.to_string(), module);
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+// Returns every problem found, each with the code "stdlib"
+pub fn check(statements: &[Stmt]) -> Result<(), Vec<Diagnostic>> {
+    let mut imports = Imports::default();
+    let mut bound = Bound::default();
+    for stmt in statements {
+        imports.visit_stmt(stmt);
+        bound.visit_stmt(stmt);
+    }
+    let mut modules = imports.modules;
+    modules.retain(|alias, _| !bound.names.contains(alias));
+
+    let mut calls = Calls { modules, diagnostics: Vec::new() };
+    for stmt in statements {
+        calls.visit_stmt(stmt);
+    }
+    if calls.diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(calls.diagnostics)
+    }
+}
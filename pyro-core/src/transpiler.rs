@@ -1,4 +1,6 @@
-use crate::ast::{BinaryOp, Expr, Stmt, Type};
+pub mod monomorphize;
+
+use crate::ast::{BinaryOp, Expr, Stmt, StringPart, Type, DISCARD};
 
 pub struct Transpiler {
     output: String,
@@ -11,14 +13,16 @@ impl Transpiler {
         }
     }
 
-    pub fn transpile(&mut self, statements: Vec<Stmt>) -> String {
+    // The Rust for `statements`, or an error naming the first construct the
+    // transpiler can't translate
+    pub fn transpile(&mut self, statements: Vec<Stmt>) -> Result<String, String> {
         self.output.clear();
         
         for stmt in statements {
-            self.transpile_stmt(stmt, 0);
+            self.transpile_stmt(stmt, 0)?;
         }
         
-        self.output.clone()
+        Ok(self.output.clone())
     }
 
     fn push_indent(&mut self, indent: usize) {
@@ -27,7 +31,7 @@ impl Transpiler {
         }
     }
 
-    fn transpile_stmt(&mut self, stmt: Stmt, indent: usize) {
+    fn transpile_stmt(&mut self, stmt: Stmt, indent: usize) -> Result<(), String> {
         self.push_indent(indent);
         match stmt {
             Stmt::Extern { func_name, generics: _, params, return_type, rust_path } => {
@@ -60,31 +64,54 @@ impl Transpiler {
             }
             Stmt::VarDecl { name, typ:_, value, mutable: _ } => {
                 self.output.push_str(&format!("let mut usr_{} = ", name));
-                self.transpile_expr(value);
+                self.transpile_expr(value)?;
+                self.output.push_str(";\n");
+            }
+            Stmt::ConstDecl { name, typ: _, value } => {
+                self.output.push_str(&format!("let usr_{} = ", name));
+                self.transpile_expr(value)?;
+                self.output.push_str(";\n");
+            }
+            Stmt::Destructure { names, value, mutable: _ } => {
+                let names: Vec<String> = names
+                    .iter()
+                    .map(|n| if n == DISCARD { n.clone() } else { format!("mut usr_{}", n) })
+                    .collect();
+                self.output.push_str(&format!("let ({}) = ", names.join(", ")));
+                self.transpile_expr(value)?;
                 self.output.push_str(";\n");
             }
             Stmt::Expr(expr) => {
-                self.transpile_expr(expr);
+                self.transpile_expr(expr)?;
                 self.output.push_str(";\n");
             }
             Stmt::Assign { name, value } => {
                 self.output.push_str(&format!("usr_{} = ", name));
-                self.transpile_expr(value);
+                self.transpile_expr(value)?;
+                self.output.push_str(";\n");
+            }
+            Stmt::DestructureAssign { names, value } => {
+                let names: Vec<String> = names
+                    .iter()
+                    .map(|n| if n == DISCARD { n.clone() } else { format!("usr_{}", n) })
+                    .collect();
+                self.output.push_str(&format!("({}) = ", names.join(", ")));
+                self.transpile_expr(value)?;
                 self.output.push_str(";\n");
             }
             Stmt::If { cond, then_block, else_block } => {
                 self.output.push_str("if ");
-                self.transpile_expr(cond);
+                self.transpile_expr(cond)?;
                 self.output.push_str(" {\n");
                 for s in then_block {
-                    self.transpile_stmt(s, indent + 1);
+                    self.transpile_stmt(s, indent + 1)?;
                 }
                 self.push_indent(indent);
                 self.output.push_str("}");
                 if let Some(else_stmts) = else_block {
                     self.output.push_str(" else {\n");
                     for s in else_stmts {
-                        self.transpile_stmt(s, indent + 1);
+                        self.transpile_stmt(s, indent + 1)?;
                     }
                     self.push_indent(indent);
                     self.output.push_str("}");
@@ -93,16 +120,16 @@ impl Transpiler {
             }
             Stmt::While { cond, body } => {
                 self.output.push_str("while ");
-                self.transpile_expr(cond);
+                self.transpile_expr(cond)?;
                 self.output.push_str(" {\n");
                 for s in body {
-                    self.transpile_stmt(s, indent + 1);
+                    self.transpile_stmt(s, indent + 1)?;
                 }
                 self.push_indent(indent);
                 self.output.push_str("}\n");
             }
-            Stmt::FnDecl { name, generics: _, params, return_type, body } => {
-                self.output.push_str(&format!("fn usr_{}(", name));
+            Stmt::FnDecl { name, generics, params, return_type, body } => {
+                self.output.push_str(&format!("fn usr_{}{}(", name, generic_params(&generics)));
                 for (i, (p_name, p_type)) in params.iter().enumerate() {
                     if i > 0 { self.output.push_str(", "); }
                     self.output.push_str(&format!("usr_{}: {}", p_name, self.map_type(p_type)));
@@ -115,39 +142,76 @@ impl Transpiler {
 
                 self.output.push_str("{\n");
                 for s in body {
-                    self.transpile_stmt(s, indent + 1);
+                    self.transpile_stmt(s, indent + 1)?;
                 }
                 self.push_indent(indent);
                 self.output.push_str("}\n");
             }
-            Stmt::RecordDef { name, generics: _, fields, methods: _ } => {
-                self.output.push_str(&format!("struct {} {{\n", name));
-                for (f_name, f_type) in fields {
-                     self.output.push_str(&format!("    pub {}: {},\n", f_name, self.map_type(&f_type)));
+            Stmt::RecordDef { name, generics, fields, derives, methods: _ } => {
+                let traits: Vec<&str> = [
+                    (derives.show, "Debug"),
+                    (derives.eq, "PartialEq"),
+                    (derives.ord, "PartialOrd"),
+                    (derives.hash, "Hash"),
+                ]
+                .into_iter()
+                .filter_map(|(set, name)| set.then_some(name))
+                .collect();
+                if !traits.is_empty() {
+                    self.output.push_str(&format!("#[derive({})]\n", traits.join(", ")));
+                }
+                let generics = generic_params(&generics);
+                self.output.push_str(&format!("struct usr_{}{} {{\n", name, generics));
+                for (f_name, f_type) in &fields {
+                     self.output.push_str(&format!("    pub {}: {},\n", f_name, self.map_type(f_type)));
                 }
                 self.output.push_str("}\n");
+
+                // Constructor, called like the record is in Pyro
+                let params: Vec<String> = fields
+                    .iter()
+                    .map(|(f_name, f_type)| format!("usr_{}: {}", f_name, self.map_type(f_type)))
+                    .collect();
+                let inits: Vec<String> = fields.iter().map(|(f_name, _)| format!("{}: usr_{}", f_name, f_name)).collect();
+                self.push_indent(indent);
+                self.output.push_str(&format!(
+                    "fn usr_{}{}({}) -> usr_{}{} {{ usr_{} {{ {} }} }}\n",
+                    name,
+                    generics,
+                    params.join(", "),
+                    name,
+                    generics,
+                    name,
+                    inits.join(", ")
+                ));
             }
             Stmt::Return(expr_opt) => {
                 self.output.push_str("return");
                 if let Some(expr) = expr_opt {
                     self.output.push_str(" ");
-                    self.transpile_expr(expr);
+                    self.transpile_expr(expr)?;
                 }
                 self.output.push_str(";\n");
             }
             Stmt::Import(_) => {
                 // Ignore imports
             }
-            Stmt::Go(_) => {
-                todo!("Transpilation for 'go' keyword not yet implemented");
-            }
-            Stmt::For { item_name, iterable, body } => {
+            Stmt::Go(_) => return unsupported("'go'"),
+            Stmt::For { names, iterable, body } => {
                 self.push_indent(indent);
-                self.output.push_str(&format!("for usr_{} in ", item_name)); // Ensure loop var is prefixed
-                self.transpile_expr(iterable);
+                let names: Vec<String> = names
+                    .iter()
+                    .map(|n| if n == DISCARD { n.clone() } else { format!("usr_{}", n) })
+                    .collect();
+                let pattern = match names.as_slice() {
+                    [name] => name.clone(),
+                    names => format!("({})", names.join(", ")),
+                };
+                self.output.push_str(&format!("for {} in ", pattern)); // Ensure loop var is prefixed
+                self.transpile_expr(iterable)?;
                 self.output.push_str(" {\n");
                 for s in body {
-                    self.transpile_stmt(s, indent + 1);
+                    self.transpile_stmt(s, indent + 1)?;
                 }
                 self.push_indent(indent);
                 self.output.push_str("}\n");
@@ -155,21 +219,45 @@ impl Transpiler {
             Stmt::InterfaceDef { .. } | Stmt::TypeAlias { .. } => {
                  self.output.push_str("// type defs not yet supported in transpiler \n");
             }
+            Stmt::Match { .. } => return unsupported("'match'"),
+            Stmt::EnumDef { .. } => return unsupported("enums"),
             Stmt::Set { .. } | Stmt::ClassDecl { .. } => {
                 self.output.push_str("// class/set not supported in transpiler yet \n");
             }
             Stmt::Break => self.output.push_str("break;\n"),
             Stmt::Continue => self.output.push_str("continue;\n"),
-            Stmt::Try { .. } | Stmt::Raise { .. } => todo!("Transpilation for Try/Raise not implemented"),
+            Stmt::Try { .. } | Stmt::Raise { .. } => return unsupported("'try' and 'raise'"),
+            Stmt::With { .. } => return unsupported("'with'"),
         }
+        Ok(())
     }
 
-    fn transpile_expr(&mut self, expr: Expr) {
+    fn transpile_expr(&mut self, expr: Expr) -> Result<(), String> {
         match expr {
             Expr::LiteralInt(i) => self.output.push_str(&i.to_string()),
             Expr::LiteralFloat(f) => self.output.push_str(&format!("{:?}", f)), // Debug format to keep decimal?
             Expr::LiteralBool(b) => self.output.push_str(&b.to_string()),
             Expr::LiteralString(s) => self.output.push_str(&format!("\"{}\".to_string()", s)), // String heap allocation
+            Expr::InterpolatedString(parts) => {
+                let mut template = String::new();
+                let mut args = Vec::new();
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => template.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                        StringPart::Expr(e) => {
+                            template.push_str("{}");
+                            args.push(e);
+                        }
+                    }
+                }
+                self.output.push_str(&format!("format!({:?}", template));
+                for arg in args {
+                    self.output.push_str(", ");
+                    self.transpile_expr(arg)?;
+                }
+                self.output.push(')');
+            }
+            Expr::LiteralChar(c) => self.output.push_str(&format!("{:?}", c)),
             Expr::Identifier(s) => {
                 if s == "print" {
                     // This creates a special case where 'print' as an identifier (not call) is not mogrified
@@ -179,9 +267,16 @@ impl Transpiler {
                      self.output.push_str(&format!("usr_{}", s));
                 }
             }
+            Expr::Binary { left, op: BinaryOp::In, right } => {
+                self.output.push('(');
+                self.transpile_expr(*right)?;
+                self.output.push_str(").contains(&");
+                self.transpile_expr(*left)?;
+                self.output.push(')');
+            }
             Expr::Binary { left, op, right } => {
                 self.output.push_str("(");
-                self.transpile_expr(*left);
+                self.transpile_expr(*left)?;
                 self.output.push_str(match op {
                     BinaryOp::Add => " + &",
                     BinaryOp::Sub => " - ",
@@ -193,8 +288,9 @@ impl Transpiler {
                     BinaryOp::Gt => " > ",
                     BinaryOp::Lte => " <= ",
                     BinaryOp::Gte => " >= ",
+                    BinaryOp::In => unreachable!(),
                 });
-                self.transpile_expr(*right);
+                self.transpile_expr(*right)?;
                 self.output.push_str(")");
             }
             Expr::Call { function, generics, args } => {
@@ -205,20 +301,20 @@ impl Transpiler {
                             self.output.push_str("");
                         } else {
                             // Only handling one arg for print for now based on this simple logic
-                             self.transpile_expr(args[0].clone());
+                             self.transpile_expr(args[0].clone())?;
                         }
                         self.output.push_str(")");
-                        return;
+                        return Ok(());
                     }
 
                     if name == "str" {
                          if let Some(arg) = args.first() {
-                             self.transpile_expr(arg.clone());
+                             self.transpile_expr(arg.clone())?;
                              self.output.push_str(".to_string()");
                          } else {
                              self.output.push_str("\"\".to_string()");
                          }
-                         return;
+                         return Ok(());
                     }
 
                     if name == "chan" {
@@ -229,34 +325,39 @@ impl Transpiler {
                             self.map_type(&generics[0])
                         };
                         
+                        // async-channel has no unbuffered channels, and a
+                        // capacity of 1 would let sends run ahead of receives
+                        let capacity = match args.first() {
+                            None | Some(Expr::LiteralInt(0)) => {
+                                return Err("The transpiler doesn't support unbuffered channels; give 'chan' a capacity".to_string())
+                            }
+                            Some(capacity) => capacity.clone(),
+                        };
                         self.output.push_str(&format!("async_channel::bounded::<{}>", type_str));
                         self.output.push_str("(");
-                        // Default capacity 1 if no arg?
-                        if args.is_empty() {
-                            self.output.push_str("1");
-                        } else {
-                            self.transpile_expr(args[0].clone());
-                        }
+                        self.transpile_expr(capacity)?;
                         self.output.push_str(")");
-                        return;
+                        return Ok(());
                     }
                 }
                 
-                self.transpile_expr(*function);
+                self.transpile_expr(*function)?;
                 self.output.push_str("(");
                 for (i, arg) in args.iter().enumerate() {
                     if i > 0 { self.output.push_str(", "); }
-                    self.transpile_expr(arg.clone());
+                    self.transpile_expr(arg.clone())?;
                 }
                 self.output.push_str(")");
             }
-            Expr::Index { .. } => todo!("Transpilation for index not implemented"),
-            Expr::Get { .. } => todo!("Transpilation for methods not implemented"),
+            Expr::Index { .. } => return unsupported("indexing"),
+            Expr::Spread(_) => return unsupported("spreading arguments with '*'"),
+            Expr::Get { .. } => return unsupported("methods and fields"),
+            Expr::Propagate(_) => return unsupported("'?'"),
             Expr::List(elements) => {
                  self.output.push_str("vec![");
                  for (i, e) in elements.iter().enumerate() {
                      if i > 0 { self.output.push_str(", "); }
-                     self.transpile_expr(e.clone());
+                     self.transpile_expr(e.clone())?;
                  }
                  self.output.push_str("]");
             }
@@ -264,7 +365,7 @@ impl Transpiler {
                  self.output.push_str("(");
                  for (i, e) in elements.iter().enumerate() {
                      if i > 0 { self.output.push_str(", "); }
-                     self.transpile_expr(e.clone());
+                     self.transpile_expr(e.clone())?;
                  }
                  // If single element tuple, need trailing comma? Rust doesn't strictly require it for (x,) if types imply it, but (x) is parens.
                  // (x,) syntax in Rust is valid.
@@ -277,7 +378,7 @@ impl Transpiler {
                  self.output.push_str("std::collections::HashSet::from([");
                  for (i, e) in elements.iter().enumerate() {
                      if i > 0 { self.output.push_str(", "); }
-                     self.transpile_expr(e.clone());
+                     self.transpile_expr(e.clone())?;
                  }
                  self.output.push_str("])");
             }
@@ -286,20 +387,54 @@ impl Transpiler {
                  for (i, (k, v)) in elements.iter().enumerate() {
                      if i > 0 { self.output.push_str(", "); }
                      self.output.push_str("(");
-                     self.transpile_expr(k.clone());
+                     self.transpile_expr(k.clone())?;
                      self.output.push_str(", ");
-                     self.transpile_expr(v.clone());
+                     self.transpile_expr(v.clone())?;
                      self.output.push_str(")");
                  }
                  self.output.push_str("])");
             }
+            Expr::Range { start, end, inclusive } => {
+                 self.output.push('(');
+                 self.transpile_expr(*start)?;
+                 self.output.push_str(if inclusive { "..=" } else { ".." });
+                 self.transpile_expr(*end)?;
+                 self.output.push(')');
+            }
+            Expr::Lambda { params, body } => {
+                 let params: Vec<String> = params.iter().map(|(name, _)| format!("usr_{}", name)).collect();
+                 self.output.push_str(&format!("|{}| {{\n", params.join(", ")));
+                 for s in body {
+                     self.transpile_stmt(s, 1)?;
+                 }
+                 self.output.push('}');
+            }
+            Expr::Conditional { cond, then_expr, else_expr } => {
+                 self.output.push_str("(if ");
+                 self.transpile_expr(*cond)?;
+                 self.output.push_str(" { ");
+                 self.transpile_expr(*then_expr)?;
+                 self.output.push_str(" } else { ");
+                 self.transpile_expr(*else_expr)?;
+                 self.output.push_str(" })");
+            }
         }
+        Ok(())
     }
 
     fn map_type(&self, t: &Type) -> String {
         match t {
             Type::Int => "i64".to_string(),
             Type::Float => "f64".to_string(),
+            Type::Int8 => "i8".to_string(),
+            Type::Int16 => "i16".to_string(),
+            Type::Int32 => "i32".to_string(),
+            Type::UInt8 => "u8".to_string(),
+            Type::UInt16 => "u16".to_string(),
+            Type::UInt32 => "u32".to_string(),
+            Type::UInt64 => "u64".to_string(),
+            Type::Float32 => "f32".to_string(),
+            Type::Char => "char".to_string(),
             Type::Bool => "bool".to_string(),
             Type::String => "String".to_string(),
             Type::Void => "()".to_string(),
@@ -311,8 +446,27 @@ impl Transpiler {
             Type::TupleMutable => "std::sync::Arc<std::sync::Mutex<Box<dyn std::any::Any>>>".to_string(),
             Type::SetMutable => "std::sync::Arc<std::sync::Mutex<std::collections::HashSet<Box<dyn std::any::Any>>>>".to_string(),
             Type::DictMutable => "std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, Box<dyn std::any::Any>>>>".to_string(),
-            Type::UserDefined(s, _generics) => format!("usr_{}", s), // TODO: generics
-            Type::Union(_types) => "Box<dyn std::any::Any>".to_string(),
+            Type::UserDefined(s, generics) if generics.is_empty() => format!("usr_{}", s),
+            Type::UserDefined(s, generics) => {
+                let args: Vec<String> = generics.iter().map(|t| self.map_type(t)).collect();
+                format!("usr_{}<{}>", s, args.join(", "))
+            }
+            Type::Union(_) | Type::Any | Type::ImplicitAny => "Box<dyn std::any::Any>".to_string(),
+            Type::Variadic(each) => format!("Vec<{}>", self.map_type(each)),
         }
     }
 }
+
+fn unsupported(what: &str) -> Result<(), String> {
+    Err(format!("The transpiler doesn't support {} yet", what))
+}
+
+// `<usr_T, usr_U>` for a generic left for rustc to instantiate
+fn generic_params(generics: &[String]) -> String {
+    if generics.is_empty() {
+        String::new()
+    } else {
+        let params: Vec<String> = generics.iter().map(|g| format!("usr_{}", g)).collect();
+        format!("<{}>", params.join(", "))
+    }
+}
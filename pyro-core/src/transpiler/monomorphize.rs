@@ -0,0 +1,269 @@
+// Compiles generics by copying them. Every top-level generic function or
+// record gets one copy per set of type arguments it is used with, with the
+// type parameters replaced: `identity<int>(5)` calls `identity__int`, and a
+// `Box<string>` annotation names the record `Box__string`. Type arguments are
+// taken from the call when written out, and otherwise worked out from what is
+// passed for parameters typed with a bare type parameter.
+//
+// A generic used somewhere its type arguments can't be worked out is also kept
+// as written and becomes a Rust generic. Programs are expected to have passed
+// `crate::generics::check`, so argument counts match.
+
+use crate::ast::visit::{walk_block_mut, walk_expr_mut, walk_stmt_mut, VisitorMut};
+use crate::ast::{type_to_source, BinaryOp, Expr, Stmt, Type};
+use crate::generics::{substitute, Substitute};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Copies per generic, so a function calling itself with a bigger type each
+// time (`f<T>` calling `f<Box<T>>`) stops somewhere
+const MAX_COPIES: usize = 64;
+
+#[derive(Default)]
+struct Instances {
+    templates: HashMap<String, Stmt>,
+    // What calling a non-generic top-level function or record gives
+    results: HashMap<String, Type>,
+    copies: HashMap<String, Vec<String>>,
+    queue: VecDeque<(String, Vec<Type>, String)>,
+    // Generics used somewhere without known type arguments
+    unresolved: HashSet<String>,
+}
+
+impl Instances {
+    // The name of the copy of `template` for `args`, queueing it the first
+    // time it is asked for
+    fn instantiate(&mut self, template: &str, args: Vec<Type>) -> Option<String> {
+        let name = mangle(template, &args);
+        let copies = self.copies.entry(template.to_string()).or_default();
+        if !copies.contains(&name) {
+            if copies.len() >= MAX_COPIES {
+                return None;
+            }
+            copies.push(name.clone());
+            self.queue.push_back((template.to_string(), args, name.clone()));
+        }
+        Some(name)
+    }
+}
+
+fn generics_of(stmt: &Stmt) -> &[String] {
+    match stmt {
+        Stmt::FnDecl { generics, .. } | Stmt::RecordDef { generics, .. } => generics,
+        _ => &[],
+    }
+}
+
+// `Box<list<int>>` -> `Box__list_int`
+fn mangle(template: &str, args: &[Type]) -> String {
+    let args: String = args
+        .iter()
+        .map(type_to_source)
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}__{}", template, args.trim_end_matches('_'))
+}
+
+// Matches a declared type against the type of a value, recording what each
+// type parameter stands for
+fn bind(pattern: &Type, actual: &Type, params: &[String], bindings: &mut HashMap<String, Type>) {
+    match (pattern, actual) {
+        (Type::UserDefined(name, args), _) if args.is_empty() && params.contains(name) => {
+            bindings.entry(name.clone()).or_insert_with(|| actual.clone());
+        }
+        (Type::UserDefined(p, p_args), Type::UserDefined(a, a_args)) if p == a && p_args.len() == a_args.len() => {
+            for (p, a) in p_args.iter().zip(a_args) {
+                bind(p, a, params, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Points calls and annotations at copies
+struct Rewriter<'a> {
+    instances: &'a mut Instances,
+    // Types of the variables in scope, where known
+    locals: HashMap<String, Type>,
+}
+
+impl Rewriter<'_> {
+    fn type_args(&self, template: &str, explicit: &[Type], args: &[Expr]) -> Option<Vec<Type>> {
+        if !explicit.is_empty() {
+            return Some(explicit.to_vec());
+        }
+        let stmt = self.instances.templates.get(template)?;
+        let params = match stmt {
+            Stmt::FnDecl { params, .. } => params,
+            Stmt::RecordDef { fields, .. } => fields,
+            _ => return None,
+        };
+        let generics = generics_of(stmt);
+        let mut bindings = HashMap::new();
+        for ((_, pattern), arg) in params.iter().zip(args) {
+            if let Some(actual) = self.infer(arg) {
+                bind(pattern, &actual, generics, &mut bindings);
+            }
+        }
+        generics.iter().map(|g| bindings.get(g).cloned()).collect()
+    }
+
+    fn infer(&self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::LiteralInt(_) => Some(Type::Int),
+            Expr::LiteralFloat(_) => Some(Type::Float),
+            Expr::LiteralBool(_) => Some(Type::Bool),
+            Expr::LiteralChar(_) => Some(Type::Char),
+            Expr::LiteralString(_) | Expr::InterpolatedString(_) => Some(Type::String),
+            Expr::Identifier(name) => self.locals.get(name).filter(|t| !matches!(t, Type::Any | Type::ImplicitAny)).cloned(),
+            Expr::Binary { op: BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div, left, .. } => self.infer(left),
+            Expr::Binary { .. } => Some(Type::Bool),
+            Expr::Conditional { then_expr, else_expr, .. } => {
+                self.infer(then_expr).filter(|t| self.infer(else_expr).as_ref() == Some(t))
+            }
+            Expr::Call { function, generics, args } => {
+                let Expr::Identifier(name) = &**function else { return None };
+                if let Some(result) = self.instances.results.get(name) {
+                    return Some(result.clone());
+                }
+                let template = self.instances.templates.get(name)?;
+                let type_args = self.type_args(name, generics, args)?;
+                match template {
+                    Stmt::FnDecl { generics, return_type, .. } => {
+                        let bindings = generics.iter().cloned().zip(type_args).collect();
+                        Some(substitute(return_type, &bindings)).filter(|t| *t != Type::Void)
+                    }
+                    _ => Some(Type::UserDefined(name.clone(), type_args)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl VisitorMut for Rewriter<'_> {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::VarDecl { name, typ, value, .. } | Stmt::ConstDecl { name, typ, value } => {
+                let known = typ.clone().or_else(|| self.infer(value));
+                let name = name.clone();
+                walk_stmt_mut(self, stmt);
+                match known {
+                    Some(typ) => self.locals.insert(name, typ),
+                    None => self.locals.remove(&name),
+                };
+            }
+            Stmt::FnDecl { params, .. } => {
+                let outer = std::mem::replace(&mut self.locals, params.iter().cloned().collect());
+                walk_stmt_mut(self, stmt);
+                self.locals = outer;
+            }
+            Stmt::For { names, .. } | Stmt::Destructure { names, .. } => {
+                for name in names.iter() {
+                    self.locals.remove(name);
+                }
+                walk_stmt_mut(self, stmt);
+            }
+            Stmt::With { name: Some(name), .. } => {
+                self.locals.remove(name);
+                walk_stmt_mut(self, stmt);
+            }
+            _ => walk_stmt_mut(self, stmt),
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Call { function, generics, args } = expr
+            && let Expr::Identifier(name) = &mut **function
+            && self.instances.templates.contains_key(name)
+        {
+            let copy = self.type_args(name, generics, args).and_then(|types| self.instances.instantiate(name, types));
+            match copy {
+                Some(copy) => {
+                    *name = copy;
+                    generics.clear();
+                }
+                None => {
+                    self.instances.unresolved.insert(name.clone());
+                }
+            }
+        }
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_type_mut(&mut self, typ: &mut Type) {
+        match typ {
+            Type::UserDefined(name, args) if self.instances.templates.contains_key(name) => {
+                if args.is_empty() {
+                    self.instances.unresolved.insert(name.clone());
+                } else if let Some(copy) = self.instances.instantiate(name, args.clone()) {
+                    *typ = Type::UserDefined(copy, Vec::new());
+                } else {
+                    self.instances.unresolved.insert(name.clone());
+                }
+            }
+            Type::UserDefined(_, types) | Type::Union(types) => types.iter_mut().for_each(|t| self.visit_type_mut(t)),
+            Type::Variadic(each) => self.visit_type_mut(each),
+            _ => {}
+        }
+    }
+}
+
+pub fn monomorphize(statements: Vec<Stmt>) -> Vec<Stmt> {
+    let mut instances = Instances::default();
+    for stmt in &statements {
+        match stmt {
+            Stmt::FnDecl { name, generics, .. } | Stmt::RecordDef { name, generics, .. } if !generics.is_empty() => {
+                instances.templates.insert(name.clone(), stmt.clone());
+            }
+            Stmt::FnDecl { name, return_type, .. } if *return_type != Type::Void => {
+                instances.results.insert(name.clone(), return_type.clone());
+            }
+            Stmt::RecordDef { name, .. } => {
+                instances.results.insert(name.clone(), Type::UserDefined(name.clone(), Vec::new()));
+            }
+            _ => {}
+        }
+    }
+
+    let mut statements = statements;
+    let mut rewriter = Rewriter { instances: &mut instances, locals: HashMap::new() };
+    for stmt in statements.iter_mut() {
+        match stmt {
+            Stmt::FnDecl { name, .. } | Stmt::RecordDef { name, .. } if rewriter.instances.templates.contains_key(name) => {}
+            _ => rewriter.visit_stmt_mut(stmt),
+        }
+    }
+
+    // Copies can use other generics, which queues more copies
+    let mut copies: HashMap<String, Vec<Stmt>> = HashMap::new();
+    while let Some((template, args, copy)) = instances.queue.pop_front() {
+        let mut stmt = instances.templates[&template].clone();
+        let bindings = generics_of(&stmt).iter().cloned().zip(args).collect();
+        walk_block_mut(&mut Substitute(&bindings), std::slice::from_mut(&mut stmt));
+        if let Stmt::FnDecl { name, generics, .. } | Stmt::RecordDef { name, generics, .. } = &mut stmt {
+            *name = copy;
+            generics.clear();
+        }
+        Rewriter { instances: &mut instances, locals: HashMap::new() }.visit_stmt_mut(&mut stmt);
+        copies.entry(template).or_default().push(stmt);
+    }
+
+    let mut output = Vec::new();
+    for stmt in statements {
+        match &stmt {
+            Stmt::FnDecl { name, .. } | Stmt::RecordDef { name, .. } if instances.templates.contains_key(name) => {
+                let specialized = copies.remove(name).unwrap_or_default();
+                let keep = specialized.is_empty() || instances.unresolved.contains(name);
+                output.extend(specialized);
+                if keep {
+                    output.push(stmt);
+                }
+            }
+            _ => output.push(stmt),
+        }
+    }
+    output
+}
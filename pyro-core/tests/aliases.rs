@@ -0,0 +1,62 @@
+mod common;
+
+use common::parse;
+use pyro_core::aliases;
+use pyro_core::ast::{Stmt, Type};
+
+fn params(stmt: &Stmt) -> Vec<Type> {
+    match stmt {
+        Stmt::FnDecl { params, .. } => params.iter().map(|(_, typ)| typ.clone()).collect(),
+        other => panic!("expected a function, got {:?}", other),
+    }
+}
+
+#[test]
+fn aliases_are_expanded_and_dropped() {
+    let source = "\
+type ID = int | string
+type Maybe<T> = T | void
+type Pair<T> = tuple
+def show(id: ID, m: Maybe<ID>, p: Pair<int>):
+    print(id)
+";
+    let program = aliases::resolve(parse(source));
+    assert_eq!(program.len(), 1);
+    let id = Type::Union(vec![Type::Int, Type::String]);
+    assert_eq!(params(&program[0]), vec![id.clone(), Type::Union(vec![id, Type::Void]), Type::Tuple]);
+}
+
+#[test]
+fn aliases_to_other_aliases_are_expanded() {
+    let program = aliases::resolve(parse("type A = int\ntype B = A\ndef f(x: B):\n    print(x)\n"));
+    assert_eq!(params(&program[0]), vec![Type::Int]);
+}
+
+#[test]
+fn recursive_aliases_are_reported() {
+    let source = "\
+record Pair<T>(a: T, b: T)
+type Tree = int | Pair<Tree>
+type A = B
+type B = A | void
+type Fine = Pair<int>
+";
+    let diagnostics = aliases::check(&parse(source)).unwrap_err();
+    let reported: Vec<(String, Vec<String>)> = diagnostics.iter().map(|d| (d.message.clone(), d.notes.clone())).collect();
+    let note = "declare the recursive part as a record instead".to_string();
+    assert_eq!(
+        reported,
+        vec![
+            ("Type alias 'Tree' expands to itself".to_string(), vec!["Tree -> Tree".to_string(), note.clone()]),
+            ("Type alias 'A' expands to itself".to_string(), vec!["A -> B -> A".to_string(), note.clone()]),
+            ("Type alias 'B' expands to itself".to_string(), vec!["B -> A -> B".to_string(), note]),
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.code == "alias"));
+}
+
+#[test]
+fn records_may_refer_to_themselves() {
+    let program = parse("record Node(value: int, next: Node | void)\ntype Chain = Node\n");
+    assert_eq!(aliases::check(&program), Ok(()));
+}
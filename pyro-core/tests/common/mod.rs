@@ -0,0 +1,22 @@
+// Helpers shared by the integration tests. Each test file is its own crate
+// and includes this with `mod common;`, using only some of it.
+#![allow(dead_code)]
+
+use pyro_core::ast::Stmt;
+use pyro_core::diagnostics::Diagnostic;
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+
+pub fn parse(source: &str) -> Vec<Stmt> {
+    let tokens = Lexer::new(source).tokenize();
+    Parser::new(&tokens).parse().expect("test program should parse").statements
+}
+
+pub fn messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+    diagnostics.iter().map(|d| d.message.clone()).collect()
+}
+
+// Each diagnostic's code and message
+pub fn reported(diagnostics: &[Diagnostic]) -> Vec<(String, String)> {
+    diagnostics.iter().map(|d| (d.code.clone(), d.message.clone())).collect()
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{messages, parse};
+use pyro_core::constants;
+use pyro_core::interpreter::{Interpreter, Value};
+
+fn message(error: &Value) -> String {
+    match error {
+        Value::Instance { fields, .. } => format!("{:?}", fields.read().unwrap().get("message")),
+        other => format!("{:?}", other),
+    }
+}
+
+#[test]
+fn program_without_reassignment_passes() {
+    let program = parse("const PI = 3.14\nlet r = 2.0\nprint(PI * r)\n");
+    assert_eq!(constants::check(&program), Ok(()));
+}
+
+#[test]
+fn reassignment_anywhere_is_reported() {
+    let source = "\
+const LIMIT = 10
+def reset():
+    LIMIT = 0
+class Counter:
+    def LIMIT(self):
+        return 1
+for LIMIT in [1, 2]:
+    print(LIMIT)
+let _, LIMIT = (1, 2)
+";
+    let errors = constants::check(&parse(source)).unwrap_err();
+    assert_eq!(messages(&errors), vec!["Cannot reassign constant 'LIMIT'".to_string(); 3]);
+    assert!(errors.iter().all(|error| error.code == "constant"));
+}
+
+#[test]
+fn duplicate_declaration_is_reported() {
+    let errors = constants::check(&parse("const A = 1\nconst A = 2\n")).unwrap_err();
+    assert_eq!(messages(&errors), vec!["Constant 'A' is declared more than once".to_string()]);
+}
+
+#[test]
+fn interpreter_rejects_reassignment_across_runs() {
+    let mut interpreter = Interpreter::new();
+    interpreter.run(parse("const NAME = \"pyro\"\n")).unwrap();
+    let error = interpreter.run(parse("NAME = \"other\"\n")).unwrap_err().value;
+    assert!(message(&error).contains("Cannot reassign constant 'NAME'"), "{}", message(&error));
+    let error = interpreter.run(parse("let NAME = 1\n")).unwrap_err().value;
+    assert!(message(&error).contains("Cannot reassign constant 'NAME'"), "{}", message(&error));
+}
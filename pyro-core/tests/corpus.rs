@@ -0,0 +1,87 @@
+// Runs every .pyro file under tests/corpus and compares what it printed, and
+// the error it raised if any, with the .expected file next to it. Set
+// PYRO_BLESS=1 to write the current output to the .expected files instead,
+// then review the diff.
+
+use pyro_core::interpreter::{Interpreter, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+fn corpus_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(corpus_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "pyro") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+fn describe(error: &Value) -> String {
+    match error {
+        Value::Instance { fields, .. } => {
+            let fields = fields.read().unwrap();
+            match (fields.get("kind"), fields.get("message")) {
+                (Some(Value::String(kind)), Some(Value::String(message))) => format!("{}: {}", kind, message),
+                _ => format!("{:?}", error),
+            }
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+// Printed lines, then `error: <kind>: <message>` if the program failed
+fn run(source: &str) -> String {
+    let tokens = Lexer::new(source).tokenize();
+    let program = match Parser::new(&tokens).parse() {
+        Ok(program) => program,
+        Err(message) => return format!("parse error: {}\n", message),
+    };
+
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+    let result = interpreter.run(program.statements);
+
+    let mut text = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    if let Err(error) = result {
+        text.push_str(&format!("error: {}\n", describe(&error.value)));
+    }
+    text
+}
+
+#[test]
+fn corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let bless = std::env::var_os("PYRO_BLESS").is_some();
+    let mut failures = Vec::new();
+
+    let files = corpus_files(&dir);
+    assert!(!files.is_empty(), "no corpus files in {:?}", dir);
+    for file in files {
+        let actual = run(&fs::read_to_string(&file).unwrap());
+        let expected_file = file.with_extension("expected");
+        if bless {
+            fs::write(&expected_file, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&expected_file) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}\n--- expected\n{}--- actual\n{}",
+                file.display(),
+                expected,
+                actual
+            )),
+            Err(_) => failures.push(format!("{}: missing {}", file.display(), expected_file.display())),
+        }
+    }
+    assert!(failures.is_empty(), "{} corpus file(s) differ (PYRO_BLESS=1 to update):\n\n{}", failures.len(), failures.join("\n"));
+}
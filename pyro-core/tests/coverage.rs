@@ -0,0 +1,67 @@
+use pyro_core::ast::{Expr, Stmt};
+use pyro_core::lexer::{Lexer, Token};
+use pyro_core::parser::{Parser, COVERAGE_PROBE};
+
+const PROGRAM: &str = r#"let a = 1
+
+def f(x):
+    # comment
+    return "two
+lines"
+
+class C:
+    def m(self):
+        return 1
+let b = f(a)
+"#;
+
+fn probe_line(stmt: &Stmt) -> Option<i64> {
+    match stmt {
+        Stmt::Expr(Expr::Call { function, args, .. }) if **function == Expr::Identifier(COVERAGE_PROBE.to_string()) => {
+            match args.as_slice() {
+                [Expr::LiteralInt(7), Expr::LiteralInt(line)] => Some(*line),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn probes_mark_the_line_of_each_statement() {
+    let (tokens, lines) = Lexer::new(PROGRAM).tokenize_with_lines();
+    assert_eq!(tokens.len(), lines.len());
+
+    let mut parser = Parser::new(&tokens).with_coverage_probes(&lines, 7);
+    let program = parser.parse().unwrap();
+    assert_eq!(parser.probed_lines(), &[1, 3, 5, 8, 10, 11]);
+
+    let top: Vec<i64> = program.statements.iter().filter_map(probe_line).collect();
+    assert_eq!(top, vec![1, 3, 8, 11]);
+    // Class bodies only hold methods, so the probe goes inside the method
+    let Stmt::ClassDecl { methods, .. } = &program.statements[5] else { panic!("expected a class") };
+    let Stmt::FnDecl { body, .. } = &methods[0] else { panic!("expected a method") };
+    assert_eq!(probe_line(&body[0]), Some(10));
+}
+
+#[test]
+fn parsing_without_probes_is_unchanged() {
+    let tokens = Lexer::new(PROGRAM).tokenize();
+    let (with_lines, _) = Lexer::new(PROGRAM).tokenize_with_lines();
+    assert_eq!(tokens, with_lines);
+    let mut parser = Parser::new(&tokens);
+    let program = parser.parse().unwrap();
+    assert!(parser.probed_lines().is_empty());
+    assert!(program.statements.iter().all(|s| probe_line(s).is_none()));
+}
+
+#[test]
+fn heredocs_count_the_lines_they_span() {
+    let source = "let q = \"\"\"\n    SELECT *\n\n    FROM t\n    \"\"\"\nlet b = 1\n";
+    let (tokens, lines) = Lexer::new(source).tokenize_with_lines();
+    assert!(tokens.contains(&Token::StringLiteral("SELECT *\n\nFROM t\n".to_string())));
+
+    let mut parser = Parser::new(&tokens).with_coverage_probes(&lines, 7);
+    parser.parse().unwrap();
+    assert_eq!(parser.probed_lines(), &[1, 6]);
+}
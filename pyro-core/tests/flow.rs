@@ -0,0 +1,271 @@
+mod common;
+
+use common::{parse, reported};
+use pyro_core::diagnostics::Severity;
+use pyro_core::flow;
+
+#[test]
+fn well_formed_program_passes() {
+    let source = "\
+def sign(n: int) -> int:
+    if n < 0:
+        return -1
+    else:
+        return 1
+def first(items: list) -> int:
+    while true:
+        let found = items[0]
+        if found > 0:
+            break
+    return found
+def safe(f) -> string:
+    try:
+        let message = f()
+    except e:
+        let message = str(e)
+    return message
+def read(path: string) -> string:
+    with fs.open(path) as f:
+        let content = f.read()
+    return content
+def forever() -> int:
+    while true:
+        print(1)
+let label = \"\"
+if sign(2) > 0:
+    label = \"positive\"
+print(label)
+let t = retry(forever, 1)
+def retry(x):
+    return x
+";
+    assert_eq!(flow::check(&parse(source)), Ok(()));
+}
+
+#[test]
+fn use_before_assignment_is_reported() {
+    let source = "\
+def pick(flag: bool):
+    if flag:
+        let chosen = 1
+    print(chosen)
+def count(items: list):
+    for item in items:
+        let last = item
+    print(last)
+    print(item)
+print(later)
+let later = 1
+let outer = 2
+def shadow():
+    print(outer)
+    let outer = 3
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![
+            ("unassigned".to_string(), "'chosen' may be used before it is assigned in function 'pick'".to_string()),
+            ("unassigned".to_string(), "'last' may be used before it is assigned in function 'count'".to_string()),
+            ("unassigned".to_string(), "'item' may be used before it is assigned in function 'count'".to_string()),
+            ("unassigned".to_string(), "'later' may be used before it is assigned in the top level".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn missing_return_is_reported() {
+    let source = "\
+def parity(n: int) -> string:
+    if n > 0:
+        return \"positive\"
+record Box(value: int):
+    def get(self) -> int:
+        print(self.value)
+def maybe(n: int) -> int | void:
+    if n > 0:
+        return n
+def nothing():
+    print(1)
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![
+            (
+                "missing-return".to_string(),
+                "Function 'parity' is declared to return string but can reach the end of its body without returning".to_string()
+            ),
+            (
+                "missing-return".to_string(),
+                "Method 'get' is declared to return int but can reach the end of its body without returning".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unreachable_code_is_a_warning() {
+    let source = "\
+def f(n: int) -> int:
+    return n
+    print(n)
+for i in [1, 2]:
+    if i > 1:
+        break
+    else:
+        continue
+    print(i)
+";
+    let diagnostics = flow::check(&parse(source)).unwrap_err();
+    assert_eq!(
+        reported(&diagnostics),
+        vec![
+            ("unreachable".to_string(), "Unreachable code in function 'f'".to_string()),
+            ("unreachable".to_string(), "Unreachable code in the top level".to_string()),
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+    assert_eq!(diagnostics[0].notes, vec!["it follows a 'return'"]);
+}
+
+#[test]
+fn match_cases_are_branches() {
+    let source = "\
+def name(n: int) -> string:
+    match n:
+        case 0:
+            return \"zero\"
+        case _:
+            return \"many\"
+def first(pair) -> int:
+    match pair:
+        case (a, _):
+            return a
+def label(n: int):
+    match n:
+        case 1:
+            let text = \"one\"
+        case other:
+            let text = \"more\"
+    print(text)
+def partial(n: int):
+    match n:
+        case 1:
+            let word = \"one\"
+    print(word)
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![
+            (
+                "missing-return".to_string(),
+                "Function 'first' is declared to return int but can reach the end of its body without returning".to_string()
+            ),
+            ("unassigned".to_string(), "'word' may be used before it is assigned in function 'partial'".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn matching_every_variant_of_an_enum_is_exhaustive() {
+    let source = "\
+enum Shape:
+    Circle(radius: float)
+    Square(side: float)
+    Empty
+def area(shape: Shape) -> float:
+    match shape:
+        case Shape.Circle(r):
+            return 3.0 * r * r
+        case Shape.Square(s):
+            return s * s
+        case Shape.Empty:
+            return 0.0
+def side(shape: Shape) -> float:
+    match shape:
+        case Shape.Square(s):
+            return s
+        case Shape.Empty:
+            return 0.0
+def unit(shape: Shape) -> float:
+    match shape:
+        case Shape.Circle(1.0):
+            return 1.0
+        case Shape.Square(_):
+            return 1.0
+        case Shape.Empty:
+            return 0.0
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![
+            (
+                "missing-return".to_string(),
+                "Function 'side' is declared to return float but can reach the end of its body without returning".to_string()
+            ),
+            (
+                "missing-return".to_string(),
+                "Function 'unit' is declared to return float but can reach the end of its body without returning".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn guarded_cases_cover_nothing_and_alternatives_cover_each_variant() {
+    let source = "\
+enum Light:
+    Red
+    Amber
+    Green
+def stop(light: Light) -> bool:
+    match light:
+        case Light.Red | Light.Amber:
+            return true
+        case Light.Green:
+            return false
+def proceed(light: Light, late: bool) -> bool:
+    match light:
+        case Light.Green:
+            return true
+        case Light.Amber if late:
+            return true
+        case Light.Red | Light.Amber:
+            return false
+def hurry(light: Light, late: bool) -> bool:
+    match light:
+        case Light.Green:
+            return true
+        case Light.Red | Light.Amber if late:
+            return true
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![(
+            "missing-return".to_string(),
+            "Function 'hurry' is declared to return bool but can reach the end of its body without returning".to_string()
+        )]
+    );
+}
+
+#[test]
+fn option_and_result_are_known_enums() {
+    let source = "\
+def unwrap_or(found: Option, fallback: int) -> int:
+    match found:
+        case Option.Some(value):
+            return value
+        case Option.None:
+            return fallback
+def succeeded(result: Result) -> bool:
+    match result:
+        case Result.Ok(_):
+            return true
+";
+    assert_eq!(
+        reported(&flow::check(&parse(source)).unwrap_err()),
+        vec![(
+            "missing-return".to_string(),
+            "Function 'succeeded' is declared to return bool but can reach the end of its body without returning".to_string()
+        )]
+    );
+}
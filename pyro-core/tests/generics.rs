@@ -0,0 +1,80 @@
+mod common;
+
+use common::{messages, parse};
+use pyro_core::generics;
+use pyro_core::transpiler::monomorphize::monomorphize;
+use pyro_core::transpiler::Transpiler;
+
+fn transpile(source: &str) -> String {
+    Transpiler::new().transpile(monomorphize(parse(source))).expect("test program should transpile")
+}
+
+const DEFINITIONS: &str = "\
+def identity<T>(x: T) -> T:
+    return x
+record Box<T>(value: T)
+";
+
+#[test]
+fn matching_type_arguments_pass() {
+    let program = parse(&format!("{}let n = identity<int>(1)\nlet b: Box<string> = Box(\"hi\")\nlet m = identity(2)\n", DEFINITIONS));
+    assert_eq!(generics::check(&program), Ok(()));
+}
+
+#[test]
+fn wrong_type_arguments_are_reported() {
+    let source = format!(
+        "{}def plain(x):\n    return x\nlet a = identity<int, string>(1)\nlet b = plain<int>(1)\nlet c: Box<int, int> = Box(1)\ndef unbox(b: Box<Box<int, int>>):\n    print(b)\n",
+        DEFINITIONS
+    );
+    let diagnostics = generics::check(&parse(&source)).unwrap_err();
+    assert_eq!(
+        messages(&diagnostics),
+        vec![
+            "'identity' takes 1 type argument, got 2",
+            "'plain' is not generic but is given type arguments",
+            "'Box' takes 1 type argument, got 2",
+            "'Box' takes 1 type argument, got 2",
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.code == "generics"));
+}
+
+#[test]
+fn repeated_type_parameters_are_reported() {
+    let diagnostics = generics::check(&parse("def pair<T, T>(a: T, b: T):\n    print(a)\n")).unwrap_err();
+    assert_eq!(messages(&diagnostics), vec!["Generic parameter 'T' of 'pair' is declared more than once"]);
+}
+
+#[test]
+fn less_than_before_a_call_is_still_a_comparison() {
+    let program = parse("def f(x):\n    return x\nlet a = 1\nlet b = a < f(2)\n");
+    assert_eq!(generics::check(&program), Ok(()));
+}
+
+#[test]
+fn each_instantiation_gets_its_own_copy() {
+    let rust = transpile(&format!("{}let n = identity<int>(5)\nlet s = identity(\"a\")\nlet b: Box<string> = Box(\"hi\")\n", DEFINITIONS));
+    assert!(rust.contains("let mut usr_n = usr_identity__int(5);"));
+    assert!(rust.contains("fn usr_identity__int(usr_x: i64) -> i64 {"));
+    assert!(rust.contains("fn usr_identity__string(usr_x: String) -> String {"));
+    assert!(rust.contains("struct usr_Box__string {\n    pub value: String,\n}"));
+    assert!(rust.contains("let mut usr_b = usr_Box__string(\"hi\".to_string());"));
+    // Every use was resolved, so the generic versions aren't needed
+    assert!(!rust.contains("usr_T"));
+}
+
+#[test]
+fn copies_instantiate_the_generics_they_use() {
+    let rust = transpile(&format!("{}def wrap<T>(x: T) -> Box<T>:\n    return Box(identity(x))\nlet w = wrap(1.5)\n", DEFINITIONS));
+    assert!(rust.contains("fn usr_wrap__float(usr_x: f64) -> usr_Box__float {"));
+    assert!(rust.contains("return usr_Box__float(usr_identity__float(usr_x));"));
+    assert!(rust.contains("struct usr_Box__float {"));
+}
+
+#[test]
+fn unresolved_uses_keep_a_rust_generic() {
+    let rust = transpile(&format!("{}def pass_on(x):\n    return identity(x)\n", DEFINITIONS));
+    assert!(rust.contains("fn usr_identity<usr_T>(usr_x: usr_T) -> usr_T {"));
+    assert!(rust.contains("struct usr_Box<usr_T> {"));
+}
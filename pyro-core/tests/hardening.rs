@@ -0,0 +1,72 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn parse(source: &str) -> Result<Vec<pyro_core::ast::Stmt>, String> {
+    let tokens = Lexer::new(source).tokenize();
+    Parser::new(&tokens).parse().map(|p| p.statements)
+}
+
+fn run(source: &str) -> Result<(), RuntimeError> {
+    run_with(Interpreter::new(), source)
+}
+
+fn run_with(mut interpreter: Interpreter, source: &str) -> Result<(), RuntimeError> {
+    interpreter.run(parse(source).expect("test program should parse")).map(|_| ())
+}
+
+fn kind(error: &Value) -> String {
+    match error {
+        Value::Instance { fields, .. } => format!("{:?}", fields.read().unwrap().get("kind")),
+        other => format!("{:?}", other),
+    }
+}
+
+#[test]
+fn out_of_range_integer_is_a_parse_error() {
+    let error = parse("let x = 99999999999999999999\n").unwrap_err();
+    assert!(error.contains("too large"), "{}", error);
+}
+
+#[test]
+fn deep_nesting_is_a_parse_error() {
+    let source = format!("let x = {}1{}\n", "(".repeat(10_000), ")".repeat(10_000));
+    let error = parse(&source).unwrap_err();
+    assert!(error.contains("nested"), "{}", error);
+}
+
+#[test]
+fn nesting_within_the_limit_runs_on_a_small_stack() {
+    let source = format!("let x = {}1{}\n", "[".repeat(200), "]".repeat(200));
+    let worker = thread::Builder::new().stack_size(256 * 1024).spawn(move || run(&source).is_ok());
+    assert!(worker.unwrap().join().unwrap());
+}
+
+#[test]
+fn unbounded_recursion_raises_recursion_error() {
+    let error = run("def f(n):\n    return f(n + 1)\nf(0)\n").unwrap_err().value;
+    assert!(kind(&error).contains("RecursionError"), "{:?}", error);
+}
+
+#[test]
+fn deadline_stops_loops_and_recursion() {
+    for source in ["while true:\n    let x = 1\n", "def f(n):\n    if n > 0:\n        f(n - 1)\n        f(n - 1)\nf(60)\n"] {
+        let interpreter = Interpreter::new();
+        interpreter.interrupt_token().set_deadline(Some(Instant::now() + Duration::from_millis(100)));
+        let error = run_with(interpreter, source).unwrap_err().value;
+        assert!(kind(&error).contains("TimedOut"), "{:?}", error);
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+#[test]
+fn fuzz_targets_reject_bad_input_without_panicking() {
+    use pyro_core::fuzz_targets::{interpret_limited, lex_bytes, parse_tokens};
+
+    assert!(parse_tokens(&lex_bytes(b"let \xff\xfe = (((")).is_err());
+    assert!(interpret_limited(b"import std.fs\nfs.read_file(\"/etc/passwd\")\n", Duration::from_secs(1)).is_err());
+    assert!(interpret_limited(b"while true:\n    let x = 1\n", Duration::from_millis(50)).is_err());
+    assert!(interpret_limited(b"let x = 1 + 2\n", Duration::from_secs(1)).is_ok());
+}
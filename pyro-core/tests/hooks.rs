@@ -0,0 +1,79 @@
+use pyro_core::ast::Stmt;
+use pyro_core::interpreter::{Interpreter, InterpreterHooks, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+    statements: usize,
+}
+
+impl InterpreterHooks for Recorder {
+    fn on_call(&mut self, function: &str, args: &[Value]) {
+        self.events.push(format!("call {} {:?}", function, args));
+    }
+
+    fn on_return(&mut self, function: &str, result: &Result<Value, RuntimeError>) {
+        match result {
+            Ok(value) => self.events.push(format!("return {} {:?}", function, value)),
+            Err(_) => self.events.push(format!("unwind {}", function)),
+        }
+    }
+
+    fn on_stmt(&mut self, _stmt: &Stmt) {
+        self.statements += 1;
+    }
+
+    fn on_raise(&mut self, error: &RuntimeError) {
+        self.events.push(format!("raise {:?}", error.value));
+    }
+}
+
+fn run(source: &str) -> (Result<(), RuntimeError>, Arc<Mutex<Recorder>>) {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    let mut interpreter = Interpreter::new();
+    interpreter.replace_hooks(Some(recorder.clone()));
+    let result = interpreter.run(program.statements).map(|_| ());
+    (result, recorder)
+}
+
+#[test]
+fn calls_and_returns_are_reported_by_name() {
+    let (result, recorder) = run("def double(x):\n    return x * 2\nlet y = double(21)\n");
+    result.unwrap();
+    let recorder = recorder.lock().unwrap();
+    assert_eq!(recorder.events, vec!["call double [Int(21)]", "return double Int(42)"]);
+    assert_eq!(recorder.statements, 3);
+}
+
+#[test]
+fn an_error_is_raised_once_however_far_it_unwinds() {
+    let source = "def inner():\n    raise \"boom\"\ndef outer():\n    inner()\nouter()\n";
+    let (result, recorder) = run(source);
+    assert!(result.is_err());
+    let recorder = recorder.lock().unwrap();
+    assert_eq!(
+        recorder.events,
+        vec!["call outer []", "call inner []", "raise String(\"boom\")", "unwind inner", "unwind outer"]
+    );
+}
+
+#[test]
+fn finally_does_not_report_the_error_again() {
+    let source = "try:\n    raise \"boom\"\nfinally:\n    let x = 1\n";
+    let (result, recorder) = run(source);
+    assert!(result.is_err());
+    assert_eq!(recorder.lock().unwrap().events, vec!["raise String(\"boom\")"]);
+}
+
+#[test]
+fn a_caught_error_does_not_hide_the_next_one() {
+    let source = "try:\n    raise \"first\"\nexcept e:\n    let x = 1\nraise \"second\"\n";
+    let (result, recorder) = run(source);
+    assert!(result.is_err());
+    assert_eq!(recorder.lock().unwrap().events, vec!["raise String(\"first\")", "raise String(\"second\")"]);
+}
@@ -0,0 +1,25 @@
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::incremental::parse_recovering;
+use pyro_core::parser::Parser;
+
+fn assert_parses_like_the_parser(source: &str) {
+    let tokens = Lexer::new(source).tokenize();
+    let expected = Parser::new(&tokens).parse().expect("test program should parse");
+    let (program, errors) = parse_recovering(source);
+    assert_eq!(errors, Vec::new());
+    assert_eq!(program, expected);
+}
+
+#[test]
+fn strings_spanning_lines_stay_in_their_statement() {
+    assert_parses_like_the_parser("let s = \"\"\"\nhello\nlet x = 1\n\"\"\"\nprint(s)\n");
+    assert_parses_like_the_parser("let s = \"\"\"say \"hi\"\"\"\"\nlet t = \"first\nlet y = (\nsecond\"\nprint(s, t)\n");
+    assert_parses_like_the_parser("let open = '('\nlet close = \")\"\nprint(open, close)\n");
+}
+
+#[test]
+fn a_broken_statement_doesnt_hide_the_rest() {
+    let (program, errors) = parse_recovering("let a = 1\nlet b = \nlet c = \"\"\"\nlet d = (\n\"\"\"\n");
+    assert_eq!(program.statements.len(), 2);
+    assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![2]);
+}
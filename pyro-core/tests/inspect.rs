@@ -0,0 +1,73 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+#[test]
+fn evals_run_inside_the_running_program() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "let count = 0\nlet stop = false\n").unwrap();
+    let requests = interpreter.requests();
+    let inspector = thread::spawn(move || {
+        let read = requests.eval("count >= 0").recv().unwrap();
+        let failed = requests.eval("missing + 1").recv().unwrap();
+        let stopped = requests.eval("stop = true").recv().unwrap();
+        (read, failed, stopped)
+    });
+
+    run(&mut interpreter, r#"
+while stop == false:
+    count = count + 1
+"#).unwrap();
+
+    let (read, failed, stopped) = inspector.join().unwrap();
+    assert_eq!(read, Ok("Bool(true)".to_string()));
+    assert_eq!(failed, Err("Error: Undefined variable: missing".to_string()));
+    assert_eq!(stopped, Ok(String::new()));
+    assert_eq!(interpreter.global("stop"), Some(Value::Bool(true)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn evals_reach_a_program_blocked_on_a_channel() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "let done = chan(1)\nlet reached = false\n").unwrap();
+    let requests = interpreter.requests();
+    let inspector = thread::spawn(move || {
+        // Long enough for the program to be waiting on the channel
+        thread::sleep(Duration::from_millis(100));
+        let reached = requests.eval("reached = true").recv().unwrap();
+        let closed = requests.eval("done.close()").recv().unwrap();
+        (reached, closed)
+    });
+
+    run(&mut interpreter, "for x in done:\n    let y = x\n").unwrap();
+
+    assert_eq!(inspector.join().unwrap(), (Ok(String::new()), Ok(String::new())));
+    assert_eq!(interpreter.global("reached"), Some(Value::Bool(true)));
+}
+
+#[test]
+fn evals_reach_a_sleeping_program() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "import std.time\n").unwrap();
+    let requests = interpreter.requests();
+    let inspector = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        let start = Instant::now();
+        let reply = requests.eval("1 + 1").recv().unwrap();
+        (reply, start.elapsed())
+    });
+
+    run(&mut interpreter, "time.sleep(2)\n").unwrap();
+
+    let (reply, waited) = inspector.join().unwrap();
+    assert_eq!(reply, Ok("Int(2)".to_string()));
+    assert!(waited < Duration::from_secs(1), "{:?}", waited);
+}
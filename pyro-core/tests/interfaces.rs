@@ -0,0 +1,115 @@
+mod common;
+
+use common::parse;
+use pyro_core::diagnostics::Diagnostic;
+use pyro_core::interfaces;
+
+fn reported(diagnostics: &[Diagnostic]) -> Vec<(String, Vec<String>)> {
+    diagnostics.iter().map(|d| (d.message.clone(), d.notes.clone())).collect()
+}
+
+const SHAPES: &str = "\
+interface Shape {
+    def area() -> float
+    def scale(factor: float) -> Shape
+}
+record Circle(r: float):
+    def area(self) -> float:
+        return 3.0 * self.r * self.r
+    def scale(self, factor: float) -> Circle:
+        return Circle(self.r * factor)
+record Square(side: int):
+    def area(self) -> int:
+        return self.side * self.side
+    def scale(self, factor: string) -> Square:
+        return self
+def total(s: Shape) -> float:
+    return s.area()
+";
+
+#[test]
+fn matching_methods_satisfy_an_interface() {
+    let source = format!("{}let c: Shape = Circle(1.0)\nlet t = total(c)\nlet u = total(Circle(2.0))\n", SHAPES);
+    assert_eq!(interfaces::check(&parse(&source)), Ok(()));
+}
+
+#[test]
+fn every_site_reports_what_is_missing() {
+    let source = format!(
+        "{}let s = Square(2)\nlet t = total(s)\nlet bad: Shape = s\ndef make() -> Shape:\n    return Square(3)\n",
+        SHAPES
+    );
+    let notes = vec![
+        "'area' returns int, the interface expects float".to_string(),
+        "'scale' parameter 'factor' is string, the interface expects float".to_string(),
+        "'scale' returns Square, the interface expects Shape".to_string(),
+    ];
+    let diagnostics = interfaces::check(&parse(&source)).unwrap_err();
+    assert_eq!(
+        reported(&diagnostics),
+        vec![
+            ("'Square' passed as argument 1 of 'total' does not satisfy interface 'Shape'".to_string(), notes.clone()),
+            ("'Square' given to 'bad' does not satisfy interface 'Shape'".to_string(), notes.clone()),
+            ("'Square' returned from 'make' does not satisfy interface 'Shape'".to_string(), notes),
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.code == "interface"));
+}
+
+#[test]
+fn inherited_methods_count() {
+    let source = "\
+interface Named {
+    def name() -> string
+    def rename(to: string)
+}
+class Animal:
+    def name(self) -> string:
+        return \"animal\"
+class Dog(Animal):
+    def rename(self, to):
+        print(to)
+class Rock:
+    def rename(self, to, now):
+        print(to)
+let d: Named = Dog()
+let r: Named = Rock()
+";
+    let diagnostics = interfaces::check(&parse(source)).unwrap_err();
+    assert_eq!(
+        reported(&diagnostics),
+        vec![(
+            "'Rock' given to 'r' does not satisfy interface 'Named'".to_string(),
+            vec!["missing method 'name'".to_string(), "'rename' takes 2 parameters, the interface expects 1".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn type_arguments_are_checked_when_given() {
+    let source = "\
+interface Box<T> {
+    def get() -> T
+}
+record IntBox(value: int):
+    def get(self) -> int:
+        return self.value
+let anything: Box = IntBox(1)
+let ints: Box<int> = IntBox(1)
+let strings: Box<string> = IntBox(1)
+";
+    let diagnostics = interfaces::check(&parse(source)).unwrap_err();
+    assert_eq!(
+        reported(&diagnostics),
+        vec![(
+            "'IntBox' given to 'strings' does not satisfy interface 'Box<string>'".to_string(),
+            vec!["'get' returns int, the interface expects string".to_string()]
+        )]
+    );
+}
+
+#[test]
+fn partially_applied_constructors_are_not_checked() {
+    let source = format!("{}let half = Square\nlet t = total(half)\n", SHAPES);
+    assert_eq!(interfaces::check(&parse(&source)), Ok(()));
+}
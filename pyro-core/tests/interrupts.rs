@@ -0,0 +1,109 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string().into())
+}
+
+// Runs the source with an interrupt requested shortly after it starts
+fn run_interrupted(source: &str) -> (Interpreter, Duration) {
+    let mut interpreter = Interpreter::new();
+    let token = interpreter.interrupt_token();
+    let interrupter = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        token.interrupt();
+    });
+    let start = Instant::now();
+    run(&mut interpreter, source).unwrap();
+    let elapsed = start.elapsed();
+    interrupter.join().unwrap();
+    (interpreter, elapsed)
+}
+
+#[test]
+fn interrupt_cancels_sleep() {
+    let (interpreter, elapsed) = run_interrupted(r#"
+import std.time
+let seen = ListMutable([])
+try:
+    time.sleep(10)
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#);
+    assert_eq!(interpreter.global("kind"), Some(string("Interrupted")));
+    assert!(elapsed < Duration::from_secs(5));
+}
+
+#[test]
+fn interrupt_cancels_retry_waits() {
+    let (interpreter, elapsed) = run_interrupted(r#"
+def always_fails():
+    raise "down"
+let seen = ListMutable([])
+try:
+    retry(always_fails, 3, "constant", false, 20)
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#);
+    assert_eq!(interpreter.global("kind"), Some(string("Interrupted")));
+    assert!(elapsed < Duration::from_secs(5));
+}
+
+#[test]
+fn interrupt_stops_loops() {
+    let (interpreter, _) = run_interrupted(r#"
+let seen = ListMutable([])
+try:
+    while true:
+        let x = 1
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#);
+    assert_eq!(interpreter.global("kind"), Some(string("Interrupted")));
+}
+
+#[test]
+fn interrupt_kills_exec() {
+    let (interpreter, elapsed) = run_interrupted(r#"
+import std.process
+let seen = ListMutable([])
+try:
+    process.exec("sleep", ["10"])
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#);
+    assert_eq!(interpreter.global("kind"), Some(string("Interrupted")));
+    assert!(elapsed < Duration::from_secs(5));
+}
+
+#[test]
+fn interrupt_only_reaches_its_own_interpreter() {
+    let other = thread::spawn(|| {
+        let mut interpreter = Interpreter::new();
+        run(&mut interpreter, "import std.time\ntime.sleep(0.3)\nlet done = true\n").map(|_| interpreter.global("done"))
+    });
+    let (interpreter, _) = run_interrupted(r#"
+import std.time
+let seen = ListMutable([])
+try:
+    time.sleep(10)
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#);
+    assert_eq!(interpreter.global("kind"), Some(string("Interrupted")));
+    assert_eq!(other.join().unwrap().unwrap(), Some(Value::Bool(true)));
+}
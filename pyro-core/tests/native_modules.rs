@@ -0,0 +1,75 @@
+use pyro_core::diagnostics::Diagnostic;
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use pyro_core::prelude::Prelude;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+fn module(answer: i64) -> Value {
+    Value::NativeModule(Arc::new(HashMap::from([("answer".to_string(), Value::Int(answer))])))
+}
+
+#[test]
+fn registered_modules_are_listed_and_imported() {
+    let mut interpreter = Interpreter::with_prelude(&Prelude::minimal());
+    interpreter.register_native_module("acme.deep", module(42));
+    interpreter.register_native_module("acme.alpha", module(1));
+
+    assert!(interpreter.has_native_module("acme.deep"));
+    assert_eq!(interpreter.list_native_modules(), vec!["acme.alpha", "acme.deep"]);
+    run(&mut interpreter, "import acme.deep\nlet x = deep.answer").unwrap();
+    assert_eq!(interpreter.global("x"), Some(Value::Int(42)));
+}
+
+#[test]
+fn the_full_prelude_lists_native_and_embedded_modules() {
+    let interpreter = Interpreter::new();
+    let listed = interpreter.list_native_modules();
+    for name in ["std.math", "std.itertools"] {
+        assert!(listed.contains(&name), "missing {}", name);
+    }
+    assert!(listed.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn unregistered_modules_no_longer_import() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "import std.math\nlet a = math.sqrt(9)").unwrap();
+
+    assert!(interpreter.unregister_native_module("std.math"));
+    assert!(!interpreter.unregister_native_module("std.math"));
+    assert!(!interpreter.has_native_module("std.math"));
+    assert!(!interpreter.list_native_modules().contains(&"std.math"));
+
+    // The earlier import keeps its binding; a new one fails
+    run(&mut interpreter, "let b = math.sqrt(16)").unwrap();
+    assert_eq!(interpreter.global("b"), Some(Value::Float(4.0)));
+    let error = run(&mut interpreter, "import std.math").unwrap_err();
+    assert_eq!(Diagnostic::from(&error).code, "NotFound");
+}
+
+#[test]
+fn unregistering_an_embedded_module_removes_it() {
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.unregister_native_module("std.itertools"));
+    assert!(run(&mut interpreter, "import std.itertools").is_err());
+}
+
+#[test]
+fn registering_again_replaces_the_module_for_later_imports() {
+    let mut interpreter = Interpreter::with_prelude(&Prelude::minimal());
+    interpreter.register_native_module("acme.deep", module(1));
+    run(&mut interpreter, "import acme.deep\nlet first = deep.answer").unwrap();
+    interpreter.register_native_module("acme.deep", module(2));
+    run(&mut interpreter, "import acme.deep\nlet second = deep.answer").unwrap();
+
+    assert_eq!(interpreter.global("first"), Some(Value::Int(1)));
+    assert_eq!(interpreter.global("second"), Some(Value::Int(2)));
+}
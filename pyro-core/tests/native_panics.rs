@@ -0,0 +1,67 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string().into())
+}
+
+fn interpreter_with_panicking_function() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_native_function("explode", |_args| {
+        let empty: Vec<i64> = Vec::new();
+        #[allow(clippy::unnecessary_literal_unwrap)]
+        let first = empty.first().copied().unwrap();
+        Ok(Value::Int(first))
+    });
+    interpreter.register_native_function("explode_with", |args| match &args[0] {
+        Value::String(s) => panic!("custom failure: {}", s),
+        _ => Ok(Value::Void),
+    });
+    interpreter
+}
+
+#[test]
+fn panics_become_catchable_errors() {
+    let mut interpreter = interpreter_with_panicking_function();
+    run(&mut interpreter, r#"
+let seen = ListMutable([])
+try:
+    explode()
+except e:
+    seen.push(e.kind)
+let kind = seen[0]
+"#).unwrap();
+    assert_eq!(interpreter.global("kind"), Some(string("Panic")));
+}
+
+#[test]
+fn panic_message_is_kept() {
+    let mut interpreter = interpreter_with_panicking_function();
+    run(&mut interpreter, r#"
+let seen = ListMutable([])
+try:
+    explode_with("disk on fire")
+except e:
+    seen.push(e.message)
+let message = seen[0]
+"#).unwrap();
+    assert_eq!(
+        interpreter.global("message"),
+        Some(string("Native function 'explode_with' panicked: custom failure: disk on fire"))
+    );
+}
+
+#[test]
+fn interpreter_keeps_working_after_a_panic() {
+    let mut interpreter = interpreter_with_panicking_function();
+    assert!(run(&mut interpreter, "explode()").is_err());
+    run(&mut interpreter, "let after = str(41 + 1)").unwrap();
+    assert_eq!(interpreter.global("after"), Some(string("42")));
+}
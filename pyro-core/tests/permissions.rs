@@ -0,0 +1,159 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use pyro_core::permissions::Permissions;
+use std::fs;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string().into())
+}
+
+#[test]
+fn only_granted_access_is_allowed() {
+    let root = std::env::temp_dir().join(format!("pyro-permissions-{}", std::process::id()));
+    fs::create_dir_all(root.join("data")).unwrap();
+    fs::write(root.join("data/allowed.txt"), "ok").unwrap();
+    fs::write(root.join("secret.txt"), "no").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_permissions(Some(Permissions::none().allow_fs(root.join("data")).allow_run("echo")));
+    interpreter.set_global("root", string(&root.to_string_lossy()));
+    let result = run(&mut interpreter, r#"
+import std.fs
+import std.process
+let kinds = ListMutable([])
+let allowed = fs.read_to_string(root + "/data/allowed.txt")
+try:
+    fs.read_to_string(root + "/data/../secret.txt")
+except e:
+    kinds.push(e.kind)
+try:
+    process.exec("ls")
+except e:
+    kinds.push(e.kind)
+try:
+    import plugin "libgeo.so"
+except e:
+    kinds.push(e.kind)
+let echoed = process.exec("echo", ["hi"])["stdout"]
+let fs_kind = kinds[0]
+let run_kind = kinds[1]
+let plugin_kind = kinds[2]
+"#);
+    #[cfg(feature = "python-interop")]
+    let python = run(&mut interpreter, r#"
+import std.python
+let python_kind = ""
+try:
+    python.call("math", "sqrt", [16])
+except e:
+    python_kind = e.kind
+"#);
+    fs::remove_dir_all(&root).unwrap();
+
+    result.unwrap();
+    assert_eq!(interpreter.global("allowed"), Some(string("ok")));
+    assert_eq!(interpreter.global("fs_kind"), Some(string("PermissionDenied")));
+    assert_eq!(interpreter.global("run_kind"), Some(string("PermissionDenied")));
+    assert_eq!(interpreter.global("plugin_kind"), Some(string("PermissionDenied")));
+    assert_eq!(interpreter.global("echoed"), Some(string("hi\n")));
+    #[cfg(feature = "python-interop")]
+    {
+        python.unwrap();
+        assert_eq!(interpreter.global("python_kind"), Some(string("PermissionDenied")));
+    }
+}
+
+// A script granted a command can't swap in its own executable of that name,
+// through PATH, a path to it or the working directory
+#[cfg(unix)]
+#[test]
+fn commands_are_granted_by_executable() {
+    use std::os::unix::fs::PermissionsExt;
+    let root = std::env::temp_dir().join(format!("pyro-permissions-run-{}", std::process::id()));
+    fs::create_dir_all(root.join("data")).unwrap();
+    let fake = root.join("data/echo");
+    fs::write(&fake, "#!/bin/sh\necho fake\n").unwrap();
+    fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_permissions(Some(Permissions::none().allow_fs(root.join("data")).allow_run("echo")));
+    interpreter.set_global("root", string(&root.to_string_lossy()));
+    let result = run(&mut interpreter, r#"
+import std.env
+import std.process
+let kinds = ListMutable([])
+def fake_echo():
+    return process.exec("echo")
+try:
+    env.set("PATH", root + "/data")
+except e:
+    kinds.push(e.kind)
+try:
+    env.unset("PATH")
+except e:
+    kinds.push(e.kind)
+try:
+    env.scoped({"PATH": root + "/data"}, fake_echo)
+except e:
+    kinds.push(e.kind)
+try:
+    process.exec(root + "/data/echo")
+except e:
+    kinds.push(e.kind)
+try:
+    env.set_cwd(root)
+except e:
+    kinds.push(e.kind)
+let denied = 0
+for kind in kinds:
+    if kind == "PermissionDenied":
+        denied = denied + 1
+let echoed = process.exec("echo", ["hi"])["stdout"]
+"#);
+    fs::remove_dir_all(&root).unwrap();
+
+    result.unwrap();
+    assert_eq!(interpreter.global("denied"), Some(Value::Int(5)));
+    assert_eq!(interpreter.global("echoed"), Some(string("hi\n")));
+}
+
+#[test]
+fn permissions_stay_with_their_interpreter() {
+    let mut sandboxed = Interpreter::new();
+    sandboxed.set_permissions(Some(Permissions::none()));
+    let mut open = Interpreter::new();
+    let source = "import std.fs\nlet found = fs.exists(\".\")\n";
+
+    let denied = run(&mut sandboxed, source).unwrap_err();
+    run(&mut open, source).unwrap();
+
+    assert!(format!("{:?}", denied.value).contains("PermissionDenied"), "{:?}", denied.value);
+    assert_eq!(open.global("found"), Some(Value::Bool(true)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn goroutines_share_the_permissions() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_permissions(Some(Permissions::none()));
+    run(&mut interpreter, r#"
+import std.fs
+let result = chan(1)
+def probe():
+    try:
+        fs.exists(".")
+        result.send("allowed")
+    except e:
+        result.send(e.kind)
+go probe()
+let kind = result.recv()
+"#).unwrap();
+
+    assert_eq!(interpreter.global("kind"), Some(string("PermissionDenied")));
+}
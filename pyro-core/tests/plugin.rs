@@ -0,0 +1,60 @@
+mod common;
+
+use common::parse;
+use pyro_core::ast::{to_source, Program, Stmt};
+use pyro_core::interpreter::{Interpreter, Value};
+use pyro_core::plugin::{ModuleDescriptor, PluginDescriptor, ABI_VERSION};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn modules() -> Vec<ModuleDescriptor> {
+    vec![ModuleDescriptor::new("acme.geo", Value::NativeModule(Arc::new(HashMap::new())))]
+}
+
+fn load_error(interpreter: &mut Interpreter, path: &str) -> String {
+    match interpreter.load_plugin(path).expect_err("loading should fail").value {
+        Value::Instance { fields, .. } => format!("{:?}", fields.read().unwrap()["message"]),
+        other => panic!("expected an Error instance, got {:?}", other),
+    }
+}
+
+#[test]
+fn import_plugin_parses_to_a_prefixed_import_and_prints_back() {
+    let statements = parse("import plugin \"lib/libgeo.so\"\n");
+    assert_eq!(statements, vec![Stmt::Import("plugin.lib/libgeo.so".to_string())]);
+    assert_eq!(to_source(&Program { statements }), "import plugin \"lib/libgeo.so\"\n");
+}
+
+#[test]
+fn a_descriptor_for_this_version_gives_its_modules() {
+    let names: Vec<String> = PluginDescriptor::new(modules).verify().unwrap().into_iter().map(|m| m.name).collect();
+    assert_eq!(names, vec!["acme.geo"]);
+}
+
+#[test]
+fn a_descriptor_for_another_abi_or_version_is_rejected() {
+    let other_abi = PluginDescriptor { abi_version: ABI_VERSION + 1, ..PluginDescriptor::new(modules) };
+    assert_eq!(
+        other_abi.verify().err().unwrap(),
+        format!("it uses plugin ABI version {}, but this pyro supports version {}", ABI_VERSION + 1, ABI_VERSION)
+    );
+
+    let other_version = PluginDescriptor { core_version: c"0.0.1".as_ptr(), ..PluginDescriptor::new(modules) };
+    assert_eq!(
+        other_version.verify().err().unwrap(),
+        format!("it was built for pyro-core 0.0.1, but this is pyro-core {}", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn files_that_are_not_plugins_fail_to_load() {
+    let mut interpreter = Interpreter::new();
+    assert!(load_error(&mut interpreter, "no/such/libgeo.so").starts_with("String(\"Could not load plugin 'no/such/libgeo.so': "));
+
+    let path = std::env::temp_dir().join(format!("pyro-plugin-{}.so", std::process::id()));
+    std::fs::write(&path, "not a library").unwrap();
+    let message = load_error(&mut interpreter, &path.to_string_lossy());
+    std::fs::remove_file(&path).unwrap();
+    assert!(message.contains("Could not load plugin"), "{}", message);
+    assert!(interpreter.list_native_modules().iter().all(|name| name.starts_with("std.")));
+}
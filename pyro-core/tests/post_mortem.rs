@@ -0,0 +1,66 @@
+use pyro_core::interpreter::{Interpreter, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::collections::BTreeMap;
+
+// Runs a failing program and returns the interpreter it failed in
+fn run(source: &str) -> Interpreter {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    let mut interpreter = Interpreter::new();
+    interpreter.run(program.statements).expect_err("test program should fail");
+    interpreter
+}
+
+fn locals(interpreter: &Interpreter) -> BTreeMap<&str, &Value> {
+    let locals = interpreter.error_locals().expect("error should leave locals");
+    locals.0.iter().map(|(name, value)| (name.as_str(), value)).collect()
+}
+
+#[test]
+fn error_keeps_the_innermost_functions_variables() {
+    let source = "\
+def inner(n: int):
+    let doubled = n * 2
+    raise \"boom\"
+def outer():
+    let unrelated = 1
+    inner(4)
+outer()
+";
+    let interpreter = run(source);
+    assert_eq!(locals(&interpreter), BTreeMap::from([("doubled", &Value::Int(8)), ("n", &Value::Int(4))]));
+}
+
+#[test]
+fn captured_variables_are_included() {
+    let source = "\
+def make(step: int):
+    def apply(x: int):
+        raise \"boom\"
+    return apply
+make(3)(1)
+";
+    let interpreter = run(source);
+    assert_eq!(locals(&interpreter).get("step"), Some(&&Value::Int(3)));
+    assert_eq!(locals(&interpreter).get("x"), Some(&&Value::Int(1)));
+}
+
+#[test]
+fn top_level_errors_have_no_locals() {
+    assert!(run("let x = 1\nraise \"boom\"\n").error_locals().is_none());
+}
+
+#[test]
+fn a_caught_error_does_not_leave_locals_for_the_next_one() {
+    let source = "\
+def fail(n: int):
+    raise \"first\"
+try:
+    fail(1)
+except e:
+    let x = 1
+raise \"second\"
+";
+    assert!(run(source).error_locals().is_none());
+}
@@ -0,0 +1,72 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use pyro_core::prelude::Prelude;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+const PROGRAM: &str = r#"
+import std.math
+import std.itertools
+
+let root = math.sqrt(16)
+let chunks = itertools.chunk([1, 2, 3], 2).len()
+let label = str(root) + "/" + str(chunks)
+"#;
+
+#[test]
+fn full_and_minimal_preludes_run_programs_the_same() {
+    let mut full = Interpreter::with_prelude(&Prelude::full());
+    let prelude = Prelude::minimal()
+        .with_module("std.math")
+        .and_then(|p| p.with_module("std.itertools"))
+        .unwrap();
+    let mut minimal = Interpreter::with_prelude(&prelude);
+
+    run(&mut full, PROGRAM).unwrap();
+    run(&mut minimal, PROGRAM).unwrap();
+
+    for name in ["root", "chunks", "label"] {
+        assert_eq!(full.global(name), minimal.global(name), "global '{}' differs", name);
+    }
+    assert_eq!(minimal.global("label"), Some(Value::String("4/2".to_string().into())));
+}
+
+#[test]
+fn new_uses_the_full_prelude() {
+    let interpreter = Interpreter::new();
+    for name in Prelude::full().modules() {
+        assert!(interpreter.has_native_module(name), "missing {}", name);
+    }
+}
+
+#[test]
+fn minimal_prelude_has_builtins_but_no_modules() {
+    let mut interpreter = Interpreter::with_prelude(&Prelude::minimal());
+    assert!(!interpreter.has_native_module("std.math"));
+    assert!(!interpreter.has_native_module("std.itertools"));
+
+    run(&mut interpreter, "let text = str(range(3).len())\nlet e = Error(\"boom\")").unwrap();
+    assert_eq!(interpreter.global("text"), Some(Value::String("3".to_string().into())));
+
+    // The import is skipped, so the module name stays undefined
+    assert!(run(&mut interpreter, "import std.math\nlet x = math.sqrt(4)").is_err());
+}
+
+#[test]
+fn unknown_modules_are_rejected() {
+    assert!(Prelude::minimal().with_module("std.nope").is_err());
+}
+
+#[test]
+fn with_module_ignores_duplicates() {
+    let prelude = Prelude::minimal()
+        .with_module("std.json")
+        .and_then(|p| p.with_module("std.json"))
+        .unwrap();
+    assert_eq!(prelude.modules(), ["std.json"]);
+}
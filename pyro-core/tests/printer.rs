@@ -0,0 +1,37 @@
+// Prints every program in tests/ and examples/ with to_source and parses the
+// output again, which must give back the same program.
+
+use pyro_core::ast::{to_source, Program};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn programs(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(dir);
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pyro"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse(source: &str) -> Result<Program, String> {
+    let tokens = Lexer::new(source).tokenize();
+    Parser::new(&tokens).parse()
+}
+
+#[test]
+fn printed_programs_parse_back_the_same() {
+    let files = [programs("tests"), programs("examples")].concat();
+    assert!(!files.is_empty());
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let program = parse(&source).unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let printed = to_source(&program);
+        let reparsed = parse(&printed).unwrap_or_else(|e| panic!("{} printed as:\n{}\ndoesn't parse: {}", file.display(), printed, e));
+        assert_eq!(reparsed, program, "{} printed as:\n{}", file.display(), printed);
+    }
+}
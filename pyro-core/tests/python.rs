@@ -0,0 +1,87 @@
+#![cfg(feature = "python-interop")]
+
+use pyo3::prelude::*;
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use pyro_core::stdlib::python::{from_python, to_python};
+use std::sync::{Arc, RwLock};
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string().into())
+}
+
+#[test]
+fn values_round_trip_through_python() {
+    let values = vec![
+        Value::Void,
+        Value::Bool(true),
+        Value::Int(-7),
+        Value::Float(2.5),
+        Value::Decimal("1.10".parse().unwrap()),
+        string("héllo"),
+        Value::List(Arc::new(vec![Value::Int(1), Value::Tuple(Arc::new(vec![Value::Bool(false), Value::Void]))])),
+        Value::Set(Arc::new(vec![Value::Int(3)])),
+        Value::Dict(Arc::new(vec![(string("a"), Value::List(Arc::new(vec![Value::Float(0.5)])))])),
+    ];
+    Python::attach(|py| {
+        for value in values {
+            let object = to_python(py, &value).unwrap();
+            assert_eq!(from_python(&object).unwrap(), value);
+        }
+    });
+}
+
+#[test]
+fn mutable_collections_and_chars_cross_as_their_python_counterparts() {
+    Python::attach(|py| {
+        let list = Value::ListMutable(Arc::new(RwLock::new(vec![Value::Char('x')])));
+        let object = to_python(py, &list).unwrap();
+        assert_eq!(object.repr().unwrap().to_string(), "['x']");
+        assert_eq!(from_python(&object).unwrap(), Value::List(Arc::new(vec![string("x")])));
+    });
+}
+
+#[test]
+fn values_without_a_counterpart_are_errors() {
+    Python::attach(|py| {
+        let error = to_python(py, &Value::Duration(std::time::Duration::from_secs(1))).unwrap_err();
+        assert_eq!(error.to_string(), "TypeError: A Pyro duration has no Python equivalent");
+
+        let object = py.eval(c"object()", None, None).unwrap();
+        assert_eq!(from_python(&object).unwrap_err(), "Python value of type 'object' has no Pyro equivalent");
+        let big = py.eval(c"10 ** 30", None, None).unwrap();
+        assert_eq!(from_python(&big).unwrap_err(), "Python int 1000000000000000000000000000000 doesn't fit in a Pyro int");
+    });
+}
+
+#[test]
+fn call_runs_a_python_function() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, r#"
+import std.python
+let root = python.call("math", "sqrt", [16])
+let parsed = python.call("json", "loads", ["""{"xs": [1, 2]}"""])
+let kind = ""
+let message = ""
+try:
+    python.call("math", "sqrt", [-1])
+except e:
+    kind = e.kind
+    message = e.message
+"#)
+    .unwrap();
+    assert_eq!(interpreter.global("root"), Some(Value::Float(4.0)));
+    assert_eq!(
+        interpreter.global("parsed"),
+        Some(Value::Dict(Arc::new(vec![(string("xs"), Value::List(Arc::new(vec![Value::Int(1), Value::Int(2)])))])))
+    );
+    assert_eq!(interpreter.global("kind"), Some(string("PythonError")));
+    assert_eq!(interpreter.global("message"), Some(string("ValueError: math domain error")));
+}
@@ -0,0 +1,41 @@
+use pyro_core::interpreter::{Interpreter, NativeClosure, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use std::sync::Arc;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeError> {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    interpreter.run(program.statements).map(|_| ())
+}
+
+#[test]
+fn reloads_apply_inside_for_loops() {
+    let dir = std::env::temp_dir().join(format!("pyro_reload_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = dir.join("greet.pyro");
+    std::fs::write(&module, "def greet():\n    return \"new\"\n").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    let requests = interpreter.requests();
+    interpreter.set_global("reload", Value::NativeFunction {
+        name: "reload".to_string(),
+        func: NativeClosure(Arc::new(move |_| {
+            requests.reload(&module.to_string_lossy());
+            Ok(Value::Void)
+        })),
+    });
+    run(&mut interpreter, r#"
+def greet():
+    return "old"
+let seen = ListMutable([])
+for i in range(3):
+    seen.push(greet())
+    if i == 0:
+        reload()
+let greetings = seen[0] + " " + seen[1] + " " + seen[2]
+"#).unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(interpreter.global("greetings"), Some(Value::String("old new new".to_string().into())));
+}
@@ -0,0 +1,47 @@
+use pyro_core::interpreter::{Interpreter, RuntimeError, Value};
+use pyro_core::lexer::Lexer;
+use pyro_core::parser::Parser;
+use pyro_core::replay;
+
+fn run(source: &str) -> (Interpreter, Result<(), RuntimeError>) {
+    let tokens = Lexer::new(source).tokenize();
+    let program = Parser::new(&tokens).parse().expect("test program should parse");
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.run(program.statements).map(|_| ());
+    (interpreter, result)
+}
+
+const SCRIPT: &str = r#"
+import std.random
+import std.time
+let n = random.randint(1, 1000000000)
+let t = time.now()
+"#;
+
+// Recording and replaying share process-wide state, so the steps run in one test
+#[test]
+fn replay_returns_recorded_inputs() {
+    replay::start_recording();
+    let (recorded, result) = run(SCRIPT);
+    result.unwrap();
+    let log = replay::finish_recording();
+    assert_eq!(log.lines().count(), 2);
+
+    replay::start_replay(&log).unwrap();
+    let (replayed, result) = run(SCRIPT);
+    result.unwrap();
+    assert_eq!(replayed.global("n"), recorded.global("n"));
+    assert_eq!(replayed.global("t"), recorded.global("t"));
+
+    // A script that makes different calls than the log is reported, not fed
+    // the wrong values
+    replay::start_replay(&log).unwrap();
+    let (_, result) = run("import std.time\nlet t = time.now()");
+    replay::stop();
+    match result {
+        Err(RuntimeError { value: Value::Instance { fields, .. }, .. }) => {
+            assert_eq!(fields.read().unwrap().get("kind"), Some(&Value::String("Replay".to_string().into())));
+        }
+        other => panic!("expected a replay error, got {:?}", other),
+    }
+}
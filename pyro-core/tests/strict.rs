@@ -0,0 +1,105 @@
+mod common;
+
+use common::{parse, reported};
+use pyro_core::ast::{to_source, Program, Stmt, Type};
+use pyro_core::strict;
+
+#[test]
+fn unannotated_parameters_are_implicitly_any() {
+    let program = parse("def f(a, b: any, c: Any):\n    print(a)\n");
+    let Stmt::FnDecl { params, .. } = &program[0] else { panic!("expected a function") };
+    let types: Vec<&Type> = params.iter().map(|(_, typ)| typ).collect();
+    assert_eq!(types, vec![&Type::ImplicitAny, &Type::Any, &Type::Any]);
+    assert_eq!(to_source(&Program { statements: program }), "def f(a, b: any, c: any):\n    print(a)\n");
+}
+
+#[test]
+fn annotated_programs_pass() {
+    let source = "\
+def double(n: int) -> int:
+    return n * 2
+def log(value: any):
+    print(value)
+def _private(x):
+    return x
+class Greeter:
+    def __init__(self, name: string):
+        self.name = name
+let d = double(2)
+";
+    assert_eq!(strict::check(&parse(source)), Ok(()));
+}
+
+#[test]
+fn public_signatures_need_annotations() {
+    let source = "\
+def loose(x, y: int):
+    return y
+record Point(x: int, y: int):
+    def shift(self, by):
+        print(by)
+    def _inner(self, by):
+        print(by)
+def outer(n: int):
+    def nested(m):
+        return m
+    print(nested(n))
+";
+    assert_eq!(
+        reported(&strict::check(&parse(source)).unwrap_err()),
+        vec![
+            ("annotation".to_string(), "Parameter 'x' of public function 'loose' has no type annotation".to_string()),
+            ("annotation".to_string(), "Public function 'loose' returns a value but declares no return type".to_string()),
+            ("annotation".to_string(), "Parameter 'by' of public method 'shift' has no type annotation".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn implicit_any_reaching_typed_code_is_reported() {
+    let source = "\
+def double(n: int) -> int:
+    return n * 2
+def _relay(x) -> int:
+    let y = x
+    let z: int = y
+    let w = double(y)
+    return x
+def _fine(x):
+    let any_value: any = x
+    print(x)
+    return x
+";
+    let diagnostics = strict::check(&parse(source)).unwrap_err();
+    assert_eq!(
+        reported(&diagnostics),
+        vec![
+            ("implicit-any".to_string(), "'y' has an implicit any type but is given to 'z', declared int".to_string()),
+            ("implicit-any".to_string(), "'y' has an implicit any type but is passed as 'n' to 'double', declared int".to_string()),
+            ("implicit-any".to_string(), "'x' has an implicit any type but is returned from '_relay', declared int".to_string()),
+        ]
+    );
+    assert_eq!(diagnostics[0].notes, vec!["'y' comes from the unannotated parameter 'x' of '_relay'"]);
+}
+
+#[test]
+fn variadic_parameters_are_checked_per_argument() {
+    let source = "\
+def total(label: string, *amounts: int) -> int:
+    return amounts.len()
+def log(*values):
+    print(values)
+def _relay(x):
+    let a = total(\"sum\", 1, x)
+    let b = total(*[\"sum\", x])
+";
+    let program = parse(source);
+    assert_eq!(to_source(&Program { statements: program.clone() }).lines().next(), Some("def total(label: string, *amounts: int) -> int:"));
+    assert_eq!(
+        reported(&strict::check(&program).unwrap_err()),
+        vec![
+            ("annotation".to_string(), "Parameter 'values' of public function 'log' has no type annotation".to_string()),
+            ("implicit-any".to_string(), "'x' has an implicit any type but is passed as 'amounts' to 'total', declared int".to_string()),
+        ]
+    );
+}
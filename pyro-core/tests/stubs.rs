@@ -0,0 +1,86 @@
+mod common;
+
+use common::{parse, reported};
+use pyro_core::interpreter::Value;
+use pyro_core::stdlib::{NATIVE_MODULES, STUBS};
+use pyro_core::stubs;
+use std::collections::BTreeSet;
+
+#[test]
+fn every_native_function_has_a_stub() {
+    assert_eq!(STUBS.len(), NATIVE_MODULES.len());
+    for (name, factory) in NATIVE_MODULES {
+        let Value::NativeModule(functions) = factory() else {
+            panic!("{} isn't a module", name);
+        };
+        let exported: BTreeSet<&String> = functions.keys().collect();
+        let declared: BTreeSet<&String> = stubs::signatures(name)
+            .unwrap_or_else(|| panic!("{} has no stub", name))
+            .keys()
+            .collect();
+        assert_eq!(declared, exported, "stub for {}", name);
+    }
+}
+
+#[test]
+fn well_typed_calls_pass() {
+    let source = "\
+import std.fs
+import std.math
+import std.env
+import std.functools
+import std.glob
+let text = fs.read_to_string(\"notes.txt\")
+print(math.sqrt(16))
+print(math.pow(2, 0.5))
+print(fs.exists(env.var(\"HOME\")))
+print(glob.match(\"*.pyro\", f\"{text}.pyro\"))
+print(glob.walk(\".\", [\"*.pyro\"]))
+let add = functools.partial(math.pow, 2, 3)
+let cached = functools.memoize() as n:
+    return n * 2
+";
+    assert_eq!(stubs::check(&parse(source)), Ok(()));
+}
+
+#[test]
+fn wrong_calls_are_reported() {
+    let source = "\
+import std.fs
+import std.time
+import std.compression
+fs.read_to_string(42)
+fs.write(\"out.txt\")
+time.format(1.5, \"%Y\", \"en\", 4)
+compression.compress(\"gzip\", 1 if true else 2)
+fs.read_everything(\"notes.txt\")
+time.sleep(fs.exists(\"lock\"))
+fs.write(\"out.txt\", *[\"text\"])
+fs.write(42, *rest)
+";
+    assert_eq!(
+        reported(&stubs::check(&parse(source)).unwrap_err()),
+        vec![
+            ("stdlib".to_string(), "Argument 1 of 'fs.read_to_string' must be string, found int".to_string()),
+            ("stdlib".to_string(), "'fs.write' takes 2 arguments, 1 given".to_string()),
+            ("stdlib".to_string(), "'time.format' takes 1 to 3 arguments, 4 given".to_string()),
+            ("stdlib".to_string(), "Argument 2 of 'compression.compress' must be string | list, found int".to_string()),
+            ("stdlib".to_string(), "'fs' has no function 'read_everything'".to_string()),
+            ("stdlib".to_string(), "Argument 1 of 'time.sleep' must be float | duration, found bool".to_string()),
+            ("stdlib".to_string(), "Argument 1 of 'fs.write' must be string, found int".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn rebound_module_names_are_not_checked() {
+    let source = "\
+import std.fs
+import std.math
+def read(fs):
+    return fs.read_to_string(42)
+let math = 1
+print(math.sqrt(1, 2))
+";
+    assert_eq!(stubs::check(&parse(source)), Ok(()));
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::parse;
+use pyro_core::transpiler::Transpiler;
+
+fn transpile(source: &str) -> Result<String, String> {
+    Transpiler::new().transpile(parse(source))
+}
+
+#[test]
+fn unsupported_constructs_are_errors() {
+    assert_eq!(
+        transpile("def f(*args):\n    return args\nlet xs = [1, 2]\nf(*xs)\n").unwrap_err(),
+        "The transpiler doesn't support spreading arguments with '*' yet"
+    );
+    assert_eq!(transpile("let xs = [1]\nprint(xs[0])\n").unwrap_err(), "The transpiler doesn't support indexing yet");
+    assert_eq!(
+        transpile("def open():\n    return 1\nwith open() as f:\n    print(f)\n").unwrap_err(),
+        "The transpiler doesn't support 'with' yet"
+    );
+    assert_eq!(
+        transpile("let n = 1\nmatch n:\n    case 1:\n        print(\"one\")\n    case _:\n        print(\"other\")\n").unwrap_err(),
+        "The transpiler doesn't support 'match' yet"
+    );
+    assert_eq!(
+        transpile("enum Light:\n    Red\n    Green\n").unwrap_err(),
+        "The transpiler doesn't support enums yet"
+    );
+}
+
+#[test]
+fn only_buffered_channels_are_transpiled() {
+    let unbuffered = "The transpiler doesn't support unbuffered channels; give 'chan' a capacity";
+    assert_eq!(transpile("let c = chan<int>()\n").unwrap_err(), unbuffered);
+    assert_eq!(transpile("let c = chan<int>(0)\n").unwrap_err(), unbuffered);
+    assert!(transpile("let c = chan<int>(4)\n").unwrap().contains("async_channel::bounded::<i64>(4)"));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::parse;
+use pyro_core::ast::visit::{self, Folder, Visitor, VisitorMut};
+use pyro_core::ast::{to_source, Expr, Program};
+
+// Uses `target` in every kind of statement and expression after binding it
+// on the first line. A node that `walk_*` or `fold_*` doesn't descend into
+// keeps its `target`, so the rewritten program prints differently.
+const PROGRAM: &str = r#"let target = 1
+const LIMIT = target + 1
+let (first, second) = (target, [target, *[target]])
+print(target)
+if target > 0:
+    print(target)
+else:
+    print(target)
+while target < 0:
+    break
+for item in target..=target:
+    continue
+match target:
+    case 1 | 2 if target > 0:
+        print(target)
+    case _:
+        print(target)
+let counter = 0
+counter = target
+(first, second) = (target, target)
+class Box:
+    def __init__(self, value):
+        self.value = target
+    def get(self):
+        return self.value
+def helper(x: int) -> int:
+    return target * x
+def maybe(x) -> Option:
+    let y = find(target)?
+    return Option.Some(y)
+try:
+    raise target
+except e:
+    print(target)
+with open(target) as f:
+    print(f)
+go helper(target)
+let items = {target: [target][0], "k": {target}}
+let label = f"value {target}"
+let picked = target if target else helper(target).value
+each([target]) as x:
+    print(x + target)
+"#;
+
+// PROGRAM with every use of `target` renamed, as the passes below should
+// leave it
+fn renamed() -> Program {
+    let (binding, rest) = PROGRAM.split_once('\n').unwrap();
+    Program { statements: parse(&format!("{}\n{}", binding, rest.replace("target", "renamed"))) }
+}
+
+fn program() -> Program {
+    Program { statements: parse(PROGRAM) }
+}
+
+struct Count(usize);
+
+impl Visitor for Count {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if *expr == Expr::Identifier("target".to_string()) {
+            self.0 += 1;
+        }
+        visit::walk_expr(self, expr);
+    }
+}
+
+struct Rename;
+
+impl VisitorMut for Rename {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if *expr == Expr::Identifier("target".to_string()) {
+            *expr = Expr::Identifier("renamed".to_string());
+        }
+        visit::walk_expr_mut(self, expr);
+    }
+}
+
+impl Folder for Rename {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Identifier(name) if name == "target" => Expr::Identifier("renamed".to_string()),
+            expr => visit::fold_expr(self, expr),
+        }
+    }
+}
+
+#[test]
+fn the_visitor_reaches_every_expression() {
+    let mut count = Count(0);
+    visit::walk_program(&mut count, &program());
+    assert_eq!(count.0, PROGRAM.matches("target").count() - 1);
+}
+
+#[test]
+fn the_mutable_visitor_rewrites_every_expression() {
+    let mut program = program();
+    visit::walk_program_mut(&mut Rename, &mut program);
+    assert_eq!(to_source(&program), to_source(&renamed()));
+}
+
+#[test]
+fn the_folder_rewrites_every_expression() {
+    let folded = visit::fold_program(&mut Rename, program());
+    assert_eq!(to_source(&folded), to_source(&renamed()));
+}